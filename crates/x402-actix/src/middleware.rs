@@ -330,11 +330,7 @@ where
             .iter()
             .find(|s| s.network == network)
             .and_then(|s| s.extra.as_ref())
-            .map(|extra| {
-                json!({
-                    "feePayer": extra.fee_payer,
-                })
-            })
+            .and_then(|extra| serde_json::to_value(extra).ok())
     }
 
     fn recompute_offers(mut self) -> Self {
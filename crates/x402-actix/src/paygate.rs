@@ -1,5 +1,4 @@
 use actix_http::header::HeaderMap;
-use serde_json::json;
 use std::sync::Arc;
 use x402_rs::{
     facilitator::Facilitator,
@@ -49,16 +48,13 @@ where
                             .kinds
                             .iter()
                             .find(|s| s.network == network.to_string())
-                            .cloned()
-                            .and_then(|s| s.extra);
+                            .and_then(|s| s.extra.as_ref())
+                            .and_then(|extra| serde_json::to_value(extra).ok());
                         if let Some(extra) = extra {
-                            r.extra = Some(json!({
-                                "feePayer": extra.fee_payer
-                            }));
-                            r
-                        } else {
-                            r
+                            // Carries `feePayer` plus any facilitator caps the client has to honour
+                            r.extra = merge_supported_extra(r.extra.take(), &extra);
                         }
+                        r
                     })
                     .collect::<Vec<_>>();
                 Err(X402Error::payment_header_required(requirements))
@@ -142,3 +138,23 @@ where
         }
     }
 }
+
+/// Adds the fields of the `extra` a facilitator advertises for a network, e.g. `feePayer` or `fee`,
+/// to the `extra` of a token. Fields of the token, like its EIP-712 `name` and `version`, are kept.
+fn merge_supported_extra(
+    extra: Option<serde_json::Value>,
+    supported_extra: &serde_json::Value,
+) -> Option<serde_json::Value> {
+    let serde_json::Value::Object(supported_extra) = supported_extra else {
+        return extra;
+    };
+    let mut extra = match extra {
+        Some(serde_json::Value::Object(extra)) => extra,
+        None => serde_json::Map::new(),
+        Some(other) => return Some(other),
+    };
+    for (key, value) in supported_extra {
+        extra.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+    Some(serde_json::Value::Object(extra))
+}
@@ -18,6 +18,8 @@
 //!
 //! - Uses `reqwest` for async HTTP requests
 //! - Supports optional timeout and headers
+//! - Retries transient failures with jittered exponential backoff, see [`RetryPolicy`]
+//! - Fails fast while the facilitator is down, if a [`CircuitBreaker`] is set (off by default)
 //! - Integrates with `tracing` if the `telemetry` feature is enabled
 //!
 //! ## Error Handling
@@ -27,11 +29,16 @@
 //! - HTTP transport failures
 //! - JSON deserialization errors
 //! - Unexpected HTTP status responses
+//!
+//! [`FacilitatorClientError::is_transient`] tells failures worth retrying later from permanent ones.
 
 use http::{HeaderMap, StatusCode};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use std::collections::hash_map::RandomState;
 use std::fmt::Display;
-use std::time::Duration;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use url::Url;
 use x402_rs::facilitator::Facilitator;
 use x402_rs::types::{
@@ -61,6 +68,156 @@ pub struct FacilitatorClient {
     headers: HeaderMap,
     /// Optional request timeout
     timeout: Option<Duration>,
+    /// Retries of failed requests
+    retry_policy: RetryPolicy,
+    /// Circuit breaker shared by all clones of this client, if any
+    circuit_breaker: Option<CircuitBreaker>,
+}
+
+/// Retries of failed facilitator requests, with jittered exponential backoff.
+///
+/// Only [transient](FacilitatorClientError::is_transient) failures are retried.
+/// `/verify` and `/supported` are idempotent and retried on any transient failure.
+/// `/settle` is retried only if the request could not be sent, unless the facilitator settles
+/// idempotently, see [`RetryPolicy::with_settle_retries`].
+///
+/// Defaults to 2 retries, backing off from 100ms up to 2s.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    retry_settle: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            retry_settle: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Makes a single attempt per request.
+    pub fn none() -> Self {
+        Self::default().with_max_retries(0)
+    }
+
+    /// Sets the number of retries after the first attempt.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the backoff before the first retry, doubled on each next retry up to `max_backoff`.
+    pub fn with_backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Retries `/settle` on any transient failure, like `/verify`.
+    ///
+    /// Only enable it if the facilitator settles idempotently: settling the same payment again
+    /// must not move funds twice, even if the first attempt succeeded but its response was lost.
+    pub fn with_settle_retries(mut self, retry_settle: bool) -> Self {
+        self.retry_settle = retry_settle;
+        self
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Delay before retry number `retry`, starting from 0: the exponential backoff, half of it jittered.
+    fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        let random = RandomState::new().build_hasher().finish();
+        backoff / 2 + (backoff / 2).mul_f64(random as f64 / u64::MAX as f64)
+    }
+}
+
+/// Fails requests fast while the facilitator is down, instead of waiting on every request for it to time out.
+///
+/// After `failure_threshold` consecutive transient failures the circuit opens: requests fail with
+/// [`FacilitatorClientError::CircuitOpen`] without being sent. After `open_duration`, a single
+/// request is let through. If it reaches the facilitator the circuit closes, otherwise it stays open
+/// for another `open_duration`.
+///
+/// Clones share the state. Defaults to opening after 5 failures, for 30s.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    state: Arc<Mutex<CircuitState>>,
+}
+
+#[derive(Debug, Default)]
+struct CircuitState {
+    consecutive_failures: u32,
+    /// Set while the circuit is open: no request is sent before that instant.
+    open_until: Option<Instant>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(30))
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            state: Arc::new(Mutex::new(CircuitState::default())),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, CircuitState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns `true` if requests currently fail fast.
+    pub fn is_open(&self) -> bool {
+        self.state()
+            .open_until
+            .is_some_and(|open_until| Instant::now() < open_until)
+    }
+
+    /// Whether a request may be sent now. Once `open_duration` has passed, lets one probe request
+    /// through per `open_duration`.
+    fn try_acquire(&self) -> bool {
+        let mut state = self.state();
+        match state.open_until {
+            Some(open_until) if Instant::now() < open_until => false,
+            Some(_) => {
+                state.open_until = Some(Instant::now() + self.open_duration);
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Records the outcome of a request that was let through.
+    fn record(&self, error: Option<&FacilitatorClientError>) {
+        let mut state = self.state();
+        if error.is_some_and(FacilitatorClientError::is_transient) {
+            state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+            if state.consecutive_failures >= self.failure_threshold {
+                state.open_until = Some(Instant::now() + self.open_duration);
+            }
+        } else {
+            *state = CircuitState::default();
+        }
+    }
 }
 
 impl Facilitator for FacilitatorClient {
@@ -152,6 +309,41 @@ pub enum FacilitatorClientError {
         #[source]
         source: reqwest::Error,
     },
+    #[error("Circuit open, facilitator unavailable: {context}")]
+    CircuitOpen { context: &'static str },
+}
+
+impl FacilitatorClientError {
+    /// Returns `true` if the failure is likely temporary and the same request may succeed later:
+    /// the facilitator could not be reached, timed out, was overloaded or failed with a 5xx error.
+    ///
+    /// Permanent failures, like an invalid URL, a `4xx` status or a response that is not JSON,
+    /// happen again on retry.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            FacilitatorClientError::Http { source, .. } => {
+                source.is_request() || source.is_timeout()
+            }
+            FacilitatorClientError::HttpStatus { status, .. } => matches!(
+                *status,
+                StatusCode::REQUEST_TIMEOUT
+                    | StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::INTERNAL_SERVER_ERROR
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            FacilitatorClientError::ResponseBodyRead { .. }
+            | FacilitatorClientError::CircuitOpen { .. } => true,
+            FacilitatorClientError::UrlParse { .. }
+            | FacilitatorClientError::JsonDeserialization { .. } => false,
+        }
+    }
+
+    /// Returns `true` if the request never reached the facilitator, so it is safe to send again.
+    fn is_unsent(&self) -> bool {
+        matches!(self, FacilitatorClientError::Http { source, .. } if source.is_connect())
+    }
 }
 
 impl FacilitatorClient {
@@ -191,6 +383,16 @@ impl FacilitatorClient {
         &self.timeout
     }
 
+    /// Returns the retry policy.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Returns the circuit breaker, if any.
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.circuit_breaker.as_ref()
+    }
+
     /// Constructs a new [`FacilitatorClient`] from a base URL.
     ///
     /// This sets up `./verify` and `./settle` endpoint URLs relative to the base.
@@ -225,6 +427,8 @@ impl FacilitatorClient {
             supported_url,
             headers: HeaderMap::new(),
             timeout: None,
+            retry_policy: RetryPolicy::default(),
+            circuit_breaker: None,
         })
     }
 
@@ -244,12 +448,33 @@ impl FacilitatorClient {
         this
     }
 
+    /// Sets how failed requests are retried, [`RetryPolicy::none`] to disable retries.
+    pub fn with_retry_policy(&self, retry_policy: RetryPolicy) -> Self {
+        let mut this = self.clone();
+        this.retry_policy = retry_policy;
+        this
+    }
+
+    /// Sets a circuit breaker, there is none by default. Pass a clone to share it with another client.
+    pub fn with_circuit_breaker(&self, circuit_breaker: CircuitBreaker) -> Self {
+        let mut this = self.clone();
+        this.circuit_breaker = Some(circuit_breaker);
+        this
+    }
+
+    /// Removes the circuit breaker: sends every request, however many failed before.
+    pub fn without_circuit_breaker(&self) -> Self {
+        let mut this = self.clone();
+        this.circuit_breaker = None;
+        this
+    }
+
     /// Sends a `POST /verify` request to the facilitator.
     pub async fn verify(
         &self,
         request: &VerifyRequest,
     ) -> Result<VerifyResponse, FacilitatorClientError> {
-        self.post_json(&self.verify_url, "POST /verify", request)
+        self.post_json(&self.verify_url, "POST /verify", true, request)
             .await
     }
 
@@ -258,7 +483,8 @@ impl FacilitatorClient {
         &self,
         request: &SettleRequest,
     ) -> Result<SettleResponse, FacilitatorClientError> {
-        self.post_json(&self.settle_url, "POST /settle", request)
+        let idempotent = self.retry_policy.retry_settle;
+        self.post_json(&self.settle_url, "POST /settle", idempotent, request)
             .await
     }
 
@@ -267,47 +493,69 @@ impl FacilitatorClient {
     }
 
     /// Generic POST helper that handles JSON serialization, error mapping,
-    /// timeout application, and telemetry integration.
+    /// timeout application, retries, and telemetry integration.
     ///
     /// `context` is a human-readable identifier used in tracing and error messages (e.g. `"POST /verify"`).
+    /// `idempotent` requests are retried on any transient failure, others only if they could not be sent.
     async fn post_json<T, R>(
         &self,
         url: &Url,
         context: &'static str,
+        idempotent: bool,
         payload: &T,
     ) -> Result<R, FacilitatorClientError>
     where
         T: serde::Serialize + ?Sized,
         R: serde::de::DeserializeOwned,
     {
-        let mut req = self.client.post(url.clone()).json(payload);
-        for (key, value) in self.headers.iter() {
-            req = req.header(key, value);
-        }
-        if let Some(timeout) = self.timeout {
-            req = req.timeout(timeout);
-        }
-        let http_response = req
-            .send()
+        self.send_json(context, idempotent, || {
+            self.client.post(url.clone()).json(payload)
+        })
+        .await
+    }
+
+    /// Generic GET helper that handles JSON serialization, error mapping,
+    /// timeout application, retries, and telemetry integration.
+    ///
+    /// `context` is a human-readable identifier used in tracing and error messages (e.g. `"POST /verify"`).
+    async fn get_json<R>(
+        &self,
+        url: &Url,
+        context: &'static str,
+    ) -> Result<R, FacilitatorClientError>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        self.send_json(context, true, || self.client.get(url.clone()))
             .await
-            .map_err(|e| FacilitatorClientError::Http { context, source: e })?;
+    }
 
-        let result = if http_response.status() == StatusCode::OK {
-            http_response
-                .json::<R>()
-                .await
-                .map_err(|e| FacilitatorClientError::JsonDeserialization { context, source: e })
-        } else {
-            let status = http_response.status();
-            let body = http_response
-                .text()
-                .await
-                .map_err(|e| FacilitatorClientError::ResponseBodyRead { context, source: e })?;
-            Err(FacilitatorClientError::HttpStatus {
-                context,
-                status,
-                body,
-            })
+    /// Sends the request built by `make_request`, retrying it as allowed by the [`RetryPolicy`].
+    async fn send_json<R>(
+        &self,
+        context: &'static str,
+        idempotent: bool,
+        make_request: impl Fn() -> RequestBuilder,
+    ) -> Result<R, FacilitatorClientError>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        let mut retry = 0;
+        let result = loop {
+            let result = self.send_json_once(context, make_request()).await;
+            match &result {
+                Err(error)
+                    if retry < self.retry_policy.max_retries
+                        && !matches!(error, FacilitatorClientError::CircuitOpen { .. })
+                        && (error.is_unsent() || idempotent && error.is_transient()) =>
+                {
+                    let backoff = self.retry_policy.backoff(retry);
+                    record_retry(context, retry, backoff, error);
+                    tokio::time::sleep(backoff).await;
+                    retry += 1;
+                }
+                _ => break result,
+            }
         };
 
         record_result_on_span(&result);
@@ -315,49 +563,54 @@ impl FacilitatorClient {
         result
     }
 
-    /// Generic GET helper that handles JSON serialization, error mapping,
-    /// timeout application, and telemetry integration.
-    ///
-    /// `context` is a human-readable identifier used in tracing and error messages (e.g. `"POST /verify"`).
-    async fn get_json<R>(
+    /// Makes a single attempt, unless the circuit is open, and records its outcome on the circuit breaker.
+    async fn send_json_once<R>(
         &self,
-        url: &Url,
         context: &'static str,
+        mut req: RequestBuilder,
     ) -> Result<R, FacilitatorClientError>
     where
         R: serde::de::DeserializeOwned,
     {
-        let mut req = self.client.get(url.clone());
+        if let Some(circuit_breaker) = &self.circuit_breaker
+            && !circuit_breaker.try_acquire()
+        {
+            return Err(FacilitatorClientError::CircuitOpen { context });
+        }
         for (key, value) in self.headers.iter() {
             req = req.header(key, value);
         }
         if let Some(timeout) = self.timeout {
             req = req.timeout(timeout);
         }
-        let http_response = req
-            .send()
-            .await
-            .map_err(|e| FacilitatorClientError::Http { context, source: e })?;
-
-        let result = if http_response.status() == StatusCode::OK {
-            http_response
-                .json::<R>()
-                .await
-                .map_err(|e| FacilitatorClientError::JsonDeserialization { context, source: e })
-        } else {
-            let status = http_response.status();
-            let body = http_response
-                .text()
-                .await
-                .map_err(|e| FacilitatorClientError::ResponseBodyRead { context, source: e })?;
-            Err(FacilitatorClientError::HttpStatus {
-                context,
-                status,
-                body,
-            })
-        };
-
-        record_result_on_span(&result);
+        let result =
+            async {
+                let http_response = req
+                    .send()
+                    .await
+                    .map_err(|e| FacilitatorClientError::Http { context, source: e })?;
+
+                if http_response.status() == StatusCode::OK {
+                    http_response.json::<R>().await.map_err(|e| {
+                        FacilitatorClientError::JsonDeserialization { context, source: e }
+                    })
+                } else {
+                    let status = http_response.status();
+                    let body = http_response.text().await.map_err(|e| {
+                        FacilitatorClientError::ResponseBodyRead { context, source: e }
+                    })?;
+                    Err(FacilitatorClientError::HttpStatus {
+                        context,
+                        status,
+                        body,
+                    })
+                }
+            }
+            .await;
+
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            circuit_breaker.record(result.as_ref().err());
+        }
 
         result
     }
@@ -395,6 +648,34 @@ fn record_result_on_span<R, E: Display>(result: &Result<R, E>) {
     }
 }
 
+/// Records a failed attempt that is retried after `backoff`.
+#[cfg(feature = "telemetry")]
+fn record_retry(
+    context: &'static str,
+    retry: u32,
+    backoff: Duration,
+    error: &FacilitatorClientError,
+) {
+    tracing::event!(
+        tracing::Level::WARN,
+        error = %error,
+        retry,
+        backoff = ?backoff,
+        "Request to facilitator failed, retrying: {context}"
+    );
+}
+
+/// Records a failed attempt that is retried after `backoff`.
+/// Noop if telemetry feature is off.
+#[cfg(not(feature = "telemetry"))]
+fn record_retry(
+    _context: &'static str,
+    _retry: u32,
+    _backoff: Duration,
+    _error: &FacilitatorClientError,
+) {
+}
+
 /// Records the outcome of a request on a tracing span, including status and errors.
 /// Noop if telemetry feature is off.
 #[cfg(not(feature = "telemetry"))]
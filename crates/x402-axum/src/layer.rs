@@ -52,6 +52,31 @@
 //!   and a base URL set via **[`X402Middleware::with_base_url`]**.
 //! - If no base URL is provided, the default is `http://localhost/` (⚠️ avoid this in production).
 //!
+//! ## Validating Against the Facilitator
+//!
+//! **[`X402Middleware::validate`]** checks every price tag against the payment kinds the facilitator supports,
+//! so that a network or scheme the facilitator can not settle fails at startup rather than when a buyer pays.
+//! It also fills `extra` of the payment requirements from the facilitator, e.g. the Solana `feePayer`.
+//!
+//! ```rust,ignore
+//! let x402 = X402Middleware::try_from("https://facilitator.example.com/")?
+//!     .with_price_tag(usdc.amount(0.025)?)
+//!     .validate()
+//!     .await?;
+//! ```
+//!
+//! The payment kinds are cached, for 5 minutes by default, see [`X402Middleware::with_supported_ttl`].
+//!
+//! ## Dynamic Prices
+//!
+//! **[`X402Middleware::with_dynamic_price`]** prices each request when it arrives instead of using
+//! fixed price tags, e.g. from query parameters or a customer tier header. See [`crate::pricing`].
+//!
+//! ## Route Tables
+//!
+//! **[`X402Middleware::with_route_table`]** prices many routes from a single layer applied to the whole router,
+//! letting the requests no route matches pass through. See [`crate::route_table`].
+//!
 //! ## Best Practices (Production)
 //!
 //! - Use [`X402Middleware::with_resource`] when the full resource URL is known.
@@ -67,9 +92,10 @@ use axum_core::{
 use http::{HeaderMap, HeaderValue, StatusCode, Uri};
 use once_cell::sync::Lazy;
 use serde_json::json;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::{
     convert::Infallible,
     future::Future,
@@ -79,12 +105,14 @@ use std::{
 use tower::util::BoxCloneSyncService;
 use tower::{Layer, Service};
 use url::Url;
+use x402_rs::chain::evm::ASSET_TRANSFER_METHOD_PERMIT;
 use x402_rs::facilitator::Facilitator;
-use x402_rs::network::Network;
+use x402_rs::network::{Network, NetworkFamily};
 use x402_rs::types::{
-    Base64Bytes, FacilitatorErrorReason, MixedAddress, PaymentPayload, PaymentRequiredResponse,
-    PaymentRequirements, Scheme, SettleRequest, SettleResponse, TokenAmount, VerifyRequest,
-    VerifyResponse, X402Version,
+    Base64Bytes, ExactPaymentPayload, FacilitatorErrorReason, MixedAddress, PaymentPayload,
+    PaymentRequiredResponse, PaymentRequirements, Scheme, SettleRequest, SettleResponse,
+    SupportedPaymentKind, SupportedPaymentKindExtra, SupportedPaymentKindsResponse, TokenAmount,
+    VerifyRequest, VerifyResponse, X402Version,
 };
 
 #[cfg(feature = "telemetry")]
@@ -92,6 +120,8 @@ use tracing::{Instrument, Level, instrument};
 
 use crate::facilitator_client::{FacilitatorClient, FacilitatorClientError};
 use crate::price::PriceTag;
+use crate::pricing::{DynamicPrice, Price, PricingHook};
+use crate::route_table::RouteTable;
 
 /// Middleware layer that enforces x402 payment verification and settlement.
 ///
@@ -127,6 +157,95 @@ pub struct X402Middleware<F> {
     /// - or a partial list without `resource`, in which case the resource URL will be computed dynamically per request.
     ///   In this case, please add `base_url` via [`X402Middleware::with_base_url`].
     payment_offers: Arc<PaymentOffers>,
+    /// Payment kinds supported by the facilitator, cached and shared by all clones.
+    supported: Arc<SupportedCache>,
+    /// `extra` of each payment kind the facilitator advertises per network, filled in by
+    /// [`X402Middleware::validate`].
+    supported_extra: HashMap<Network, Vec<serde_json::Value>>,
+    /// Fields shared by all payment requirements, to build them for dynamic prices.
+    offer_template: Arc<OfferTemplate>,
+    /// Hook pricing each request, overriding the fixed price tags if set.
+    dynamic_price: DynamicPrice,
+    /// Priced routes, overriding the fixed price tags and the pricing hook if set.
+    route_table: Option<Arc<RouteTable>>,
+    /// Payment offers of each route of `route_table`, in the same order.
+    route_offers: Arc<Vec<PaymentOffers>>,
+}
+
+/// Payment kinds supported by a facilitator, as returned by [`Facilitator::supported`], cached for a time-to-live.
+#[derive(Debug)]
+pub struct SupportedCache {
+    ttl: Duration,
+    cached: Mutex<Option<(Instant, Arc<SupportedPaymentKindsResponse>)>>,
+}
+
+impl SupportedCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Returns the cached payment kinds, or asks `facilitator` if they expired. Errors are not cached.
+    pub async fn get<F: Facilitator>(
+        &self,
+        facilitator: &F,
+    ) -> Result<Arc<SupportedPaymentKindsResponse>, F::Error> {
+        let cached = self
+            .cached
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            .map(|(_, supported)| supported.clone());
+        if let Some(supported) = cached {
+            return Ok(supported);
+        }
+        let supported = Arc::new(facilitator.supported().await?);
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some((Instant::now(), supported.clone()));
+        Ok(supported)
+    }
+}
+
+/// Why a price tag can not be paid through the facilitator, see [`X402Middleware::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum UnsupportedReason {
+    /// The facilitator does not support the `exact` scheme on the network of the price tag.
+    #[error("the facilitator does not support the exact scheme on this network")]
+    Network,
+    /// The facilitator does not advertise the `feePayer` a Solana payment transaction needs.
+    #[error("the facilitator does not advertise a feePayer")]
+    MissingFeePayer,
+}
+
+/// A price tag the facilitator can not settle.
+#[derive(Debug, Clone)]
+pub struct UnsupportedPriceTag {
+    pub price_tag: PriceTag,
+    pub reason: UnsupportedReason,
+}
+
+impl Display for UnsupportedPriceTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} on {}: {}",
+            self.price_tag.token.address(),
+            self.price_tag.token.network(),
+            self.reason
+        )
+    }
+}
+
+/// Error returned by [`X402Middleware::validate`].
+#[derive(Debug, thiserror::Error)]
+pub enum X402ConfigError {
+    #[error("Unable to retrieve supported payment schemes: {0}")]
+    Supported(String),
+    #[error("Price tags not supported by the facilitator: {}", .0.iter().map(|t| t.to_string()).collect::<Vec<_>>().join("; "))]
+    UnsupportedPriceTags(Vec<UnsupportedPriceTag>),
 }
 
 impl TryFrom<&str> for X402Middleware<FacilitatorClient> {
@@ -161,6 +280,20 @@ impl<F> X402Middleware<F> {
             output_schema: None,
             settle_before_execution: false,
             payment_offers: Arc::new(PaymentOffers::Ready(Arc::new(Vec::new()))),
+            supported: Arc::new(SupportedCache::new(Duration::from_secs(300))),
+            supported_extra: HashMap::new(),
+            offer_template: Arc::new(OfferTemplate {
+                resource: None,
+                base_url: Url::parse("http://localhost/").unwrap(),
+                description: String::new(),
+                mime_type: "application/json".to_string(),
+                max_timeout_seconds: 300,
+                output_schema: None,
+                supported_extra: HashMap::new(),
+            }),
+            dynamic_price: DynamicPrice::default(),
+            route_table: None,
+            route_offers: Arc::new(Vec::new()),
         }
     }
 
@@ -345,93 +478,168 @@ where
         this
     }
 
+    /// Prices every request with `hook` instead of the fixed price tags.
+    ///
+    /// If the hook returns [`Price::Free`], the request passes through without payment.
+    /// Otherwise a payment has to match one of the returned price tags, like with [`X402Middleware::with_price_tag`].
+    pub fn with_dynamic_price<H: PricingHook>(&self, hook: H) -> Self {
+        let mut this = self.clone();
+        this.dynamic_price.hook = Some(Arc::new(hook));
+        this
+    }
+
+    /// Buffers up to `limit` bytes of the request body for the pricing hook, see [`X402Middleware::with_dynamic_price`].
+    ///
+    /// Requests with a larger body are refused with `413 Payload Too Large`.
+    pub fn with_pricing_body_limit(&self, limit: usize) -> Self {
+        let mut this = self.clone();
+        this.dynamic_price.body_limit = Some(limit);
+        this
+    }
+
+    /// Prices requests by the first route of `route_table` they match, letting the others pass through.
+    ///
+    /// Meant for a single middleware applied to a whole router, it overrides the fixed price tags
+    /// and the pricing hook. Routes inherit the description, MIME type and schemas of the middleware,
+    /// unless they set their own. The resource of each request is computed from the base URL and the request path.
+    pub fn with_route_table(&self, route_table: RouteTable) -> Self {
+        let mut this = self.clone();
+        this.route_table = Some(Arc::new(route_table));
+        this.recompute_offers()
+    }
+
+    /// Sets how long the payment kinds supported by the facilitator are cached, 5 minutes by default.
+    ///
+    /// [`Duration::ZERO`] asks the facilitator on every request that needs them.
+    pub fn with_supported_ttl(&self, ttl: Duration) -> Self {
+        let mut this = self.clone();
+        this.supported = Arc::new(SupportedCache::new(ttl));
+        this
+    }
+
     fn recompute_offers(mut self) -> Self {
-        let base_url = self.base_url();
-        let description = self.description.clone().unwrap_or_default();
-        let mime_type = self
-            .mime_type
-            .clone()
-            .unwrap_or("application/json".to_string());
-        let max_timeout_seconds = self.max_timeout_seconds;
-
-        // Construct the complete output_schema from input and output schemas
-        let complete_output_schema = match (&self.input_schema, &self.output_schema) {
-            (Some(input), Some(output)) => Some(json!({
-                "input": input,
-                "output": output
-            })),
-            (Some(input), None) => Some(json!({
-                "input": input
-            })),
-            (None, Some(output)) => Some(json!({
-                "output": output
-            })),
-            (None, None) => None,
+        let offer_template = OfferTemplate {
+            resource: self.resource.clone(),
+            base_url: self.base_url(),
+            description: self.description.clone().unwrap_or_default(),
+            mime_type: self
+                .mime_type
+                .clone()
+                .unwrap_or("application/json".to_string()),
+            max_timeout_seconds: self.max_timeout_seconds,
+            output_schema: complete_output_schema(
+                self.input_schema.as_ref(),
+                self.output_schema.as_ref(),
+            ),
+            supported_extra: self.supported_extra.clone(),
         };
-
-        let payment_offers = if let Some(resource) = self.resource.clone() {
-            let payment_requirements = self
-                .price_tag
+        self.payment_offers = Arc::new(offer_template.offers(&self.price_tag));
+        if let Some(route_table) = &self.route_table {
+            let route_offers = route_table
+                .routes()
                 .iter()
-                .map(|price_tag| {
-                    let extra = if let Some(eip712) = price_tag.token.eip712.clone() {
-                        Some(json!({
-                            "name": eip712.name,
-                            "version": eip712.version
-                        }))
-                    } else {
-                        None
+                .map(|route| {
+                    let input_schema = route.input_schema.as_ref().or(self.input_schema.as_ref());
+                    let output_schema =
+                        route.output_schema.as_ref().or(self.output_schema.as_ref());
+                    let route_template = OfferTemplate {
+                        resource: None,
+                        description: route
+                            .description
+                            .clone()
+                            .unwrap_or(offer_template.description.clone()),
+                        mime_type: route
+                            .mime_type
+                            .clone()
+                            .unwrap_or(offer_template.mime_type.clone()),
+                        output_schema: complete_output_schema(input_schema, output_schema),
+                        ..offer_template.clone()
                     };
-                    PaymentRequirements {
-                        scheme: Scheme::Exact,
-                        network: price_tag.token.network(),
-                        max_amount_required: price_tag.amount,
-                        resource: resource.clone(),
-                        description: description.clone(),
-                        mime_type: mime_type.clone(),
-                        pay_to: price_tag.pay_to.clone(),
-                        max_timeout_seconds,
-                        asset: price_tag.token.address(),
-                        extra,
-                        output_schema: complete_output_schema.clone(),
-                    }
+                    route_template.offers(route.price_tags())
                 })
-                .collect::<Vec<_>>();
-            PaymentOffers::Ready(Arc::new(payment_requirements))
-        } else {
-            let no_resource = self
-                .price_tag
+                .collect();
+            self.route_offers = Arc::new(route_offers);
+        }
+        self.offer_template = Arc::new(offer_template);
+        self
+    }
+}
+
+impl<F> X402Middleware<F>
+where
+    F: Facilitator + Clone,
+{
+    /// Checks every price tag, including the ones of the route table, against the payment kinds
+    /// the facilitator supports, see [`Facilitator::supported`].
+    ///
+    /// Returns the middleware with `extra` of its payment requirements filled from the facilitator,
+    /// e.g. the `feePayer` of Solana payments, or lists the price tags the facilitator can not settle.
+    /// A price tag is offered once per transfer method the facilitator supports for its network,
+    /// e.g. through ERC-3009 and through an EIP-2612 permit.
+    /// Call it once configured, typically at startup: price tags added afterwards are not checked.
+    pub async fn validate(&self) -> Result<Self, X402ConfigError> {
+        let supported = self
+            .supported
+            .get(self.facilitator.as_ref())
+            .await
+            .map_err(|e| X402ConfigError::Supported(e.to_string()))?;
+        let mut unsupported = Vec::new();
+        let mut supported_extra = HashMap::new();
+        let route_price_tags = self
+            .route_table
+            .iter()
+            .flat_map(|route_table| route_table.routes())
+            .flat_map(|route| route.price_tags());
+        let mut seen = HashSet::new();
+        for price_tag in self.price_tag.iter().chain(route_price_tags) {
+            if !seen.insert(price_tag) {
+                continue;
+            }
+            let network = price_tag.token.network();
+            let is_solana = matches!(NetworkFamily::from(network), NetworkFamily::Solana);
+            let mut methods = HashSet::new();
+            let kinds = supported
+                .kinds
                 .iter()
-                .map(|price_tag| {
-                    let extra = if let Some(eip712) = price_tag.token.eip712.clone() {
-                        Some(json!({
-                            "name": eip712.name,
-                            "version": eip712.version
-                        }))
-                    } else {
-                        None
-                    };
-                    PaymentRequirementsNoResource {
-                        scheme: Scheme::Exact,
-                        network: price_tag.token.network(),
-                        max_amount_required: price_tag.amount,
-                        description: description.clone(),
-                        mime_type: mime_type.clone(),
-                        pay_to: price_tag.pay_to.clone(),
-                        max_timeout_seconds,
-                        asset: price_tag.token.address(),
-                        extra,
-                        output_schema: complete_output_schema.clone(),
-                    }
-                })
+                .filter(|k| k.scheme == Scheme::Exact && k.network == network.to_string())
+                // A Solana payment can not be built without the fee payer
+                .filter(|k| !is_solana || k.extra.is_some())
+                // One offer per transfer method, from the first kind advertising it
+                .filter(|k| methods.insert(asset_transfer_method(k)))
                 .collect::<Vec<_>>();
-            PaymentOffers::NoResource {
-                partial: no_resource,
-                base_url,
+            let reason = if !kinds.is_empty() {
+                let extras = kinds
+                    .iter()
+                    .map(|kind| match &kind.extra {
+                        Some(extra) => offered_extra(network, extra),
+                        None => json!({}),
+                    })
+                    .collect();
+                supported_extra.insert(network, extras);
+                None
+            } else if is_solana
+                && supported
+                    .kinds
+                    .iter()
+                    .any(|k| k.scheme == Scheme::Exact && k.network == network.to_string())
+            {
+                Some(UnsupportedReason::MissingFeePayer)
+            } else {
+                Some(UnsupportedReason::Network)
+            };
+            if let Some(reason) = reason {
+                unsupported.push(UnsupportedPriceTag {
+                    price_tag: price_tag.clone(),
+                    reason,
+                });
             }
-        };
-        self.payment_offers = Arc::new(payment_offers);
-        self
+        }
+        if !unsupported.is_empty() {
+            return Err(X402ConfigError::UnsupportedPriceTags(unsupported));
+        }
+        let mut this = self.clone();
+        this.supported_extra = supported_extra;
+        Ok(this.recompute_offers())
     }
 }
 
@@ -448,6 +656,16 @@ pub struct X402MiddlewareService<F> {
     facilitator: Arc<F>,
     /// Payment requirements either with static or dynamic resource URLs
     payment_offers: Arc<PaymentOffers>,
+    /// Payment kinds supported by the facilitator, cached
+    supported: Arc<SupportedCache>,
+    /// Fields shared by all payment requirements, for dynamic prices
+    offer_template: Arc<OfferTemplate>,
+    /// Hook pricing each request, if any
+    dynamic_price: DynamicPrice,
+    /// Priced routes, if any
+    route_table: Option<Arc<RouteTable>>,
+    /// Payment offers of each priced route
+    route_offers: Arc<Vec<PaymentOffers>>,
     /// Whether to settle payment before executing the request (true) or after (false)
     settle_before_execution: bool,
     /// The inner Axum service being wrapped
@@ -472,6 +690,11 @@ where
         X402MiddlewareService {
             facilitator: self.facilitator.clone(),
             payment_offers: self.payment_offers.clone(),
+            supported: self.supported.clone(),
+            offer_template: self.offer_template.clone(),
+            dynamic_price: self.dynamic_price.clone(),
+            route_table: self.route_table.clone(),
+            route_offers: self.route_offers.clone(),
             settle_before_execution: self.settle_before_execution,
             inner: BoxCloneSyncService::new(inner),
         }
@@ -493,15 +716,49 @@ where
 
     /// Intercepts the request, injects payment enforcement logic, and forwards to the wrapped service.
     fn call(&mut self, req: Request) -> Self::Future {
-        let payment_requirements =
-            gather_payment_requirements(self.payment_offers.as_ref(), req.uri());
-        let gate = X402Paygate {
+        let mut inner = self.inner.clone();
+        if let Some(route_table) = &self.route_table {
+            return match route_table.position(req.method(), req.uri().path()) {
+                Some(index) => {
+                    let payment_requirements =
+                        gather_payment_requirements(&self.route_offers[index], req.uri());
+                    Box::pin(self.paygate(payment_requirements).call(inner, req))
+                }
+                None => Box::pin(async move { inner.call(req).await }),
+            };
+        }
+        let Some(hook) = self.dynamic_price.hook.clone() else {
+            let payment_requirements =
+                gather_payment_requirements(self.payment_offers.as_ref(), req.uri());
+            return Box::pin(self.paygate(payment_requirements).call(inner, req));
+        };
+        let this = self.clone();
+        Box::pin(async move {
+            let (req, price) = match this.dynamic_price.price(hook.as_ref(), req).await {
+                Ok(priced) => priced,
+                Err(response) => return Ok(response),
+            };
+            match price {
+                Price::Free => inner.call(req).await,
+                Price::Tags(price_tags) => {
+                    let payment_offers = this.offer_template.offers(&price_tags);
+                    let payment_requirements =
+                        gather_payment_requirements(&payment_offers, req.uri());
+                    this.paygate(payment_requirements).call(inner, req).await
+                }
+            }
+        })
+    }
+}
+
+impl<F> X402MiddlewareService<F> {
+    fn paygate(&self, payment_requirements: Arc<Vec<PaymentRequirements>>) -> X402Paygate<F> {
+        X402Paygate {
             facilitator: self.facilitator.clone(),
             payment_requirements,
+            supported: self.supported.clone(),
             settle_before_execution: self.settle_before_execution,
-        };
-        let inner = self.inner.clone();
-        Box::pin(gate.call(inner, req))
+        }
     }
 }
 
@@ -597,6 +854,7 @@ impl IntoResponse for X402Error {
 pub struct X402Paygate<F> {
     pub facilitator: Arc<F>,
     pub payment_requirements: Arc<Vec<PaymentRequirements>>,
+    pub supported: Arc<SupportedCache>,
     pub settle_before_execution: bool,
 }
 
@@ -610,13 +868,17 @@ where
         headers: &HeaderMap,
     ) -> Result<PaymentPayload, X402Error> {
         let payment_header = headers.get("X-Payment");
-        let supported = self.facilitator.supported().await.map_err(|e| {
-            X402Error(PaymentRequiredResponse {
-                x402_version: X402Version::V1,
-                error: format!("Unable to retrieve supported payment schemes: {e}"),
-                accepts: vec![],
-            })
-        })?;
+        let supported = self
+            .supported
+            .get(self.facilitator.as_ref())
+            .await
+            .map_err(|e| {
+                X402Error(PaymentRequiredResponse {
+                    x402_version: X402Version::V1,
+                    error: format!("Unable to retrieve supported payment schemes: {e}"),
+                    accepts: vec![],
+                })
+            })?;
         match payment_header {
             None => {
                 let requirements = self
@@ -626,20 +888,22 @@ where
                     .map(|r| {
                         let mut r = r.clone();
                         let network = r.network;
+                        let method = requirements_transfer_method(&r).map(str::to_owned);
                         let extra = supported
                             .kinds
                             .iter()
-                            .find(|s| s.network == network.to_string())
-                            .cloned()
-                            .and_then(|s| s.extra);
+                            .find(|s| {
+                                s.scheme == r.scheme
+                                    && s.network == network.to_string()
+                                    && asset_transfer_method(s) == method.as_deref()
+                            })
+                            .and_then(|s| s.extra.as_ref())
+                            .map(|extra| offered_extra(network, extra));
                         if let Some(extra) = extra {
-                            r.extra = Some(json!({
-                                "feePayer": extra.fee_payer
-                            }));
-                            r
-                        } else {
-                            r
+                            // Carries `feePayer` plus any facilitator caps the client has to honour
+                            r.extra = merge_supported_extra(r.extra.take(), &extra);
                         }
+                        r
                     })
                     .collect::<Vec<_>>();
                 Err(X402Error::payment_header_required(requirements))
@@ -657,16 +921,22 @@ where
         }
     }
 
-    /// Finds the payment requirement entry matching the given payload's scheme and network.
+    /// Finds the payment requirement entry matching the given payload's scheme, network and
+    /// transfer method.
     fn find_matching_payment_requirements(
         &self,
         payment_payload: &PaymentPayload,
     ) -> Option<PaymentRequirements> {
+        let method = match payment_payload.payload {
+            ExactPaymentPayload::EvmPermit(_) => Some(ASSET_TRANSFER_METHOD_PERMIT),
+            ExactPaymentPayload::Evm(_) | ExactPaymentPayload::Solana(_) => None,
+        };
         self.payment_requirements
             .iter()
             .find(|requirement| {
                 requirement.scheme == payment_payload.scheme
                     && requirement.network == payment_payload.network
+                    && requirements_transfer_method(requirement) == method
             })
             .cloned()
     }
@@ -935,6 +1205,163 @@ pub enum PaymentOffers {
     },
 }
 
+/// `outputSchema` of payment requirements, combining the input and output schemas of an endpoint.
+fn complete_output_schema(
+    input_schema: Option<&serde_json::Value>,
+    output_schema: Option<&serde_json::Value>,
+) -> Option<serde_json::Value> {
+    match (input_schema, output_schema) {
+        (Some(input), Some(output)) => Some(json!({
+            "input": input,
+            "output": output
+        })),
+        (Some(input), None) => Some(json!({
+            "input": input
+        })),
+        (None, Some(output)) => Some(json!({
+            "output": output
+        })),
+        (None, None) => None,
+    }
+}
+
+/// Transfer method of a supported payment kind, `None` for the default, e.g. ERC-3009 on EVM.
+fn asset_transfer_method(kind: &SupportedPaymentKind) -> Option<&str> {
+    kind.extra
+        .as_ref()
+        .and_then(|extra| extra.asset_transfer_method.as_deref())
+}
+
+/// Transfer method payment requirements were offered for, see [`asset_transfer_method`].
+fn requirements_transfer_method(requirements: &PaymentRequirements) -> Option<&str> {
+    requirements
+        .extra
+        .as_ref()
+        .and_then(|extra| extra.get("assetTransferMethod"))
+        .and_then(serde_json::Value::as_str)
+}
+
+/// Fields of the `extra` a facilitator advertises for a payment kind that an offer of it needs.
+///
+/// A Solana buyer builds the transaction, so it needs the fee payer and the compute budget caps.
+/// An EVM payment is submitted by the facilitator: the fee payer is left out, and only a permit
+/// offer names its transfer method and spender. Every offer carries the facilitator fee, if any.
+fn offered_extra(network: Network, extra: &SupportedPaymentKindExtra) -> serde_json::Value {
+    let fields: &[&str] = match NetworkFamily::from(network) {
+        NetworkFamily::Solana => &[
+            "feePayer",
+            "maxComputeUnitPrice",
+            "maxComputeUnitLimit",
+            "maxLamportsPerTransaction",
+            "fee",
+        ],
+        NetworkFamily::Evm if extra.asset_transfer_method.is_some() => {
+            &["fee", "assetTransferMethod", "spender"]
+        }
+        NetworkFamily::Evm => &["fee"],
+    };
+    match serde_json::to_value(extra) {
+        Ok(serde_json::Value::Object(mut extra)) => {
+            extra.retain(|key, _| fields.contains(&key.as_str()));
+            serde_json::Value::Object(extra)
+        }
+        _ => json!({}),
+    }
+}
+
+/// Adds the fields of the `extra` a facilitator advertises for a payment kind, e.g. `feePayer`
+/// or `fee`, see [`offered_extra`], to the `extra` of a token. Fields of the token, like its
+/// EIP-712 `name` and `version`, are kept.
+fn merge_supported_extra(
+    extra: Option<serde_json::Value>,
+    supported_extra: &serde_json::Value,
+) -> Option<serde_json::Value> {
+    let serde_json::Value::Object(supported_extra) = supported_extra else {
+        return extra;
+    };
+    if supported_extra.is_empty() {
+        return extra;
+    }
+    let mut extra = match extra {
+        Some(serde_json::Value::Object(extra)) => extra,
+        None => serde_json::Map::new(),
+        Some(other) => return Some(other),
+    };
+    for (key, value) in supported_extra {
+        extra.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+    Some(serde_json::Value::Object(extra))
+}
+
+/// Fields of [`PaymentRequirements`] shared by every price tag of a middleware.
+#[derive(Clone, Debug)]
+struct OfferTemplate {
+    resource: Option<Url>,
+    base_url: Url,
+    description: String,
+    mime_type: String,
+    max_timeout_seconds: u64,
+    output_schema: Option<serde_json::Value>,
+    /// `extra` of each payment kind the facilitator advertises per network.
+    supported_extra: HashMap<Network, Vec<serde_json::Value>>,
+}
+
+impl OfferTemplate {
+    /// `extra` of each payment requirements offered for `price_tag`: the EIP-712 domain of the
+    /// token, merged with what the facilitator advertises for each payment kind of the network.
+    /// A single offer with the token `extra` only if the payment kinds are not known.
+    fn extras(&self, price_tag: &PriceTag) -> Vec<Option<serde_json::Value>> {
+        let extra = price_tag.token.eip712.as_ref().map(|eip712| {
+            json!({
+                "name": eip712.name,
+                "version": eip712.version
+            })
+        });
+        match self.supported_extra.get(&price_tag.token.network()) {
+            Some(supported_extras) if !supported_extras.is_empty() => supported_extras
+                .iter()
+                .map(|supported_extra| merge_supported_extra(extra.clone(), supported_extra))
+                .collect(),
+            _ => vec![extra],
+        }
+    }
+
+    /// Payment offers for `price_tags`, with a static resource if one is set.
+    fn offers(&self, price_tags: &[PriceTag]) -> PaymentOffers {
+        let no_resource = price_tags
+            .iter()
+            .flat_map(|price_tag| {
+                self.extras(price_tag)
+                    .into_iter()
+                    .map(move |extra| PaymentRequirementsNoResource {
+                        scheme: Scheme::Exact,
+                        network: price_tag.token.network(),
+                        max_amount_required: price_tag.amount,
+                        description: self.description.clone(),
+                        mime_type: self.mime_type.clone(),
+                        pay_to: price_tag.pay_to.clone(),
+                        max_timeout_seconds: self.max_timeout_seconds,
+                        asset: price_tag.token.address(),
+                        extra,
+                        output_schema: self.output_schema.clone(),
+                    })
+            })
+            .collect::<Vec<_>>();
+        match &self.resource {
+            Some(resource) => PaymentOffers::Ready(Arc::new(
+                no_resource
+                    .iter()
+                    .map(|partial| partial.to_payment_requirements(resource.clone()))
+                    .collect(),
+            )),
+            None => PaymentOffers::NoResource {
+                partial: no_resource,
+                base_url: self.base_url.clone(),
+            },
+        }
+    }
+}
+
 /// Constructs a full list of [`PaymentRequirements`] for a request.
 ///
 /// This function returns a shared, reference-counted vector of [`PaymentRequirements`]
@@ -37,10 +37,14 @@
 //! To define price tags for your protected routes, see the [`price`] module.
 //! It provides builder-style helpers like [`IntoPriceTag`] and types like [`PriceTag`]
 //! for working with tokens, networks, and payment amounts.
+//! For prices decided per request, see the [`pricing`] module,
+//! and to price many routes from a single layer, the [`route_table`] module.
 
 pub mod facilitator_client;
 pub mod layer;
 pub mod price;
+pub mod pricing;
+pub mod route_table;
 
 pub use layer::X402Middleware;
 pub use price::*;
@@ -0,0 +1,171 @@
+//! Per-request pricing for [`crate::X402Middleware`].
+//!
+//! A [`PricingHook`] decides the price of each request when it arrives, from its method, path,
+//! query, headers and, if enabled with [`crate::X402Middleware::with_pricing_body_limit`], its body.
+//! Any async closure taking a [`PricingRequest`] and returning a [`Price`] is a hook:
+//!
+//! ```rust,ignore
+//! use x402_axum::pricing::{Price, PricingRequest};
+//!
+//! let x402 = X402Middleware::try_from("https://facilitator.example.com/")?.with_dynamic_price(
+//!     move |request: PricingRequest| {
+//!         let usdc = usdc.clone();
+//!         async move {
+//!             match request.query_param("model").as_deref() {
+//!                 Some("small") => Price::Free,
+//!                 Some("large") => usdc.amount("0.10").unwrap().into(),
+//!                 _ => usdc.amount("0.01").unwrap().into(),
+//!             }
+//!         }
+//!     },
+//! );
+//! ```
+//!
+//! The price tags returned by the hook are turned into [`x402_rs::types::PaymentRequirements`]
+//! with the description, MIME type, resource and schemas configured on the middleware,
+//! and a payment is accepted only if it matches one of them.
+
+use axum_core::body::Body;
+use axum_core::extract::Request;
+use axum_core::response::{IntoResponse, Response};
+use bytes::Bytes;
+use http::{HeaderMap, Method, StatusCode, Uri};
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::price::PriceTag;
+
+/// What a [`PricingHook`] knows of a request.
+#[derive(Clone, Debug)]
+pub struct PricingRequest {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    /// Request body, if buffering is enabled with [`crate::X402Middleware::with_pricing_body_limit`].
+    pub body: Option<Bytes>,
+}
+
+impl PricingRequest {
+    /// Path of the request, e.g. `/weather`.
+    pub fn path(&self) -> &str {
+        self.uri.path()
+    }
+
+    /// Raw query string of the request, without the leading `?`.
+    pub fn query(&self) -> Option<&str> {
+        self.uri.query()
+    }
+
+    /// Decoded value of the first query parameter called `name`.
+    pub fn query_param(&self, name: &str) -> Option<String> {
+        url::form_urlencoded::parse(self.query()?.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    /// Value of the header `name`, if present and valid UTF-8.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
+}
+
+/// Price of a request, as decided by a [`PricingHook`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Price {
+    /// The request passes through without payment.
+    Free,
+    /// The request has to be paid with one of the price tags.
+    Tags(Vec<PriceTag>),
+}
+
+impl From<PriceTag> for Price {
+    fn from(value: PriceTag) -> Self {
+        Price::Tags(vec![value])
+    }
+}
+
+impl From<Vec<PriceTag>> for Price {
+    fn from(value: Vec<PriceTag>) -> Self {
+        Price::Tags(value)
+    }
+}
+
+/// Decides the [`Price`] of a request. Implemented by async closures `Fn(PricingRequest) -> impl Future<Output = Price>`.
+pub trait PricingHook: Send + Sync + 'static {
+    fn price(&self, request: PricingRequest) -> impl Future<Output = Price> + Send;
+}
+
+impl<F, Fut> PricingHook for F
+where
+    F: Fn(PricingRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Price> + Send,
+{
+    fn price(&self, request: PricingRequest) -> impl Future<Output = Price> + Send {
+        self(request)
+    }
+}
+
+/// Object-safe [`PricingHook`], so that the middleware does not have to be generic over the hook.
+pub(crate) trait DynPricingHook: Send + Sync {
+    fn price(&self, request: PricingRequest) -> Pin<Box<dyn Future<Output = Price> + Send + '_>>;
+}
+
+impl<H: PricingHook> DynPricingHook for H {
+    fn price(&self, request: PricingRequest) -> Pin<Box<dyn Future<Output = Price> + Send + '_>> {
+        Box::pin(PricingHook::price(self, request))
+    }
+}
+
+/// Pricing hook of a middleware, if any, and how much of the body it may see.
+#[derive(Clone, Default)]
+pub(crate) struct DynamicPrice {
+    pub(crate) hook: Option<Arc<dyn DynPricingHook>>,
+    pub(crate) body_limit: Option<usize>,
+}
+
+impl Debug for DynamicPrice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicPrice")
+            .field("hook", &self.hook.as_ref().map(|_| "PricingHook"))
+            .field("body_limit", &self.body_limit)
+            .finish()
+    }
+}
+
+impl DynamicPrice {
+    /// Prices `request` with `hook`, buffering its body first if enabled.
+    ///
+    /// Returns the request to pass on, with its body restored, or the response to send back
+    /// if the body could not be buffered: `413 Payload Too Large` if it exceeds the limit.
+    pub(crate) async fn price(
+        &self,
+        hook: &dyn DynPricingHook,
+        request: Request,
+    ) -> Result<(Request, Price), Response> {
+        let (parts, body) = request.into_parts();
+        let (body, buffered) = match self.body_limit {
+            None => (body, None),
+            Some(limit) => match Limited::new(body, limit).collect().await {
+                Ok(collected) => {
+                    let bytes = collected.to_bytes();
+                    (Body::from(bytes.clone()), Some(bytes))
+                }
+                Err(e) if e.is::<LengthLimitError>() => {
+                    return Err(StatusCode::PAYLOAD_TOO_LARGE.into_response());
+                }
+                Err(_) => return Err(StatusCode::BAD_REQUEST.into_response()),
+            },
+        };
+        let pricing_request = PricingRequest {
+            method: parts.method.clone(),
+            uri: parts.uri.clone(),
+            headers: parts.headers.clone(),
+            body: buffered,
+        };
+        let price = hook.price(pricing_request).await;
+        Ok((Request::from_parts(parts, body), price))
+    }
+}
@@ -0,0 +1,358 @@
+//! Prices of many routes, enforced by a single [`crate::X402Middleware`] layer.
+//!
+//! A [`RouteTable`] maps path patterns and HTTP methods to price tags, descriptions and schemas.
+//! Set it with [`crate::X402Middleware::with_route_table`] and apply the middleware once, to the whole router:
+//! requests matching a route have to pay its price, the others pass through.
+//!
+//! Path patterns use the Axum syntax: `{name}` matches a single segment and `{*name}`, only last,
+//! matches the rest of the path. Routes are tried in order, the first match wins.
+//!
+//! ```rust,ignore
+//! use x402_axum::route_table::RouteTable;
+//!
+//! let routes = RouteTable::from_file("pricing.json")?;
+//! let app = Router::new()
+//!     .route("/weather/{city}", get(weather))
+//!     .route("/health", get(health))
+//!     .layer(x402.with_route_table(routes));
+//! ```
+//!
+//! ## Configuration File
+//!
+//! ```json
+//! {
+//!   "routes": [
+//!     {
+//!       "path": "/weather/{city}",
+//!       "methods": ["GET"],
+//!       "description": "Weather forecast",
+//!       "outputSchema": { "type": "object" },
+//!       "accepts": [
+//!         { "network": "base", "payTo": "0xYourAddress", "amount": "0.01" },
+//!         {
+//!           "network": "base",
+//!           "payTo": "0xYourAddress",
+//!           "tokenAmount": "10000000000000000",
+//!           "asset": { "address": "0xTokenAddress", "decimals": 18, "eip712": { "name": "Token", "version": "1" } }
+//!         }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `methods` is optional, and matches any method when missing. An entry of `accepts` is paid in USDC
+//! unless it names an `asset`, and is priced either by a human `amount` or a `tokenAmount` in base units.
+
+use http::Method;
+use serde::Deserialize;
+use std::path::Path;
+use x402_rs::network::{Network, USDCDeployment};
+use x402_rs::types::{
+    MixedAddress, MoneyAmount, TokenAmount, TokenAsset, TokenDeployment, TokenDeploymentEip712,
+};
+
+use crate::price::PriceTag;
+
+/// Errors building or loading a [`RouteTable`].
+#[derive(Debug, thiserror::Error)]
+pub enum RouteTableError {
+    #[error("Invalid path pattern {pattern:?}: {reason}")]
+    InvalidPattern {
+        pattern: String,
+        reason: &'static str,
+    },
+    #[error("Invalid price of route {path:?}: {reason}")]
+    InvalidPrice { path: String, reason: String },
+    #[error("Can not read route table: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid route table: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    /// `{name}`: any single segment.
+    Param,
+    /// `{*name}`: the rest of the path, possibly empty.
+    CatchAll,
+}
+
+/// A parsed path pattern, e.g. `/weather/{city}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct PathPattern(Vec<Segment>);
+
+impl PathPattern {
+    fn parse(pattern: &str) -> Result<Self, RouteTableError> {
+        let invalid = |reason| RouteTableError::InvalidPattern {
+            pattern: pattern.to_string(),
+            reason,
+        };
+        let path = pattern
+            .strip_prefix('/')
+            .ok_or_else(|| invalid("must start with /"))?;
+        let mut segments = Vec::new();
+        for segment in path.split('/') {
+            if segments.last() == Some(&Segment::CatchAll) {
+                return Err(invalid("{*name} must be the last segment"));
+            }
+            let parsed = match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                Some(name) if name.starts_with('*') && name.len() > 1 => Segment::CatchAll,
+                Some(name) if !name.is_empty() && !name.starts_with('*') => Segment::Param,
+                Some(_) => return Err(invalid("parameters need a name")),
+                None if segment.contains(['{', '}']) => {
+                    return Err(invalid("parameters must span a whole segment"));
+                }
+                None => Segment::Literal(segment.to_string()),
+            };
+            segments.push(parsed);
+        }
+        Ok(Self(segments))
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let Some(path) = path.strip_prefix('/') else {
+            return false;
+        };
+        let mut parts = path.split('/');
+        for segment in &self.0 {
+            match segment {
+                Segment::CatchAll => return true,
+                Segment::Param => match parts.next() {
+                    Some(part) if !part.is_empty() => {}
+                    _ => return false,
+                },
+                Segment::Literal(literal) => {
+                    if parts.next() != Some(literal.as_str()) {
+                        return false;
+                    }
+                }
+            }
+        }
+        parts.next().is_none()
+    }
+}
+
+/// Price, description and schemas of the requests matching a path pattern and, optionally, methods.
+#[derive(Clone, Debug)]
+pub struct PricedRoute {
+    path: String,
+    pattern: PathPattern,
+    methods: Vec<Method>,
+    pub(crate) price_tags: Vec<PriceTag>,
+    pub(crate) description: Option<String>,
+    pub(crate) mime_type: Option<String>,
+    pub(crate) input_schema: Option<serde_json::Value>,
+    pub(crate) output_schema: Option<serde_json::Value>,
+}
+
+impl PricedRoute {
+    /// Creates a route priced at `price_tag` for any method. Fails if `path` is not a valid pattern.
+    pub fn new<T: Into<Vec<PriceTag>>>(path: &str, price_tag: T) -> Result<Self, RouteTableError> {
+        Ok(Self {
+            path: path.to_string(),
+            pattern: PathPattern::parse(path)?,
+            methods: Vec::new(),
+            price_tags: price_tag.into(),
+            description: None,
+            mime_type: None,
+            input_schema: None,
+            output_schema: None,
+        })
+    }
+
+    /// Restricts the route to `methods`.
+    pub fn with_methods<I: IntoIterator<Item = Method>>(mut self, methods: I) -> Self {
+        self.methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Sets the description of the route, overriding [`crate::X402Middleware::with_description`].
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Sets the MIME type of the route, overriding [`crate::X402Middleware::with_mime_type`].
+    pub fn with_mime_type(mut self, mime_type: &str) -> Self {
+        self.mime_type = Some(mime_type.to_string());
+        self
+    }
+
+    /// Sets the input schema of the route, overriding [`crate::X402Middleware::with_input_schema`].
+    pub fn with_input_schema(mut self, schema: serde_json::Value) -> Self {
+        self.input_schema = Some(schema);
+        self
+    }
+
+    /// Sets the output schema of the route, overriding [`crate::X402Middleware::with_output_schema`].
+    pub fn with_output_schema(mut self, schema: serde_json::Value) -> Self {
+        self.output_schema = Some(schema);
+        self
+    }
+
+    /// Path pattern of the route, as given.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn price_tags(&self) -> &[PriceTag] {
+        &self.price_tags
+    }
+
+    /// Whether a request with `method` to `path` has to pay the price of this route.
+    pub fn matches(&self, method: &Method, path: &str) -> bool {
+        (self.methods.is_empty() || self.methods.contains(method)) && self.pattern.matches(path)
+    }
+}
+
+/// Priced routes, tried in order. See the [module documentation](self).
+#[derive(Clone, Debug, Default)]
+pub struct RouteTable {
+    routes: Vec<PricedRoute>,
+}
+
+impl RouteTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a route, tried after the ones added before it.
+    pub fn with_route(mut self, route: PricedRoute) -> Self {
+        self.routes.push(route);
+        self
+    }
+
+    pub fn routes(&self) -> &[PricedRoute] {
+        &self.routes
+    }
+
+    /// Index of the first route matching a request with `method` to `path`.
+    pub(crate) fn position(&self, method: &Method, path: &str) -> Option<usize> {
+        self.routes
+            .iter()
+            .position(|route| route.matches(method, path))
+    }
+
+    /// First route matching a request with `method` to `path`.
+    pub fn find(&self, method: &Method, path: &str) -> Option<&PricedRoute> {
+        self.position(method, path).map(|index| &self.routes[index])
+    }
+
+    /// Parses a route table from its JSON configuration, see the [module documentation](self).
+    pub fn from_json(json: &str) -> Result<Self, RouteTableError> {
+        let config: RouteTableConfig = serde_json::from_str(json)?;
+        config
+            .routes
+            .into_iter()
+            .try_fold(Self::new(), |table, route| {
+                Ok(table.with_route(route.into_priced_route()?))
+            })
+    }
+
+    /// Reads a route table from a JSON configuration file, see the [module documentation](self).
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, RouteTableError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RouteTableConfig {
+    routes: Vec<RouteConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RouteConfig {
+    path: String,
+    #[serde(default)]
+    methods: Vec<String>,
+    description: Option<String>,
+    mime_type: Option<String>,
+    input_schema: Option<serde_json::Value>,
+    output_schema: Option<serde_json::Value>,
+    accepts: Vec<PriceTagConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct PriceTagConfig {
+    network: Network,
+    pay_to: MixedAddress,
+    amount: Option<String>,
+    token_amount: Option<TokenAmount>,
+    asset: Option<AssetConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct AssetConfig {
+    address: MixedAddress,
+    decimals: u8,
+    eip712: Option<Eip712Config>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Eip712Config {
+    name: String,
+    version: String,
+}
+
+impl RouteConfig {
+    fn into_priced_route(self) -> Result<PricedRoute, RouteTableError> {
+        let invalid = |reason: String| RouteTableError::InvalidPrice {
+            path: self.path.clone(),
+            reason,
+        };
+        let methods = self
+            .methods
+            .iter()
+            .map(|method| {
+                Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                    .map_err(|_| invalid(format!("invalid method {method:?}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let price_tags = self
+            .accepts
+            .into_iter()
+            .map(|accept| accept.into_price_tag().map_err(invalid))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut route = PricedRoute::new(&self.path, price_tags)?.with_methods(methods);
+        route.description = self.description;
+        route.mime_type = self.mime_type;
+        route.input_schema = self.input_schema;
+        route.output_schema = self.output_schema;
+        Ok(route)
+    }
+}
+
+impl PriceTagConfig {
+    fn into_price_tag(self) -> Result<PriceTag, String> {
+        let token = match self.asset {
+            Some(asset) => TokenDeployment {
+                asset: TokenAsset {
+                    address: asset.address,
+                    network: self.network,
+                },
+                decimals: asset.decimals,
+                eip712: asset.eip712.map(|eip712| TokenDeploymentEip712 {
+                    name: eip712.name,
+                    version: eip712.version,
+                }),
+            },
+            None => USDCDeployment::by_network(self.network).0.clone(),
+        };
+        let amount = match (self.amount, self.token_amount) {
+            (Some(amount), None) => MoneyAmount::parse(&amount)
+                .map_err(|e| format!("invalid amount {amount:?}: {e}"))?
+                .as_token_amount(token.decimals as u32)
+                .map_err(|e| format!("invalid amount {amount:?}: {e}"))?,
+            (None, Some(token_amount)) => token_amount,
+            _ => return Err("exactly one of amount and tokenAmount must be set".to_string()),
+        };
+        Ok(PriceTag::new(self.pay_to, amount, token))
+    }
+}
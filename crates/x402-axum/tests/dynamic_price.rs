@@ -0,0 +1,156 @@
+//! Per-request prices decided by a pricing hook, paid through a [`MockFacilitator`].
+
+use axum::Router;
+use axum::body::{Body, to_bytes};
+use axum::routing::{get, post};
+use http::{Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+use x402_axum::pricing::{Price, PricingRequest};
+use x402_axum::{IntoPriceTag, PriceTag, X402Middleware};
+use x402_rs::facilitator_mock::MockFacilitator;
+use x402_rs::network::{Network, USDCDeployment};
+use x402_rs::types::{Base64Bytes, EvmAddress, PaymentRequiredResponse};
+
+const PAY_TO: &str = "0x000000000000000000000000000000000000bEEF";
+
+fn price_tag(amount: &str) -> PriceTag {
+    let pay_to: EvmAddress = PAY_TO.parse().unwrap();
+    USDCDeployment::by_network(Network::BaseSepolia)
+        .pay_to(pay_to)
+        .amount(amount)
+        .unwrap()
+}
+
+/// Free for the `pro` tier, else priced by the `model` query parameter.
+async fn price(request: PricingRequest) -> Price {
+    if request.header("X-Tier") == Some("pro") {
+        return Price::Free;
+    }
+    match request.query_param("model").as_deref() {
+        Some("large") => price_tag("0.002").into(),
+        _ => price_tag("0.001").into(),
+    }
+}
+
+fn app(facilitator: MockFacilitator) -> Router {
+    let x402 = X402Middleware::new(facilitator)
+        .with_base_url("https://example.com/".parse().unwrap())
+        .with_dynamic_price(price);
+    Router::new().route("/generate", get(|| async { "generated" }).layer(x402))
+}
+
+fn payment_header(value: &str) -> String {
+    let payload = json!({
+        "x402Version": 1,
+        "scheme": "exact",
+        "network": "base-sepolia",
+        "payload": {
+            "signature": format!("0x{}", "11".repeat(65)),
+            "authorization": {
+                "from": "0x0000000000000000000000000000000000000037",
+                "to": PAY_TO,
+                "value": value,
+                "validAfter": "0",
+                "validBefore": "4102444800",
+                "nonce": format!("0x{}", "22".repeat(32)),
+            }
+        }
+    });
+    let encoded = Base64Bytes::encode(serde_json::to_vec(&payload).unwrap());
+    String::from_utf8(encoded.as_ref().to_vec()).unwrap()
+}
+
+async fn payment_required(response: http::Response<Body>) -> PaymentRequiredResponse {
+    assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn price_depends_on_the_request() {
+    let facilitator = MockFacilitator::new();
+    let app = app(facilitator.clone());
+
+    let request = Request::get("/generate?model=large").body(Body::empty());
+    let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+    let accepts = payment_required(response).await.accepts;
+    assert_eq!(accepts[0].max_amount_required.to_string(), "2000");
+    assert_eq!(
+        accepts[0].resource.as_str(),
+        "https://example.com/generate?model=large"
+    );
+
+    let request = Request::get("/generate?model=small").body(Body::empty());
+    let response = app.oneshot(request.unwrap()).await.unwrap();
+    let accepts = payment_required(response).await.accepts;
+    assert_eq!(accepts[0].max_amount_required.to_string(), "1000");
+    facilitator.assert_verify_count(0);
+}
+
+#[tokio::test]
+async fn free_requests_pass_through() {
+    let facilitator = MockFacilitator::new();
+    let request = Request::get("/generate")
+        .header("X-Tier", "pro")
+        .body(Body::empty())
+        .unwrap();
+    let response = app(facilitator.clone()).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response.headers().contains_key("X-Payment-Response"));
+    assert!(facilitator.calls().is_empty());
+}
+
+#[tokio::test]
+async fn payments_are_matched_against_the_dynamic_price() {
+    let facilitator = MockFacilitator::new();
+    let request = Request::get("/generate?model=large")
+        .header("X-Payment", payment_header("2000"))
+        .body(Body::empty())
+        .unwrap();
+    let response = app(facilitator.clone()).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("X-Payment-Response"));
+    let verified = facilitator.verify_requests();
+    assert_eq!(
+        verified[0]
+            .payment_requirements
+            .max_amount_required
+            .to_string(),
+        "2000"
+    );
+    facilitator.assert_settle_count(1);
+}
+
+#[tokio::test]
+async fn body_is_buffered_for_pricing() {
+    let x402 = X402Middleware::new(MockFacilitator::new())
+        .with_dynamic_price(|request: PricingRequest| async move {
+            let body: serde_json::Value = serde_json::from_slice(&request.body.unwrap()).unwrap();
+            if body["tokens"].as_u64().unwrap() <= 100 {
+                Price::Free
+            } else {
+                price_tag("0.001").into()
+            }
+        })
+        .with_pricing_body_limit(1024);
+    let app = Router::new().route(
+        "/generate",
+        post(|body: String| async move { body }).layer(x402),
+    );
+
+    let request = Request::post("/generate").body(Body::from(r#"{"tokens":10}"#));
+    let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    // The handler still receives the body
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, r#"{"tokens":10}"#);
+
+    let request = Request::post("/generate").body(Body::from(r#"{"tokens":1000}"#));
+    let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+
+    let request = Request::post("/generate").body(Body::from(vec![b' '; 2048]));
+    let response = app.oneshot(request.unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
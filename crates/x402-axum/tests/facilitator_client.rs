@@ -0,0 +1,227 @@
+//! Retries and circuit breaking of [`FacilitatorClient`] against a flaky facilitator.
+
+use axum::extract::{Request, State};
+use axum::middleware::{Next, from_fn_with_state};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use http::StatusCode;
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use x402_axum::facilitator_client::{
+    CircuitBreaker, FacilitatorClient, FacilitatorClientError, RetryPolicy,
+};
+use x402_rs::facilitator::Facilitator;
+use x402_rs::facilitator_mock::MockFacilitator;
+use x402_rs::types::{SettleRequest, VerifyRequest};
+
+/// Facilitator answering `status` to the next `failures` requests.
+#[derive(Clone)]
+struct Flaky {
+    status: StatusCode,
+    failures: Arc<AtomicUsize>,
+    requests: Arc<AtomicUsize>,
+}
+
+impl Flaky {
+    fn fail_next(&self, failures: usize) {
+        self.failures.store(failures, Ordering::SeqCst);
+    }
+
+    fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+async fn fail_on_demand(State(flaky): State<Flaky>, request: Request, next: Next) -> Response {
+    flaky.requests.fetch_add(1, Ordering::SeqCst);
+    let failing = flaky
+        .failures
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok();
+    if failing {
+        return flaky.status.into_response();
+    }
+    next.run(request).await
+}
+
+/// Serves `/verify`, `/settle` and `/supported` of a [`MockFacilitator`], failing with `status` on demand.
+async fn serve(status: StatusCode) -> (FacilitatorClient, Flaky) {
+    let flaky = Flaky {
+        status,
+        failures: Arc::new(AtomicUsize::new(0)),
+        requests: Arc::new(AtomicUsize::new(0)),
+    };
+    let app =
+        Router::new()
+            .route(
+                "/verify",
+                post(
+                    |State(facilitator): State<MockFacilitator>,
+                     Json(body): Json<VerifyRequest>| async move {
+                        Json(facilitator.verify(&body).await.unwrap())
+                    },
+                ),
+            )
+            .route(
+                "/settle",
+                post(
+                    |State(facilitator): State<MockFacilitator>,
+                     Json(body): Json<SettleRequest>| async move {
+                        Json(facilitator.settle(&body).await.unwrap())
+                    },
+                ),
+            )
+            .route(
+                "/supported",
+                get(|State(facilitator): State<MockFacilitator>| async move {
+                    Json(facilitator.supported().await.unwrap())
+                }),
+            )
+            .with_state(MockFacilitator::new())
+            .layer(from_fn_with_state(flaky.clone(), fail_on_demand));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let client = FacilitatorClient::try_from(format!("http://{addr}").as_str())
+        .unwrap()
+        .with_retry_policy(
+            RetryPolicy::default().with_backoff(Duration::from_millis(1), Duration::from_millis(5)),
+        );
+    (client, flaky)
+}
+
+fn verify_request() -> VerifyRequest {
+    serde_json::from_value(json!({
+        "x402Version": 1,
+        "paymentPayload": {
+            "x402Version": 1,
+            "scheme": "exact",
+            "network": "base-sepolia",
+            "payload": {
+                "signature": format!("0x{}", "11".repeat(65)),
+                "authorization": {
+                    "from": "0x0000000000000000000000000000000000000037",
+                    "to": "0x000000000000000000000000000000000000bEEF",
+                    "value": "1000",
+                    "validAfter": "0",
+                    "validBefore": "4102444800",
+                    "nonce": format!("0x{}", "22".repeat(32)),
+                }
+            }
+        },
+        "paymentRequirements": {
+            "scheme": "exact",
+            "network": "base-sepolia",
+            "maxAmountRequired": "1000",
+            "resource": "https://example.com/paywall",
+            "description": "",
+            "mimeType": "application/json",
+            "payTo": "0x000000000000000000000000000000000000bEEF",
+            "maxTimeoutSeconds": 300,
+            "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+        }
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn transient_failures_are_retried() {
+    let (client, flaky) = serve(StatusCode::SERVICE_UNAVAILABLE).await;
+    flaky.fail_next(2);
+    client.verify(&verify_request()).await.unwrap();
+    assert_eq!(flaky.requests(), 3);
+
+    flaky.fail_next(3);
+    let error = client.supported().await.unwrap_err();
+    assert!(error.is_transient(), "{error}");
+    assert_eq!(flaky.requests(), 6);
+}
+
+#[tokio::test]
+async fn permanent_failures_are_not_retried() {
+    let (client, flaky) = serve(StatusCode::BAD_REQUEST).await;
+    flaky.fail_next(1);
+    let error = client.verify(&verify_request()).await.unwrap_err();
+    assert!(!error.is_transient(), "{error}");
+    assert_eq!(flaky.requests(), 1);
+}
+
+#[tokio::test]
+async fn settle_is_retried_only_if_idempotent() {
+    let (client, flaky) = serve(StatusCode::BAD_GATEWAY).await;
+    flaky.fail_next(1);
+    let error = client.settle(&verify_request()).await.unwrap_err();
+    assert!(matches!(
+        error,
+        FacilitatorClientError::HttpStatus {
+            status: StatusCode::BAD_GATEWAY,
+            ..
+        }
+    ));
+    assert_eq!(flaky.requests(), 1);
+
+    let client = client.with_retry_policy(client.retry_policy().clone().with_settle_retries(true));
+    flaky.fail_next(1);
+    let response = client.settle(&verify_request()).await.unwrap();
+    assert!(response.success);
+    assert_eq!(flaky.requests(), 3);
+}
+
+#[tokio::test]
+async fn unsent_settle_is_retried() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let client = FacilitatorClient::try_from(format!("http://{addr}").as_str())
+        .unwrap()
+        .without_circuit_breaker()
+        .with_retry_policy(
+            RetryPolicy::default()
+                .with_backoff(Duration::from_millis(20), Duration::from_millis(20)),
+        );
+    let started = std::time::Instant::now();
+    let error = client.settle(&verify_request()).await.unwrap_err();
+    assert!(error.is_transient(), "{error}");
+    // Two retries, each after at least half of the backoff
+    assert!(started.elapsed() >= Duration::from_millis(20));
+}
+
+#[tokio::test]
+async fn circuit_breaker_is_off_by_default() {
+    let (client, flaky) = serve(StatusCode::SERVICE_UNAVAILABLE).await;
+    assert!(client.circuit_breaker().is_none());
+    let client = client.with_retry_policy(RetryPolicy::none());
+    flaky.fail_next(usize::MAX);
+    for _ in 0..10 {
+        client.supported().await.unwrap_err();
+    }
+    assert_eq!(flaky.requests(), 10);
+}
+
+#[tokio::test]
+async fn circuit_opens_after_consecutive_failures() {
+    let (client, flaky) = serve(StatusCode::SERVICE_UNAVAILABLE).await;
+    let client = client
+        .with_retry_policy(RetryPolicy::none())
+        .with_circuit_breaker(CircuitBreaker::new(2, Duration::from_millis(100)));
+    flaky.fail_next(usize::MAX);
+    client.supported().await.unwrap_err();
+    client.supported().await.unwrap_err();
+    assert!(client.circuit_breaker().unwrap().is_open());
+
+    // Fails fast while open, without reaching the facilitator
+    let error = client.verify(&verify_request()).await.unwrap_err();
+    assert!(matches!(error, FacilitatorClientError::CircuitOpen { .. }));
+    assert_eq!(flaky.requests(), 2);
+
+    // A single probe once open, closing the circuit if it succeeds
+    flaky.fail_next(0);
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    client.supported().await.unwrap();
+    assert!(!client.circuit_breaker().unwrap().is_open());
+    client.supported().await.unwrap();
+    assert_eq!(flaky.requests(), 4);
+}
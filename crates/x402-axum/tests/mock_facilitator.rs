@@ -0,0 +1,91 @@
+//! End-to-end tests of a protected route against [`MockFacilitator`].
+
+use axum::Router;
+use axum::body::Body;
+use axum::routing::get;
+use http::{Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+use x402_axum::{IntoPriceTag, X402Middleware};
+use x402_rs::facilitator_mock::{MockFacilitator, MockOutcome};
+use x402_rs::network::{Network, USDCDeployment};
+use x402_rs::types::{Base64Bytes, EvmAddress, FacilitatorErrorReason};
+
+const PAY_TO: &str = "0x000000000000000000000000000000000000bEEF";
+
+fn app(facilitator: MockFacilitator) -> Router {
+    let x402 =
+        X402Middleware::new(facilitator).with_base_url("https://example.com/".parse().unwrap());
+    let usdc = USDCDeployment::by_network(Network::BaseSepolia).pay_to(pay_to());
+    Router::new().route(
+        "/paywall",
+        get(|| async { "paid content" }).layer(x402.with_price_tag(usdc.amount(0.001).unwrap())),
+    )
+}
+
+fn pay_to() -> EvmAddress {
+    PAY_TO.parse().unwrap()
+}
+
+fn payment_header() -> String {
+    let payload = json!({
+        "x402Version": 1,
+        "scheme": "exact",
+        "network": "base-sepolia",
+        "payload": {
+            "signature": format!("0x{}", "11".repeat(65)),
+            "authorization": {
+                "from": "0x0000000000000000000000000000000000000037",
+                "to": PAY_TO,
+                "value": "1000",
+                "validAfter": "0",
+                "validBefore": "4102444800",
+                "nonce": format!("0x{}", "22".repeat(32)),
+            }
+        }
+    });
+    let encoded = Base64Bytes::encode(serde_json::to_vec(&payload).unwrap());
+    String::from_utf8(encoded.as_ref().to_vec()).unwrap()
+}
+
+async fn get_paywall(app: Router, payment: Option<String>) -> http::Response<Body> {
+    let mut request = Request::get("/paywall");
+    if let Some(payment) = payment {
+        request = request.header("X-Payment", payment);
+    }
+    app.oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn missing_payment_is_not_sent_to_facilitator() {
+    let facilitator = MockFacilitator::new();
+    let response = get_paywall(app(facilitator.clone()), None).await;
+    assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+    facilitator.assert_verify_count(0);
+    facilitator.assert_settle_count(0);
+}
+
+#[tokio::test]
+async fn valid_payment_is_verified_and_settled() {
+    let facilitator = MockFacilitator::new();
+    let response = get_paywall(app(facilitator.clone()), Some(payment_header())).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("X-Payment-Response"));
+    facilitator.assert_verify_count(1);
+    facilitator.assert_settle_count(1);
+    facilitator.assert_settled_to(Network::BaseSepolia, &pay_to().into());
+}
+
+#[tokio::test]
+async fn rejected_payment_is_not_settled() {
+    let facilitator = MockFacilitator::new().then_verify(MockOutcome::Reject(
+        FacilitatorErrorReason::InsufficientFunds,
+    ));
+    let response = get_paywall(app(facilitator.clone()), Some(payment_header())).await;
+    assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+    facilitator.assert_verify_count(1);
+    facilitator.assert_settle_count(0);
+    facilitator.assert_script_consumed();
+}
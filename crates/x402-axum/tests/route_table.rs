@@ -0,0 +1,166 @@
+//! Many routes priced by a single [`X402Middleware`] with a [`RouteTable`], against a [`MockFacilitator`].
+
+use axum::Router;
+use axum::body::{Body, to_bytes};
+use axum::routing::get;
+use http::{Method, Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+use x402_axum::layer::X402ConfigError;
+use x402_axum::route_table::{PricedRoute, RouteTable, RouteTableError};
+use x402_axum::{IntoPriceTag, PriceTag, X402Middleware};
+use x402_rs::facilitator_mock::MockFacilitator;
+use x402_rs::network::{Network, USDCDeployment};
+use x402_rs::types::{EvmAddress, PaymentRequiredResponse};
+
+const PAY_TO: &str = "0x000000000000000000000000000000000000bEEF";
+
+const PRICING: &str = r#"{
+  "routes": [
+    {
+      "path": "/weather/{city}",
+      "methods": ["GET"],
+      "description": "Weather forecast",
+      "outputSchema": { "type": "object" },
+      "accepts": [{ "network": "base-sepolia", "payTo": "0x000000000000000000000000000000000000bEEF", "amount": "0.001" }]
+    },
+    {
+      "path": "/files/{*path}",
+      "accepts": [{ "network": "base-sepolia", "payTo": "0x000000000000000000000000000000000000bEEF", "tokenAmount": "5000" }]
+    }
+  ]
+}"#;
+
+fn app(facilitator: MockFacilitator, routes: RouteTable) -> Router {
+    let x402 = X402Middleware::new(facilitator)
+        .with_base_url("https://example.com/".parse().unwrap())
+        .with_description("Paid API")
+        .with_route_table(routes);
+    Router::new()
+        .route(
+            "/weather/{city}",
+            get(|| async { "sunny" }).post(|| async { "subscribed" }),
+        )
+        .route("/files/{*path}", get(|| async { "file" }))
+        .route("/health", get(|| async { "ok" }))
+        .layer(x402)
+}
+
+async fn send(app: &Router, method: Method, uri: &str) -> http::Response<Body> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+async fn payment_required(response: http::Response<Body>) -> PaymentRequiredResponse {
+    assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn routes_are_priced_from_the_table() {
+    let app = app(
+        MockFacilitator::new(),
+        RouteTable::from_json(PRICING).unwrap(),
+    );
+
+    let response = send(&app, Method::GET, "/weather/paris").await;
+    let accepts = payment_required(response).await.accepts;
+    assert_eq!(accepts[0].max_amount_required.to_string(), "1000");
+    assert_eq!(accepts[0].description, "Weather forecast");
+    assert_eq!(
+        accepts[0].output_schema,
+        Some(json!({ "output": { "type": "object" } }))
+    );
+    assert_eq!(
+        accepts[0].resource.as_str(),
+        "https://example.com/weather/paris"
+    );
+
+    let response = send(&app, Method::GET, "/files/reports/2025.pdf").await;
+    let accepts = payment_required(response).await.accepts;
+    assert_eq!(accepts[0].max_amount_required.to_string(), "5000");
+    // Inherited from the middleware
+    assert_eq!(accepts[0].description, "Paid API");
+}
+
+#[tokio::test]
+async fn unmatched_requests_pass_through() {
+    let facilitator = MockFacilitator::new();
+    let app = app(facilitator.clone(), RouteTable::from_json(PRICING).unwrap());
+
+    let response = send(&app, Method::GET, "/health").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    // Priced for GET only
+    let response = send(&app, Method::POST, "/weather/paris").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(&app, Method::GET, "/weather").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(facilitator.calls().is_empty());
+}
+
+#[tokio::test]
+async fn first_matching_route_wins() {
+    let pay_to: EvmAddress = PAY_TO.parse().unwrap();
+    let usdc = USDCDeployment::by_network(Network::BaseSepolia).pay_to(pay_to);
+    let price_tag = |amount: &str| -> PriceTag { usdc.amount(amount).unwrap() };
+    let routes = RouteTable::new()
+        .with_route(PricedRoute::new("/weather/london", price_tag("0.002")).unwrap())
+        .with_route(
+            PricedRoute::new("/weather/{city}", price_tag("0.001"))
+                .unwrap()
+                .with_methods([Method::GET]),
+        );
+    let app = app(MockFacilitator::new(), routes);
+
+    let response = send(&app, Method::GET, "/weather/london").await;
+    let accepts = payment_required(response).await.accepts;
+    assert_eq!(accepts[0].max_amount_required.to_string(), "2000");
+    let response = send(&app, Method::GET, "/weather/paris").await;
+    let accepts = payment_required(response).await.accepts;
+    assert_eq!(accepts[0].max_amount_required.to_string(), "1000");
+}
+
+#[tokio::test]
+async fn route_price_tags_are_validated() {
+    let facilitator = MockFacilitator::new().with_supported_networks([Network::Base]);
+    let x402 =
+        X402Middleware::new(facilitator).with_route_table(RouteTable::from_json(PRICING).unwrap());
+    let error = x402.validate().await.unwrap_err();
+    let X402ConfigError::UnsupportedPriceTags(unsupported) = error else {
+        panic!("Expected UnsupportedPriceTags, got {error}");
+    };
+    assert_eq!(unsupported.len(), 2);
+}
+
+#[test]
+fn invalid_tables_are_refused() {
+    assert!(matches!(
+        PricedRoute::new("weather", Vec::<PriceTag>::new()),
+        Err(RouteTableError::InvalidPattern { .. })
+    ));
+    assert!(matches!(
+        PricedRoute::new("/files/{*path}/meta", Vec::<PriceTag>::new()),
+        Err(RouteTableError::InvalidPattern { .. })
+    ));
+    assert!(matches!(
+        PricedRoute::new("/weather/city-{id}", Vec::<PriceTag>::new()),
+        Err(RouteTableError::InvalidPattern { .. })
+    ));
+    let both_amounts = r#"{ "routes": [{ "path": "/a", "accepts": [
+        { "network": "base", "payTo": "0x000000000000000000000000000000000000bEEF", "amount": "1", "tokenAmount": "1" }
+    ] }] }"#;
+    assert!(matches!(
+        RouteTable::from_json(both_amounts),
+        Err(RouteTableError::InvalidPrice { .. })
+    ));
+    let unknown_field = r#"{ "routes": [{ "path": "/a", "price": "1", "accepts": [] }] }"#;
+    assert!(matches!(
+        RouteTable::from_json(unknown_field),
+        Err(RouteTableError::Json(_))
+    ));
+}
@@ -0,0 +1,254 @@
+//! Startup validation of price tags against the payment kinds of a [`MockFacilitator`].
+
+use axum::Router;
+use axum::body::{Body, to_bytes};
+use axum::routing::get;
+use http::{Request, StatusCode};
+use serde_json::json;
+use std::time::Duration;
+use tower::ServiceExt;
+use x402_axum::layer::{UnsupportedReason, X402ConfigError};
+use x402_axum::{IntoPriceTag, PriceTag, X402Middleware};
+use x402_rs::facilitator::Facilitator;
+use x402_rs::facilitator_mock::{MockCall, MockFacilitator};
+use x402_rs::network::{Network, USDCDeployment};
+use x402_rs::types::{
+    Base64Bytes, EvmAddress, FacilitatorFee, MixedAddress, PaymentRequiredResponse, Scheme,
+    SettleRequest, SettleResponse, SupportedPaymentKind, SupportedPaymentKindExtra,
+    SupportedPaymentKindsResponse, TokenAmount, VerifyRequest, VerifyResponse, X402Version,
+};
+
+const FEE_PAYER: &str = "2wKupLR9q6wXYppw8Gr2NvWxKBUqm4PPJKkQfoxHDBg4";
+
+fn evm_price_tag() -> PriceTag {
+    let pay_to: EvmAddress = "0x000000000000000000000000000000000000bEEF"
+        .parse()
+        .unwrap();
+    USDCDeployment::by_network(Network::BaseSepolia)
+        .pay_to(pay_to)
+        .amount(0.001)
+        .unwrap()
+}
+
+fn solana_price_tag() -> PriceTag {
+    USDCDeployment::by_network(Network::SolanaDevnet)
+        .pay_to(solana_address(
+            "EGBQqKn968sVv5cQh5Cr72pSTHfxsuzq7o7asqYB5uEV",
+        ))
+        .amount(0.001)
+        .unwrap()
+}
+
+fn solana_address(address: &str) -> MixedAddress {
+    serde_json::from_value(json!(address)).unwrap()
+}
+
+fn supported_calls(facilitator: &MockFacilitator) -> usize {
+    facilitator
+        .calls()
+        .iter()
+        .filter(|call| matches!(call, MockCall::Supported))
+        .count()
+}
+
+#[tokio::test]
+async fn unsupported_price_tags_are_reported() {
+    let facilitator = MockFacilitator::new().with_supported_networks([Network::BaseSepolia]);
+    let x402 =
+        X402Middleware::new(facilitator).with_price_tag(vec![evm_price_tag(), solana_price_tag()]);
+    let error = x402.validate().await.unwrap_err();
+    let X402ConfigError::UnsupportedPriceTags(unsupported) = error else {
+        panic!("Expected UnsupportedPriceTags, got {error}");
+    };
+    assert_eq!(unsupported.len(), 1);
+    assert_eq!(unsupported[0].price_tag, solana_price_tag());
+    assert_eq!(unsupported[0].reason, UnsupportedReason::Network);
+}
+
+#[tokio::test]
+async fn solana_requires_a_fee_payer() {
+    let facilitator = MockFacilitator::new().with_supported_networks([Network::SolanaDevnet]);
+    let x402 = X402Middleware::new(facilitator).with_price_tag(solana_price_tag());
+    let error = x402.validate().await.unwrap_err();
+    let X402ConfigError::UnsupportedPriceTags(unsupported) = error else {
+        panic!("Expected UnsupportedPriceTags, got {error}");
+    };
+    assert_eq!(unsupported[0].reason, UnsupportedReason::MissingFeePayer);
+}
+
+#[tokio::test]
+async fn facilitator_errors_fail_validation() {
+    let x402 = X402Middleware::new(FailingSupported).with_price_tag(evm_price_tag());
+    assert!(matches!(
+        x402.validate().await,
+        Err(X402ConfigError::Supported(_))
+    ));
+}
+
+#[tokio::test]
+async fn validation_fills_fee_payer_and_caches_supported() {
+    let facilitator = MockFacilitator::new()
+        .with_supported_networks([Network::BaseSepolia])
+        .with_fee_payer(Network::SolanaDevnet, solana_address(FEE_PAYER));
+    let x402 = X402Middleware::new(facilitator.clone())
+        .with_resource("https://example.com/paywall".parse().unwrap())
+        .with_price_tag(vec![evm_price_tag(), solana_price_tag()])
+        .validate()
+        .await
+        .unwrap();
+    let app = Router::new().route("/paywall", get(|| async { "paid content" }).layer(x402));
+
+    for _ in 0..2 {
+        let request = Request::get("/paywall").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let payment_required: PaymentRequiredResponse = serde_json::from_slice(&body).unwrap();
+        let extras = payment_required
+            .accepts
+            .iter()
+            .map(|r| r.extra.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(extras[0].as_ref().unwrap()["name"], "USDC");
+        assert_eq!(extras[1].as_ref().unwrap()["feePayer"], FEE_PAYER);
+    }
+    assert_eq!(supported_calls(&facilitator), 1);
+}
+
+#[tokio::test]
+async fn supported_extra_is_merged_into_the_token_extra() {
+    let fee_payer: MixedAddress = "0x0000000000000000000000000000000000000FEE"
+        .parse::<EvmAddress>()
+        .unwrap()
+        .into();
+    let facilitator = MockFacilitator::new().with_supported_kind(SupportedPaymentKind {
+        x402_version: X402Version::V1,
+        scheme: Scheme::Exact,
+        network: Network::BaseSepolia.to_string(),
+        extra: Some(SupportedPaymentKindExtra {
+            fee_payer: fee_payer.clone(),
+            max_compute_unit_price: None,
+            max_compute_unit_limit: None,
+            max_lamports_per_transaction: None,
+            fee: Some(FacilitatorFee {
+                recipient: fee_payer,
+                flat: Some(TokenAmount::from(100u64)),
+                basis_points: None,
+            }),
+            asset_transfer_method: None,
+            spender: None,
+        }),
+    });
+    let x402 = X402Middleware::new(facilitator)
+        .with_resource("https://example.com/paywall".parse().unwrap())
+        .with_price_tag(evm_price_tag())
+        .validate()
+        .await
+        .unwrap();
+    let app = Router::new().route("/paywall", get(|| async { "paid content" }).layer(x402));
+
+    let request = Request::get("/paywall").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let payment_required: PaymentRequiredResponse = serde_json::from_slice(&body).unwrap();
+    let extra = payment_required.accepts[0].extra.clone().unwrap();
+    // The EIP-712 domain of the token is kept
+    assert_eq!(extra["name"], "USDC");
+    assert_eq!(extra["version"], "2");
+    assert_eq!(extra["fee"]["flat"], "100");
+    // The facilitator submits EVM payments itself, the buyer has no use for its address
+    assert!(extra.get("feePayer").is_none());
+}
+
+#[tokio::test]
+async fn every_transfer_method_is_offered_with_its_own_extra() {
+    let spender: MixedAddress = "0x0000000000000000000000000000000000005Be0"
+        .parse::<EvmAddress>()
+        .unwrap()
+        .into();
+    let facilitator = MockFacilitator::new()
+        .with_supported_networks([Network::BaseSepolia])
+        .with_permit_spender(Network::BaseSepolia, spender);
+    let x402 = X402Middleware::new(facilitator.clone())
+        .with_resource("https://example.com/paywall".parse().unwrap())
+        .with_price_tag(evm_price_tag())
+        .validate()
+        .await
+        .unwrap();
+    let app = Router::new().route("/paywall", get(|| async { "paid content" }).layer(x402));
+
+    let request = Request::get("/paywall").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let payment_required: PaymentRequiredResponse = serde_json::from_slice(&body).unwrap();
+    let extras = payment_required
+        .accepts
+        .iter()
+        .map(|r| r.extra.clone().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(extras.len(), 2);
+    assert_eq!(extras[0], json!({ "name": "USDC", "version": "2" }));
+    assert_eq!(extras[1]["assetTransferMethod"], "permit");
+    assert_eq!(
+        extras[1]["spender"],
+        "0x0000000000000000000000000000000000005Be0"
+    );
+    assert!(extras[1].get("feePayer").is_none());
+
+    // A permit is verified against the permit offer
+    let payload = json!({
+        "x402Version": 1,
+        "scheme": "exact",
+        "network": "base-sepolia",
+        "payload": {
+            "signature": format!("0x{}", "11".repeat(65)),
+            "permit": {
+                "owner": "0x0000000000000000000000000000000000000037",
+                "spender": "0x0000000000000000000000000000000000005Be0",
+                "value": "1000",
+                "nonce": "0",
+                "deadline": "4102444800",
+            }
+        }
+    });
+    let encoded = Base64Bytes::encode(serde_json::to_vec(&payload).unwrap());
+    let request = Request::get("/paywall")
+        .header("X-Payment", encoded.as_ref())
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let verified = facilitator.verify_requests().pop().unwrap();
+    assert_eq!(verified.payment_requirements.extra, Some(extras[1].clone()));
+}
+
+#[tokio::test]
+async fn supported_is_refreshed_after_ttl() {
+    let facilitator = MockFacilitator::new();
+    let x402 = X402Middleware::new(facilitator.clone())
+        .with_price_tag(evm_price_tag())
+        .with_supported_ttl(Duration::ZERO);
+    x402.validate().await.unwrap();
+    x402.validate().await.unwrap();
+    assert_eq!(supported_calls(&facilitator), 2);
+}
+
+/// A facilitator whose `supported` always fails.
+#[derive(Clone)]
+struct FailingSupported;
+
+impl Facilitator for FailingSupported {
+    type Error = String;
+
+    async fn verify(&self, _: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+        unreachable!("not paid")
+    }
+
+    async fn settle(&self, _: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+        unreachable!("not paid")
+    }
+
+    async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+        Err("facilitator is down".to_string())
+    }
+}
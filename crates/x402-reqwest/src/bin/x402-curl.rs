@@ -0,0 +1,252 @@
+//! `x402-curl`: a command-line HTTP client that pays for `402 Payment Required` responses.
+//!
+//! Sends the request, and if the server answers `402`, selects one of the accepted
+//! [`PaymentRequirements`] with [`X402Payments`], checks it against `--max`, signs the payment
+//! and retries the request with the `X-Payment` header. The response body goes to stdout,
+//! the settlement receipt decoded from `X-Payment-Response` goes to stderr.
+//!
+//! `--max` is a human amount, e.g. `0.10`, converted with the decimals of the known USDC deployment
+//! on the network of the selected requirement. Other assets are refused.
+//! With `--dry-run` the selected requirement and the signed payload are printed instead of paid.
+//!
+//! Wallets:
+//! - EVM: `--evm-keystore <path>`, decrypted with `EVM_KEYSTORE_PASSWORD`, or `EVM_PRIVATE_KEY`.
+//!   `--evm-rpc` (or `EVM_RPC_URL`) is only needed for tokens paid by `permit`.
+//! - Solana: `--solana-keypair <path>` in the Solana CLI format, or `SOLANA_PRIVATE_KEY` (base58).
+//!   Requires `--solana-rpc` (or `SOLANA_RPC_URL`).
+//!
+//! Requires the `cli` feature: `cargo install x402-reqwest --features cli`.
+
+use alloy_provider::ProviderBuilder;
+use alloy_signer_local::PrivateKeySigner;
+use clap::Parser;
+use http::{HeaderName, HeaderValue, Method, StatusCode};
+use reqwest::{Client, RequestBuilder};
+use serde::Serialize;
+use solana_keypair::Keypair;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use x402_reqwest::chains::evm::EvmSenderWallet;
+use x402_reqwest::chains::solana::SolanaSenderWallet;
+use x402_reqwest::{MaxTokenAmountFromAmount, X402Payments};
+use x402_rs::from_env::{ENV_EVM_PRIVATE_KEY, ENV_SOLANA_PRIVATE_KEY};
+use x402_rs::network::USDCDeployment;
+use x402_rs::types::{
+    Base64Bytes, MoneyAmount, PaymentPayload, PaymentRequiredResponse, PaymentRequirements,
+    SettleResponse,
+};
+
+const ENV_EVM_KEYSTORE_PASSWORD: &str = "EVM_KEYSTORE_PASSWORD";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "x402-curl",
+    version,
+    about = "HTTP client paying for x402 resources"
+)]
+struct Args {
+    /// URL to request.
+    url: reqwest::Url,
+    /// HTTP method.
+    #[arg(short = 'X', long = "request", default_value = "GET")]
+    method: Method,
+    /// Extra header, `Name: value`. May be repeated.
+    #[arg(short = 'H', long = "header", value_name = "HEADER")]
+    headers: Vec<String>,
+    /// Request body, `@path` to read it from a file.
+    #[arg(short = 'd', long = "data")]
+    data: Option<String>,
+    /// Maximum amount to pay, e.g. `0.10` for 10 cents of USDC.
+    #[arg(long)]
+    max: MoneyAmount,
+    /// Print the selected payment requirements and the signed payload without paying.
+    #[arg(long)]
+    dry_run: bool,
+    /// Encrypted JSON keystore of the EVM wallet, decrypted with `EVM_KEYSTORE_PASSWORD`.
+    #[arg(long, value_name = "PATH")]
+    evm_keystore: Option<PathBuf>,
+    /// EVM RPC endpoint, to read EIP-2612 nonces for `permit` payments.
+    #[arg(long, env = "EVM_RPC_URL", value_name = "URL")]
+    evm_rpc: Option<reqwest::Url>,
+    /// Solana CLI keypair file of the Solana wallet.
+    #[arg(long, value_name = "PATH")]
+    solana_keypair: Option<PathBuf>,
+    /// Solana RPC endpoint, required for Solana payments.
+    #[arg(long, env = "SOLANA_RPC_URL", value_name = "URL")]
+    solana_rpc: Option<String>,
+}
+
+impl Args {
+    /// EVM wallet from `--evm-keystore` or `EVM_PRIVATE_KEY`, if any.
+    fn evm_wallet(&self) -> Result<Option<EvmSenderWallet>, Box<dyn std::error::Error>> {
+        let signer = match (&self.evm_keystore, std::env::var(ENV_EVM_PRIVATE_KEY)) {
+            (Some(path), _) => {
+                let password = std::env::var(ENV_EVM_KEYSTORE_PASSWORD).map_err(|_| {
+                    format!("{ENV_EVM_KEYSTORE_PASSWORD} must be set to decrypt the keystore")
+                })?;
+                PrivateKeySigner::decrypt_keystore(path, password)
+                    .map_err(|e| format!("Can not decrypt {}: {e}", path.display()))?
+            }
+            (None, Ok(private_key)) => private_key
+                .parse()
+                .map_err(|e| format!("Invalid {ENV_EVM_PRIVATE_KEY}: {e}"))?,
+            (None, Err(_)) => return Ok(None),
+        };
+        let wallet = EvmSenderWallet::new(signer);
+        let wallet = match &self.evm_rpc {
+            Some(url) => wallet.with_provider(ProviderBuilder::new().connect_http(url.clone())),
+            None => wallet,
+        };
+        Ok(Some(wallet))
+    }
+
+    /// Solana wallet from `--solana-keypair` or `SOLANA_PRIVATE_KEY`, if any.
+    fn solana_wallet(&self) -> Result<Option<SolanaSenderWallet>, Box<dyn std::error::Error>> {
+        let keypair = match (&self.solana_keypair, std::env::var(ENV_SOLANA_PRIVATE_KEY)) {
+            (Some(path), _) => solana_keypair::read_keypair_file(path)
+                .map_err(|e| format!("Can not read {}: {e}", path.display()))?,
+            (None, Ok(private_key)) => {
+                let bytes = bs58::decode(private_key.trim())
+                    .into_vec()
+                    .map_err(|e| format!("Invalid {ENV_SOLANA_PRIVATE_KEY}: {e}"))?;
+                Keypair::try_from(bytes.as_slice())
+                    .map_err(|e| format!("Invalid {ENV_SOLANA_PRIVATE_KEY}: {e}"))?
+            }
+            (None, Err(_)) => return Ok(None),
+        };
+        let rpc_url = self
+            .solana_rpc
+            .as_deref()
+            .ok_or("--solana-rpc or SOLANA_RPC_URL must be set for a Solana wallet")?;
+        let rpc_client = solana_rpc_client::rpc_client::RpcClient::new(rpc_url.to_string());
+        Ok(Some(SolanaSenderWallet::new(keypair, rpc_client)))
+    }
+
+    /// Payments with every configured wallet.
+    fn payments(&self) -> Result<X402Payments, Box<dyn std::error::Error>> {
+        let mut payments = self.evm_wallet()?.map(X402Payments::with_wallet);
+        if let Some(wallet) = self.solana_wallet()? {
+            payments = Some(match payments {
+                Some(payments) => payments.and_with_wallet(wallet),
+                None => X402Payments::with_wallet(wallet),
+            });
+        }
+        let payments = payments.ok_or(format!(
+            "No wallet configured: use --evm-keystore, --solana-keypair, {ENV_EVM_PRIVATE_KEY} or {ENV_SOLANA_PRIVATE_KEY}"
+        ))?;
+        Ok(payments)
+    }
+
+    fn request(&self, client: &Client) -> Result<RequestBuilder, Box<dyn std::error::Error>> {
+        let mut request = client.request(self.method.clone(), self.url.clone());
+        for header in &self.headers {
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| format!("Invalid header {header:?}, expected `Name: value`"))?;
+            request = request.header(
+                HeaderName::try_from(name.trim())?,
+                HeaderValue::try_from(value.trim())?,
+            );
+        }
+        if let Some(data) = &self.data {
+            let body = match data.strip_prefix('@') {
+                Some(path) => {
+                    std::fs::read(path).map_err(|e| format!("Can not read {path}: {e}"))?
+                }
+                None => data.clone().into_bytes(),
+            };
+            request = request.body(body);
+        }
+        Ok(request)
+    }
+}
+
+/// What `--dry-run` prints: the payment that would be sent.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DryRun<'a> {
+    payment_requirements: &'a PaymentRequirements,
+    payment_payload: &'a PaymentPayload,
+    /// Value of the `X-Payment` header.
+    x_payment: &'a str,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args).await {
+        Ok(status) if status.is_success() => ExitCode::SUCCESS,
+        Ok(status) => {
+            eprintln!("HTTP {status}");
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: &Args) -> Result<StatusCode, Box<dyn std::error::Error>> {
+    let payments = args.payments()?;
+    let client = Client::new();
+    let response = args.request(&client)?.send().await?;
+    if response.status() != StatusCode::PAYMENT_REQUIRED {
+        let status = response.status();
+        print_body(response).await?;
+        return Ok(status);
+    }
+    let payment_required = response.json::<PaymentRequiredResponse>().await?;
+    let selected = payments.select_payment_requirements(&payment_required.accepts)?;
+    let max = max_amount(&selected, &args.max)?;
+    payments.max(max).assert_max_amount(&selected)?;
+    let payload = payments.make_payment_payload(selected.clone()).await?;
+    let header = X402Payments::encode_payment_header(&payload)?;
+    if args.dry_run {
+        let dry_run = DryRun {
+            payment_requirements: &selected,
+            payment_payload: &payload,
+            x_payment: header.to_str()?,
+        };
+        println!("{}", serde_json::to_string_pretty(&dry_run)?);
+        return Ok(StatusCode::OK);
+    }
+    let response = args
+        .request(&client)?
+        .header("X-Payment", header)
+        .send()
+        .await?;
+    let status = response.status();
+    if let Some(receipt) = response.headers().get("X-Payment-Response") {
+        let json = Base64Bytes::from(receipt.as_bytes()).decode()?;
+        let settle_response: SettleResponse = serde_json::from_slice(&json)?;
+        eprintln!("{}", serde_json::to_string_pretty(&settle_response)?);
+    }
+    print_body(response).await?;
+    Ok(status)
+}
+
+/// Converts `--max` for the USDC deployment the selected requirement asks for.
+fn max_amount(
+    selected: &PaymentRequirements,
+    max: &MoneyAmount,
+) -> Result<x402_reqwest::MaxTokenAmount, Box<dyn std::error::Error>> {
+    let usdc = USDCDeployment::by_network(selected.network);
+    if selected.asset != usdc.address() {
+        return Err(format!(
+            "Can not enforce --max for asset {} on {}: only USDC is known",
+            selected.asset, selected.network
+        )
+        .into());
+    }
+    Ok(usdc.amount(max.clone())?)
+}
+
+async fn print_body(response: reqwest::Response) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+    let body = response.bytes().await?;
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(&body)?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -1,31 +1,147 @@
 use crate::X402PaymentsError;
-use crate::chains::{IntoSenderWallet, SenderWallet};
-use alloy_primitives::FixedBytes;
+use crate::chains::{IntoSenderWallet, SenderWallet, facilitator_fee};
+use alloy_primitives::{Address, FixedBytes, TxKind};
+use alloy_provider::{DynProvider, Provider};
+use alloy_rpc_types::{TransactionInput, TransactionRequest};
 use alloy_signer::Signer;
 use alloy_signer_local::PrivateKeySigner;
-use alloy_sol_types::{SolStruct, eip712_domain};
+use alloy_sol_types::{Eip712Domain, SolCall, SolStruct, eip712_domain, sol};
 use async_trait::async_trait;
 use rand::{Rng, rng};
 use std::sync::Arc;
-use x402_rs::chain::evm::EvmChain;
+use x402_rs::chain::evm::{ASSET_TRANSFER_METHOD_PERMIT, EvmChain};
 use x402_rs::network::NetworkFamily;
 use x402_rs::timestamp::UnixTimestamp;
 use x402_rs::types::{
-    EvmSignature, ExactEvmPayload, ExactEvmPayloadAuthorization, ExactPaymentPayload,
-    HexEncodedNonce, PaymentPayload, PaymentRequirements, Scheme, TransferWithAuthorization,
+    EvmAddress, EvmSignature, ExactEvmFeePayload, ExactEvmPayload, ExactEvmPayloadAuthorization,
+    ExactEvmPayloadPermit, ExactEvmPermitPayload, ExactPaymentPayload, HexEncodedNonce,
+    PaymentPayload, PaymentRequirements, Permit, Scheme, TokenAmount, TransferWithAuthorization,
 };
 
+sol! {
+    function nonces(address owner) external view returns (uint256);
+}
+
 #[derive(Clone)]
 pub struct EvmSenderWallet {
     signer: Arc<dyn Signer + Send + Sync>,
+    provider: Option<DynProvider>,
 }
 
 impl EvmSenderWallet {
     pub fn new(signer: impl Signer + Send + Sync + 'static) -> Self {
         Self {
             signer: Arc::new(signer),
+            provider: None,
         }
     }
+
+    /// Attach an RPC provider, used to read the sender's EIP-2612 `nonces()`
+    /// when the requirements ask for a `permit` payload.
+    pub fn with_provider(mut self, provider: impl Provider + 'static) -> Self {
+        self.provider = Some(provider.erased());
+        self
+    }
+
+    /// Sign an EIP-2612 `permit` allowing `spender` to pull `max_amount_required` and
+    /// `fee_amount` until the requirements' timeout elapses.
+    async fn permit_payload(
+        &self,
+        selected: PaymentRequirements,
+        domain: Eip712Domain,
+        spender: EvmAddress,
+        fee_amount: TokenAmount,
+    ) -> Result<ExactPaymentPayload, X402PaymentsError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(X402PaymentsError::SigningError(
+                "permit payment requires an RPC provider, see EvmSenderWallet::with_provider"
+                    .to_string(),
+            ))?;
+        let asset: Address = selected
+            .asset
+            .try_into()
+            .map_err(X402PaymentsError::InvalidEVMAddress)?;
+        let owner = self.signer.address();
+        let nonces_tx = TransactionRequest {
+            to: Some(TxKind::Call(asset)),
+            input: TransactionInput::new(noncesCall { owner }.abi_encode().into()),
+            ..Default::default()
+        };
+        let nonce = provider
+            .call(nonces_tx)
+            .await
+            .map_err(|e| X402PaymentsError::SigningError(format!("{e:?}")))?;
+        let nonce = noncesCall::abi_decode_returns(&nonce)
+            .map_err(|e| X402PaymentsError::SigningError(format!("{e:?}")))?;
+        let now = UnixTimestamp::try_now().map_err(X402PaymentsError::ClockError)?;
+        let permit = ExactEvmPayloadPermit {
+            owner: owner.into(),
+            spender,
+            value: TokenAmount(selected.max_amount_required.0.saturating_add(fee_amount.0)),
+            nonce: nonce.into(),
+            deadline: now + selected.max_timeout_seconds,
+        };
+        #[cfg(feature = "telemetry")]
+        tracing::debug!(?permit, "Constructed permit payload");
+        let eip712_hash = Permit {
+            owner: permit.owner.into(),
+            spender: permit.spender.into(),
+            value: permit.value.into(),
+            nonce: permit.nonce.into(),
+            deadline: permit.deadline.into(),
+        }
+        .eip712_signing_hash(&domain);
+        let signature = self
+            .signer
+            .sign_hash(&eip712_hash)
+            .await
+            .map_err(|e| X402PaymentsError::SigningError(format!("{e:?}")))?;
+        Ok(ExactPaymentPayload::EvmPermit(ExactEvmPermitPayload {
+            signature: EvmSignature::from(signature.as_bytes()),
+            permit,
+        }))
+    }
+
+    /// Sign an ERC-3009 `transferWithAuthorization` of `value` to `to`, with a random nonce.
+    async fn authorization(
+        &self,
+        domain: &Eip712Domain,
+        to: EvmAddress,
+        value: TokenAmount,
+        valid_after: UnixTimestamp,
+        valid_before: UnixTimestamp,
+    ) -> Result<(ExactEvmPayloadAuthorization, EvmSignature), X402PaymentsError> {
+        let nonce: [u8; 32] = rng().random();
+        let authorization = ExactEvmPayloadAuthorization {
+            from: self.signer.address().into(),
+            to,
+            value,
+            valid_after,
+            valid_before,
+            nonce: HexEncodedNonce(nonce),
+        };
+        #[cfg(feature = "telemetry")]
+        tracing::debug!(?authorization, "Constructed authorization payload");
+        let transfer_with_authorization = TransferWithAuthorization {
+            from: authorization.from.into(),
+            to: authorization.to.into(),
+            value: authorization.value.into(),
+            validAfter: authorization.valid_after.into(),
+            validBefore: authorization.valid_before.into(),
+            nonce: FixedBytes(nonce),
+        };
+        let eip712_hash = transfer_with_authorization.eip712_signing_hash(domain);
+        let signature = self
+            .signer
+            .sign_hash(&eip712_hash)
+            .await
+            .map_err(|e| X402PaymentsError::SigningError(format!("{e:?}")))?;
+        #[cfg(feature = "telemetry")]
+        tracing::debug!(?signature, "Signature obtained");
+        Ok((authorization, EvmSignature::from(signature.as_bytes())))
+    }
 }
 
 impl<S> From<S> for EvmSenderWallet
@@ -64,8 +180,8 @@ impl SenderWallet for EvmSenderWallet {
         &self,
         selected: PaymentRequirements,
     ) -> Result<PaymentPayload, X402PaymentsError> {
-        let (name, version) = match selected.extra {
-            None => (None, None),
+        let (name, version, permit_spender) = match &selected.extra {
+            None => (None, None, None),
             Some(extra) => {
                 let name = extra
                     .get("name")
@@ -75,7 +191,22 @@ impl SenderWallet for EvmSenderWallet {
                     .get("version")
                     .and_then(|v| v.as_str())
                     .map(ToOwned::to_owned);
-                (name, version)
+                let permit_spender = match extra.get("assetTransferMethod").and_then(|v| v.as_str())
+                {
+                    Some(ASSET_TRANSFER_METHOD_PERMIT) => {
+                        let spender = extra
+                            .get("spender")
+                            .and_then(|v| v.as_str())
+                            .ok_or(X402PaymentsError::SigningError(
+                                "permit requirements are missing extra.spender".to_string(),
+                            ))?
+                            .parse::<EvmAddress>()
+                            .map_err(|e| X402PaymentsError::SigningError(format!("{e:?}")))?;
+                        Some(spender)
+                    }
+                    _ => None,
+                };
+                (name, version, permit_spender)
             }
         };
         let network = selected.network;
@@ -87,48 +218,69 @@ impl SenderWallet for EvmSenderWallet {
             name: name.unwrap_or("".to_string()),
             version: version.unwrap_or("".to_string()),
             chain_id: chain_id,
-            verifying_contract: selected.asset.try_into().map_err(X402PaymentsError::InvalidEVMAddress)?,
+            verifying_contract: selected.asset.clone().try_into().map_err(X402PaymentsError::InvalidEVMAddress)?,
         };
+        // The facilitator fee is paid along with the price, see `extra.fee`
+        let fee = facilitator_fee(&selected)?
+            .map(|fee| {
+                let amount = fee.amount(selected.max_amount_required);
+                let recipient: EvmAddress = fee
+                    .recipient
+                    .try_into()
+                    .map_err(X402PaymentsError::InvalidEVMAddress)?;
+                Ok::<_, X402PaymentsError>((recipient, amount))
+            })
+            .transpose()?
+            .filter(|(_, amount)| !amount.0.is_zero());
+        if let Some(spender) = permit_spender {
+            // A permit covers the fee too, pulled by the spender to the fee recipient
+            let fee_amount = fee.map_or(TokenAmount::from(0u64), |(_, amount)| amount);
+            return Ok(PaymentPayload {
+                x402_version: x402_rs::types::X402Version::V1,
+                scheme: Scheme::Exact,
+                network,
+                payload: self
+                    .permit_payload(selected, domain, spender, fee_amount)
+                    .await?,
+            });
+        }
         let now = UnixTimestamp::try_now().map_err(X402PaymentsError::ClockError)?;
         let valid_after = UnixTimestamp(now.seconds_since_epoch() - 10 * 60); // 10 mins before
         let valid_before = now + selected.max_timeout_seconds;
-        let nonce: [u8; 32] = rng().random();
-        let authorization = ExactEvmPayloadAuthorization {
-            from: self.signer.address().into(),
-            to: selected
-                .pay_to
-                .try_into()
-                .map_err(X402PaymentsError::InvalidEVMAddress)?,
-            value: selected.max_amount_required,
-            valid_after,
-            valid_before,
-            nonce: HexEncodedNonce(nonce),
-        };
-        #[cfg(feature = "telemetry")]
-        tracing::debug!(?authorization, "Constructed authorization payload");
-        let transfer_with_authorization = TransferWithAuthorization {
-            from: authorization.from.into(),
-            to: authorization.to.into(),
-            value: authorization.value.into(),
-            validAfter: authorization.valid_after.into(),
-            validBefore: authorization.valid_before.into(),
-            nonce: FixedBytes(nonce),
+        let pay_to = selected
+            .pay_to
+            .try_into()
+            .map_err(X402PaymentsError::InvalidEVMAddress)?;
+        let (authorization, signature) = self
+            .authorization(
+                &domain,
+                pay_to,
+                selected.max_amount_required,
+                valid_after,
+                valid_before,
+            )
+            .await?;
+        // A second authorization, with its own nonce, pays the fee
+        let fee = match fee {
+            Some((recipient, amount)) => {
+                let (authorization, signature) = self
+                    .authorization(&domain, recipient, amount, valid_after, valid_before)
+                    .await?;
+                Some(ExactEvmFeePayload {
+                    signature,
+                    authorization,
+                })
+            }
+            None => None,
         };
-        let eip712_hash = transfer_with_authorization.eip712_signing_hash(&domain);
-        let signature = self
-            .signer
-            .sign_hash(&eip712_hash)
-            .await
-            .map_err(|e| X402PaymentsError::SigningError(format!("{e:?}")))?;
-        #[cfg(feature = "telemetry")]
-        tracing::debug!(?signature, "Signature obtained");
         let payment_payload = PaymentPayload {
             x402_version: x402_rs::types::X402Version::V1,
             scheme: Scheme::Exact,
             network,
             payload: ExactPaymentPayload::Evm(ExactEvmPayload {
-                signature: EvmSignature::from(signature.as_bytes()),
+                signature,
                 authorization,
+                fee,
             }),
         };
         Ok(payment_payload)
@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use x402_rs::types::{PaymentPayload, PaymentRequirements};
+use x402_rs::types::{FacilitatorFee, PaymentPayload, PaymentRequirements, TokenAmount};
 
 use crate::X402PaymentsError;
 
@@ -18,3 +18,29 @@ pub trait SenderWallet: Send + Sync {
 pub trait IntoSenderWallet {
     fn into_sender_wallet(self) -> Arc<dyn SenderWallet>;
 }
+
+/// Fee the facilitator charges on top of the price, advertised in `extra.fee`.
+pub fn facilitator_fee(
+    requirements: &PaymentRequirements,
+) -> Result<Option<FacilitatorFee>, X402PaymentsError> {
+    let fee = requirements
+        .extra
+        .as_ref()
+        .and_then(|extra| extra.get("fee"));
+    match fee {
+        None => Ok(None),
+        Some(fee) => serde_json::from_value(fee.clone())
+            .map(Some)
+            .map_err(|e| X402PaymentsError::SigningError(format!("invalid extra.fee: {e}"))),
+    }
+}
+
+/// Amount of the facilitator fee owed for `requirements`, zero if none is charged.
+pub fn facilitator_fee_amount(
+    requirements: &PaymentRequirements,
+) -> Result<TokenAmount, X402PaymentsError> {
+    let fee = facilitator_fee(requirements)?;
+    Ok(fee.map_or(TokenAmount::from(0u64), |fee| {
+        fee.amount(requirements.max_amount_required)
+    }))
+}
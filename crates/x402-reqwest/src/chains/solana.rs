@@ -1,32 +1,42 @@
 use async_trait::async_trait;
-use solana_rpc_client::rpc_client::RpcClient;
-use solana_rpc_client_api::config::RpcSimulateTransactionConfig;
 use solana_compute_budget_interface::ComputeBudgetInstruction;
 use solana_hash::Hash;
 use solana_instruction::Instruction;
+use solana_keypair::Keypair;
 use solana_message::{VersionedMessage, v0::Message as MessageV0};
+use solana_nonce::state::State as NonceState;
+use solana_nonce::versions::Versions as NonceVersions;
 use solana_program_pack::Pack;
 use solana_pubkey::Pubkey;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::RpcSimulateTransactionConfig;
 use solana_signature::Signature;
-use solana_keypair::Keypair;
 use solana_signer::Signer;
+use solana_system_interface::instruction::advance_nonce_account;
 use solana_transaction::versioned::VersionedTransaction;
+use spl_associated_token_account_interface::address::get_associated_token_address_with_program_id;
 use spl_associated_token_account_interface::instruction::create_associated_token_account_idempotent;
+use spl_token_2022_interface::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022_interface::extension::{BaseStateWithExtensions, StateWithExtensions};
 use std::str::FromStr;
 use std::sync::Arc;
-use x402_rs::chain::solana::{SolanaAddress, TransactionInt};
+use x402_rs::chain::solana::{SolanaAddress, SolanaComputeBudgetLimits, TransactionInt};
 use x402_rs::network::NetworkFamily;
 use x402_rs::types::{
     ExactPaymentPayload, ExactSolanaPayload, PaymentPayload, PaymentRequirements, X402Version,
 };
 
 use crate::X402PaymentsError;
-use crate::chains::{IntoSenderWallet, SenderWallet};
+use crate::chains::{IntoSenderWallet, SenderWallet, facilitator_fee};
+
+/// Compute units consumed by the System Program `AdvanceNonceAccount` instruction.
+const ADVANCE_NONCE_COMPUTE_UNITS: u32 = 150;
 
 #[derive(Clone)]
 pub struct SolanaSenderWallet {
     keypair: Arc<Keypair>,
     rpc_client: Arc<RpcClient>,
+    nonce_account: Option<Pubkey>,
 }
 
 impl SolanaSenderWallet {
@@ -34,6 +44,41 @@ impl SolanaSenderWallet {
         Self {
             keypair: Arc::new(keypair),
             rpc_client: Arc::new(rpc_client),
+            nonce_account: None,
+        }
+    }
+
+    /// Build transactions against a durable nonce account instead of a recent blockhash,
+    /// so the payment stays valid until the nonce is advanced rather than for ~60–90 seconds.
+    ///
+    /// The wallet keypair must be the nonce authority.
+    pub fn with_durable_nonce(mut self, nonce_account: Pubkey) -> Self {
+        self.nonce_account = Some(nonce_account);
+        self
+    }
+
+    /// Read the current durable nonce stored in `nonce_account`.
+    fn fetch_durable_nonce(&self, nonce_account: &Pubkey) -> Result<Hash, X402PaymentsError> {
+        let account = self.rpc_client.get_account(nonce_account).map_err(|e| {
+            X402PaymentsError::SigningError(format!(
+                "failed to fetch nonce account {nonce_account}: {e}"
+            ))
+        })?;
+        let versions: NonceVersions = bincode::deserialize(&account.data).map_err(|e| {
+            X402PaymentsError::SigningError(format!(
+                "failed to unpack nonce account {nonce_account}: {e}"
+            ))
+        })?;
+        match versions.state() {
+            NonceState::Initialized(data) if data.authority == self.keypair.pubkey() => {
+                Ok(data.blockhash())
+            }
+            NonceState::Initialized(_) => Err(X402PaymentsError::SigningError(format!(
+                "nonce account {nonce_account} is not controlled by the wallet"
+            ))),
+            NonceState::Uninitialized => Err(X402PaymentsError::SigningError(format!(
+                "nonce account {nonce_account} is not initialized"
+            ))),
         }
     }
 
@@ -53,14 +98,17 @@ impl SolanaSenderWallet {
                 token_program: spl_token_interface::ID,
             })
         } else if account.owner == spl_token_2022_interface::ID {
-            let mint = spl_token_2022_interface::state::Mint::unpack(&account.data).map_err(|e| {
-                X402PaymentsError::SigningError(format!(
-                    "failed to unpack mint {mint_address}: {e}",
-                ))
-            })?;
+            let mint =
+                StateWithExtensions::<spl_token_2022_interface::state::Mint>::unpack(&account.data)
+                    .map_err(|e| {
+                        X402PaymentsError::SigningError(format!(
+                            "failed to unpack mint {mint_address}: {e}",
+                        ))
+                    })?;
             Ok(Mint::Token2022 {
-                decimals: mint.decimals,
+                decimals: mint.base.decimals,
                 token_program: spl_token_2022_interface::ID,
+                transfer_fee: mint.get_extension::<TransferFeeConfig>().ok().copied(),
             })
         } else {
             Err(X402PaymentsError::SigningError(format!(
@@ -168,45 +216,60 @@ impl SenderWallet for SolanaSenderWallet {
             .0
             .try_into()
             .map_err(|e| X402PaymentsError::SigningError(format!("{e}")))?;
-        let transfer_instruction = match mint {
-            Mint::Token {
-                decimals,
-                token_program,
-            } => {
-                spl_token_interface::instruction::transfer_checked(
-                    &token_program,
-                    &source_ata,
-                    &asset_address,
-                    &destination_ata,
-                    &client_address,
-                    &[], // extra signer pubkeys if using multisig; else empty
-                    amount,
-                    decimals,
-                )
-                .map_err(|e| X402PaymentsError::SigningError(format!("{e}")))?
-            }
+        // A Token-2022 transfer fee is fixed per epoch
+        let epoch = match mint {
             Mint::Token2022 {
-                decimals,
-                token_program,
-            } => {
-                spl_token_2022_interface::instruction::transfer_checked(
-                    &token_program,
+                transfer_fee: Some(_),
+                ..
+            } => Some(
+                self.rpc_client
+                    .get_epoch_info()
+                    .map_err(|e| X402PaymentsError::SigningError(format!("{e}")))?
+                    .epoch,
+            ),
+            _ => None,
+        };
+        let transfer_instruction = mint.transfer_checked(
+            epoch,
+            &source_ata,
+            &asset_address,
+            &destination_ata,
+            &client_address,
+            amount,
+        )?;
+        // The facilitator fee, if any, goes to the fee recipient's token account after the payment
+        let fee_transfer_instruction = match facilitator_fee(&selected)? {
+            Some(fee) => {
+                let amount: u64 = fee
+                    .amount(selected.max_amount_required)
+                    .0
+                    .try_into()
+                    .map_err(|e| X402PaymentsError::SigningError(format!("{e}")))?;
+                let recipient: SolanaAddress = fee.recipient.try_into().map_err(|e| {
+                    X402PaymentsError::SigningError(format!("invalid fee recipient: {e}"))
+                })?;
+                let recipient_ata = get_associated_token_address_with_program_id(
+                    &recipient.into(),
+                    &asset_address,
+                    mint.token_program(),
+                );
+                let instruction = mint.transfer_checked(
+                    epoch,
                     &source_ata,
                     &asset_address,
-                    &destination_ata,
+                    &recipient_ata,
                     &client_address,
-                    &[], // extra signer pubkeys if using multisig; else empty
                     amount,
-                    decimals,
-                )
-                .map_err(|e| X402PaymentsError::SigningError(format!("{e}")))?
+                )?;
+                (amount > 0).then_some(instruction)
             }
+            None => None,
         };
-        let transfer_instructions = if let Some(create_ata_instruction) = create_ata_instruction {
-            vec![create_ata_instruction, transfer_instruction]
-        } else {
-            vec![transfer_instruction]
-        };
+        let transfer_instructions = create_ata_instruction
+            .into_iter()
+            .chain([transfer_instruction])
+            .chain(fee_transfer_instruction)
+            .collect::<Vec<_>>();
 
         // createTransferTransactionMessage
         // 1. Build a message to simulate the transfer, 1 microlamport priority fee
@@ -218,21 +281,48 @@ impl SenderWallet for SolanaSenderWallet {
             self.rpc_client.as_ref(),
             &[fee_payer, destination_ata, source_ata],
         )?;
+        // Stay within the compute unit price the facilitator is willing to pay for
+        let fee = match extra_u64(&selected, "maxComputeUnitPrice") {
+            Some(max_compute_unit_price) => fee.min(max_compute_unit_price),
+            None => fee,
+        };
         let (msg_to_sim, instructions) =
             build_message_to_simulate(fee_payer, &transfer_instructions, fee, recent_blockhash)?;
         // 2) Estimate CU via simulation
         let estimated_cu = estimate_compute_units(self.rpc_client.as_ref(), &msg_to_sim)?;
+        // With a durable nonce, `AdvanceNonceAccount` goes first and the nonce replaces the blockhash
+        let (advance_nonce_ix, estimated_cu, blockhash) = match self.nonce_account {
+            Some(nonce_account) => {
+                let durable_nonce = self.fetch_durable_nonce(&nonce_account)?;
+                let ix = advance_nonce_account(&nonce_account, &client_address);
+                (
+                    Some(ix),
+                    estimated_cu + ADVANCE_NONCE_COMPUTE_UNITS,
+                    durable_nonce,
+                )
+            }
+            None => (None, estimated_cu, recent_blockhash),
+        };
+        // Nor ask for more compute units than the facilitator accepts
+        let estimated_cu = compute_unit_limit(&selected, estimated_cu);
         // prepend the CU limit instruction
         let cu_ix = ComputeBudgetInstruction::set_compute_unit_limit(estimated_cu);
         let msg = {
             let instructions_original = instructions;
-            // Order: [CU limit] + [compute price] + transfer instructions
-            let mut instructions = Vec::with_capacity(instructions_original.len() + 1);
+            // Order: [advance nonce] + [CU limit] + [compute price] + transfer instructions
+            let mut instructions = Vec::with_capacity(instructions_original.len() + 2);
+            instructions.extend(advance_nonce_ix);
             instructions.push(cu_ix);
             instructions.extend(instructions_original.clone());
-            MessageV0::try_compile(&fee_payer, &instructions, &[], recent_blockhash)
+            MessageV0::try_compile(&fee_payer, &instructions, &[], blockhash)
                 .map_err(|e| X402PaymentsError::SigningError(format!("{e:?}")))?
         };
+        assert_transaction_fee(
+            &selected,
+            msg.header.num_required_signatures,
+            estimated_cu,
+            fee,
+        )?;
         let tx = VersionedTransaction {
             signatures: vec![],
             message: VersionedMessage::V0(msg),
@@ -259,8 +349,15 @@ impl SenderWallet for SolanaSenderWallet {
 
 #[derive(Debug)]
 enum Mint {
-    Token { decimals: u8, token_program: Pubkey },
-    Token2022 { decimals: u8, token_program: Pubkey },
+    Token {
+        decimals: u8,
+        token_program: Pubkey,
+    },
+    Token2022 {
+        decimals: u8,
+        token_program: Pubkey,
+        transfer_fee: Option<TransferFeeConfig>,
+    },
 }
 
 impl Mint {
@@ -270,6 +367,62 @@ impl Mint {
             Mint::Token2022 { token_program, .. } => token_program,
         }
     }
+
+    /// `TransferChecked` delivering `amount` to `destination`.
+    ///
+    /// With a Token-2022 transfer fee the recipient receives the transfer net of the fee, so
+    /// the transfer is grossed up by the fee of the current `epoch`.
+    fn transfer_checked(
+        &self,
+        epoch: Option<u64>,
+        source: &Pubkey,
+        mint: &Pubkey,
+        destination: &Pubkey,
+        authority: &Pubkey,
+        amount: u64,
+    ) -> Result<Instruction, X402PaymentsError> {
+        match self {
+            Mint::Token {
+                decimals,
+                token_program,
+            } => spl_token_interface::instruction::transfer_checked(
+                token_program,
+                source,
+                mint,
+                destination,
+                authority,
+                &[], // extra signer pubkeys if using multisig; else empty
+                amount,
+                *decimals,
+            ),
+            Mint::Token2022 {
+                decimals,
+                token_program,
+                transfer_fee,
+            } => {
+                let amount = match (transfer_fee, epoch) {
+                    (Some(config), Some(epoch)) => config
+                        .calculate_inverse_epoch_fee(epoch, amount)
+                        .and_then(|fee| amount.checked_add(fee))
+                        .ok_or(X402PaymentsError::SigningError(
+                            "failed to calculate transfer fee".to_string(),
+                        ))?,
+                    _ => amount,
+                };
+                spl_token_2022_interface::instruction::transfer_checked(
+                    token_program,
+                    source,
+                    mint,
+                    destination,
+                    authority,
+                    &[], // extra signer pubkeys if using multisig; else empty
+                    amount,
+                    *decimals,
+                )
+            }
+        }
+        .map_err(|e| X402PaymentsError::SigningError(format!("{e}")))
+    }
 }
 
 //  Build the message we want to simulate (priority fee + transfer Ixs).
@@ -346,6 +499,43 @@ pub fn get_priority_fee_micro_lamports(
     Ok(fee)
 }
 
+/// `estimated` compute units, clamped to the `maxComputeUnitLimit` of the facilitator, if any.
+fn compute_unit_limit(requirements: &PaymentRequirements, estimated: u32) -> u32 {
+    match extra_u64(requirements, "maxComputeUnitLimit") {
+        Some(max) => estimated.min(u32::try_from(max).unwrap_or(u32::MAX)),
+        None => estimated,
+    }
+}
+
+/// Fails before signing if the facilitator would refuse to pay the fee of the transaction,
+/// over its `maxLamportsPerTransaction`.
+fn assert_transaction_fee(
+    requirements: &PaymentRequirements,
+    num_signatures: u8,
+    compute_unit_limit: u32,
+    compute_unit_price: u64,
+) -> Result<(), X402PaymentsError> {
+    let Some(max_lamports) = extra_u64(requirements, "maxLamportsPerTransaction") else {
+        return Ok(());
+    };
+    let lamports = SolanaComputeBudgetLimits::transaction_fee(
+        num_signatures,
+        compute_unit_limit,
+        compute_unit_price,
+    );
+    if lamports > max_lamports {
+        return Err(X402PaymentsError::SigningError(format!(
+            "transaction fee of {lamports} lamports exceeds the facilitator maximum of {max_lamports}"
+        )));
+    }
+    Ok(())
+}
+
+/// An unsigned integer in the `extra` of `requirements`, such as a compute budget cap.
+fn extra_u64(requirements: &PaymentRequirements, key: &str) -> Option<u64> {
+    requirements.extra.as_ref()?.get(key)?.as_u64()
+}
+
 /// Update the first set_compute_unit_limit ix if it exists, else append a new one.
 pub fn update_or_append_set_compute_unit_limit(ixs: &mut Vec<Instruction>, units: u32) {
     // opcode 0x02 = SetComputeUnitLimit
@@ -362,3 +552,128 @@ pub fn update_or_append_set_compute_unit_limit(ixs: &mut Vec<Instruction>, units
         ixs.push(new_ix); // append
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token_2022_interface::extension::transfer_fee::TransferFee;
+    use spl_token_2022_interface::instruction::TokenInstruction;
+
+    const PRICE: u64 = 1_000_000;
+
+    /// 1% up to `maximum_fee` until epoch 10, then 2% up to `maximum_fee`.
+    fn token_2022(maximum_fee: u64) -> Mint {
+        let transfer_fee = |epoch: u64, basis_points: u16| TransferFee {
+            epoch: epoch.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: basis_points.into(),
+        };
+        Mint::Token2022 {
+            decimals: 6,
+            token_program: spl_token_2022_interface::ID,
+            transfer_fee: Some(TransferFeeConfig {
+                transfer_fee_config_authority: Default::default(),
+                withdraw_withheld_authority: Default::default(),
+                withheld_amount: 0.into(),
+                older_transfer_fee: transfer_fee(0, 100),
+                newer_transfer_fee: transfer_fee(10, 200),
+            }),
+        }
+    }
+
+    fn transferred_amount(mint: &Mint, epoch: Option<u64>) -> u64 {
+        let (source, asset, destination, authority) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let instruction = mint
+            .transfer_checked(epoch, &source, &asset, &destination, &authority, PRICE)
+            .unwrap();
+        assert_eq!(&instruction.program_id, mint.token_program());
+        match TokenInstruction::unpack(&instruction.data).unwrap() {
+            TokenInstruction::TransferChecked { amount, .. } => amount,
+            other => panic!("expected TransferChecked, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn transfer_fee_is_grossed_up_with_the_fee_of_the_epoch() {
+        let mint = token_2022(u64::MAX);
+        let Mint::Token2022 {
+            transfer_fee: Some(config),
+            ..
+        } = &mint
+        else {
+            unreachable!()
+        };
+        for epoch in [5, 10] {
+            let amount = transferred_amount(&mint, Some(epoch));
+            // The recipient is left with exactly the price once the fee is withheld
+            let fee = config.calculate_epoch_fee(epoch, amount).unwrap();
+            assert_eq!(amount - fee, PRICE, "epoch {epoch}");
+        }
+        assert!(transferred_amount(&mint, Some(10)) > transferred_amount(&mint, Some(5)));
+    }
+
+    #[test]
+    fn transfer_fee_gross_up_stops_at_the_maximum_fee() {
+        let mint = token_2022(5_000);
+        assert_eq!(transferred_amount(&mint, Some(5)), PRICE + 5_000);
+    }
+
+    #[test]
+    fn transfer_without_fee_is_not_grossed_up() {
+        let token = Mint::Token {
+            decimals: 6,
+            token_program: spl_token_interface::ID,
+        };
+        assert_eq!(transferred_amount(&token, None), PRICE);
+        let token_2022 = Mint::Token2022 {
+            decimals: 6,
+            token_program: spl_token_2022_interface::ID,
+            transfer_fee: None,
+        };
+        assert_eq!(transferred_amount(&token_2022, None), PRICE);
+    }
+
+    fn requirements(extra: serde_json::Value) -> PaymentRequirements {
+        serde_json::from_value(serde_json::json!({
+            "scheme": "exact",
+            "network": "solana-devnet",
+            "maxAmountRequired": PRICE.to_string(),
+            "resource": "https://example.com/resource",
+            "description": "",
+            "mimeType": "application/json",
+            "payTo": Pubkey::new_unique().to_string(),
+            "maxTimeoutSeconds": 600,
+            "asset": Pubkey::new_unique().to_string(),
+            "extra": extra,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn compute_unit_limit_is_clamped_to_the_facilitator_maximum() {
+        let capped = requirements(serde_json::json!({ "maxComputeUnitLimit": 20_000 }));
+        assert_eq!(compute_unit_limit(&capped, 30_000), 20_000);
+        assert_eq!(compute_unit_limit(&capped, 10_000), 10_000);
+        let uncapped = requirements(serde_json::json!({}));
+        assert_eq!(compute_unit_limit(&uncapped, 30_000), 30_000);
+    }
+
+    #[test]
+    fn transaction_fee_over_the_facilitator_maximum_is_refused() {
+        // 2 signatures * 5000 + ceil(20 000 * 1 000 / 1 000 000) = 10 020
+        let at_limit = requirements(serde_json::json!({ "maxLamportsPerTransaction": 10_020 }));
+        assert!(assert_transaction_fee(&at_limit, 2, 20_000, 1_000).is_ok());
+        let below = requirements(serde_json::json!({ "maxLamportsPerTransaction": 10_019 }));
+        assert!(matches!(
+            assert_transaction_fee(&below, 2, 20_000, 1_000),
+            Err(X402PaymentsError::SigningError(_))
+        ));
+        let uncapped = requirements(serde_json::json!({}));
+        assert!(assert_transaction_fee(&uncapped, 2, u32::MAX, u64::MAX).is_ok());
+    }
+}
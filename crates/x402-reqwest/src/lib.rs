@@ -126,6 +126,7 @@
 //! ## Crate Layout
 //! - [`middleware`] – The core [`X402Payments`] middleware and logic
 //! - [`builder`] – Builder traits for attaching `X402Payments` to [`reqwest::Client`] or [`reqwest::ClientBuilder`]
+//! - `x402-curl` – Command-line client paying for x402 resources, behind the `cli` feature
 //!
 //! ## Related Crates
 //! - [`x402-rs`](https://docs.rs/x402-rs) – protocol types and network info
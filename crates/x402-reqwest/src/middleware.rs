@@ -22,7 +22,7 @@ use x402_rs::types::{
     PaymentRequiredResponse, PaymentRequirements, TokenAmount, TokenAsset, TokenDeployment,
 };
 
-use crate::chains::{IntoSenderWallet, SenderWallet};
+use crate::chains::{IntoSenderWallet, SenderWallet, facilitator_fee_amount};
 
 /// Represents the maximum allowed amount for a specific token asset.
 pub struct MaxTokenAmount {
@@ -218,17 +218,20 @@ impl X402Payments {
         })
     }
 
-    /// Ensures that the selected requirement does not exceed the max configured amount.
+    /// Ensures that the selected requirement, with the facilitator fee, does not exceed
+    /// the max configured amount.
     pub fn assert_max_amount(
         &self,
         selected: &PaymentRequirements,
     ) -> Result<(), X402PaymentsError> {
         let token_asset = selected.token_asset();
+        let fee = facilitator_fee_amount(selected)?;
+        let requested = TokenAmount(selected.max_amount_required.0.saturating_add(fee.0));
         if let Some(max) = self.max_token_amount.get(&token_asset)
-            && &selected.max_amount_required > max
+            && &requested > max
         {
             return Err(X402PaymentsError::PaymentAmountTooLarge {
-                requested: selected.max_amount_required,
+                requested,
                 allowed: *max,
                 asset: token_asset,
             });
@@ -0,0 +1,119 @@
+use alloy_primitives::{Address, U256, address};
+use alloy_provider::ProviderBuilder;
+use alloy_signer_local::PrivateKeySigner;
+use serde_json::{Value, json};
+use x402_reqwest::chains::SenderWallet;
+use x402_reqwest::chains::evm::EvmSenderWallet;
+use x402_rs::chain::evm::EvmProvider;
+use x402_rs::chain::evm_offline::OfflineEvmChain;
+use x402_rs::facilitator::Facilitator;
+use x402_rs::network::Network;
+use x402_rs::types::{
+    FacilitatorFee, PaymentRequirements, SettleRequest, TokenAmount, VerifyResponse, X402Version,
+};
+
+const PAY_TO: Address = address!("0x00000000000000000000000000000000000000a1");
+const FEE_RECIPIENT: Address = address!("0x00000000000000000000000000000000000000fe");
+
+struct Setup {
+    chain: OfflineEvmChain,
+    usdc: Address,
+    facilitator: PrivateKeySigner,
+    provider: EvmProvider,
+}
+
+/// A facilitator charging 10 base units plus 1% on every payment.
+fn setup() -> Setup {
+    let chain = OfflineEvmChain::new(Network::BaseSepolia).unwrap();
+    let usdc = chain.deploy_usdc();
+    let facilitator = PrivateKeySigner::random();
+    let provider = chain
+        .provider(facilitator.clone().into())
+        .unwrap()
+        .with_fee(Some(FacilitatorFee {
+            recipient: FEE_RECIPIENT.into(),
+            flat: Some(TokenAmount::from(10u64)),
+            basis_points: Some(100),
+        }));
+    Setup {
+        chain,
+        usdc,
+        facilitator,
+        provider,
+    }
+}
+
+/// Requirements for 1000 base units, with the token extra merged with the `/supported` extra.
+async fn requirements(setup: &Setup, transfer_method: Value) -> PaymentRequirements {
+    let supported = setup.provider.supported().await.unwrap();
+    let mut extra = serde_json::to_value(&supported.kinds[0].extra).unwrap();
+    let extra_map = extra.as_object_mut().unwrap();
+    extra_map.insert("name".to_string(), json!("USDC"));
+    extra_map.insert("version".to_string(), json!("2"));
+    if let Value::Object(transfer_method) = transfer_method {
+        extra_map.extend(transfer_method);
+    }
+    serde_json::from_value(json!({
+        "scheme": "exact",
+        "network": "base-sepolia",
+        "maxAmountRequired": "1000",
+        "resource": "https://example.com/resource",
+        "description": "",
+        "mimeType": "application/json",
+        "payTo": PAY_TO,
+        "maxTimeoutSeconds": 600,
+        "asset": setup.usdc,
+        "extra": extra,
+    }))
+    .unwrap()
+}
+
+async fn pay(setup: &Setup, wallet: &EvmSenderWallet, requirements: PaymentRequirements) {
+    let payment_payload = wallet.payment_payload(requirements.clone()).await.unwrap();
+    let request = SettleRequest {
+        x402_version: X402Version::V1,
+        payment_payload,
+        payment_requirements: requirements,
+    };
+    let verified = setup.provider.verify(&request).await.unwrap();
+    assert!(matches!(verified, VerifyResponse::Valid { .. }));
+    let settled = setup.provider.settle(&request).await.unwrap();
+    assert!(settled.success);
+    assert_eq!(setup.chain.balance_of(setup.usdc, PAY_TO), U256::from(1000));
+    // 10 + 1% of 1000
+    assert_eq!(
+        setup.chain.balance_of(setup.usdc, FEE_RECIPIENT),
+        U256::from(20)
+    );
+}
+
+#[tokio::test]
+async fn authorization_payment_pays_the_advertised_fee() {
+    let setup = setup();
+    let buyer = PrivateKeySigner::random();
+    setup
+        .chain
+        .mint(setup.usdc, buyer.address(), U256::from(1020));
+    let requirements = requirements(&setup, Value::Null).await;
+    pay(&setup, &EvmSenderWallet::new(buyer), requirements).await;
+}
+
+#[tokio::test]
+async fn permit_payment_covers_the_advertised_fee() {
+    let setup = setup();
+    let buyer = PrivateKeySigner::random();
+    setup
+        .chain
+        .mint(setup.usdc, buyer.address(), U256::from(1020));
+    let requirements = requirements(
+        &setup,
+        json!({
+            "assetTransferMethod": "permit",
+            "spender": setup.facilitator.address(),
+        }),
+    )
+    .await;
+    let rpc = ProviderBuilder::new().connect_client(setup.chain.client());
+    let wallet = EvmSenderWallet::new(buyer).with_provider(rpc);
+    pay(&setup, &wallet, requirements).await;
+}
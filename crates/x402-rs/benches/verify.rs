@@ -0,0 +1,401 @@
+//! RPC requests and latency of EVM `/verify` against a mock JSON-RPC node.
+//!
+//! The mock node answers `eth_call` by function selector, and `eth_getCode` with empty code.
+//! Each request takes a fixed simulated latency. Concurrent reads are still separate JSON-RPC
+//! requests, not a batch, but their latencies overlap: requests in flight at the same time count
+//! as one *round*, so the number of rounds is how many latencies in a row the payer waits for.
+//!
+//! Run with:
+//!
+//! ```sh
+//! cargo bench -p x402-rs --bench verify
+//! ```
+
+use alloy_json_rpc::{RequestPacket, Response, ResponsePacket, ResponsePayload, SerializedRequest};
+use alloy_network::Ethereum;
+use alloy_primitives::{Address, B256, Bytes, FixedBytes, U256, address};
+use alloy_provider::RootProvider;
+use alloy_rpc_client::RpcClient;
+use alloy_rpc_types::TransactionReceipt;
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{SolCall, SolStruct, eip712_domain, sol};
+use alloy_transport::{TransportError, TransportFut};
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use x402_rs::chain::FacilitatorLocalError;
+use x402_rs::chain::evm::{
+    Eip712DomainCache, EvmChain, MetaEvmProvider, MetaTransaction, PermitSettlements,
+    VerificationCache,
+};
+use x402_rs::facilitator::Facilitator;
+use x402_rs::network::{Network, USDCDeployment};
+use x402_rs::types::{FacilitatorFee, TransferWithAuthorization, VerifyRequest};
+
+sol! {
+    function balanceOf(address account) external view returns (uint256);
+    function transferWithAuthorization(address from, address to, uint256 value, uint256 validAfter, uint256 validBefore, bytes32 nonce, bytes signature) external;
+    function eip712Domain() external view returns (bytes1 fields, string name, string version, uint256 chainId, address verifyingContract, bytes32 salt, uint256[] extensions);
+}
+
+/// Simulated latency of a single JSON-RPC request.
+const RPC_LATENCY: Duration = Duration::from_millis(25);
+/// Number of verifications timed per scenario.
+const ITERATIONS: u32 = 20;
+/// An ERC-3009 token without static EIP-712 metadata.
+const UNKNOWN_TOKEN: Address = address!("0x00000000000000000000000000000000000e3009");
+
+/// Source of unique ERC-3009 authorization nonces.
+static NONCE: AtomicU64 = AtomicU64::new(1);
+
+/// Request and round counters shared by the mock node.
+#[derive(Debug, Default)]
+struct Counters {
+    requests: AtomicUsize,
+    rounds: AtomicUsize,
+    in_flight: AtomicUsize,
+}
+
+impl Counters {
+    fn take(&self) -> (usize, usize) {
+        (
+            self.requests.swap(0, Ordering::SeqCst),
+            self.rounds.swap(0, Ordering::SeqCst),
+        )
+    }
+}
+
+/// Mock JSON-RPC node, usable as an Alloy transport.
+#[derive(Debug, Clone)]
+struct MockNode {
+    counters: Arc<Counters>,
+    chain_id: u64,
+}
+
+impl MockNode {
+    fn respond(&self, request: &SerializedRequest) -> Response {
+        let payload = match request.method() {
+            "eth_chainId" => success(&U256::from(self.chain_id)),
+            "eth_getCode" => success(&Bytes::new()),
+            "eth_call" => self.call(request),
+            method => {
+                ResponsePayload::internal_error_message(format!("{method} is not mocked").into())
+            }
+        };
+        Response {
+            id: request.id().clone(),
+            payload,
+        }
+    }
+
+    fn call(&self, request: &SerializedRequest) -> ResponsePayload {
+        let params: serde_json::Value = request
+            .params()
+            .and_then(|params| serde_json::from_str(params.get()).ok())
+            .unwrap_or_default();
+        let tx = &params[0];
+        let input: Bytes = tx
+            .get("input")
+            .or_else(|| tx.get("data"))
+            .and_then(|input| serde_json::from_value(input.clone()).ok())
+            .unwrap_or_default();
+        let selector = input.get(..4).unwrap_or_default();
+        if selector == balanceOfCall::SELECTOR {
+            success(&Bytes::from(balanceOfCall::abi_encode_returns(&U256::MAX)))
+        } else if selector == transferWithAuthorizationCall::SELECTOR {
+            success(&Bytes::new())
+        } else if selector == eip712DomainCall::SELECTOR {
+            let verifying_contract: Address =
+                serde_json::from_value(tx["to"].clone()).unwrap_or_default();
+            success(&Bytes::from(eip712DomainCall::abi_encode_returns(
+                &eip712DomainReturn {
+                    fields: FixedBytes([0x0f]),
+                    name: "Token".to_string(),
+                    version: "1".to_string(),
+                    chainId: U256::from(self.chain_id),
+                    verifyingContract: verifying_contract,
+                    salt: B256::ZERO,
+                    extensions: vec![],
+                },
+            )))
+        } else {
+            ResponsePayload::internal_error_message("execution reverted".into())
+        }
+    }
+}
+
+fn success<T: serde::Serialize>(value: &T) -> ResponsePayload {
+    let raw = serde_json::value::to_raw_value(value).expect("mock response serializes");
+    ResponsePayload::Success(raw)
+}
+
+impl tower::Service<RequestPacket> for MockNode {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, packet: RequestPacket) -> Self::Future {
+        let node = self.clone();
+        Box::pin(async move {
+            let counters = &node.counters;
+            if counters.in_flight.fetch_add(1, Ordering::SeqCst) == 0 {
+                counters.rounds.fetch_add(1, Ordering::SeqCst);
+            }
+            tokio::time::sleep(RPC_LATENCY).await;
+            let response = match packet {
+                RequestPacket::Single(request) => {
+                    counters.requests.fetch_add(1, Ordering::SeqCst);
+                    ResponsePacket::Single(node.respond(&request))
+                }
+                RequestPacket::Batch(requests) => {
+                    counters
+                        .requests
+                        .fetch_add(requests.len(), Ordering::SeqCst);
+                    ResponsePacket::Batch(requests.iter().map(|r| node.respond(r)).collect())
+                }
+            };
+            counters.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(response)
+        })
+    }
+}
+
+/// Read-only EVM facilitator over the mock node.
+struct BenchProvider {
+    inner: RootProvider<Ethereum>,
+    chain: EvmChain,
+    signer_addresses: Vec<Address>,
+    eip712_domains: Eip712DomainCache,
+    verifications: VerificationCache,
+    permit_settlements: PermitSettlements,
+}
+
+impl MetaEvmProvider for BenchProvider {
+    type Error = FacilitatorLocalError;
+    type Inner = RootProvider<Ethereum>;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    fn chain(&self) -> &EvmChain {
+        &self.chain
+    }
+
+    fn signer_addresses(&self) -> &[Address] {
+        &self.signer_addresses
+    }
+
+    fn eip712_domains(&self) -> &Eip712DomainCache {
+        &self.eip712_domains
+    }
+
+    fn verifications(&self) -> &VerificationCache {
+        &self.verifications
+    }
+
+    fn fee(&self) -> Option<&FacilitatorFee> {
+        None
+    }
+
+    fn permit_settlements(&self) -> &PermitSettlements {
+        &self.permit_settlements
+    }
+
+    async fn send_transaction(
+        &self,
+        _tx: MetaTransaction,
+    ) -> Result<TransactionReceipt, Self::Error> {
+        Err(FacilitatorLocalError::ContractCall(
+            "transactions are not mocked".to_string(),
+        ))
+    }
+}
+
+/// Builds a `/verify` request for 1000 units of `asset`, signed by `payer` for the given domain.
+fn verify_request(
+    payer: &PrivateKeySigner,
+    chain: &EvmChain,
+    asset: Address,
+    name: &str,
+    version: &str,
+    extra: serde_json::Value,
+) -> VerifyRequest {
+    let pay_to = address!("0x000000000000000000000000000000000000beef");
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("clock after epoch")
+        .as_secs();
+    let nonce = B256::from(U256::from(NONCE.fetch_add(1, Ordering::Relaxed)));
+    let authorization = TransferWithAuthorization {
+        from: payer.address(),
+        to: pay_to,
+        value: U256::from(1_000),
+        validAfter: U256::from(now - 60),
+        validBefore: U256::from(now + 600),
+        nonce,
+    };
+    let domain = eip712_domain! {
+        name: name.to_string(),
+        version: version.to_string(),
+        chain_id: chain.chain_id,
+        verifying_contract: asset,
+    };
+    let signature = payer
+        .sign_hash_sync(&authorization.eip712_signing_hash(&domain))
+        .expect("signing succeeds");
+    serde_json::from_value(json!({
+        "x402Version": 1,
+        "paymentPayload": {
+            "x402Version": 1,
+            "scheme": "exact",
+            "network": chain.network,
+            "payload": {
+                "signature": Bytes::from(signature.as_bytes()),
+                "authorization": {
+                    "from": payer.address(),
+                    "to": pay_to,
+                    "value": "1000",
+                    "validAfter": (now - 60).to_string(),
+                    "validBefore": (now + 600).to_string(),
+                    "nonce": nonce,
+                }
+            }
+        },
+        "paymentRequirements": {
+            "scheme": "exact",
+            "network": chain.network,
+            "maxAmountRequired": "1000",
+            "resource": "https://example.com/resource",
+            "description": "",
+            "mimeType": "application/json",
+            "payTo": pay_to,
+            "maxTimeoutSeconds": 600,
+            "asset": asset,
+            "extra": extra,
+        }
+    }))
+    .expect("valid verify request")
+}
+
+/// Runs one verification and reports its RPC requests, rounds and wall time.
+async fn measure(
+    provider: &BenchProvider,
+    counters: &Counters,
+    request: &VerifyRequest,
+) -> (usize, usize, Duration) {
+    counters.take();
+    let started = Instant::now();
+    let response = provider.verify(request).await;
+    let elapsed = started.elapsed();
+    assert!(response.is_ok(), "verification failed: {response:?}");
+    let (requests, rounds) = counters.take();
+    (requests, rounds, elapsed)
+}
+
+async fn scenario(
+    label: &str,
+    provider: &BenchProvider,
+    counters: &Counters,
+    request: impl Fn() -> VerifyRequest,
+) {
+    let mut total = Duration::ZERO;
+    let mut trips = (0, 0);
+    for _ in 0..ITERATIONS {
+        let (requests, rounds, elapsed) = measure(provider, counters, &request()).await;
+        trips = (requests, rounds);
+        total += elapsed;
+    }
+    println!(
+        "{label:<32} {:>8} {:>6} {:>10.1?}",
+        trips.0,
+        trips.1,
+        total / ITERATIONS
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    let network = Network::Base;
+    let chain = EvmChain::try_from(network).expect("EVM network");
+    let counters = Arc::new(Counters::default());
+    let node = MockNode {
+        counters: counters.clone(),
+        chain_id: chain.chain_id,
+    };
+    let provider = BenchProvider {
+        inner: RootProvider::new(RpcClient::new(node, true)),
+        chain,
+        signer_addresses: vec![Address::repeat_byte(0xfa)],
+        eip712_domains: Eip712DomainCache::default(),
+        verifications: VerificationCache::default(),
+        permit_settlements: PermitSettlements::default(),
+    };
+    let payer = PrivateKeySigner::random();
+    let usdc = USDCDeployment::by_network(network);
+    let usdc_address: Address = usdc.address().try_into().expect("EVM USDC");
+    let usdc_eip712 = usdc.eip712.clone().expect("USDC EIP-712 metadata");
+
+    println!("simulated RPC latency {RPC_LATENCY:?}, {ITERATIONS} verifications per scenario\n");
+    println!(
+        "{:<32} {:>8} {:>6} {:>10}",
+        "scenario", "requests", "rounds", "mean"
+    );
+
+    // Cold cache: the domain is discovered once, in the first iteration
+    let cold = Eip712DomainCache::default();
+    let cold_provider = BenchProvider {
+        eip712_domains: cold,
+        inner: provider.inner.clone(),
+        chain,
+        signer_addresses: provider.signer_addresses.clone(),
+        verifications: VerificationCache::default(),
+        permit_settlements: PermitSettlements::default(),
+    };
+    let (requests, rounds, elapsed) = measure(
+        &cold_provider,
+        &counters,
+        &verify_request(&payer, &chain, UNKNOWN_TOKEN, "Token", "1", json!(null)),
+    )
+    .await;
+    println!(
+        "{:<32} {requests:>8} {rounds:>6} {elapsed:>10.1?}",
+        "unknown token, cold cache"
+    );
+    scenario(
+        "unknown token, cached domain",
+        &cold_provider,
+        &counters,
+        || verify_request(&payer, &chain, UNKNOWN_TOKEN, "Token", "1", json!(null)),
+    )
+    .await;
+    scenario("USDC, static metadata", &provider, &counters, || {
+        verify_request(
+            &payer,
+            &chain,
+            usdc_address,
+            &usdc_eip712.name,
+            &usdc_eip712.version,
+            json!(null),
+        )
+    })
+    .await;
+    scenario("token, domain in extra", &provider, &counters, || {
+        verify_request(
+            &payer,
+            &chain,
+            UNKNOWN_TOKEN,
+            "Token",
+            "1",
+            json!({ "name": "Token", "version": "1" }),
+        )
+    })
+    .await;
+}
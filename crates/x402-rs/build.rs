@@ -0,0 +1,15 @@
+//! Generates the gRPC facilitator service from `proto/` with the `grpc` feature.
+//!
+//! The `.proto` files are parsed by `protox`, so no `protoc` is needed.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto");
+        let file_descriptors =
+            protox::compile(["x402/facilitator/v1/facilitator.proto"], ["proto"])?;
+        tonic_build::configure().compile_fds(file_descriptors)?;
+    }
+    Ok(())
+}
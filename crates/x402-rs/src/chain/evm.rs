@@ -5,9 +5,21 @@
 //!   counterfactual wallet inside the same simulation.
 //! - **Settle**: if the signer wallet is not yet deployed, we deploy it (via the 6492
 //!   factory+calldata) and then call ERC-3009 `transferWithAuthorization` in a real tx.
+//! - **Permit**: for tokens with EIP-2612 but without ERC-3009, the payer signs a `permit`
+//!   naming a facilitator signer as `spender`. Verification simulates `permit`; settlement
+//!   submits `permit` and then `transferFrom` to `payTo`, both from that spender.
+//! - **EIP-7702**: a delegated EOA carries a delegation designator as code. Its signature is
+//!   accepted if it is a valid ECDSA signature by the EOA, or if the delegate's ERC-1271
+//!   `isValidSignature` accepts it. Such an account counts as deployed for EIP-6492.
+//! - **Fees**: with a [`FacilitatorFee`] configured, an ERC-3009 payment carries a second
+//!   authorization paying the fee, settled in the same Multicall3 transaction as the payment.
+//!   A permit must cover price and fee; the fee is moved by a second `transferFrom`.
+//! - **EIP-712 domain**: taken from `extra.name`/`extra.version` or the known USDC deployment,
+//!   otherwise discovered from the token via EIP-5267 `eip712Domain()`, falling back to
+//!   `name()`/`version()` checked against `DOMAIN_SEPARATOR()`.
 //!
 //! Assumptions:
-//! - Target tokens implement ERC-3009 and support ERC-1271 for contract signers.
+//! - Target tokens implement ERC-3009 (or EIP-2612) and support ERC-1271 for contract signers.
 //! - The validator contract exists at [`VALIDATOR_ADDRESS`] on supported chains.
 //!
 //! Invariants:
@@ -18,7 +30,7 @@ use alloy_contract::SolCallBuilder;
 use alloy_dyn_abi::SolType;
 use alloy_network::{Ethereum as AlloyEthereum, EthereumWallet, NetworkWallet, TransactionBuilder};
 use alloy_primitives::hex;
-use alloy_primitives::{Address, Bytes, FixedBytes, U256, address};
+use alloy_primitives::{Address, B256, Bytes, FixedBytes, U256, address, keccak256};
 use alloy_provider::ProviderBuilder;
 use alloy_provider::bindings::IMulticall3;
 use alloy_provider::fillers::NonceManager;
@@ -34,8 +46,10 @@ use alloy_sol_types::sol;
 use alloy_sol_types::{Eip712Domain, SolCall, SolStruct, eip712_domain};
 use async_trait::async_trait;
 use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tracing::{Instrument, instrument};
 use tracing_core::Level;
@@ -46,9 +60,10 @@ use crate::from_env;
 use crate::network::{Network, USDCDeployment};
 use crate::timestamp::UnixTimestamp;
 use crate::types::{
-    EvmAddress, EvmSignature, ExactPaymentPayload, FacilitatorErrorReason, HexEncodedNonce,
-    MixedAddress, PaymentPayload, PaymentRequirements, Scheme, SettleRequest, SettleResponse,
-    SupportedPaymentKind, SupportedPaymentKindsResponse, TokenAmount, TransactionHash,
+    EvmAddress, EvmSignature, ExactEvmPayload, ExactEvmPayloadAuthorization, ExactPaymentPayload,
+    FacilitatorErrorReason, FacilitatorFee, HexEncodedNonce, MixedAddress, PaymentPayload,
+    PaymentRequirements, Scheme, SettleRequest, SettleResponse, SettledFee, SupportedPaymentKind,
+    SupportedPaymentKindExtra, SupportedPaymentKindsResponse, TokenAmount, TransactionHash,
     TransferWithAuthorization, VerifyRequest, VerifyResponse, X402Version,
 };
 
@@ -70,9 +85,37 @@ sol! {
     "abi/Validator6492.json"
 }
 
+sol! {
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    #[sol(rpc)]
+    interface IERC1271 {
+        function isValidSignature(bytes32 hash, bytes signature) external view returns (bytes4 magicValue);
+    }
+}
+
+sol! {
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    #[sol(rpc)]
+    interface IERC5267 {
+        function eip712Domain() external view returns (bytes1 fields, string name, string version, uint256 chainId, address verifyingContract, bytes32 salt, uint256[] extensions);
+    }
+}
+
+/// Value returned by ERC-1271 `isValidSignature` for a valid signature.
+pub(crate) const ERC1271_MAGIC_VALUE: [u8; 4] = hex!("1626ba7e");
+
+/// Value of `extra.assetTransferMethod` asking for an EIP-2612 `permit` payload, approving
+/// `extra.spender`, instead of an ERC-3009 authorization.
+pub const ASSET_TRANSFER_METHOD_PERMIT: &str = "permit";
+
+/// Prefix of the code of an EIP-7702 delegated EOA, followed by the 20-byte delegate address.
+pub(crate) const EIP7702_DELEGATION_PREFIX: [u8; 3] = hex!("ef0100");
+
 /// Signature verifier for EIP-6492, EIP-1271, EOA, universally deployed on the supported EVM chains
 /// If absent on a target chain, verification will fail; you should deploy the validator there.
-const VALIDATOR_ADDRESS: alloy_primitives::Address =
+pub(crate) const VALIDATOR_ADDRESS: alloy_primitives::Address =
     address!("0xdAcD51A54883eb67D95FAEb2BBfdC4a9a6BD2a3B");
 
 /// Combined filler type for gas, blob gas, nonce, and chain ID.
@@ -158,6 +201,37 @@ pub struct ExactEvmPayment {
     pub signature: EvmSignature,
 }
 
+impl ExactEvmPayment {
+    /// Signature passed to `transferWithAuthorization`: the inner one if EIP-6492 wrapped.
+    fn transfer_signature(&self) -> Result<Bytes, FacilitatorLocalError> {
+        let structured_signature: StructuredSignature = self.signature.clone().try_into()?;
+        match structured_signature {
+            StructuredSignature::EIP6492 { inner, .. } => Ok(inner),
+            StructuredSignature::EIP1271(signature) => Ok(signature),
+        }
+    }
+}
+
+/// A fully specified EIP-2612 permit payload for EVM settlement via `transferFrom`.
+pub struct ExactEvmPermitPayment {
+    /// Token owner granting the allowance.
+    pub owner: EvmAddress,
+    /// Facilitator signer allowed to spend the allowance.
+    pub spender: EvmAddress,
+    /// Recipient of the follow-up `transferFrom` (`payTo` from the requirements).
+    pub pay_to: EvmAddress,
+    /// Allowance (token units), transferred to `pay_to` minus the fee.
+    pub value: TokenAmount,
+    /// Facilitator fee recipient and amount, taken out of `value`.
+    pub fee: Option<(EvmAddress, TokenAmount)>,
+    /// Sequential EIP-2612 nonce of the owner.
+    pub nonce: TokenAmount,
+    /// Not valid after this timestamp.
+    pub deadline: UnixTimestamp,
+    /// Raw signature bytes (65-byte ECDSA or EIP-1271).
+    pub signature: EvmSignature,
+}
+
 /// EVM implementation of the x402 facilitator.
 ///
 /// Holds a composed Alloy ethereum provider [`InnerProvider`],
@@ -176,6 +250,14 @@ pub struct EvmProvider {
     signer_cursor: Arc<AtomicUsize>,
     /// Nonce manager for resetting nonces on transaction failures.
     nonce_manager: PendingNonceManager,
+    /// EIP-712 domains discovered from token contracts.
+    eip712_domains: Eip712DomainCache,
+    /// Recent successful verifications, reused by settlement.
+    verifications: VerificationCache,
+    /// Fee charged on top of every payment, if any.
+    fee: Option<FacilitatorFee>,
+    /// Permit settlements started and not completed yet.
+    permit_settlements: PermitSettlements,
 }
 
 impl EvmProvider {
@@ -185,6 +267,22 @@ impl EvmProvider {
         rpc_url: &str,
         eip1559: bool,
         network: Network,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let client = RpcClient::builder()
+            .connect(rpc_url)
+            .await
+            .map_err(|e| format!("Failed to connect to {network}: {e}"))?;
+        let provider = Self::try_new_with_client(wallet, client, eip1559, network)?;
+        tracing::info!(network=%network, rpc=rpc_url, signers=?provider.signer_addresses, "Initialized provider");
+        Ok(provider)
+    }
+
+    /// Build an [`EvmProvider`] on top of an already connected RPC `client`.
+    pub fn try_new_with_client(
+        wallet: EthereumWallet,
+        client: RpcClient,
+        eip1559: bool,
+        network: Network,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let chain = EvmChain::try_from(network)?;
         let signer_addresses: Vec<Address> =
@@ -194,10 +292,6 @@ impl EvmProvider {
         }
         let signer_addresses = Arc::new(signer_addresses);
         let signer_cursor = Arc::new(AtomicUsize::new(0));
-        let client = RpcClient::builder()
-            .connect(rpc_url)
-            .await
-            .map_err(|e| format!("Failed to connect to {network}: {e}"))?;
 
         // Create nonce manager explicitly so we can store a reference for error handling
         let nonce_manager = PendingNonceManager::default();
@@ -220,8 +314,6 @@ impl EvmProvider {
             .wallet(wallet)
             .connect_client(client);
 
-        tracing::info!(network=%network, rpc=rpc_url, signers=?signer_addresses, "Initialized provider");
-
         Ok(Self {
             inner,
             eip1559,
@@ -229,9 +321,39 @@ impl EvmProvider {
             signer_addresses,
             signer_cursor,
             nonce_manager,
+            eip712_domains: Eip712DomainCache::default(),
+            verifications: VerificationCache::default(),
+            fee: None,
+            permit_settlements: PermitSettlements::default(),
         })
     }
 
+    /// Replaces the cache of successful verifications, e.g. to configure its limits.
+    pub fn with_verification_cache(mut self, verifications: VerificationCache) -> Self {
+        self.verifications = verifications;
+        self
+    }
+
+    /// Sets the fee charged on top of every payment.
+    pub fn with_fee(mut self, fee: Option<FacilitatorFee>) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Native balance, in wei, of every signer.
+    pub async fn signer_balances(&self) -> Result<Vec<(Address, U256)>, FacilitatorLocalError> {
+        let mut balances = Vec::with_capacity(self.signer_addresses.len());
+        for address in self.signer_addresses.iter() {
+            let balance = self
+                .inner
+                .get_balance(*address)
+                .await
+                .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+            balances.push((*address, balance));
+        }
+        Ok(balances)
+    }
+
     /// Round-robin selection of next signer from wallet.
     fn next_signer_address(&self) -> Address {
         debug_assert!(!self.signer_addresses.is_empty());
@@ -256,6 +378,16 @@ pub trait MetaEvmProvider {
     fn inner(&self) -> &Self::Inner;
     /// Returns reference to chain descriptor.
     fn chain(&self) -> &EvmChain;
+    /// Returns addresses of all signers that can send transactions.
+    fn signer_addresses(&self) -> &[Address];
+    /// Returns the cache of EIP-712 domains discovered from token contracts.
+    fn eip712_domains(&self) -> &Eip712DomainCache;
+    /// Returns the cache of recent successful verifications, consulted by settlement.
+    fn verifications(&self) -> &VerificationCache;
+    /// Returns the fee charged on top of every payment, if any.
+    fn fee(&self) -> Option<&FacilitatorFee>;
+    /// Returns the permit settlements started and not completed yet.
+    fn permit_settlements(&self) -> &PermitSettlements;
 
     /// Sends a meta-transaction to the network.
     fn send_transaction(
@@ -266,6 +398,8 @@ pub trait MetaEvmProvider {
 
 /// Meta-transaction parameters: target address, calldata, and required confirmations.
 pub struct MetaTransaction {
+    /// Signer to send from; `None` selects the next signer in rotation.
+    pub from: Option<Address>,
     /// Target contract address.
     pub to: Address,
     /// Transaction calldata (encoded function call).
@@ -274,6 +408,29 @@ pub struct MetaTransaction {
     pub confirmations: u64,
 }
 
+impl MetaTransaction {
+    /// Runs `calls` in order in one transaction, through Multicall3 `aggregate3` unless it is
+    /// a single call that must not fail, which is sent to its target directly.
+    pub fn batch(mut calls: Vec<IMulticall3::Call3>) -> Self {
+        if calls.len() == 1 && !calls[0].allowFailure {
+            let call = calls.remove(0);
+            return MetaTransaction {
+                from: None,
+                to: call.target,
+                calldata: call.callData,
+                confirmations: 1,
+            };
+        }
+        let aggregate_call = IMulticall3::aggregate3Call { calls };
+        MetaTransaction {
+            from: None,
+            to: MULTICALL3_ADDRESS,
+            calldata: aggregate_call.abi_encode().into(),
+            confirmations: 1,
+        }
+    }
+}
+
 impl MetaEvmProvider for EvmProvider {
     type Error = FacilitatorLocalError;
     type Inner = InnerProvider;
@@ -286,11 +443,31 @@ impl MetaEvmProvider for EvmProvider {
         &self.chain
     }
 
+    fn signer_addresses(&self) -> &[Address] {
+        &self.signer_addresses
+    }
+
+    fn eip712_domains(&self) -> &Eip712DomainCache {
+        &self.eip712_domains
+    }
+
+    fn verifications(&self) -> &VerificationCache {
+        &self.verifications
+    }
+
+    fn fee(&self) -> Option<&FacilitatorFee> {
+        self.fee.as_ref()
+    }
+
+    fn permit_settlements(&self) -> &PermitSettlements {
+        &self.permit_settlements
+    }
+
     /// Send a meta-transaction with provided `to`, `calldata`, and automatically selected signer.
     ///
-    /// This method constructs a transaction from the provided [`MetaTransaction`], automatically
-    /// selects the next available signer using round-robin selection, and handles gas pricing
-    /// based on whether the network supports EIP-1559.
+    /// This method constructs a transaction from the provided [`MetaTransaction`], uses its `from`
+    /// signer or selects the next available one using round-robin selection, and handles gas
+    /// pricing based on whether the network supports EIP-1559.
     ///
     /// If the transaction fails at any point (during submission or receipt fetching), the nonce
     /// for the sending address is reset to force a fresh query on the next transaction. This
@@ -327,7 +504,7 @@ impl MetaEvmProvider for EvmProvider {
         &self,
         tx: MetaTransaction,
     ) -> Result<TransactionReceipt, Self::Error> {
-        let from_address = self.next_signer_address();
+        let from_address = tx.from.unwrap_or_else(|| self.next_signer_address());
         let mut txr = TransactionRequest::default()
             .with_to(tx.to)
             .with_from(from_address)
@@ -413,6 +590,13 @@ impl FromEnvByNetworkBuild for EvmProvider {
             Network::SeiTestnet => true,
         };
         let provider = EvmProvider::try_new(wallet, &rpc_url, is_eip1559, network).await?;
+        let signer_address = provider.inner.default_signer_address();
+        let provider = provider
+            .with_verification_cache(VerificationCache::from_env()?)
+            .with_fee(from_env::facilitator_fee_from_env(
+                network,
+                EvmAddress::from(signer_address),
+            )?);
         Ok(Some(provider))
     }
 }
@@ -429,90 +613,58 @@ where
     /// For EIP-6492 signatures, perform a multicall: first the validator’s
     /// `isValidSigWithSideEffects` (which *may* deploy the counterfactual wallet in sim),
     /// then the token’s `transferWithAuthorization`. Both run within a single `eth_call`
-    /// so the state is shared during simulation.
+    /// so the state is shared during simulation, see [`simulate_transfer`].
+    /// A fee authorization is simulated the same way, concurrently with the payment.
+    ///
+    /// EIP-2612 permit payloads are verified by [`verify_permit`].
     ///
     /// # Errors
     /// - [`FacilitatorLocalError::NetworkMismatch`], [`FacilitatorLocalError::SchemeMismatch`], [`FacilitatorLocalError::ReceiverMismatch`] if inputs are inconsistent.
     /// - [`FacilitatorLocalError::InvalidTiming`] if outside `validAfter/validBefore`.
     /// - [`FacilitatorLocalError::InsufficientFunds`] / `FacilitatorLocalError::InsufficientValue` on balance/value checks.
+    /// - [`FacilitatorLocalError::InsufficientFee`] if the fee authorization is missing or too low.
     /// - [`FacilitatorLocalError::ContractCall`] if on-chain calls revert.
     async fn verify(&self, request: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+        if let ExactPaymentPayload::EvmPermit(_) = request.payment_payload.payload {
+            return verify_permit(self, request).await;
+        }
         let payload = &request.payment_payload;
         let requirements = &request.payment_requirements;
-        let (contract, payment, eip712_domain) =
-            assert_valid_payment(self.inner(), self.chain(), payload, requirements).await?;
-
-        let signed_message = SignedMessage::extract(&payment, &eip712_domain)?;
-        let payer = signed_message.address;
-        let hash = signed_message.hash;
-        match signed_message.signature {
-            StructuredSignature::EIP6492 {
-                factory: _,
-                factory_calldata: _,
-                inner,
-                original,
-            } => {
-                // Prepare the call to validate EIP-6492 signature
-                let validator6492 = Validator6492::new(VALIDATOR_ADDRESS, self.inner());
-                let is_valid_signature_call =
-                    validator6492.isValidSigWithSideEffects(payer, hash, original);
-                // Prepare the call to simulate transfer the funds
-                let transfer_call = transferWithAuthorization_0(&contract, &payment, inner).await?;
-                // Execute both calls in a single transaction simulation to accommodate for possible smart wallet creation
-                let (is_valid_signature_result, transfer_result) = self
-                    .inner()
-                    .multicall()
-                    .add(is_valid_signature_call)
-                    .add(transfer_call.tx)
-                    .aggregate3()
-                    .instrument(tracing::info_span!("call_transferWithAuthorization_0",
-                            from = %transfer_call.from,
-                            to = %transfer_call.to,
-                            value = %transfer_call.value,
-                            valid_after = %transfer_call.valid_after,
-                            valid_before = %transfer_call.valid_before,
-                            nonce = %transfer_call.nonce,
-                            signature = %transfer_call.signature,
-                            token_contract = %transfer_call.contract_address,
-                            otel.kind = "client",
-                    ))
-                    .await
-                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
-                let is_valid_signature_result = is_valid_signature_result
-                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
-                if !is_valid_signature_result {
-                    return Err(FacilitatorLocalError::InvalidSignature(
-                        payer.into(),
-                        "Incorrect signature".to_string(),
-                    ));
+        let ValidPayment {
+            contract,
+            payment,
+            fee_payment,
+            domain: eip712_domain,
+            payer_code,
+        } = assert_valid_payment(
+            self.inner(),
+            self.chain(),
+            self.eip712_domains(),
+            self.fee(),
+            payload,
+            requirements,
+        )
+        .await?;
+
+        // The fee authorization is simulated on its own: its balance is covered by assert_valid_payment
+        let fee_simulation = async {
+            match &fee_payment {
+                Some(fee_payment) => {
+                    simulate_transfer(&contract, fee_payment, &eip712_domain, payer_code).await
                 }
-                transfer_result.map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?;
-            }
-            StructuredSignature::EIP1271(signature) => {
-                // It is EOA or EIP-1271 signature, which we can pass to the transfer simulation
-                let transfer_call =
-                    transferWithAuthorization_0(&contract, &payment, signature).await?;
-                transfer_call
-                    .tx
-                    .call()
-                    .into_future()
-                    .instrument(tracing::info_span!("call_transferWithAuthorization_0",
-                            from = %transfer_call.from,
-                            to = %transfer_call.to,
-                            value = %transfer_call.value,
-                            valid_after = %transfer_call.valid_after,
-                            valid_before = %transfer_call.valid_before,
-                            nonce = %transfer_call.nonce,
-                            signature = %transfer_call.signature,
-                            token_contract = %transfer_call.contract_address,
-                            otel.kind = "client",
-                    ))
-                    .await
-                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+                None => Ok(()),
             }
-        }
+        };
+        let (simulation, fee_simulation) = tokio::join!(
+            simulate_transfer(&contract, &payment, &eip712_domain, payer_code),
+            fee_simulation
+        );
+        simulation?;
+        fee_simulation?;
 
-        Ok(VerifyResponse::valid(payer.into()))
+        self.verifications()
+            .insert(request, eip712_domain, payer_code);
+        Ok(VerifyResponse::valid(payment.from.into()))
     }
 
     /// Settle a verified payment on-chain.
@@ -527,6 +679,11 @@ where
     /// If the wallet is already deployed (or the signature is plain EIP-1271/EOA),
     /// we submit a single `transferWithAuthorization` transaction.
     ///
+    /// A fee authorization is appended to the same batch, so payment and fee
+    /// settle or revert together. Without a deployment, that batch also goes through Multicall3.
+    ///
+    /// EIP-2612 permit payloads are settled by [`settle_permit`].
+    ///
     /// # Returns
     /// A [`SettleResponse`] containing success flag and transaction hash.
     ///
@@ -534,13 +691,63 @@ where
     /// Propagates [`FacilitatorLocalError::ContractCall`] on deployment or transfer failures
     /// and all prior validation errors.
     async fn settle(&self, request: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+        if let ExactPaymentPayload::EvmPermit(_) = request.payment_payload.payload {
+            return settle_permit(self, request).await;
+        }
         let payload = &request.payment_payload;
         let requirements = &request.payment_requirements;
-        let (contract, payment, eip712_domain) =
-            assert_valid_payment(self.inner(), self.chain(), payload, requirements).await?;
+        // Taking the entry invalidates it: a verification is good for one settlement at most
+        let ValidPayment {
+            contract,
+            payment,
+            fee_payment,
+            domain: eip712_domain,
+            payer_code,
+        } = match self.verifications().take(request) {
+            Some(verified) => {
+                assert_verified_payment(
+                    self.inner(),
+                    self.chain(),
+                    verified,
+                    self.fee(),
+                    payload,
+                    requirements,
+                )
+                .await?
+            }
+            None => {
+                assert_valid_payment(
+                    self.inner(),
+                    self.chain(),
+                    self.eip712_domains(),
+                    self.fee(),
+                    payload,
+                    requirements,
+                )
+                .await?
+            }
+        };
 
         let signed_message = SignedMessage::extract(&payment, &eip712_domain)?;
         let payer = signed_message.address;
+        let fee_call = match &fee_payment {
+            Some(fee_payment) => {
+                let signature = fee_payment.transfer_signature()?;
+                let fee_hash = SignedMessage::extract(fee_payment, &eip712_domain)?.hash;
+                if is_delegated_ecdsa(&signature, &fee_hash, &payer, payer_code) {
+                    Some(
+                        transferWithAuthorization_1(&contract, fee_payment, signature)?.multicall(),
+                    )
+                } else {
+                    Some(
+                        transferWithAuthorization_0(&contract, fee_payment, signature)
+                            .await?
+                            .multicall(),
+                    )
+                }
+            }
+            None => None,
+        };
         let transaction_receipt_fut = match signed_message.signature {
             StructuredSignature::EIP6492 {
                 factory,
@@ -550,13 +757,19 @@ where
             } => {
                 let is_contract_deployed = is_contract_deployed(self.inner(), &payer).await?;
                 let transfer_call = transferWithAuthorization_0(&contract, &payment, inner).await?;
+                let transfer_with_authorization_call = IMulticall3::Call3 {
+                    allowFailure: false,
+                    target: transfer_call.tx.target(),
+                    callData: transfer_call.tx.calldata().clone(),
+                };
                 if is_contract_deployed {
                     // transferWithAuthorization with inner signature
-                    self.send_transaction(MetaTransaction {
-                        to: transfer_call.tx.target(),
-                        calldata: transfer_call.tx.calldata().clone(),
-                        confirmations: 1,
-                    })
+                    self.send_transaction(MetaTransaction::batch(
+                        [Some(transfer_with_authorization_call), fee_call]
+                            .into_iter()
+                            .flatten()
+                            .collect(),
+                    ))
                     .instrument(
                         tracing::info_span!("call_transferWithAuthorization_0",
                             from = %transfer_call.from,
@@ -578,19 +791,16 @@ where
                         target: factory,
                         callData: factory_calldata,
                     };
-                    let transfer_with_authorization_call = IMulticall3::Call3 {
-                        allowFailure: false,
-                        target: transfer_call.tx.target(),
-                        callData: transfer_call.tx.calldata().clone(),
-                    };
-                    let aggregate_call = IMulticall3::aggregate3Call {
-                        calls: vec![deployment_call, transfer_with_authorization_call],
-                    };
-                    self.send_transaction(MetaTransaction {
-                        to: MULTICALL3_ADDRESS,
-                        calldata: aggregate_call.abi_encode().into(),
-                        confirmations: 1,
-                    })
+                    self.send_transaction(MetaTransaction::batch(
+                        [
+                            Some(deployment_call),
+                            Some(transfer_with_authorization_call),
+                            fee_call,
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .collect(),
+                    ))
                     .instrument(
                         tracing::info_span!("call_transferWithAuthorization_0",
                             from = %transfer_call.from,
@@ -608,28 +818,52 @@ where
                 }
             }
             StructuredSignature::EIP1271(eip1271_signature) => {
+                // The payer code was found during verification: no need to fetch it again
+                let delegated_ecdsa = is_delegated_ecdsa(
+                    &eip1271_signature,
+                    &signed_message.hash,
+                    &payer,
+                    payer_code,
+                );
+                if let AccountCode::Delegated(_) = payer_code {
+                    assert_delegated_signature(
+                        self.inner(),
+                        payer,
+                        signed_message.hash,
+                        &eip1271_signature,
+                    )
+                    .await?;
+                }
                 let transfer_call =
-                    transferWithAuthorization_0(&contract, &payment, eip1271_signature).await?;
-                // transferWithAuthorization with eip1271 signature
-                self.send_transaction(MetaTransaction {
-                    to: transfer_call.tx.target(),
-                    calldata: transfer_call.tx.calldata().clone(),
-                    confirmations: 1,
-                })
-                .instrument(
-                    tracing::info_span!("call_transferWithAuthorization_0",
-                        from = %transfer_call.from,
-                        to = %transfer_call.to,
-                        value = %transfer_call.value,
-                        valid_after = %transfer_call.valid_after,
-                        valid_before = %transfer_call.valid_before,
-                        nonce = %transfer_call.nonce,
-                        signature = %transfer_call.signature,
-                        token_contract = %transfer_call.contract_address,
-                        sig_kind="EIP1271",
-                        otel.kind = "client",
-                    ),
-                )
+                    transferWithAuthorization_0(&contract, &payment, eip1271_signature.clone())
+                        .await?;
+                let (transfer_with_authorization_call, sig_kind) = if delegated_ecdsa {
+                    // transferWithAuthorization with (v, r, s), see is_delegated_ecdsa
+                    let transfer_call =
+                        transferWithAuthorization_1(&contract, &payment, eip1271_signature)?;
+                    (transfer_call.multicall(), "EIP7702")
+                } else {
+                    // transferWithAuthorization with eip1271 signature
+                    (transfer_call.multicall(), "EIP1271")
+                };
+                self.send_transaction(MetaTransaction::batch(
+                    [Some(transfer_with_authorization_call), fee_call]
+                        .into_iter()
+                        .flatten()
+                        .collect(),
+                ))
+                .instrument(tracing::info_span!("call_transferWithAuthorization",
+                    from = %transfer_call.from,
+                    to = %transfer_call.to,
+                    value = %transfer_call.value,
+                    valid_after = %transfer_call.valid_after,
+                    valid_before = %transfer_call.valid_before,
+                    nonce = %transfer_call.nonce,
+                    signature = %transfer_call.signature,
+                    token_contract = %transfer_call.contract_address,
+                    sig_kind = sig_kind,
+                    otel.kind = "client",
+                ))
             }
         };
         let receipt = transaction_receipt_fut.await?;
@@ -646,6 +880,11 @@ where
                 payer: payment.from.into(),
                 transaction: Some(TransactionHash::Evm(receipt.transaction_hash.0)),
                 network: payload.network,
+                fee: fee_payment.map(|fee_payment| SettledFee {
+                    recipient: fee_payment.to.into(),
+                    amount: fee_payment.value,
+                    transaction: None,
+                }),
             })
         } else {
             tracing::event!(
@@ -660,31 +899,186 @@ where
                 payer: payment.from.into(),
                 transaction: Some(TransactionHash::Evm(receipt.transaction_hash.0)),
                 network: payload.network,
+                fee: None,
             })
         }
     }
 
     /// Report payment kinds supported by this provider on its current network.
+    ///
+    /// The facilitator fee, if any, is advertised in `extra`.
+    /// Advertises ERC-3009 payments first, then EIP-2612 `permit` payments with the signer
+    /// to approve as `extra.spender`. Both carry the facilitator fee, if any.
     async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
-        let kinds = vec![SupportedPaymentKind {
-            network: self.chain().network().to_string(),
+        let network = self.chain().network().to_string();
+        let extra = self
+            .fee()
+            .zip(self.signer_addresses().first())
+            .map(|(fee, signer)| SupportedPaymentKindExtra {
+                fee_payer: (*signer).into(),
+                max_compute_unit_price: None,
+                max_compute_unit_limit: None,
+                max_lamports_per_transaction: None,
+                fee: Some(fee.clone()),
+                asset_transfer_method: None,
+                spender: None,
+            });
+        let mut kinds = vec![SupportedPaymentKind {
+            network: network.clone(),
             x402_version: X402Version::V1,
             scheme: Scheme::Exact,
-            extra: None,
+            extra,
         }];
+        if let Some(signer) = self.signer_addresses().first() {
+            kinds.push(SupportedPaymentKind {
+                network,
+                x402_version: X402Version::V1,
+                scheme: Scheme::Exact,
+                extra: Some(SupportedPaymentKindExtra {
+                    fee_payer: (*signer).into(),
+                    max_compute_unit_price: None,
+                    max_compute_unit_limit: None,
+                    max_lamports_per_transaction: None,
+                    fee: self.fee().cloned(),
+                    asset_transfer_method: Some(ASSET_TRANSFER_METHOD_PERMIT.to_string()),
+                    spender: Some((*signer).into()),
+                }),
+            });
+        }
         Ok(SupportedPaymentKindsResponse { kinds })
     }
 }
 
+/// Simulates `transferWithAuthorization` for `payment` in an `eth_call`, checking its signature.
+///
+/// For EIP-6492 signatures, perform a multicall: first the validator’s
+/// `isValidSigWithSideEffects` (which *may* deploy the counterfactual wallet in sim),
+/// then the token’s `transferWithAuthorization`, so the state is shared during simulation.
+/// An ECDSA signature of an EIP-7702 delegated payer goes through the `(v, r, s)` overload,
+/// see [`is_delegated_ecdsa`]; `payer_code` is the one found by [`assert_valid_payment`].
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] if the signature is rejected, and
+/// [`FacilitatorLocalError::ContractCall`] if the simulation reverts or the RPC call fails.
+async fn simulate_transfer<P: Provider>(
+    contract: &USDC::USDCInstance<P>,
+    payment: &ExactEvmPayment,
+    domain: &Eip712Domain,
+    payer_code: AccountCode,
+) -> Result<(), FacilitatorLocalError> {
+    let signed_message = SignedMessage::extract(payment, domain)?;
+    let payer = signed_message.address;
+    let hash = signed_message.hash;
+    match signed_message.signature {
+        StructuredSignature::EIP6492 {
+            factory: _,
+            factory_calldata: _,
+            inner,
+            original,
+        } => {
+            // Prepare the call to validate EIP-6492 signature
+            let validator6492 = Validator6492::new(VALIDATOR_ADDRESS, contract.provider());
+            let is_valid_signature_call =
+                validator6492.isValidSigWithSideEffects(payer, hash, original);
+            // Prepare the call to simulate transfer the funds
+            let transfer_call = transferWithAuthorization_0(contract, payment, inner).await?;
+            // Execute both calls in a single transaction simulation to accommodate for possible smart wallet creation
+            let (is_valid_signature_result, transfer_result) = contract
+                .provider()
+                .multicall()
+                .add(is_valid_signature_call)
+                .add(transfer_call.tx)
+                .aggregate3()
+                .instrument(tracing::info_span!("call_transferWithAuthorization_0",
+                        from = %transfer_call.from,
+                        to = %transfer_call.to,
+                        value = %transfer_call.value,
+                        valid_after = %transfer_call.valid_after,
+                        valid_before = %transfer_call.valid_before,
+                        nonce = %transfer_call.nonce,
+                        signature = %transfer_call.signature,
+                        token_contract = %transfer_call.contract_address,
+                        otel.kind = "client",
+                ))
+                .await
+                .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+            let is_valid_signature_result = is_valid_signature_result
+                .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+            if !is_valid_signature_result {
+                return Err(FacilitatorLocalError::InvalidSignature(
+                    payer.into(),
+                    "Incorrect signature".to_string(),
+                ));
+            }
+            transfer_result.map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?;
+        }
+        StructuredSignature::EIP1271(signature)
+            if is_delegated_ecdsa(&signature, &hash, &payer, payer_code) =>
+        {
+            // The payer key signed: the `(v, r, s)` overload checks it without asking the delegate
+            let transfer_call = transferWithAuthorization_1(contract, payment, signature)?;
+            transfer_call
+                .tx
+                .call()
+                .into_future()
+                .instrument(tracing::info_span!("call_transferWithAuthorization_1",
+                        from = %transfer_call.from,
+                        to = %transfer_call.to,
+                        value = %transfer_call.value,
+                        valid_after = %transfer_call.valid_after,
+                        valid_before = %transfer_call.valid_before,
+                        nonce = %transfer_call.nonce,
+                        signature = %transfer_call.signature,
+                        token_contract = %transfer_call.contract_address,
+                        otel.kind = "client",
+                ))
+                .await
+                .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+        }
+        StructuredSignature::EIP1271(signature) => {
+            // Any other signature of a 7702 delegated EOA is for its delegate to validate
+            let delegation_check = async {
+                if let AccountCode::Delegated(delegate) = payer_code {
+                    tracing::debug!(payer = %payer, delegate = %delegate, "EIP-7702 delegated payer");
+                    assert_delegated_signature(contract.provider(), payer, hash, &signature)
+                        .await?;
+                }
+                Ok::<_, FacilitatorLocalError>(())
+            };
+            // It is EOA or EIP-1271 signature, which we can pass to the transfer simulation
+            let transfer_call =
+                transferWithAuthorization_0(contract, payment, signature.clone()).await?;
+            let simulation = transfer_call.tx.call().into_future().instrument(
+                tracing::info_span!("call_transferWithAuthorization_0",
+                        from = %transfer_call.from,
+                        to = %transfer_call.to,
+                        value = %transfer_call.value,
+                        valid_after = %transfer_call.valid_after,
+                        valid_before = %transfer_call.valid_before,
+                        nonce = %transfer_call.nonce,
+                        signature = %transfer_call.signature,
+                        token_contract = %transfer_call.contract_address,
+                        otel.kind = "client",
+                ),
+            );
+            // Both are independent reads: run them concurrently, report the signature first
+            let (delegation_check, simulation) = tokio::join!(delegation_check, simulation);
+            delegation_check?;
+            simulation.map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+        }
+    }
+    Ok(())
+}
+
 /// A prepared call to `transferWithAuthorization` (ERC-3009) including all derived fields.
 ///
 /// This struct wraps the assembled call builder, making it reusable across verification
 /// (`.call()`) and settlement (`.send()`) flows, along with context useful for tracing/logging.
 ///
-/// This is created by [`EvmProvider::transferWithAuthorization_0`].
-pub struct TransferWithAuthorization0Call<P> {
+/// This is created by [`transferWithAuthorization_0`] or [`transferWithAuthorization_1`].
+pub struct TransferWithAuthorizationCall<P, C> {
     /// The prepared call builder that can be `.call()`ed or `.send()`ed.
-    pub tx: SolCallBuilder<P, USDC::transferWithAuthorization_0Call>,
+    pub tx: SolCallBuilder<P, C>,
     /// The sender (`from`) address for the authorization.
     pub from: alloy_primitives::Address,
     /// The recipient (`to`) address for the authorization.
@@ -703,6 +1097,25 @@ pub struct TransferWithAuthorization0Call<P> {
     pub contract_address: alloy_primitives::Address,
 }
 
+/// A call to the overload of `transferWithAuthorization` taking the signature as `bytes`.
+pub type TransferWithAuthorization0Call<P> =
+    TransferWithAuthorizationCall<P, USDC::transferWithAuthorization_0Call>;
+
+/// A call to the overload of `transferWithAuthorization` taking the signature as `(v, r, s)`.
+pub type TransferWithAuthorization1Call<P> =
+    TransferWithAuthorizationCall<P, USDC::transferWithAuthorization_1Call>;
+
+impl<P: Provider, C: SolCall> TransferWithAuthorizationCall<P, C> {
+    /// This call as part of a Multicall3 batch, reverting the batch if it fails.
+    fn multicall(&self) -> IMulticall3::Call3 {
+        IMulticall3::Call3 {
+            allowFailure: false,
+            target: self.tx.target(),
+            callData: self.tx.calldata().clone(),
+        }
+    }
+}
+
 /// Validates that the current time is within the `validAfter` and `validBefore` bounds.
 ///
 /// Adds a 6-second grace buffer when checking expiration to account for latency.
@@ -791,11 +1204,59 @@ fn assert_enough_value(
     }
 }
 
+/// Kind of account at an address, judged by its code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountCode {
+    /// No code: a plain EOA, or a counterfactual wallet that is not deployed yet.
+    Empty,
+    /// An EIP-7702 delegated EOA; its code is a delegation designator pointing to the delegate.
+    Delegated(Address),
+    /// A deployed contract.
+    Contract,
+}
+
+impl AccountCode {
+    /// Classifies account `code` as returned by `eth_getCode`.
+    pub fn from_code(code: &[u8]) -> Self {
+        if code.is_empty() {
+            AccountCode::Empty
+        } else if code.len() == EIP7702_DELEGATION_PREFIX.len() + 20
+            && code.starts_with(&EIP7702_DELEGATION_PREFIX)
+        {
+            AccountCode::Delegated(Address::from_slice(
+                &code[EIP7702_DELEGATION_PREFIX.len()..],
+            ))
+        } else {
+            AccountCode::Contract
+        }
+    }
+}
+
+/// Fetches the code at `address` and classifies it as [`AccountCode`].
+///
+/// # Errors
+/// Return [`FacilitatorLocalError::ContractCall`] if the RPC call fails.
+async fn account_code<P: Provider>(
+    provider: P,
+    address: &Address,
+) -> Result<AccountCode, FacilitatorLocalError> {
+    let bytes = provider
+        .get_code_at(*address)
+        .into_future()
+        .instrument(tracing::info_span!("get_code_at",
+            address = %address,
+            otel.kind = "client",
+        ))
+        .await
+        .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+    Ok(AccountCode::from_code(&bytes))
+}
+
 /// Check whether contract code is present at `address`.
 ///
 /// Uses `eth_getCode` against this provider. This is useful after a counterfactual
 /// deployment to confirm visibility on the sending RPC before submitting a
-/// follow-up transaction.
+/// follow-up transaction. An EIP-7702 delegated EOA counts as deployed.
 ///
 /// # Errors
 /// Return [`FacilitatorLocalError::ContractCall`] if the RPC call fails.
@@ -803,118 +1264,553 @@ async fn is_contract_deployed<P: Provider>(
     provider: P,
     address: &Address,
 ) -> Result<bool, FacilitatorLocalError> {
-    let bytes = provider
-        .get_code_at(*address)
+    let code = account_code(provider, address).await?;
+    Ok(code != AccountCode::Empty)
+}
+
+/// Whether `signature` is a 65-byte ECDSA signature of `hash` by `address`.
+fn is_ecdsa_signed_by(signature: &[u8], hash: &FixedBytes<32>, address: &Address) -> bool {
+    recover_ecdsa_signer(signature, hash).is_some_and(|recovered| recovered == *address)
+}
+
+/// Whether `signature` is an ECDSA signature of `hash` by an EIP-7702 delegated `payer`.
+///
+/// Such a signature has to go through the `(v, r, s)` overload of `transferWithAuthorization`:
+/// the `bytes` overload sees code at the payer and asks it for ERC-1271 validation instead,
+/// which reverts if the delegate does not implement `isValidSignature`.
+fn is_delegated_ecdsa(
+    signature: &[u8],
+    hash: &FixedBytes<32>,
+    payer: &Address,
+    payer_code: AccountCode,
+) -> bool {
+    matches!(payer_code, AccountCode::Delegated(_)) && is_ecdsa_signed_by(signature, hash, payer)
+}
+
+/// The EOA that made `signature` over `hash`, if `signature` is a 65-byte ECDSA signature.
+pub(crate) fn recover_ecdsa_signer(signature: &[u8], hash: &FixedBytes<32>) -> Option<Address> {
+    alloy_primitives::Signature::try_from(signature)
+        .and_then(|signature| signature.recover_address_from_prehash(hash))
+        .ok()
+}
+
+/// Rejects a plain 65-byte signature that was not made by the payer, before any simulation.
+///
+/// A signature recovering to the payer is accepted locally. A contract wallet may sign with
+/// 65 bytes that do not recover to it, so on a mismatch the payer's code decides: an account
+/// without code can only sign with ECDSA, and is rejected. EIP-6492 and longer signatures are
+/// left to the on-chain validation.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] if an EOA did not make the signature.
+fn assert_local_signature(
+    signed_message: &SignedMessage,
+    payer_code: AccountCode,
+) -> Result<(), FacilitatorLocalError> {
+    let signature = match &signed_message.signature {
+        StructuredSignature::EIP1271(signature) if signature.len() == 65 => signature,
+        _ => return Ok(()),
+    };
+    let payer = signed_message.address;
+    if is_ecdsa_signed_by(signature, &signed_message.hash, &payer) {
+        return Ok(());
+    }
+    match payer_code {
+        AccountCode::Empty => Err(FacilitatorLocalError::InvalidSignature(
+            payer.into(),
+            "Signature does not recover to the payer".to_string(),
+        )),
+        AccountCode::Delegated(_) | AccountCode::Contract => Ok(()),
+    }
+}
+
+/// Validates a signature made for an EIP-7702 delegated EOA at `address`.
+///
+/// The EOA keeps its private key, so a valid ECDSA signature by it is accepted without RPC.
+/// Otherwise the delegate's code decides: ERC-1271 `isValidSignature` is called on the EOA itself.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] if neither accepts the signature, and
+/// [`FacilitatorLocalError::ContractCall`] if the RPC call fails.
+#[instrument(skip_all, err, fields(address = %address))]
+async fn assert_delegated_signature<P: Provider>(
+    provider: P,
+    address: Address,
+    hash: FixedBytes<32>,
+    signature: &Bytes,
+) -> Result<(), FacilitatorLocalError> {
+    if is_ecdsa_signed_by(signature, &hash, &address) {
+        return Ok(());
+    }
+    let magic_value = IERC1271::new(address, provider)
+        .isValidSignature(hash, signature.clone())
+        .call()
         .into_future()
-        .instrument(tracing::info_span!("get_code_at",
+        .instrument(tracing::info_span!("call_isValidSignature",
             address = %address,
             otel.kind = "client",
         ))
-        .await
-        .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
-    Ok(!bytes.is_empty())
+        .await;
+    match magic_value {
+        Ok(magic_value) if magic_value.0 == ERC1271_MAGIC_VALUE => Ok(()),
+        // Transport failures are not a verdict on the signature
+        Err(alloy_contract::Error::TransportError(e)) if e.as_error_resp().is_none() => {
+            Err(FacilitatorLocalError::ContractCall(format!("{e:?}")))
+        }
+        // Wrong magic value, revert, or a delegate without ERC-1271
+        _ => Err(FacilitatorLocalError::InvalidSignature(
+            address.into(),
+            "Incorrect signature for EIP-7702 delegated account".to_string(),
+        )),
+    }
 }
 
 /// Constructs the correct EIP-712 domain for signature verification.
 ///
 /// Resolves the `name` and `version` based on:
-/// - Static metadata from [`USDCDeployment`] (if available),
-/// - Or by calling `version()` on the token contract if not matched statically.
+/// - `requirements.extra`, which takes precedence,
+/// - Static metadata from [`USDCDeployment`] (if the asset is the known USDC deployment),
+/// - Or the domain the token contract describes itself, see [`Eip712DomainCache::discover`].
+///
+/// The token contract is queried only when neither of the former provides both fields.
 #[instrument(skip_all, err, fields(
-    network = %payload.network,
+    network = %chain.network,
     asset = %asset_address
 ))]
 async fn assert_domain<P: Provider>(
     chain: &EvmChain,
+    eip712_domains: &Eip712DomainCache,
     token_contract: &USDC::USDCInstance<P>,
-    payload: &PaymentPayload,
     asset_address: &Address,
     requirements: &PaymentRequirements,
 ) -> Result<Eip712Domain, FacilitatorLocalError> {
-    let usdc = USDCDeployment::by_network(payload.network);
-    let name = requirements
-        .extra
-        .as_ref()
-        .and_then(|e| e.get("name")?.as_str().map(str::to_string))
-        .or_else(|| usdc.eip712.clone().map(|e| e.name))
-        .ok_or(FacilitatorLocalError::UnsupportedNetwork(None))?;
-    let chain_id = chain.chain_id;
-    let version = requirements
-        .extra
-        .as_ref()
-        .and_then(|extra| extra.get("version"))
-        .and_then(|version| version.as_str().map(|s| s.to_string()));
-    let version = if let Some(extra_version) = version {
-        Some(extra_version)
-    } else if usdc.address() == (*asset_address).into() {
-        usdc.eip712.clone().map(|e| e.version)
-    } else {
-        None
-    };
-    let version = if let Some(version) = version {
-        version
-    } else {
-        token_contract
-            .version()
-            .call()
-            .into_future()
-            .instrument(tracing::info_span!(
-                "fetch_eip712_version",
-                otel.kind = "client",
-            ))
-            .await
-            .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?
+    let (name, version) = static_domain_fields(chain, asset_address, requirements.extra.as_ref());
+    if let (Some(name), Some(version)) = (name.clone(), version.clone()) {
+        let domain = eip712_domain! {
+            name: name,
+            version: version,
+            chain_id: chain.chain_id,
+            verifying_contract: *asset_address,
+        };
+        return Ok(domain);
+    }
+    let mut domain = eip712_domains.discover(chain, token_contract).await?;
+    if let Some(name) = name {
+        domain.name = Some(name.into());
+    }
+    if let Some(version) = version {
+        domain.version = Some(version.into());
+    }
+    Ok(domain)
+}
+
+/// The EIP-712 domain `name` and `version` of `asset_address` known without querying the token.
+///
+/// Taken from `extra`, if present, then from the known [`USDCDeployment`] on the network.
+/// See [`assert_domain`].
+pub(crate) fn static_domain_fields(
+    chain: &EvmChain,
+    asset_address: &Address,
+    extra: Option<&serde_json::Value>,
+) -> (Option<String>, Option<String>) {
+    let extra_field =
+        |field: &str| extra.and_then(|extra| extra.get(field)?.as_str().map(str::to_string));
+    let usdc = USDCDeployment::by_network(chain.network);
+    let known = if usdc.address() == (*asset_address).into() {
+        usdc.eip712.clone()
+    } else {
+        None
     };
-    let domain = eip712_domain! {
-        name: name,
-        version: version,
-        chain_id: chain_id,
-        verifying_contract: *asset_address,
+    let name = extra_field("name").or_else(|| known.as_ref().map(|e| e.name.clone()));
+    let version = extra_field("version").or_else(|| known.map(|e| e.version));
+    (name, version)
+}
+
+/// Default time-to-live of a discovered EIP-712 domain.
+pub const EIP712_DOMAIN_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// EIP-712 domains discovered from token contracts, cached per (network, asset).
+///
+/// A domain only changes when an upgradeable token is upgraded, so entries live for a
+/// long time-to-live, [`EIP712_DOMAIN_CACHE_TTL`] by default.
+#[derive(Debug, Clone)]
+pub struct Eip712DomainCache {
+    domains: Arc<DashMap<(Network, Address), (Eip712Domain, Instant)>>,
+    ttl: Duration,
+}
+
+impl Default for Eip712DomainCache {
+    fn default() -> Self {
+        Self::new(EIP712_DOMAIN_CACHE_TTL)
+    }
+}
+
+impl Eip712DomainCache {
+    /// Creates an empty cache whose entries expire after `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            domains: Arc::new(DashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Returns the EIP-712 domain of `token_contract`, querying the contract on a cache miss.
+    ///
+    /// Uses EIP-5267 `eip712Domain()` when the token implements it. Otherwise reads `name()` and
+    /// `version()`, and checks the resulting domain against `DOMAIN_SEPARATOR()`. A token without
+    /// `version()` is matched against the common versions `"1"` and `"2"`.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::ContractCall`] if the domain can not be determined.
+    pub async fn discover<P: Provider>(
+        &self,
+        chain: &EvmChain,
+        token_contract: &USDC::USDCInstance<P>,
+    ) -> Result<Eip712Domain, FacilitatorLocalError> {
+        let key = (chain.network, *token_contract.address());
+        if let Some(entry) = self.domains.get(&key) {
+            let (domain, cached_at) = entry.value();
+            if cached_at.elapsed() < self.ttl {
+                return Ok(domain.clone());
+            }
+        }
+        let domain = match erc5267_domain(token_contract).await {
+            Some(domain) => domain,
+            None => legacy_domain(chain, token_contract).await?,
+        };
+        tracing::debug!(network = %chain.network, asset = %key.1, ?domain, "Discovered EIP-712 domain");
+        self.domains.insert(key, (domain.clone(), Instant::now()));
+        Ok(domain)
+    }
+}
+
+/// Default time-to-live of a successful verification.
+pub const VERIFICATION_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Default maximum number of cached verifications.
+pub const VERIFICATION_CACHE_MAX_ENTRIES: usize = 10_000;
+
+/// Recent successful verifications, so that settling the same request skips repeated checks.
+///
+/// Sellers usually call `/settle` with the very request they have just verified. Entries are keyed
+/// by the Keccak-256 hash of the JSON-serialized request and hold the EIP-712 domain it was
+/// verified against, with the [`AccountCode`] of the payer. An entry is used by one settlement at most, and only within its time-to-live.
+/// A cache with `max_entries` of zero is disabled.
+#[derive(Debug, Clone)]
+pub struct VerificationCache {
+    entries: Arc<DashMap<B256, (Eip712Domain, AccountCode, Instant)>>,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+impl Default for VerificationCache {
+    fn default() -> Self {
+        Self::new(VERIFICATION_CACHE_TTL, VERIFICATION_CACHE_MAX_ENTRIES)
+    }
+}
+
+impl VerificationCache {
+    /// Creates an empty cache holding up to `max_entries` verifications for `ttl` each.
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            entries: Arc::new(DashMap::new()),
+            ttl,
+            max_entries,
+        }
+    }
+
+    /// Reads the limits from environment, falling back to [`Default`] for unset values.
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let ttl = from_env::parse_optional_env(from_env::ENV_VERIFICATION_CACHE_TTL_SECS)?
+            .map(Duration::from_secs)
+            .unwrap_or(VERIFICATION_CACHE_TTL);
+        let max_entries =
+            from_env::parse_optional_env(from_env::ENV_VERIFICATION_CACHE_MAX_ENTRIES)?
+                .unwrap_or(VERIFICATION_CACHE_MAX_ENTRIES);
+        Ok(Self::new(ttl, max_entries))
+    }
+
+    fn key(request: &VerifyRequest) -> Option<B256> {
+        serde_json::to_vec(request).ok().map(keccak256)
+    }
+
+    /// Records that `request` verified successfully against `domain`, for a payer with `payer_code`.
+    pub fn insert(&self, request: &VerifyRequest, domain: Eip712Domain, payer_code: AccountCode) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let Some(key) = Self::key(request) else {
+            return;
+        };
+        if self.entries.len() >= self.max_entries {
+            self.entries
+                .retain(|_, (_, _, verified_at)| verified_at.elapsed() < self.ttl);
+            if self.entries.len() >= self.max_entries {
+                self.entries.clear();
+            }
+        }
+        self.entries
+            .insert(key, (domain, payer_code, Instant::now()));
+    }
+
+    /// Removes the verification of `request`, returning its domain and payer code if it has not expired.
+    pub fn take(&self, request: &VerifyRequest) -> Option<(Eip712Domain, AccountCode)> {
+        let key = Self::key(request)?;
+        let (_, (domain, payer_code, verified_at)) = self.entries.remove(&key)?;
+        (verified_at.elapsed() < self.ttl).then_some((domain, payer_code))
+    }
+}
+
+/// Permit settlements this facilitator started and has not completed, by token, owner and nonce.
+///
+/// A permit does not bind `payTo`, and the allowance it grants outlives it. Once a permit has landed,
+/// its payload must not be settled again just because the spender holds enough allowance, which
+/// may come from any later approval. Only a settlement started here for the same recipient, value
+/// and signature resumes from a landed permit, see [`permit_state`].
+///
+/// Kept in memory: a restarted facilitator does not resume settlements interrupted before.
+#[derive(Clone, Debug, Default)]
+pub struct PermitSettlements {
+    entries: Arc<DashMap<(Address, Address, U256), PermitSettlement>>,
+}
+
+#[derive(Debug)]
+struct PermitSettlement {
+    /// Hash of the recipient, value and signature of the payment.
+    payment: B256,
+    /// Whether a settlement is running, which another settlement must not race.
+    in_flight: bool,
+}
+
+impl PermitSettlements {
+    fn key(token: Address, payment: &ExactEvmPermitPayment) -> (Address, Address, U256) {
+        (token, payment.owner.0, payment.nonce.0)
+    }
+
+    fn payment_hash(payment: &ExactEvmPermitPayment) -> B256 {
+        keccak256(
+            [
+                payment.pay_to.0.as_slice(),
+                &payment.value.0.to_be_bytes::<32>(),
+                &payment.signature.0,
+            ]
+            .concat(),
+        )
+    }
+
+    /// Whether a settlement of `payment` of `token` started here, and has not completed.
+    fn started(&self, token: Address, payment: &ExactEvmPermitPayment) -> bool {
+        self.entries
+            .get(&Self::key(token, payment))
+            .is_some_and(|entry| entry.payment == Self::payment_hash(payment))
+    }
+
+    /// Records a settlement of `payment` of `token` as running until the returned guard drops,
+    /// or returns `None` if one already is.
+    fn begin(
+        &self,
+        token: Address,
+        payment: &ExactEvmPermitPayment,
+    ) -> Option<PermitSettlementGuard<'_>> {
+        let key = Self::key(token, payment);
+        let settlement = PermitSettlement {
+            payment: Self::payment_hash(payment),
+            in_flight: true,
+        };
+        match self.entries.entry(key) {
+            Entry::Occupied(entry) if entry.get().in_flight => return None,
+            Entry::Occupied(mut entry) => {
+                entry.insert(settlement);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(settlement);
+            }
+        }
+        Some(PermitSettlementGuard {
+            settlements: self,
+            key,
+            settled: false,
+        })
+    }
+}
+
+/// A running permit settlement. Forgotten once [settled](Self::settled), otherwise kept to resume.
+struct PermitSettlementGuard<'a> {
+    settlements: &'a PermitSettlements,
+    key: (Address, Address, U256),
+    settled: bool,
+}
+
+impl PermitSettlementGuard<'_> {
+    /// The payment landed: its permit must never be settled again.
+    fn settled(mut self) {
+        self.settled = true;
+    }
+}
+
+impl Drop for PermitSettlementGuard<'_> {
+    fn drop(&mut self) {
+        if self.settled {
+            self.settlements.entries.remove(&self.key);
+        } else if let Some(mut entry) = self.settlements.entries.get_mut(&self.key) {
+            entry.in_flight = false;
+        }
+    }
+}
+
+/// Reads the EIP-712 domain through EIP-5267 `eip712Domain()`.
+///
+/// Returns `None` if the token does not implement it, or reports extensions we do not understand.
+async fn erc5267_domain<P: Provider>(
+    token_contract: &USDC::USDCInstance<P>,
+) -> Option<Eip712Domain> {
+    let reported = IERC5267::new(*token_contract.address(), token_contract.provider())
+        .eip712Domain()
+        .call()
+        .into_future()
+        .instrument(tracing::info_span!(
+            "fetch_eip712_domain",
+            token_contract = %token_contract.address(),
+            otel.kind = "client",
+        ))
+        .await
+        .inspect_err(|e| tracing::debug!(error = ?e, "eip712Domain() unavailable"))
+        .ok()?;
+    if !reported.extensions.is_empty() {
+        return None;
+    }
+    let fields = reported.fields.0[0];
+    let has = |bit: u8| fields & bit != 0;
+    Some(Eip712Domain::new(
+        has(0x01).then(|| reported.name.into()),
+        has(0x02).then(|| reported.version.into()),
+        has(0x04).then_some(reported.chainId),
+        has(0x08).then_some(reported.verifyingContract),
+        has(0x10).then_some(reported.salt),
+    ))
+}
+
+/// Builds the EIP-712 domain from `name()` and `version()`, checked against `DOMAIN_SEPARATOR()`.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::ContractCall`] if `name()` fails, or if no candidate domain
+/// matches the token's `DOMAIN_SEPARATOR()`.
+async fn legacy_domain<P: Provider>(
+    chain: &EvmChain,
+    token_contract: &USDC::USDCInstance<P>,
+) -> Result<Eip712Domain, FacilitatorLocalError> {
+    let asset_address = *token_contract.address();
+    let name_call = token_contract.name();
+    let version_call = token_contract.version();
+    let separator_call = token_contract.DOMAIN_SEPARATOR();
+    let (name, version, separator) = async {
+        tokio::join!(
+            name_call.call().into_future(),
+            version_call.call().into_future(),
+            separator_call.call().into_future(),
+        )
+    }
+    .instrument(tracing::info_span!(
+        "fetch_eip712_metadata",
+        token_contract = %asset_address,
+        otel.kind = "client",
+    ))
+    .await;
+    let name = name.map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+    let version_known = version.is_ok();
+    let candidates = match version {
+        Ok(version) => vec![version],
+        Err(_) => vec!["1".to_string(), "2".to_string()],
     };
-    Ok(domain)
+    let mut domains = candidates.into_iter().map(|version| {
+        eip712_domain! {
+            name: name.clone(),
+            version: version,
+            chain_id: chain.chain_id,
+            verifying_contract: asset_address,
+        }
+    });
+    let domain = match separator {
+        Ok(separator) => domains.find(|domain| domain.separator() == separator),
+        // Without a separator to compare with, only an explicit version() is trusted
+        Err(_) => domains.next().filter(|_| version_known),
+    };
+    domain.ok_or_else(|| {
+        FacilitatorLocalError::ContractCall(format!(
+            "Can not determine EIP-712 domain of token {asset_address}"
+        ))
+    })
 }
 
-/// Runs all preconditions needed for a successful payment:
-/// - Valid scheme, network, and receiver.
-/// - Valid time window (validAfter/validBefore).
-/// - Correct EIP-712 domain construction.
-/// - Sufficient on-chain balance.
-/// - Sufficient value in payload.
-#[instrument(skip_all, err)]
-async fn assert_valid_payment<P: Provider>(
-    provider: P,
+/// Checks that the payload and requirements target this chain and agree on the scheme.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::NetworkMismatch`] or [`FacilitatorLocalError::SchemeMismatch`].
+fn assert_network_and_scheme(
     chain: &EvmChain,
     payload: &PaymentPayload,
     requirements: &PaymentRequirements,
-) -> Result<(USDC::USDCInstance<P>, ExactEvmPayment, Eip712Domain), FacilitatorLocalError> {
-    let payment_payload = match &payload.payload {
-        ExactPaymentPayload::Evm(payload) => payload,
-        ExactPaymentPayload::Solana(_) => {
-            return Err(FacilitatorLocalError::UnsupportedNetwork(None));
-        }
-    };
-    let payer = payment_payload.authorization.from;
+    payer: &EvmAddress,
+) -> Result<(), FacilitatorLocalError> {
     if payload.network != chain.network {
         return Err(FacilitatorLocalError::NetworkMismatch(
-            Some(payer.into()),
+            Some((*payer).into()),
             chain.network,
             payload.network,
         ));
     }
     if requirements.network != chain.network {
         return Err(FacilitatorLocalError::NetworkMismatch(
-            Some(payer.into()),
+            Some((*payer).into()),
             chain.network,
             requirements.network,
         ));
     }
     if payload.scheme != requirements.scheme {
         return Err(FacilitatorLocalError::SchemeMismatch(
-            Some(payer.into()),
+            Some((*payer).into()),
             requirements.scheme,
             payload.scheme,
         ));
     }
+    Ok(())
+}
+
+/// A payment that passed [`assert_valid_payment`] or [`assert_verified_payment`].
+struct ValidPayment<P> {
+    contract: USDC::USDCInstance<P>,
+    payment: ExactEvmPayment,
+    fee_payment: Option<ExactEvmPayment>,
+    domain: Eip712Domain,
+    /// Code of the payer, which decides how the token is given its signature.
+    payer_code: AccountCode,
+}
+
+/// Runs all preconditions needed for a successful payment:
+/// - Valid scheme, network, and receiver.
+/// - Valid time window (validAfter/validBefore).
+/// - Correct EIP-712 domain construction.
+/// - Plain ECDSA signature by the payer, checked locally (see [`assert_local_signature`]).
+/// - Sufficient on-chain balance, for the price and the fee.
+/// - Sufficient value in payload.
+/// - A fee authorization if the facilitator charges a fee, see [`assert_fee_payment`].
+#[instrument(skip_all, err)]
+async fn assert_valid_payment<P: Provider>(
+    provider: P,
+    chain: &EvmChain,
+    eip712_domains: &Eip712DomainCache,
+    fee: Option<&FacilitatorFee>,
+    payload: &PaymentPayload,
+    requirements: &PaymentRequirements,
+) -> Result<ValidPayment<P>, FacilitatorLocalError> {
+    let payment_payload = match &payload.payload {
+        ExactPaymentPayload::Evm(payload) => payload,
+        ExactPaymentPayload::EvmPermit(_) | ExactPaymentPayload::Solana(_) => {
+            return Err(FacilitatorLocalError::UnsupportedNetwork(None));
+        }
+    };
+    let payer = payment_payload.authorization.from;
+    assert_network_and_scheme(chain, payload, requirements, &payer)?;
     let payload_to: EvmAddress = payment_payload.authorization.to;
     let requirements_to: EvmAddress = requirements
         .pay_to
@@ -936,19 +1832,10 @@ async fn assert_valid_payment<P: Provider>(
         .clone()
         .try_into()
         .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
-    let contract = USDC::new(asset_address, provider);
-
-    let domain = assert_domain(chain, &contract, payload, &asset_address, requirements).await?;
-
     let amount_required = requirements.max_amount_required.0;
-    assert_enough_balance(
-        &contract,
-        &payment_payload.authorization.from,
-        amount_required,
-    )
-    .await?;
     let value: U256 = payment_payload.authorization.value.into();
     assert_enough_value(&payer, &value, &amount_required)?;
+    let fee_payment = assert_fee_payment(chain, fee, payment_payload, requirements)?;
 
     let payment = ExactEvmPayment {
         chain: *chain,
@@ -961,7 +1848,646 @@ async fn assert_valid_payment<P: Provider>(
         signature: payment_payload.signature.clone(),
     };
 
-    Ok((contract, payment, domain))
+    let contract = USDC::new(asset_address, provider);
+    // The domain is static or cached for known tokens: check the signature before spending RPC on it
+    let (domain, payer_code) = tokio::join!(
+        assert_domain(
+            chain,
+            eip712_domains,
+            &contract,
+            &asset_address,
+            requirements,
+        ),
+        account_code(contract.provider(), &payer.0)
+    );
+    let (domain, payer_code) = (domain?, payer_code?);
+    let signed_message = SignedMessage::extract(&payment, &domain)?;
+    assert_local_signature(&signed_message, payer_code)?;
+    let fee_amount = match &fee_payment {
+        Some(fee_payment) => {
+            let signed_message = SignedMessage::extract(fee_payment, &domain)?;
+            assert_local_signature(&signed_message, payer_code)?;
+            fee_payment.value.0
+        }
+        None => U256::ZERO,
+    };
+    assert_enough_balance(
+        &contract,
+        &payer,
+        amount_required.saturating_add(fee_amount),
+    )
+    .await?;
+
+    Ok(ValidPayment {
+        contract,
+        payment,
+        fee_payment,
+        domain,
+        payer_code,
+    })
+}
+
+/// Checks the fee authorization of an ERC-3009 payment against the facilitator fee.
+///
+/// Returns `None` if the facilitator charges no fee for this payment; a fee authorization
+/// sent anyway is ignored. Otherwise the fee authorization must be from the payer,
+/// to the fee recipient, for at least the fee, within its time window, and must not
+/// reuse the nonce of the payment.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InsufficientFee`] if the fee authorization is missing or
+/// does not pay the fee, and [`FacilitatorLocalError::InvalidTiming`] if it is not valid now.
+fn assert_fee_payment(
+    chain: &EvmChain,
+    fee: Option<&FacilitatorFee>,
+    payment_payload: &ExactEvmPayload,
+    requirements: &PaymentRequirements,
+) -> Result<Option<ExactEvmPayment>, FacilitatorLocalError> {
+    let Some(fee) = fee else {
+        return Ok(None);
+    };
+    let amount = fee.amount(requirements.max_amount_required);
+    if amount.0.is_zero() {
+        return Ok(None);
+    }
+    let payer = payment_payload.authorization.from;
+    let fee_payload = payment_payload.fee.as_ref().ok_or_else(|| {
+        FacilitatorLocalError::InsufficientFee(
+            payer.into(),
+            format!("Missing fee authorization for {amount}"),
+        )
+    })?;
+    let authorization: &ExactEvmPayloadAuthorization = &fee_payload.authorization;
+    let recipient: EvmAddress = fee
+        .recipient
+        .clone()
+        .try_into()
+        .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
+    if authorization.from != payer {
+        return Err(FacilitatorLocalError::InsufficientFee(
+            payer.into(),
+            format!("Fee authorization from {}", authorization.from),
+        ));
+    }
+    if authorization.to != recipient {
+        return Err(FacilitatorLocalError::InsufficientFee(
+            payer.into(),
+            format!(
+                "Fee authorization to {}, expected {recipient}",
+                authorization.to
+            ),
+        ));
+    }
+    if authorization.value < amount {
+        return Err(FacilitatorLocalError::InsufficientFee(
+            payer.into(),
+            format!(
+                "Fee authorization of {}, expected {amount}",
+                authorization.value
+            ),
+        ));
+    }
+    if authorization.nonce == payment_payload.authorization.nonce {
+        return Err(FacilitatorLocalError::InsufficientFee(
+            payer.into(),
+            "Fee authorization reuses the payment nonce".to_string(),
+        ));
+    }
+    assert_time(
+        payer.into(),
+        authorization.valid_after,
+        authorization.valid_before,
+    )?;
+    Ok(Some(ExactEvmPayment {
+        chain: *chain,
+        from: authorization.from,
+        to: authorization.to,
+        value: authorization.value,
+        valid_after: authorization.valid_after,
+        valid_before: authorization.valid_before,
+        nonce: authorization.nonce,
+        signature: fee_payload.signature.clone(),
+    }))
+}
+
+/// Re-checks a payment that passed [`assert_valid_payment`] recently, see [`VerificationCache`].
+///
+/// Only what may have changed since verification is checked again: the time window,
+/// and whether the authorization nonces of the payment and the fee have been used.
+#[instrument(skip_all, err)]
+async fn assert_verified_payment<P: Provider>(
+    provider: P,
+    chain: &EvmChain,
+    (domain, payer_code): (Eip712Domain, AccountCode),
+    fee: Option<&FacilitatorFee>,
+    payload: &PaymentPayload,
+    requirements: &PaymentRequirements,
+) -> Result<ValidPayment<P>, FacilitatorLocalError> {
+    let payment_payload = match &payload.payload {
+        ExactPaymentPayload::Evm(payload) => payload,
+        ExactPaymentPayload::EvmPermit(_) | ExactPaymentPayload::Solana(_) => {
+            return Err(FacilitatorLocalError::UnsupportedNetwork(None));
+        }
+    };
+    let authorization = &payment_payload.authorization;
+    assert_time(
+        authorization.from.into(),
+        authorization.valid_after,
+        authorization.valid_before,
+    )?;
+    let asset_address = requirements
+        .asset
+        .clone()
+        .try_into()
+        .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
+    let payment = ExactEvmPayment {
+        chain: *chain,
+        from: authorization.from,
+        to: authorization.to,
+        value: authorization.value,
+        valid_after: authorization.valid_after,
+        valid_before: authorization.valid_before,
+        nonce: authorization.nonce,
+        signature: payment_payload.signature.clone(),
+    };
+    let fee_payment = assert_fee_payment(chain, fee, payment_payload, requirements)?;
+    let contract = USDC::new(asset_address, provider);
+    assert_authorization_unused(&contract, &payment).await?;
+    if let Some(fee_payment) = &fee_payment {
+        assert_authorization_unused(&contract, fee_payment).await?;
+    }
+    Ok(ValidPayment {
+        contract,
+        payment,
+        fee_payment,
+        domain,
+        payer_code,
+    })
+}
+
+/// Checks that the ERC-3009 authorization nonce has not been used or canceled.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] if the authorization can no longer be used.
+/// Returns [`FacilitatorLocalError::ContractCall`] if the `authorizationState()` query fails.
+async fn assert_authorization_unused<P: Provider>(
+    contract: &USDC::USDCInstance<P>,
+    payment: &ExactEvmPayment,
+) -> Result<(), FacilitatorLocalError> {
+    let nonce = FixedBytes(payment.nonce.0);
+    let used = contract
+        .authorizationState(payment.from.0, nonce)
+        .call()
+        .into_future()
+        .instrument(tracing::info_span!(
+            "fetch_authorization_state",
+            token_contract = %contract.address(),
+            authorizer = %payment.from,
+            nonce = %nonce,
+            otel.kind = "client"
+        ))
+        .await
+        .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+    if used {
+        return Err(FacilitatorLocalError::InvalidSignature(
+            payment.from.into(),
+            "Authorization already used".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Runs all preconditions needed for a successful permit payment:
+/// - Valid scheme and network.
+/// - `spender` is one of the facilitator signers.
+/// - Permit `deadline` not yet passed.
+/// - Sufficient on-chain balance.
+/// - Sufficient value in payload.
+#[instrument(skip_all, err)]
+async fn assert_valid_permit_payment<P: Provider>(
+    provider: P,
+    chain: &EvmChain,
+    signer_addresses: &[Address],
+    fee: Option<&FacilitatorFee>,
+    payload: &PaymentPayload,
+    requirements: &PaymentRequirements,
+) -> Result<(USDC::USDCInstance<P>, ExactEvmPermitPayment), FacilitatorLocalError> {
+    let permit_payload = match &payload.payload {
+        ExactPaymentPayload::EvmPermit(payload) => payload,
+        ExactPaymentPayload::Evm(_) | ExactPaymentPayload::Solana(_) => {
+            return Err(FacilitatorLocalError::UnsupportedNetwork(None));
+        }
+    };
+    let permit = permit_payload.permit;
+    let payer = permit.owner;
+    assert_network_and_scheme(chain, payload, requirements, &payer)?;
+    if !signer_addresses.contains(&permit.spender.0) {
+        return Err(FacilitatorLocalError::SpenderMismatch(
+            payer.into(),
+            permit.spender.to_string(),
+            format!("{signer_addresses:?}"),
+        ));
+    }
+    let pay_to: EvmAddress = requirements
+        .pay_to
+        .clone()
+        .try_into()
+        .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
+    assert_time(payer.into(), UnixTimestamp(0), permit.deadline)?;
+    let asset_address = requirements
+        .asset
+        .clone()
+        .try_into()
+        .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
+    let contract = USDC::new(asset_address, provider);
+
+    let amount_required = requirements.max_amount_required.0;
+    let value: U256 = permit.value.into();
+    assert_enough_value(&payer, &value, &amount_required)?;
+    // The allowance pays the fee too, on top of the price
+    let fee = match fee {
+        Some(fee) => {
+            let amount = fee.amount(requirements.max_amount_required);
+            let recipient: EvmAddress = fee
+                .recipient
+                .clone()
+                .try_into()
+                .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
+            (!amount.0.is_zero()).then_some((recipient, amount))
+        }
+        None => None,
+    };
+    let fee_amount = fee.map(|(_, amount)| amount.0).unwrap_or_default();
+    let amount_with_fee = amount_required.saturating_add(fee_amount);
+    if value < amount_with_fee {
+        return Err(FacilitatorLocalError::InsufficientFee(
+            payer.into(),
+            format!("Permit of {value}, expected {amount_required} plus fee {fee_amount}"),
+        ));
+    }
+    assert_enough_balance(&contract, &payer, amount_with_fee).await?;
+
+    let payment = ExactEvmPermitPayment {
+        owner: permit.owner,
+        spender: permit.spender,
+        pay_to,
+        value: permit.value,
+        fee,
+        nonce: permit.nonce,
+        deadline: permit.deadline,
+        signature: permit_payload.signature.clone(),
+    };
+
+    Ok((contract, payment))
+}
+
+/// Whether the `permit` of a payment still has to be submitted, see [`permit_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PermitState {
+    /// The permit carries the owner's current nonce.
+    Pending,
+    /// The permit nonce is the last one consumed by a settlement of this payment started here,
+    /// and the spender holds the permitted allowance: the settlement, interrupted after `permit`
+    /// landed, resumes with `transferFrom`.
+    Granted,
+}
+
+/// Checks the permit against the owner's EIP-2612 nonce.
+///
+/// A permit with the current nonce is [`PermitState::Pending`]. A permit with the nonce just
+/// consumed is [`PermitState::Granted`] only if its settlement started here, see
+/// [`PermitSettlements`], and the spender's allowance still covers its value. An allowance alone
+/// proves nothing: it may come from a later approval, and would let the permit be replayed.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] if the nonce is stale or from the future.
+/// Returns [`FacilitatorLocalError::ContractCall`] if the `nonces()` or `allowance()` query fails.
+async fn permit_state<P: Provider>(
+    contract: &USDC::USDCInstance<P>,
+    payment: &ExactEvmPermitPayment,
+    settlements: &PermitSettlements,
+) -> Result<PermitState, FacilitatorLocalError> {
+    let nonce = contract
+        .nonces(payment.owner.0)
+        .call()
+        .into_future()
+        .instrument(tracing::info_span!(
+            "fetch_permit_nonce",
+            token_contract = %contract.address(),
+            owner = %payment.owner,
+            otel.kind = "client"
+        ))
+        .await
+        .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+    if nonce == payment.nonce.0 {
+        return Ok(PermitState::Pending);
+    }
+    if nonce == payment.nonce.0.saturating_add(U256::ONE)
+        && settlements.started(*contract.address(), payment)
+    {
+        let allowance = contract
+            .allowance(payment.owner.0, payment.spender.0)
+            .call()
+            .into_future()
+            .instrument(tracing::info_span!(
+                "fetch_allowance",
+                token_contract = %contract.address(),
+                owner = %payment.owner,
+                spender = %payment.spender,
+                otel.kind = "client"
+            ))
+            .await
+            .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+        if allowance >= payment.value.0 {
+            return Ok(PermitState::Granted);
+        }
+    }
+    Err(FacilitatorLocalError::InvalidSignature(
+        payment.owner.into(),
+        format!(
+            "Permit nonce mismatch: expected {nonce}, got {}",
+            payment.nonce
+        ),
+    ))
+}
+
+/// Encodes the `permit` calldata for a validated permit payment.
+///
+/// 65-byte signatures go to the canonical EIP-2612 `(v, r, s)` overload. Other signatures go to
+/// the `bytes` overload, which tokens like USDC validate through ERC-1271.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] for EIP-6492 wrapped signatures:
+/// a permit can not deploy the owner wallet.
+fn permit_calldata(payment: &ExactEvmPermitPayment) -> Result<Bytes, FacilitatorLocalError> {
+    let owner: Address = payment.owner.into();
+    let spender: Address = payment.spender.into();
+    let value: U256 = payment.value.into();
+    let deadline: U256 = payment.deadline.into();
+    let structured_signature: StructuredSignature = payment.signature.clone().try_into()?;
+    let signature = match structured_signature {
+        StructuredSignature::EIP6492 { .. } => {
+            return Err(FacilitatorLocalError::InvalidSignature(
+                owner.into(),
+                "EIP-6492 signatures are not supported for permit".to_string(),
+            ));
+        }
+        StructuredSignature::EIP1271(signature) => signature,
+    };
+    let calldata = if signature.len() == 65 {
+        let v = signature[64];
+        USDC::permit_1Call {
+            owner,
+            spender,
+            value,
+            deadline,
+            v: if v < 27 { v + 27 } else { v },
+            r: FixedBytes::from_slice(&signature[..32]),
+            s: FixedBytes::from_slice(&signature[32..64]),
+        }
+        .abi_encode()
+    } else {
+        USDC::permit_0Call {
+            owner,
+            spender,
+            value,
+            deadline,
+            signature,
+        }
+        .abi_encode()
+    };
+    Ok(calldata.into())
+}
+
+/// Verify an EIP-2612 permit payment.
+///
+/// Runs [`assert_valid_permit_payment`], checks the owner's permit nonce, and simulates `permit`
+/// in an `eth_call` unless it has landed already. The follow-up `transferFrom` is not simulated:
+/// it must be sent by the spender, and the allowance it spends only exists once `permit` has landed.
+async fn verify_permit<P>(
+    provider: &P,
+    request: &VerifyRequest,
+) -> Result<VerifyResponse, FacilitatorLocalError>
+where
+    P: MetaEvmProvider + Sync,
+{
+    let (contract, payment) = assert_valid_permit_payment(
+        provider.inner(),
+        provider.chain(),
+        provider.signer_addresses(),
+        provider.fee(),
+        &request.payment_payload,
+        &request.payment_requirements,
+    )
+    .await?;
+    if permit_state(&contract, &payment, provider.permit_settlements()).await?
+        == PermitState::Granted
+    {
+        return Ok(VerifyResponse::valid(payment.owner.into()));
+    }
+    let permit_tx = TransactionRequest::default()
+        .with_to(*contract.address())
+        .with_input(permit_calldata(&payment)?);
+    provider
+        .inner()
+        .call(permit_tx)
+        .into_future()
+        .instrument(tracing::info_span!("call_permit",
+            owner = %payment.owner,
+            spender = %payment.spender,
+            value = %payment.value,
+            deadline = %payment.deadline,
+            token_contract = %contract.address(),
+            otel.kind = "client",
+        ))
+        .await
+        .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+    Ok(VerifyResponse::valid(payment.owner.into()))
+}
+
+/// Settle an EIP-2612 permit payment on-chain.
+///
+/// Submits `permit`, then `transferFrom(owner, payTo, value - fee)`, both from the spender signer.
+/// The two calls can not share a Multicall3 batch: `transferFrom` must come from the spender,
+/// and naming Multicall3 as spender would let anyone replay the permit to their own address.
+///
+/// `permit` is submitted unless a settlement of the same payment started here already landed it,
+/// and its allowance is unspent, see [`permit_state`]. Concurrent settlements of one permit are
+/// refused, and a settled permit is never resumed, so a payload pays out at most once.
+///
+/// The facilitator fee, if any, is moved by one more `transferFrom` once the payment landed.
+/// The payment stands if that fails, as the payee is already paid: the fee is lost, and the
+/// response reports no fee. This is the known loss documented in the README fee section.
+async fn settle_permit<P>(
+    provider: &P,
+    request: &SettleRequest,
+) -> Result<SettleResponse, FacilitatorLocalError>
+where
+    P: MetaEvmProvider + Sync,
+    FacilitatorLocalError: From<P::Error>,
+{
+    let network = request.payment_payload.network;
+    let (contract, payment) = assert_valid_permit_payment(
+        provider.inner(),
+        provider.chain(),
+        provider.signer_addresses(),
+        provider.fee(),
+        &request.payment_payload,
+        &request.payment_requirements,
+    )
+    .await?;
+    let settlements = provider.permit_settlements();
+    let permit_state = permit_state(&contract, &payment, settlements).await?;
+    let settlement = settlements
+        .begin(*contract.address(), &payment)
+        .ok_or_else(|| {
+            FacilitatorLocalError::InvalidSignature(
+                payment.owner.into(),
+                format!("Permit nonce {} is being settled already", payment.nonce),
+            )
+        })?;
+    let spender: Address = payment.spender.into();
+    if permit_state == PermitState::Pending {
+        let permit_receipt = provider
+            .send_transaction(MetaTransaction {
+                from: Some(spender),
+                to: *contract.address(),
+                calldata: permit_calldata(&payment)?,
+                confirmations: 1,
+            })
+            .instrument(tracing::info_span!("call_permit",
+                owner = %payment.owner,
+                spender = %payment.spender,
+                value = %payment.value,
+                deadline = %payment.deadline,
+                token_contract = %contract.address(),
+                otel.kind = "client",
+            ))
+            .await?;
+        if !permit_receipt.status() {
+            tracing::event!(
+                Level::WARN,
+                status = "failed",
+                tx = %permit_receipt.transaction_hash,
+                "permit failed"
+            );
+            return Ok(SettleResponse {
+                success: false,
+                error_reason: Some(FacilitatorErrorReason::InvalidScheme),
+                payer: payment.owner.into(),
+                transaction: Some(TransactionHash::Evm(permit_receipt.transaction_hash.0)),
+                network,
+                fee: None,
+            });
+        }
+    }
+    let fee_amount = payment.fee.map(|(_, amount)| amount.0).unwrap_or_default();
+    let transfer_from_call = contract.transferFrom(
+        payment.owner.into(),
+        payment.pay_to.into(),
+        payment.value.0.saturating_sub(fee_amount),
+    );
+    let receipt = provider
+        .send_transaction(MetaTransaction {
+            from: Some(spender),
+            to: transfer_from_call.target(),
+            calldata: transfer_from_call.calldata().clone(),
+            confirmations: 1,
+        })
+        .instrument(tracing::info_span!("call_transferFrom",
+            from = %payment.owner,
+            to = %payment.pay_to,
+            value = %payment.value,
+            spender = %payment.spender,
+            token_contract = %contract.address(),
+            otel.kind = "client",
+        ))
+        .await?;
+    let success = receipt.status();
+    if success {
+        settlement.settled();
+        tracing::event!(Level::INFO,
+            status = "ok",
+            tx = %receipt.transaction_hash,
+            "transferFrom succeeded"
+        );
+    } else {
+        tracing::event!(
+            Level::WARN,
+            status = "failed",
+            tx = %receipt.transaction_hash,
+            "transferFrom failed"
+        );
+    }
+    let fee = match payment.fee {
+        Some((recipient, amount)) if success => {
+            settle_permit_fee(provider, &contract, &payment, recipient, amount).await
+        }
+        _ => None,
+    };
+    Ok(SettleResponse {
+        success,
+        error_reason: (!success).then_some(FacilitatorErrorReason::InvalidScheme),
+        payer: payment.owner.into(),
+        transaction: Some(TransactionHash::Evm(receipt.transaction_hash.0)),
+        network,
+        fee,
+    })
+}
+
+/// Moves the facilitator fee of a settled permit payment with `transferFrom(owner, recipient, amount)`.
+///
+/// Returns `None`, after logging, if the transfer fails: the payment itself already landed.
+async fn settle_permit_fee<P>(
+    provider: &P,
+    contract: &USDC::USDCInstance<&P::Inner>,
+    payment: &ExactEvmPermitPayment,
+    recipient: EvmAddress,
+    amount: TokenAmount,
+) -> Option<SettledFee>
+where
+    P: MetaEvmProvider + Sync,
+    FacilitatorLocalError: From<P::Error>,
+{
+    let transfer_from_call =
+        contract.transferFrom(payment.owner.into(), recipient.into(), amount.into());
+    let receipt = provider
+        .send_transaction(MetaTransaction {
+            from: Some(payment.spender.into()),
+            to: transfer_from_call.target(),
+            calldata: transfer_from_call.calldata().clone(),
+            confirmations: 1,
+        })
+        .instrument(tracing::info_span!("call_transferFrom",
+            from = %payment.owner,
+            to = %recipient,
+            value = %amount,
+            spender = %payment.spender,
+            token_contract = %contract.address(),
+            otel.kind = "client",
+        ))
+        .await
+        .map_err(FacilitatorLocalError::from);
+    match receipt {
+        Ok(receipt) if receipt.status() => Some(SettledFee {
+            recipient: recipient.into(),
+            amount,
+            transaction: Some(TransactionHash::Evm(receipt.transaction_hash.0)),
+        }),
+        Ok(receipt) => {
+            tracing::event!(
+                Level::WARN,
+                status = "failed",
+                tx = %receipt.transaction_hash,
+                "fee transferFrom failed"
+            );
+            None
+        }
+        Err(error) => {
+            tracing::event!(Level::WARN, status = "failed", error = %error, "fee transferFrom failed");
+            None
+        }
+    }
 }
 
 /// Constructs a full `transferWithAuthorization` call for a verified payment payload.
@@ -1005,6 +2531,49 @@ async fn transferWithAuthorization_0<'a, P: Provider>(
     })
 }
 
+/// Constructs a call to the `(v, r, s)` overload of `transferWithAuthorization`, see [`is_delegated_ecdsa`].
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] if `signature` is not a 65-byte ECDSA signature.
+#[allow(non_snake_case)]
+fn transferWithAuthorization_1<'a, P: Provider>(
+    contract: &'a USDC::USDCInstance<P>,
+    payment: &ExactEvmPayment,
+    signature: Bytes,
+) -> Result<TransferWithAuthorization1Call<&'a P>, FacilitatorLocalError> {
+    let ecdsa = alloy_primitives::Signature::try_from(signature.as_ref()).map_err(|e| {
+        FacilitatorLocalError::InvalidSignature(payment.from.into(), format!("{e}"))
+    })?;
+    let from: Address = payment.from.into();
+    let to: Address = payment.to.into();
+    let value: U256 = payment.value.into();
+    let valid_after: U256 = payment.valid_after.into();
+    let valid_before: U256 = payment.valid_before.into();
+    let nonce = FixedBytes(payment.nonce.0);
+    let tx = contract.transferWithAuthorization_1(
+        from,
+        to,
+        value,
+        valid_after,
+        valid_before,
+        nonce,
+        27 + u8::from(ecdsa.v()),
+        ecdsa.r().into(),
+        ecdsa.s().into(),
+    );
+    Ok(TransferWithAuthorization1Call {
+        tx,
+        from,
+        to,
+        value,
+        valid_after,
+        valid_before,
+        nonce,
+        signature,
+        contract_address: *contract.address(),
+    })
+}
+
 /// A structured representation of an Ethereum signature.
 ///
 /// This enum normalizes two supported cases:
@@ -1014,7 +2583,7 @@ async fn transferWithAuthorization_0<'a, P: Provider>(
 ///   signature that the wallet contract will validate after deployment.
 /// - **EIP-1271 signatures**: plain contract (or EOA-style) signatures.
 #[derive(Debug, Clone)]
-enum StructuredSignature {
+pub(crate) enum StructuredSignature {
     /// An EIP-6492 wrapped signature.
     EIP6492 {
         /// Factory contract that can deploy the wallet deterministically
@@ -1093,7 +2662,7 @@ impl SignedMessage {
 ///
 /// Any signature ending with this constant is treated as a 6492-wrapped
 /// signature; the preceding bytes are ABI-decoded as `(address factory, bytes factoryCalldata, bytes innerSig)`.
-const EIP6492_MAGIC_SUFFIX: [u8; 32] =
+pub(crate) const EIP6492_MAGIC_SUFFIX: [u8; 32] =
     hex!("6492649264926492649264926492649264926492649264926492649264926492");
 
 sol! {
@@ -1233,6 +2802,7 @@ impl PendingNonceManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::ExactEvmFeePayload;
     use alloy_primitives::address;
 
     #[tokio::test]
@@ -1377,4 +2947,303 @@ mod tests {
             assert_eq!(*nonce_lock.lock().await, u64::MAX);
         }
     }
+
+    #[test]
+    fn test_account_code_from_code() {
+        let delegate = address!("0x63c0c19a282a1B52b07dD5a65b58948A07DAE32B");
+        let mut designator = EIP7702_DELEGATION_PREFIX.to_vec();
+        designator.extend_from_slice(delegate.as_slice());
+        assert_eq!(AccountCode::from_code(&[]), AccountCode::Empty);
+        assert_eq!(
+            AccountCode::from_code(&designator),
+            AccountCode::Delegated(delegate)
+        );
+        assert_eq!(
+            AccountCode::from_code(&designator[..22]),
+            AccountCode::Contract
+        );
+        assert_eq!(
+            AccountCode::from_code(&hex!("6080604052")),
+            AccountCode::Contract
+        );
+    }
+
+    fn mocked_provider() -> (impl Provider, alloy_transport::mock::Asserter) {
+        let asserter = alloy_transport::mock::Asserter::new();
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+        (provider, asserter)
+    }
+
+    #[tokio::test]
+    async fn test_delegated_signature_accepts_ecdsa_without_rpc() {
+        use alloy_signer::SignerSync;
+        let signer = alloy_signer_local::PrivateKeySigner::random();
+        let hash = FixedBytes::<32>::repeat_byte(0x11);
+        let signature = Bytes::from(signer.sign_hash_sync(&hash).unwrap().as_bytes());
+        // No response queued: any RPC call would fail
+        let (provider, _asserter) = mocked_provider();
+        assert_delegated_signature(provider, signer.address(), hash, &signature)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delegated_signature_falls_back_to_erc1271() {
+        use alloy_signer::SignerSync;
+        let signer = alloy_signer_local::PrivateKeySigner::random();
+        let account = address!("0x0000000000000000000000000000000000007702");
+        let hash = FixedBytes::<32>::repeat_byte(0x22);
+        let signature = Bytes::from(signer.sign_hash_sync(&hash).unwrap().as_bytes());
+
+        let (provider, asserter) = mocked_provider();
+        let mut magic = [0u8; 32];
+        magic[..4].copy_from_slice(&ERC1271_MAGIC_VALUE);
+        asserter.push_success(&Bytes::from(magic));
+        assert_delegated_signature(&provider, account, hash, &signature)
+            .await
+            .unwrap();
+
+        asserter.push_success(&Bytes::from([0u8; 32]));
+        let err = assert_delegated_signature(&provider, account, hash, &signature)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FacilitatorLocalError::InvalidSignature(..)));
+    }
+
+    #[tokio::test]
+    async fn test_discover_domain_from_erc5267() {
+        let (provider, asserter) = mocked_provider();
+        let chain = EvmChain::try_from(Network::Base).unwrap();
+        let asset = address!("0x0000000000000000000000000000000000005267");
+        asserter.push_success(&Bytes::from(
+            IERC5267::eip712DomainCall::abi_encode_returns(&IERC5267::eip712DomainReturn {
+                fields: FixedBytes([0x0f]),
+                name: "Token".to_string(),
+                version: "3".to_string(),
+                chainId: U256::from(chain.chain_id),
+                verifyingContract: asset,
+                salt: FixedBytes::ZERO,
+                extensions: vec![],
+            }),
+        ));
+        let cache = Eip712DomainCache::default();
+        let contract = USDC::new(asset, &provider);
+        let expected = eip712_domain! {
+            name: "Token",
+            version: "3",
+            chain_id: chain.chain_id,
+            verifying_contract: asset,
+        };
+        assert_eq!(cache.discover(&chain, &contract).await.unwrap(), expected);
+        // Served from cache: no response is queued for a second lookup
+        assert_eq!(cache.discover(&chain, &contract).await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_discover_domain_from_domain_separator() {
+        let (provider, asserter) = mocked_provider();
+        let chain = EvmChain::try_from(Network::Base).unwrap();
+        let asset = address!("0x0000000000000000000000000000000000002612");
+        let expected = eip712_domain! {
+            name: "Token",
+            version: "2",
+            chain_id: chain.chain_id,
+            verifying_contract: asset,
+        };
+        asserter.push_failure_msg("execution reverted");
+        asserter.push_success(&Bytes::from(USDC::nameCall::abi_encode_returns(
+            &"Token".to_string(),
+        )));
+        asserter.push_failure_msg("execution reverted");
+        asserter.push_success(&Bytes::from(
+            USDC::DOMAIN_SEPARATORCall::abi_encode_returns(&expected.separator()),
+        ));
+        let cache = Eip712DomainCache::default();
+        let contract = USDC::new(asset, &provider);
+        assert_eq!(cache.discover(&chain, &contract).await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_discovered_domain_expires() {
+        let (provider, asserter) = mocked_provider();
+        let chain = EvmChain::try_from(Network::Base).unwrap();
+        let asset = address!("0x0000000000000000000000000000000000005267");
+        let reported = |version: &str| {
+            Bytes::from(IERC5267::eip712DomainCall::abi_encode_returns(
+                &IERC5267::eip712DomainReturn {
+                    fields: FixedBytes([0x0f]),
+                    name: "Token".to_string(),
+                    version: version.to_string(),
+                    chainId: U256::from(chain.chain_id),
+                    verifyingContract: asset,
+                    salt: FixedBytes::ZERO,
+                    extensions: vec![],
+                },
+            ))
+        };
+        asserter.push_success(&reported("1"));
+        asserter.push_success(&reported("2"));
+        let cache = Eip712DomainCache::new(Duration::ZERO);
+        let contract = USDC::new(asset, &provider);
+        let first = cache.discover(&chain, &contract).await.unwrap();
+        let second = cache.discover(&chain, &contract).await.unwrap();
+        assert_eq!(first.version.as_deref(), Some("1"));
+        assert_eq!(second.version.as_deref(), Some("2"));
+    }
+
+    fn signed_message(
+        signer: &alloy_signer_local::PrivateKeySigner,
+        payer: Address,
+    ) -> SignedMessage {
+        use alloy_signer::SignerSync;
+        let hash = FixedBytes::<32>::repeat_byte(0x36);
+        let signature = signer.sign_hash_sync(&hash).unwrap();
+        SignedMessage {
+            address: payer,
+            hash,
+            signature: StructuredSignature::EIP1271(Bytes::from(signature.as_bytes())),
+        }
+    }
+
+    #[test]
+    fn test_local_signature_check() {
+        let signer = alloy_signer_local::PrivateKeySigner::random();
+        let other = address!("0x0000000000000000000000000000000000000036");
+
+        // Signed by the payer: accepted whatever its code
+        assert_local_signature(
+            &signed_message(&signer, signer.address()),
+            AccountCode::Empty,
+        )
+        .unwrap();
+
+        // Not signed by an EOA payer: rejected
+        let err = assert_local_signature(&signed_message(&signer, other), AccountCode::Empty)
+            .unwrap_err();
+        assert!(matches!(err, FacilitatorLocalError::InvalidSignature(..)));
+
+        // A contract wallet validates the signature itself
+        assert_local_signature(&signed_message(&signer, other), AccountCode::Contract).unwrap();
+    }
+
+    fn verify_request(nonce: u8) -> VerifyRequest {
+        serde_json::from_value(serde_json::json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "exact",
+                "network": "base",
+                "payload": {
+                    "signature": format!("0x{}", "11".repeat(65)),
+                    "authorization": {
+                        "from": "0x0000000000000000000000000000000000000037",
+                        "to": "0x000000000000000000000000000000000000beef",
+                        "value": "1000",
+                        "validAfter": "0",
+                        "validBefore": "4102444800",
+                        "nonce": format!("0x{}", hex::encode([nonce; 32])),
+                    }
+                }
+            },
+            "paymentRequirements": {
+                "scheme": "exact",
+                "network": "base",
+                "maxAmountRequired": "1000",
+                "resource": "https://example.com/resource",
+                "description": "",
+                "mimeType": "application/json",
+                "payTo": "0x000000000000000000000000000000000000beef",
+                "maxTimeoutSeconds": 600,
+                "asset": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+                "extra": null,
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verification_cache_is_used_once() {
+        let cache = VerificationCache::default();
+        let request = verify_request(1);
+        let domain = eip712_domain! { name: "USD Coin", version: "2", };
+        assert!(cache.take(&request).is_none());
+        cache.insert(&request, domain.clone(), AccountCode::Empty);
+        assert!(cache.take(&verify_request(2)).is_none());
+        assert_eq!(cache.take(&request), Some((domain, AccountCode::Empty)));
+        // Settlement consumed the entry
+        assert!(cache.take(&request).is_none());
+    }
+
+    #[test]
+    fn test_verification_cache_limits() {
+        let domain = eip712_domain! { name: "USD Coin", version: "2", };
+        let expired = VerificationCache::new(Duration::ZERO, 10);
+        expired.insert(&verify_request(1), domain.clone(), AccountCode::Empty);
+        assert!(expired.take(&verify_request(1)).is_none());
+
+        let disabled = VerificationCache::new(VERIFICATION_CACHE_TTL, 0);
+        disabled.insert(&verify_request(1), domain.clone(), AccountCode::Empty);
+        assert!(disabled.take(&verify_request(1)).is_none());
+
+        let bounded = VerificationCache::new(VERIFICATION_CACHE_TTL, 2);
+        for nonce in 1..=3 {
+            bounded.insert(&verify_request(nonce), domain.clone(), AccountCode::Empty);
+        }
+        assert!(bounded.entries.len() <= 2);
+        assert!(bounded.take(&verify_request(3)).is_some());
+    }
+
+    #[test]
+    fn test_fee_authorization_must_pay_the_fee() {
+        let chain = EvmChain::try_from(Network::Base).unwrap();
+        let fee = FacilitatorFee {
+            recipient: address!("0x000000000000000000000000000000000000fee0").into(),
+            flat: Some(TokenAmount::from(10u64)),
+            basis_points: Some(50),
+        };
+        let mut request = verify_request(1);
+        let payload = match &mut request.payment_payload.payload {
+            ExactPaymentPayload::Evm(payload) => payload,
+            _ => unreachable!("fixture is an ERC-3009 payload"),
+        };
+        let requirements = &request.payment_requirements;
+        // No fee configured: nothing to check
+        assert!(
+            assert_fee_payment(&chain, None, payload, requirements)
+                .unwrap()
+                .is_none()
+        );
+        assert!(matches!(
+            assert_fee_payment(&chain, Some(&fee), payload, requirements),
+            Err(FacilitatorLocalError::InsufficientFee(..))
+        ));
+
+        // 10 flat + 0.5% of 1000
+        let mut authorization = payload.authorization;
+        authorization.to = address!("0x000000000000000000000000000000000000fee0").into();
+        authorization.value = TokenAmount::from(15u64);
+        authorization.nonce = HexEncodedNonce([2; 32]);
+        payload.fee = Some(ExactEvmFeePayload {
+            signature: payload.signature.clone(),
+            authorization,
+        });
+        let fee_payment = assert_fee_payment(&chain, Some(&fee), payload, requirements)
+            .unwrap()
+            .expect("fee is charged");
+        assert_eq!(fee_payment.value, TokenAmount::from(15u64));
+
+        let fee_payload = payload.fee.as_mut().unwrap();
+        fee_payload.authorization.value = TokenAmount::from(14u64);
+        assert!(matches!(
+            assert_fee_payment(&chain, Some(&fee), payload, requirements),
+            Err(FacilitatorLocalError::InsufficientFee(..))
+        ));
+        let fee_payload = payload.fee.as_mut().unwrap();
+        fee_payload.authorization.value = TokenAmount::from(15u64);
+        fee_payload.authorization.to = payload.authorization.to;
+        assert!(matches!(
+            assert_fee_payment(&chain, Some(&fee), payload, requirements),
+            Err(FacilitatorLocalError::InsufficientFee(..))
+        ));
+    }
 }
@@ -0,0 +1,1443 @@
+//! In-memory EVM chain for exercising [`EvmProvider`] without network access.
+//!
+//! [`OfflineEvmChain`] answers the JSON-RPC calls the facilitator makes, from state held in
+//! memory. Contracts on it are implemented natively rather than as bytecode:
+//! - a USDC-compatible token with ERC-3009 `transferWithAuthorization` and EIP-2612 `permit`,
+//!   checking signatures the way FiatToken v2.2 does (ERC-1271 for accounts with code, ECDSA otherwise),
+//!   except for the `(v, r, s)` overload of `transferWithAuthorization`, which recovers an ECDSA
+//!   signer as the EIP-3009 reference implementation does,
+//! - the EIP-6492 validator at [`VALIDATOR_ADDRESS`],
+//! - Multicall3 `aggregate3` at [`MULTICALL3_ADDRESS`],
+//! - ERC-1271 smart wallets owned by an EOA key, and a factory deploying them counterfactually,
+//! - EIP-7702 delegated EOAs, running the code of their delegate.
+//!
+//! Every transaction is mined into its own block as soon as it is received, and a call that
+//! reverts leaves no trace in state. Gas is free.
+//!
+//! Available with the `evm-offline` feature.
+//!
+//! ```rust
+//! use x402_rs::chain::evm_offline::OfflineEvmChain;
+//! use x402_rs::network::Network;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let chain = OfflineEvmChain::new(Network::BaseSepolia)?;
+//! let usdc = chain.deploy_usdc();
+//! let signer = alloy_signer_local::PrivateKeySigner::random();
+//! chain.mint(usdc, signer.address(), alloy_primitives::U256::from(1_000_000));
+//! let provider = chain.provider(signer.into())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use alloy_consensus::transaction::SignerRecoverable;
+use alloy_consensus::{Transaction, TxEnvelope};
+use alloy_json_rpc::{
+    ErrorPayload, RequestPacket, Response, ResponsePacket, ResponsePayload, SerializedRequest,
+};
+use alloy_network::EthereumWallet;
+use alloy_network::eip2718::Decodable2718;
+use alloy_primitives::{Address, B256, Bytes, FixedBytes, Signature, U256, keccak256};
+use alloy_provider::MULTICALL3_ADDRESS;
+use alloy_provider::bindings::IMulticall3;
+use alloy_rpc_client::RpcClient;
+use alloy_rpc_types::TransactionRequest;
+use alloy_sol_types::{Eip712Domain, Revert, SolCall, SolError, SolInterface, SolStruct, SolValue};
+use alloy_sol_types::{eip712_domain, sol};
+use alloy_transport::{TransportError, TransportFut};
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::chain::FacilitatorLocalError;
+use crate::chain::evm::{
+    EIP6492_MAGIC_SUFFIX, EIP7702_DELEGATION_PREFIX, ERC1271_MAGIC_VALUE, EvmChain, EvmProvider,
+    IERC1271, USDC, VALIDATOR_ADDRESS, Validator6492,
+};
+use crate::network::{Network, USDCDeployment};
+use crate::types::{EvmAddress, Permit, TransferWithAuthorization};
+
+sol! {
+    /// Factory of the ERC-1271 wallets of an [`OfflineEvmChain`].
+    #[derive(Debug)]
+    interface IOfflineWalletFactory {
+        /// Deploys the wallet of `owner` for `salt`, if not deployed yet, and returns its address.
+        function deploy(address owner, bytes32 salt) external returns (address wallet);
+    }
+}
+
+/// Address of the wallet factory on every [`OfflineEvmChain`].
+pub const OFFLINE_WALLET_FACTORY: Address =
+    alloy_primitives::address!("0x000000000000000000000000000000000000fAC7");
+
+/// Gas reported by `eth_estimateGas` and used by every transaction.
+const GAS: u64 = 100_000;
+
+/// Base fee and priority fee of every block, 1 gwei.
+const GAS_PRICE: u128 = 1_000_000_000;
+
+/// Code of accounts holding a native contract. Only its presence matters.
+const NATIVE_CODE: [u8; 1] = [0xfe];
+
+/// Result of a call: its return data, or the data it reverted with.
+type CallResult = Result<Bytes, Bytes>;
+
+/// Encodes a revert with a reason string, as `require(false, reason)` does.
+fn revert(reason: &str) -> Bytes {
+    Revert::from(reason).abi_encode().into()
+}
+
+/// Current time, which is also the timestamp of the block being built.
+fn now() -> U256 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    U256::from(seconds)
+}
+
+/// State of a USDC-compatible token.
+#[derive(Debug, Clone)]
+struct TokenState {
+    name: String,
+    version: String,
+    decimals: u8,
+    total_supply: U256,
+    balances: HashMap<Address, U256>,
+    allowances: HashMap<(Address, Address), U256>,
+    /// ERC-3009 authorizations used or canceled, by authorizer and nonce.
+    authorizations: HashSet<(Address, B256)>,
+    /// EIP-2612 permit nonces.
+    nonces: HashMap<Address, U256>,
+}
+
+impl TokenState {
+    fn balance(&self, owner: &Address) -> U256 {
+        self.balances.get(owner).copied().unwrap_or_default()
+    }
+
+    fn move_balance(&mut self, from: Address, to: Address, value: U256) -> Result<(), Bytes> {
+        let balance = self.balance(&from);
+        if balance < value {
+            return Err(revert("ERC20: transfer amount exceeds balance"));
+        }
+        self.balances.insert(from, balance - value);
+        *self.balances.entry(to).or_default() += value;
+        Ok(())
+    }
+}
+
+/// A contract implemented natively by the chain.
+#[derive(Debug, Clone)]
+enum Contract {
+    Token(Box<TokenState>),
+    Validator6492,
+    Multicall3,
+    /// ERC-1271 wallet accepting ECDSA signatures of its owner.
+    Wallet {
+        owner: Address,
+    },
+    WalletFactory,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Account {
+    nonce: u64,
+    contract: Option<Contract>,
+    /// EIP-7702 delegate of an EOA, whose code runs for the EOA.
+    delegate: Option<Address>,
+}
+
+/// A mined transaction.
+#[derive(Debug, Clone)]
+struct MinedTransaction {
+    hash: B256,
+    from: Address,
+    to: Address,
+    block_number: u64,
+    status: bool,
+}
+
+#[derive(Debug, Clone)]
+struct ChainState {
+    chain_id: u64,
+    accounts: HashMap<Address, Account>,
+    /// Timestamps of the mined blocks, by number. Block 0 is genesis.
+    blocks: Vec<U256>,
+    transactions: HashMap<B256, MinedTransaction>,
+}
+
+impl ChainState {
+    fn code(&self, address: &Address) -> Bytes {
+        match self.accounts.get(address) {
+            Some(Account {
+                contract: Some(_), ..
+            }) => Bytes::from_static(&NATIVE_CODE),
+            Some(Account {
+                delegate: Some(delegate),
+                ..
+            }) => [EIP7702_DELEGATION_PREFIX.as_slice(), delegate.as_slice()]
+                .concat()
+                .into(),
+            _ => Bytes::new(),
+        }
+    }
+
+    fn has_code(&self, address: &Address) -> bool {
+        !self.code(address).is_empty()
+    }
+
+    fn deploy(&mut self, address: Address, contract: Contract) {
+        self.accounts.entry(address).or_default().contract = Some(contract);
+    }
+
+    fn token(&self, address: &Address) -> Option<&TokenState> {
+        match self.accounts.get(address)?.contract.as_ref()? {
+            Contract::Token(token) => Some(token),
+            _ => None,
+        }
+    }
+
+    fn token_mut(&mut self, address: &Address) -> &mut TokenState {
+        match self
+            .accounts
+            .get_mut(address)
+            .and_then(|a| a.contract.as_mut())
+        {
+            Some(Contract::Token(token)) => token,
+            _ => panic!("no token at {address}"),
+        }
+    }
+
+    /// Runs a call, keeping its state changes only if it succeeds.
+    fn call(&mut self, caller: Address, to: Address, input: &[u8]) -> CallResult {
+        let mut scratch = self.clone();
+        let result = scratch.execute(caller, to, input);
+        if result.is_ok() {
+            *self = scratch;
+        }
+        result
+    }
+
+    /// Runs a call without keeping any of its state changes.
+    fn static_call(&self, caller: Address, to: Address, input: &[u8]) -> CallResult {
+        self.clone().execute(caller, to, input)
+    }
+
+    fn execute(&mut self, caller: Address, to: Address, input: &[u8]) -> CallResult {
+        let account = self.accounts.get(&to);
+        let contract = account.and_then(|a| a.contract.clone()).or_else(|| {
+            let delegate = account?.delegate?;
+            self.accounts.get(&delegate)?.contract.clone()
+        });
+        match contract {
+            // A call to an account without code succeeds and returns nothing
+            None => Ok(Bytes::new()),
+            Some(Contract::Token(_)) => self.execute_token(caller, to, input),
+            Some(Contract::Validator6492) => self.execute_validator(input),
+            Some(Contract::Multicall3) => self.execute_multicall(input),
+            Some(Contract::Wallet { owner }) => {
+                let call =
+                    IERC1271::isValidSignatureCall::abi_decode(input).map_err(|_| Bytes::new())?;
+                let valid = is_ecdsa_signed_by(&call.signature, &call.hash, &owner);
+                let magic = if valid {
+                    FixedBytes(ERC1271_MAGIC_VALUE)
+                } else {
+                    FixedBytes::ZERO
+                };
+                Ok(IERC1271::isValidSignatureCall::abi_encode_returns(&magic).into())
+            }
+            Some(Contract::WalletFactory) => {
+                let call = IOfflineWalletFactory::deployCall::abi_decode(input)
+                    .map_err(|_| Bytes::new())?;
+                let wallet = wallet_address(call.owner, call.salt);
+                if !self.has_code(&wallet) {
+                    self.deploy(wallet, Contract::Wallet { owner: call.owner });
+                }
+                Ok(IOfflineWalletFactory::deployCall::abi_encode_returns(&wallet).into())
+            }
+        }
+    }
+
+    /// Checks a signature as FiatToken's `SignatureChecker` does: through ERC-1271 if the
+    /// signer has code, by ECDSA recovery otherwise.
+    fn is_valid_signature_now(&self, signer: Address, hash: B256, signature: &Bytes) -> bool {
+        if !self.has_code(&signer) {
+            return is_ecdsa_signed_by(signature, &hash, &signer);
+        }
+        self.is_valid_erc1271_signature(signer, hash, signature)
+            .unwrap_or(false)
+    }
+
+    /// Calls ERC-1271 `isValidSignature` on `signer`; `Err` carries the revert data.
+    fn is_valid_erc1271_signature(
+        &self,
+        signer: Address,
+        hash: B256,
+        signature: &Bytes,
+    ) -> Result<bool, Bytes> {
+        let input = IERC1271::isValidSignatureCall {
+            hash,
+            signature: signature.clone(),
+        }
+        .abi_encode();
+        let output = self.static_call(Address::ZERO, signer, &input)?;
+        Ok(IERC1271::isValidSignatureCall::abi_decode_returns(&output)
+            .is_ok_and(|magic| magic.0 == ERC1271_MAGIC_VALUE))
+    }
+
+    fn execute_token(&mut self, caller: Address, address: Address, input: &[u8]) -> CallResult {
+        use USDC::USDCCalls;
+        let call = USDCCalls::abi_decode(input).map_err(|_| Bytes::new())?;
+        let token = self.token(&address).expect("token").clone();
+        let domain = eip712_domain! {
+            name: token.name.clone(),
+            version: token.version.clone(),
+            chain_id: self.chain_id,
+            verifying_contract: address,
+        };
+        let output = match call {
+            USDCCalls::name(_) => USDC::nameCall::abi_encode_returns(&token.name),
+            USDCCalls::symbol(_) => USDC::symbolCall::abi_encode_returns(&token.name),
+            USDCCalls::version(_) => USDC::versionCall::abi_encode_returns(&token.version),
+            USDCCalls::decimals(_) => USDC::decimalsCall::abi_encode_returns(&token.decimals),
+            USDCCalls::totalSupply(_) => {
+                USDC::totalSupplyCall::abi_encode_returns(&token.total_supply)
+            }
+            USDCCalls::DOMAIN_SEPARATOR(_) => {
+                USDC::DOMAIN_SEPARATORCall::abi_encode_returns(&domain.separator())
+            }
+            USDCCalls::balanceOf(call) => {
+                USDC::balanceOfCall::abi_encode_returns(&token.balance(&call.account))
+            }
+            USDCCalls::allowance(call) => {
+                let allowance = token
+                    .allowances
+                    .get(&(call.owner, call.spender))
+                    .copied()
+                    .unwrap_or_default();
+                USDC::allowanceCall::abi_encode_returns(&allowance)
+            }
+            USDCCalls::authorizationState(call) => {
+                let used = token
+                    .authorizations
+                    .contains(&(call.authorizer, call.nonce));
+                USDC::authorizationStateCall::abi_encode_returns(&used)
+            }
+            USDCCalls::nonces(call) => {
+                let nonce = token.nonces.get(&call.owner).copied().unwrap_or_default();
+                USDC::noncesCall::abi_encode_returns(&nonce)
+            }
+            USDCCalls::transfer(call) => {
+                self.token_mut(&address)
+                    .move_balance(caller, call.to, call.value)?;
+                USDC::transferCall::abi_encode_returns(&true)
+            }
+            USDCCalls::approve(call) => {
+                self.token_mut(&address)
+                    .allowances
+                    .insert((caller, call.spender), call.value);
+                USDC::approveCall::abi_encode_returns(&true)
+            }
+            USDCCalls::transferFrom(call) => {
+                let allowance = token
+                    .allowances
+                    .get(&(call.from, caller))
+                    .copied()
+                    .unwrap_or_default();
+                if allowance < call.value {
+                    return Err(revert("ERC20: transfer amount exceeds allowance"));
+                }
+                let token = self.token_mut(&address);
+                token
+                    .allowances
+                    .insert((call.from, caller), allowance - call.value);
+                token.move_balance(call.from, call.to, call.value)?;
+                USDC::transferFromCall::abi_encode_returns(&true)
+            }
+            USDCCalls::transferWithAuthorization_0(call) => {
+                let authorization = TransferWithAuthorization {
+                    from: call.from,
+                    to: call.to,
+                    value: call.value,
+                    validAfter: call.validAfter,
+                    validBefore: call.validBefore,
+                    nonce: call.nonce,
+                };
+                self.transfer_with_authorization(
+                    address,
+                    &domain,
+                    authorization,
+                    call.signature,
+                    false,
+                )?;
+                Vec::new()
+            }
+            USDCCalls::transferWithAuthorization_1(call) => {
+                let authorization = TransferWithAuthorization {
+                    from: call.from,
+                    to: call.to,
+                    value: call.value,
+                    validAfter: call.validAfter,
+                    validBefore: call.validBefore,
+                    nonce: call.nonce,
+                };
+                let signature = packed_signature(call.v, call.r, call.s);
+                self.transfer_with_authorization(address, &domain, authorization, signature, true)?;
+                Vec::new()
+            }
+            USDCCalls::permit_0(call) => {
+                self.permit(
+                    address,
+                    &domain,
+                    call.owner,
+                    call.spender,
+                    call.value,
+                    call.deadline,
+                    call.signature,
+                )?;
+                Vec::new()
+            }
+            USDCCalls::permit_1(call) => {
+                let signature = packed_signature(call.v, call.r, call.s);
+                self.permit(
+                    address,
+                    &domain,
+                    call.owner,
+                    call.spender,
+                    call.value,
+                    call.deadline,
+                    signature,
+                )?;
+                Vec::new()
+            }
+            _ => return Err(revert("OfflineEvmChain: unsupported token function")),
+        };
+        Ok(output.into())
+    }
+
+    /// Runs `transferWithAuthorization`. With `ecdsa_only`, the signature has to recover to
+    /// `from` even if it has code, as the `(v, r, s)` overload of EIP-3009 tokens checks it.
+    fn transfer_with_authorization(
+        &mut self,
+        address: Address,
+        domain: &Eip712Domain,
+        authorization: TransferWithAuthorization,
+        signature: Bytes,
+        ecdsa_only: bool,
+    ) -> Result<(), Bytes> {
+        let now = now();
+        if now <= authorization.validAfter {
+            return Err(revert("FiatTokenV2: authorization is not yet valid"));
+        }
+        if now >= authorization.validBefore {
+            return Err(revert("FiatTokenV2: authorization is expired"));
+        }
+        let key = (authorization.from, authorization.nonce);
+        if self.token_mut(&address).authorizations.contains(&key) {
+            return Err(revert("FiatTokenV2: authorization is used or canceled"));
+        }
+        let hash = authorization.eip712_signing_hash(domain);
+        let valid = if ecdsa_only {
+            is_ecdsa_signed_by(&signature, &hash, &authorization.from)
+        } else {
+            self.is_valid_signature_now(authorization.from, hash, &signature)
+        };
+        if !valid {
+            return Err(revert("FiatTokenV2: invalid signature"));
+        }
+        let token = self.token_mut(&address);
+        token.authorizations.insert(key);
+        token.move_balance(authorization.from, authorization.to, authorization.value)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn permit(
+        &mut self,
+        address: Address,
+        domain: &Eip712Domain,
+        owner: Address,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+        signature: Bytes,
+    ) -> Result<(), Bytes> {
+        if deadline < now() {
+            return Err(revert("FiatTokenV2: permit is expired"));
+        }
+        let nonce = self
+            .token_mut(&address)
+            .nonces
+            .get(&owner)
+            .copied()
+            .unwrap_or_default();
+        let permit = Permit {
+            owner,
+            spender,
+            value,
+            nonce,
+            deadline,
+        };
+        let hash = permit.eip712_signing_hash(domain);
+        if !self.is_valid_signature_now(owner, hash, &signature) {
+            return Err(revert("EIP2612: invalid signature"));
+        }
+        let token = self.token_mut(&address);
+        token.nonces.insert(owner, nonce + U256::ONE);
+        token.allowances.insert((owner, spender), value);
+        Ok(())
+    }
+
+    /// The reference EIP-6492 `UniversalSigValidator`.
+    fn execute_validator(&mut self, input: &[u8]) -> CallResult {
+        use Validator6492::Validator6492Calls;
+        let valid = match Validator6492Calls::abi_decode(input).map_err(|_| Bytes::new())? {
+            Validator6492Calls::isValidSigWithSideEffects(call) => {
+                self.is_valid_sig(call._signer, call._hash, &call._signature)?
+            }
+            Validator6492Calls::isValidSig(call) => {
+                self.clone()
+                    .is_valid_sig(call._signer, call._hash, &call._signature)?
+            }
+        };
+        Ok(Validator6492::isValidSigCall::abi_encode_returns(&valid).into())
+    }
+
+    fn is_valid_sig(
+        &mut self,
+        signer: Address,
+        hash: B256,
+        signature: &Bytes,
+    ) -> Result<bool, Bytes> {
+        let deployed = self.has_code(&signer);
+        let is_6492 =
+            signature.len() >= 32 && signature[signature.len() - 32..] == EIP6492_MAGIC_SUFFIX;
+        let signature = if is_6492 {
+            let body = &signature[..signature.len() - 32];
+            let (factory, factory_calldata, inner) =
+                <(Address, Bytes, Bytes)>::abi_decode_params(body).map_err(|_| Bytes::new())?;
+            if !deployed {
+                self.call(VALIDATOR_ADDRESS, factory, &factory_calldata)
+                    .map_err(|error| {
+                        Bytes::from(Validator6492::ERC6492DeployFailed { error }.abi_encode())
+                    })?;
+            }
+            inner
+        } else {
+            signature.clone()
+        };
+        if self.has_code(&signer) {
+            return self
+                .is_valid_erc1271_signature(signer, hash, &signature)
+                .map_err(|error| Bytes::from(Validator6492::ERC1271Revert { error }.abi_encode()));
+        }
+        if signature.len() != 65 {
+            return Err(revert(
+                "SignatureValidator#recoverSigner: invalid signature length",
+            ));
+        }
+        Ok(is_ecdsa_signed_by(&signature, &hash, &signer))
+    }
+
+    fn execute_multicall(&mut self, input: &[u8]) -> CallResult {
+        let call = IMulticall3::aggregate3Call::abi_decode(input).map_err(|_| Bytes::new())?;
+        let mut results = Vec::with_capacity(call.calls.len());
+        for call in call.calls {
+            let result = self.call(MULTICALL3_ADDRESS, call.target, &call.callData);
+            if result.is_err() && !call.allowFailure {
+                return Err(revert("Multicall3: call failed"));
+            }
+            let (success, return_data) = match result {
+                Ok(output) => (true, output),
+                Err(output) => (false, output),
+            };
+            results.push(IMulticall3::Result {
+                success,
+                returnData: return_data,
+            });
+        }
+        Ok(IMulticall3::aggregate3Call::abi_encode_returns(&results).into())
+    }
+}
+
+/// Joins `(v, r, s)` into the 65-byte `r || s || v` form.
+fn packed_signature(v: u8, r: B256, s: B256) -> Bytes {
+    let mut signature = Vec::with_capacity(65);
+    signature.extend_from_slice(r.as_slice());
+    signature.extend_from_slice(s.as_slice());
+    signature.push(v);
+    signature.into()
+}
+
+fn is_ecdsa_signed_by(signature: &[u8], hash: &B256, address: &Address) -> bool {
+    Signature::try_from(signature)
+        .and_then(|signature| signature.recover_address_from_prehash(hash))
+        .is_ok_and(|recovered| recovered == *address)
+}
+
+/// Address [`OFFLINE_WALLET_FACTORY`] deploys the wallet of `owner` for `salt` to.
+fn wallet_address(owner: Address, salt: B256) -> Address {
+    let salt = keccak256((owner, salt).abi_encode());
+    OFFLINE_WALLET_FACTORY.create2(salt, keccak256(b"OfflineWallet"))
+}
+
+/// A wallet that [`OFFLINE_WALLET_FACTORY`] can deploy, and that can sign before it is deployed.
+#[derive(Debug, Clone)]
+pub struct CounterfactualWallet {
+    /// Address the wallet is deployed to.
+    pub address: Address,
+    /// Factory deploying the wallet.
+    pub factory: Address,
+    /// Calldata deploying the wallet through `factory`.
+    pub factory_calldata: Bytes,
+}
+
+impl CounterfactualWallet {
+    /// Wraps a signature of the wallet owner into an EIP-6492 signature.
+    pub fn wrap_signature(&self, signature: &[u8]) -> Bytes {
+        let mut wrapped = (
+            self.factory,
+            self.factory_calldata.clone(),
+            Bytes::copy_from_slice(signature),
+        )
+            .abi_encode_params();
+        wrapped.extend_from_slice(&EIP6492_MAGIC_SUFFIX);
+        wrapped.into()
+    }
+}
+
+/// An in-memory EVM chain, usable as the transport of an Alloy provider.
+///
+/// Clones share the chain state.
+#[derive(Debug, Clone)]
+pub struct OfflineEvmChain {
+    chain: EvmChain,
+    state: Arc<Mutex<ChainState>>,
+}
+
+impl OfflineEvmChain {
+    /// Starts a chain with the id of `network`, holding the EIP-6492 validator, Multicall3 and
+    /// the wallet factory.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::UnsupportedNetwork`] if `network` is not an EVM network.
+    pub fn new(network: Network) -> Result<Self, FacilitatorLocalError> {
+        let chain = EvmChain::try_from(network)?;
+        let mut state = ChainState {
+            chain_id: chain.chain_id,
+            accounts: HashMap::new(),
+            blocks: vec![now()],
+            transactions: HashMap::new(),
+        };
+        state.deploy(VALIDATOR_ADDRESS, Contract::Validator6492);
+        state.deploy(MULTICALL3_ADDRESS, Contract::Multicall3);
+        state.deploy(OFFLINE_WALLET_FACTORY, Contract::WalletFactory);
+        Ok(Self {
+            chain,
+            state: Arc::new(Mutex::new(state)),
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, ChainState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Chain descriptor of this chain.
+    pub fn chain(&self) -> EvmChain {
+        self.chain
+    }
+
+    /// Deploys a USDC-compatible token at `address`, with the given EIP-712 `name` and `version`.
+    pub fn deploy_token(&self, address: Address, name: &str, version: &str, decimals: u8) {
+        let token = TokenState {
+            name: name.to_string(),
+            version: version.to_string(),
+            decimals,
+            total_supply: U256::ZERO,
+            balances: HashMap::new(),
+            allowances: HashMap::new(),
+            authorizations: HashSet::new(),
+            nonces: HashMap::new(),
+        };
+        self.state()
+            .deploy(address, Contract::Token(Box::new(token)));
+    }
+
+    /// Deploys the token at the address of [`USDCDeployment`] on this network, and returns it.
+    pub fn deploy_usdc(&self) -> Address {
+        let usdc = USDCDeployment::by_network(self.chain.network);
+        let address: EvmAddress = usdc
+            .address()
+            .try_into()
+            .expect("USDC of an EVM network has an EVM address");
+        let (name, version) = usdc
+            .eip712
+            .as_ref()
+            .map(|eip712| (eip712.name.as_str(), eip712.version.as_str()))
+            .unwrap_or(("USD Coin", "2"));
+        self.deploy_token(address.0, name, version, usdc.decimals);
+        address.0
+    }
+
+    /// Credits `amount` of `token` to `to`.
+    ///
+    /// # Panics
+    /// If there is no token at `token`.
+    pub fn mint(&self, token: Address, to: Address, amount: U256) {
+        let mut state = self.state();
+        let token = state.token_mut(&token);
+        token.total_supply += amount;
+        *token.balances.entry(to).or_default() += amount;
+    }
+
+    /// Balance of `owner` in `token`, zero if there is no token there.
+    pub fn balance_of(&self, token: Address, owner: Address) -> U256 {
+        self.state()
+            .token(&token)
+            .map(|token| token.balance(&owner))
+            .unwrap_or_default()
+    }
+
+    /// Allowance of `spender` over the `token` of `owner`.
+    pub fn allowance(&self, token: Address, owner: Address, spender: Address) -> U256 {
+        self.state()
+            .token(&token)
+            .and_then(|token| token.allowances.get(&(owner, spender)).copied())
+            .unwrap_or_default()
+    }
+
+    /// Whether the ERC-3009 authorization `nonce` of `authorizer` has been used in `token`.
+    pub fn authorization_used(&self, token: Address, authorizer: Address, nonce: B256) -> bool {
+        self.state()
+            .token(&token)
+            .is_some_and(|token| token.authorizations.contains(&(authorizer, nonce)))
+    }
+
+    /// Deploys the ERC-1271 wallet of `owner` for `salt` right away, and returns its address.
+    pub fn deploy_wallet(&self, owner: Address, salt: B256) -> Address {
+        let address = wallet_address(owner, salt);
+        self.state().deploy(address, Contract::Wallet { owner });
+        address
+    }
+
+    /// Delegates the EOA `account` to `delegate`, as an EIP-7702 authorization does.
+    /// The account keeps its key, and runs the code at `delegate`, if any, when called.
+    pub fn delegate(&self, account: Address, delegate: Address) {
+        self.state().accounts.entry(account).or_default().delegate = Some(delegate);
+    }
+
+    /// The ERC-1271 wallet of `owner` for `salt`, without deploying it.
+    pub fn counterfactual_wallet(&self, owner: Address, salt: B256) -> CounterfactualWallet {
+        CounterfactualWallet {
+            address: wallet_address(owner, salt),
+            factory: OFFLINE_WALLET_FACTORY,
+            factory_calldata: IOfflineWalletFactory::deployCall { owner, salt }
+                .abi_encode()
+                .into(),
+        }
+    }
+
+    /// Whether there is code at `address`.
+    pub fn is_deployed(&self, address: Address) -> bool {
+        self.state().has_code(&address)
+    }
+
+    /// Number of transactions sent from `address`.
+    pub fn transaction_count(&self, address: Address) -> u64 {
+        self.state()
+            .accounts
+            .get(&address)
+            .map(|account| account.nonce)
+            .unwrap_or_default()
+    }
+
+    /// Number of the latest block.
+    pub fn block_number(&self) -> u64 {
+        self.state().blocks.len() as u64 - 1
+    }
+
+    /// An RPC client answered by this chain.
+    pub fn client(&self) -> RpcClient {
+        RpcClient::new(self.clone(), true)
+    }
+
+    /// An [`EvmProvider`] on this chain, sending transactions with `wallet`.
+    ///
+    /// # Errors
+    /// Returns an error if `wallet` has no signer.
+    pub fn provider(
+        &self,
+        wallet: EthereumWallet,
+    ) -> Result<EvmProvider, Box<dyn std::error::Error>> {
+        EvmProvider::try_new_with_client(wallet, self.client(), true, self.chain.network)
+    }
+
+    fn respond(&self, request: &SerializedRequest) -> Response {
+        let params: Vec<Value> = request
+            .params()
+            .and_then(|params| serde_json::from_str(params.get()).ok())
+            .unwrap_or_default();
+        let payload = match self.dispatch(request.method(), &params) {
+            Ok(value) => {
+                ResponsePayload::Success(serde_json::value::to_raw_value(&value).expect("json"))
+            }
+            Err(error) => ResponsePayload::Failure(error),
+        };
+        Response {
+            id: request.id().clone(),
+            payload,
+        }
+    }
+
+    fn dispatch(&self, method: &str, params: &[Value]) -> Result<Value, ErrorPayload> {
+        let param = |index: usize| params.get(index).cloned().unwrap_or(Value::Null);
+        let mut state = self.state();
+        let latest = state.blocks.len() as u64 - 1;
+        let value = match method {
+            "eth_chainId" => json!(hex_quantity(state.chain_id)),
+            "net_version" => json!(state.chain_id.to_string()),
+            "eth_blockNumber" => json!(hex_quantity(latest)),
+            "eth_gasPrice" | "eth_maxPriorityFeePerGas" => json!(U256::from(GAS_PRICE)),
+            "eth_feeHistory" => json!({
+                "oldestBlock": hex_quantity(latest),
+                "baseFeePerGas": [U256::from(GAS_PRICE), U256::from(GAS_PRICE)],
+                "gasUsedRatio": [0.5],
+                "reward": [[U256::from(GAS_PRICE)]],
+            }),
+            "eth_getBalance" => json!(U256::ZERO),
+            "eth_getCode" => json!(state.code(&parse_param::<Address>(param(0))?)),
+            "eth_getTransactionCount" => {
+                let address = parse_param::<Address>(param(0))?;
+                let nonce = state
+                    .accounts
+                    .get(&address)
+                    .map(|a| a.nonce)
+                    .unwrap_or_default();
+                json!(hex_quantity(nonce))
+            }
+            "eth_call" | "eth_estimateGas" => {
+                let request = parse_param::<TransactionRequest>(param(0))?;
+                let to = request.to.and_then(|to| to.to().copied()).ok_or_else(|| {
+                    rpc_error(-32602, "contract creation is not supported".to_string())
+                })?;
+                let from = request.from.unwrap_or_default();
+                let input = request.input.input().cloned().unwrap_or_default();
+                let output = state
+                    .static_call(from, to, &input)
+                    .map_err(execution_reverted)?;
+                if method == "eth_call" {
+                    json!(output)
+                } else {
+                    json!(hex_quantity(GAS))
+                }
+            }
+            "eth_sendRawTransaction" => {
+                let raw = parse_param::<Bytes>(param(0))?;
+                json!(state.send_raw_transaction(&raw)?)
+            }
+            "eth_getTransactionReceipt" => {
+                let hash = parse_param::<B256>(param(0))?;
+                match state.transactions.get(&hash) {
+                    Some(transaction) => receipt_json(transaction),
+                    None => Value::Null,
+                }
+            }
+            "eth_getBlockByNumber" => {
+                let number = match param(0).as_str() {
+                    Some("latest" | "pending" | "safe" | "finalized") | None => latest,
+                    Some("earliest") => 0,
+                    Some(number) => parse_param::<alloy_primitives::U64>(json!(number))?.to(),
+                };
+                match state.blocks.get(number as usize) {
+                    Some(timestamp) => {
+                        let transactions: Vec<B256> = state
+                            .transactions
+                            .values()
+                            .filter(|t| t.block_number == number)
+                            .map(|t| t.hash)
+                            .collect();
+                        block_json(number, *timestamp, transactions)
+                    }
+                    None => Value::Null,
+                }
+            }
+            _ => {
+                return Err(rpc_error(
+                    -32601,
+                    format!("method {method} is not supported by OfflineEvmChain"),
+                ));
+            }
+        };
+        Ok(value)
+    }
+}
+
+impl ChainState {
+    /// Executes a signed transaction and mines it into a block of its own.
+    fn send_raw_transaction(&mut self, raw: &[u8]) -> Result<B256, ErrorPayload> {
+        let envelope = TxEnvelope::decode_2718(&mut &raw[..])
+            .map_err(|e| rpc_error(-32602, format!("invalid transaction: {e}")))?;
+        let from = envelope
+            .recover_signer()
+            .map_err(|e| rpc_error(-32602, format!("invalid signature: {e}")))?;
+        if envelope.chain_id().is_some_and(|id| id != self.chain_id) {
+            return Err(rpc_error(-32000, "invalid chain id".to_string()));
+        }
+        let account_nonce = self
+            .accounts
+            .get(&from)
+            .map(|a| a.nonce)
+            .unwrap_or_default();
+        if envelope.nonce() < account_nonce {
+            return Err(rpc_error(-32000, "nonce too low".to_string()));
+        }
+        if envelope.nonce() > account_nonce {
+            return Err(rpc_error(-32000, "nonce too high".to_string()));
+        }
+        let to = envelope
+            .to()
+            .ok_or_else(|| rpc_error(-32602, "contract creation is not supported".to_string()))?;
+        let status = self.call(from, to, envelope.input()).is_ok();
+        self.accounts.entry(from).or_default().nonce += 1;
+        let hash = *envelope.tx_hash();
+        self.blocks.push(now());
+        let block_number = self.blocks.len() as u64 - 1;
+        self.transactions.insert(
+            hash,
+            MinedTransaction {
+                hash,
+                from,
+                to,
+                block_number,
+                status,
+            },
+        );
+        Ok(hash)
+    }
+}
+
+fn hex_quantity(value: u64) -> String {
+    format!("{value:#x}")
+}
+
+fn block_hash(number: u64) -> B256 {
+    keccak256(number.to_be_bytes())
+}
+
+fn rpc_error(code: i64, message: String) -> ErrorPayload {
+    ErrorPayload {
+        code,
+        message: message.into(),
+        data: None,
+    }
+}
+
+/// The error of a reverted `eth_call`, carrying the revert data as nodes do.
+fn execution_reverted(data: Bytes) -> ErrorPayload {
+    let reason = Revert::abi_decode(&data)
+        .map(|revert| format!("execution reverted: {}", revert.reason))
+        .unwrap_or_else(|_| "execution reverted".to_string());
+    ErrorPayload {
+        code: 3,
+        message: reason.into(),
+        data: serde_json::value::to_raw_value(&data).ok(),
+    }
+}
+
+fn parse_param<T: serde::de::DeserializeOwned>(value: Value) -> Result<T, ErrorPayload> {
+    serde_json::from_value(value).map_err(|e| rpc_error(-32602, format!("invalid params: {e}")))
+}
+
+fn receipt_json(transaction: &MinedTransaction) -> Value {
+    json!({
+        "transactionHash": transaction.hash,
+        "transactionIndex": "0x0",
+        "blockHash": block_hash(transaction.block_number),
+        "blockNumber": hex_quantity(transaction.block_number),
+        "from": transaction.from,
+        "to": transaction.to,
+        "cumulativeGasUsed": hex_quantity(GAS),
+        "gasUsed": hex_quantity(GAS),
+        "effectiveGasPrice": U256::from(GAS_PRICE),
+        "contractAddress": null,
+        "logs": [],
+        "logsBloom": alloy_primitives::Bloom::ZERO,
+        "type": "0x2",
+        "status": if transaction.status { "0x1" } else { "0x0" },
+    })
+}
+
+fn block_json(number: u64, timestamp: U256, transactions: Vec<B256>) -> Value {
+    json!({
+        "hash": block_hash(number),
+        "parentHash": if number == 0 { B256::ZERO } else { block_hash(number - 1) },
+        "sha3Uncles": B256::ZERO,
+        "miner": Address::ZERO,
+        "stateRoot": B256::ZERO,
+        "transactionsRoot": B256::ZERO,
+        "receiptsRoot": B256::ZERO,
+        "logsBloom": alloy_primitives::Bloom::ZERO,
+        "difficulty": "0x0",
+        "number": hex_quantity(number),
+        "gasLimit": hex_quantity(30_000_000),
+        "gasUsed": hex_quantity(GAS * transactions.len() as u64),
+        "timestamp": timestamp,
+        "extraData": "0x",
+        "mixHash": B256::ZERO,
+        "nonce": "0x0000000000000000",
+        "baseFeePerGas": U256::from(GAS_PRICE),
+        "size": "0x0",
+        "uncles": [],
+        "transactions": transactions,
+    })
+}
+
+impl tower::Service<RequestPacket> for OfflineEvmChain {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let response = match request {
+            RequestPacket::Single(request) => ResponsePacket::Single(self.respond(&request)),
+            RequestPacket::Batch(requests) => {
+                ResponsePacket::Batch(requests.iter().map(|r| self.respond(r)).collect())
+            }
+        };
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::facilitator::Facilitator;
+    use crate::types::{VerifyRequest, VerifyResponse};
+    use alloy_primitives::hex;
+    use alloy_signer::SignerSync;
+    use alloy_signer_local::PrivateKeySigner;
+
+    const PAY_TO: Address =
+        alloy_primitives::address!("0x000000000000000000000000000000000000bEEF");
+
+    struct Setup {
+        chain: OfflineEvmChain,
+        usdc: Address,
+        facilitator: PrivateKeySigner,
+        provider: EvmProvider,
+    }
+
+    fn setup() -> Setup {
+        let chain = OfflineEvmChain::new(Network::BaseSepolia).unwrap();
+        let usdc = chain.deploy_usdc();
+        let facilitator = PrivateKeySigner::random();
+        let provider = chain.provider(facilitator.clone().into()).unwrap();
+        Setup {
+            chain,
+            usdc,
+            facilitator,
+            provider,
+        }
+    }
+
+    fn domain(setup: &Setup) -> Eip712Domain {
+        let eip712 = USDCDeployment::by_network(Network::BaseSepolia)
+            .eip712
+            .clone()
+            .unwrap();
+        eip712_domain! {
+            name: eip712.name,
+            version: eip712.version,
+            chain_id: setup.chain.chain().chain_id,
+            verifying_contract: setup.usdc,
+        }
+    }
+
+    fn requirements(setup: &Setup) -> Value {
+        json!({
+            "scheme": "exact",
+            "network": "base-sepolia",
+            "maxAmountRequired": "1000",
+            "resource": "https://example.com/resource",
+            "description": "",
+            "mimeType": "application/json",
+            "payTo": PAY_TO,
+            "maxTimeoutSeconds": 600,
+            "asset": setup.usdc,
+            "extra": null,
+        })
+    }
+
+    /// A payment of 1000 from `from`, signed by `signer`, with `wrap` applied to the signature.
+    fn payment(
+        setup: &Setup,
+        from: Address,
+        signer: &PrivateKeySigner,
+        nonce: u8,
+        wrap: impl Fn(&[u8]) -> Bytes,
+    ) -> VerifyRequest {
+        let authorization = TransferWithAuthorization {
+            from,
+            to: PAY_TO,
+            value: U256::from(1000),
+            validAfter: U256::ZERO,
+            validBefore: now() + U256::from(600),
+            nonce: B256::repeat_byte(nonce),
+        };
+        let hash = authorization.eip712_signing_hash(&domain(setup));
+        let signature = signer.sign_hash_sync(&hash).unwrap();
+        serde_json::from_value(json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "payload": {
+                    "signature": wrap(&signature.as_bytes()),
+                    "authorization": {
+                        "from": from,
+                        "to": PAY_TO,
+                        "value": "1000",
+                        "validAfter": "0",
+                        "validBefore": authorization.validBefore.to_string(),
+                        "nonce": authorization.nonce,
+                    }
+                }
+            },
+            "paymentRequirements": requirements(setup),
+        }))
+        .unwrap()
+    }
+
+    fn plain(signature: &[u8]) -> Bytes {
+        Bytes::copy_from_slice(signature)
+    }
+
+    #[tokio::test]
+    async fn eoa_payment_verifies_and_settles_once() {
+        let setup = setup();
+        let payer = PrivateKeySigner::random();
+        setup
+            .chain
+            .mint(setup.usdc, payer.address(), U256::from(1500));
+        let request = payment(&setup, payer.address(), &payer, 1, plain);
+
+        let verified = setup.provider.verify(&request).await.unwrap();
+        assert!(matches!(verified, VerifyResponse::Valid { .. }));
+        // Verification only simulates
+        assert_eq!(
+            setup.chain.balance_of(setup.usdc, payer.address()),
+            U256::from(1500)
+        );
+
+        let settled = setup.provider.settle(&request).await.unwrap();
+        assert!(settled.success);
+        assert_eq!(
+            setup.chain.balance_of(setup.usdc, payer.address()),
+            U256::from(500)
+        );
+        assert_eq!(setup.chain.balance_of(setup.usdc, PAY_TO), U256::from(1000));
+        assert!(
+            setup
+                .chain
+                .authorization_used(setup.usdc, payer.address(), B256::repeat_byte(1))
+        );
+        assert_eq!(
+            setup.chain.transaction_count(setup.facilitator.address()),
+            1
+        );
+
+        // The authorization nonce is spent
+        let replay = setup.provider.verify(&request).await;
+        assert!(replay.is_err());
+        let replay = setup.provider.settle(&request).await;
+        assert!(replay.is_err());
+        assert_eq!(setup.chain.balance_of(setup.usdc, PAY_TO), U256::from(1000));
+    }
+
+    #[tokio::test]
+    async fn transaction_nonces_follow_the_chain() {
+        let setup = setup();
+        let payer = PrivateKeySigner::random();
+        setup
+            .chain
+            .mint(setup.usdc, payer.address(), U256::from(3000));
+        for nonce in 1..=3 {
+            let request = payment(&setup, payer.address(), &payer, nonce, plain);
+            assert!(setup.provider.settle(&request).await.unwrap().success);
+        }
+        assert_eq!(
+            setup.chain.transaction_count(setup.facilitator.address()),
+            3
+        );
+        assert_eq!(setup.chain.block_number(), 3);
+    }
+
+    #[tokio::test]
+    async fn insufficient_balance_and_foreign_signature_are_rejected() {
+        let setup = setup();
+        let payer = PrivateKeySigner::random();
+        setup
+            .chain
+            .mint(setup.usdc, payer.address(), U256::from(999));
+        let request = payment(&setup, payer.address(), &payer, 1, plain);
+        let err = setup.provider.verify(&request).await.unwrap_err();
+        assert!(matches!(err, FacilitatorLocalError::InsufficientFunds(_)));
+
+        setup
+            .chain
+            .mint(setup.usdc, payer.address(), U256::from(1000));
+        let other = PrivateKeySigner::random();
+        let request = payment(&setup, payer.address(), &other, 2, plain);
+        let err = setup.provider.verify(&request).await.unwrap_err();
+        assert!(matches!(err, FacilitatorLocalError::InvalidSignature(..)));
+    }
+
+    #[tokio::test]
+    async fn erc1271_wallet_payment_settles() {
+        let setup = setup();
+        let owner = PrivateKeySigner::random();
+        let wallet = setup.chain.deploy_wallet(owner.address(), B256::ZERO);
+        setup.chain.mint(setup.usdc, wallet, U256::from(1000));
+
+        // Signed by someone else than the owner: the wallet rejects it
+        let other = PrivateKeySigner::random();
+        let request = payment(&setup, wallet, &other, 1, plain);
+        assert!(setup.provider.verify(&request).await.is_err());
+
+        let request = payment(&setup, wallet, &owner, 2, plain);
+        let verified = setup.provider.verify(&request).await.unwrap();
+        assert!(matches!(verified, VerifyResponse::Valid { .. }));
+        assert!(setup.provider.settle(&request).await.unwrap().success);
+        assert_eq!(setup.chain.balance_of(setup.usdc, wallet), U256::ZERO);
+        assert_eq!(setup.chain.balance_of(setup.usdc, PAY_TO), U256::from(1000));
+    }
+
+    #[tokio::test]
+    async fn delegated_eoa_payment_settles_through_vrs_overload() {
+        let setup = setup();
+        let payer = PrivateKeySigner::random();
+        // A delegate without ERC-1271: the `bytes` overload would fail to validate the payer
+        let delegate = alloy_primitives::address!("0x0000000000000000000000000000000000007702");
+        setup.chain.delegate(payer.address(), delegate);
+        setup
+            .chain
+            .mint(setup.usdc, payer.address(), U256::from(1000));
+
+        let request = payment(&setup, payer.address(), &payer, 1, plain);
+        let verified = setup.provider.verify(&request).await.unwrap();
+        assert!(matches!(verified, VerifyResponse::Valid { .. }));
+        assert!(setup.provider.settle(&request).await.unwrap().success);
+        assert_eq!(setup.chain.balance_of(setup.usdc, PAY_TO), U256::from(1000));
+
+        // Another key is for the delegate to accept, and it can not
+        setup
+            .chain
+            .mint(setup.usdc, payer.address(), U256::from(1000));
+        let other = PrivateKeySigner::random();
+        let request = payment(&setup, payer.address(), &other, 2, plain);
+        let err = setup.provider.verify(&request).await.unwrap_err();
+        assert!(matches!(err, FacilitatorLocalError::InvalidSignature(..)));
+    }
+
+    #[tokio::test]
+    async fn counterfactual_wallet_is_deployed_on_settlement() {
+        let setup = setup();
+        let owner = PrivateKeySigner::random();
+        let wallet = setup
+            .chain
+            .counterfactual_wallet(owner.address(), B256::repeat_byte(7));
+        setup
+            .chain
+            .mint(setup.usdc, wallet.address, U256::from(1000));
+        let request = payment(&setup, wallet.address, &owner, 1, |signature| {
+            wallet.wrap_signature(signature)
+        });
+
+        let verified = setup.provider.verify(&request).await.unwrap();
+        assert!(matches!(verified, VerifyResponse::Valid { .. }));
+        // The deployment was only simulated
+        assert!(!setup.chain.is_deployed(wallet.address));
+
+        assert!(setup.provider.settle(&request).await.unwrap().success);
+        assert!(setup.chain.is_deployed(wallet.address));
+        assert_eq!(setup.chain.balance_of(setup.usdc, PAY_TO), U256::from(1000));
+    }
+
+    #[tokio::test]
+    async fn counterfactual_wallet_rejects_foreign_signature() {
+        let setup = setup();
+        let owner = PrivateKeySigner::random();
+        let other = PrivateKeySigner::random();
+        let wallet = setup
+            .chain
+            .counterfactual_wallet(owner.address(), B256::ZERO);
+        setup
+            .chain
+            .mint(setup.usdc, wallet.address, U256::from(1000));
+        let request = payment(&setup, wallet.address, &other, 1, |signature| {
+            wallet.wrap_signature(signature)
+        });
+        // The wallet rejects the signature, reverting the whole simulated batch
+        assert!(setup.provider.verify(&request).await.is_err());
+        assert!(setup.provider.settle(&request).await.is_err());
+        assert!(!setup.chain.is_deployed(wallet.address));
+    }
+
+    /// A permit payment of 1000 from `owner` approving `spender`, with the signed permit.
+    fn permit_payment(
+        setup: &Setup,
+        owner: &PrivateKeySigner,
+        spender: Address,
+    ) -> (Permit, Signature, VerifyRequest) {
+        let permit = Permit {
+            owner: owner.address(),
+            spender,
+            value: U256::from(1000),
+            nonce: U256::ZERO,
+            deadline: now() + U256::from(600),
+        };
+        let signature = owner
+            .sign_hash_sync(&permit.eip712_signing_hash(&domain(setup)))
+            .unwrap();
+        let request = serde_json::from_value(json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "payload": {
+                    "signature": format!("0x{}", hex::encode(signature.as_bytes())),
+                    "permit": {
+                        "owner": owner.address(),
+                        "spender": spender,
+                        "value": "1000",
+                        "nonce": "0",
+                        "deadline": permit.deadline.to_string(),
+                    }
+                }
+            },
+            "paymentRequirements": requirements(setup),
+        }))
+        .unwrap();
+        (permit, signature, request)
+    }
+
+    #[tokio::test]
+    async fn permit_payment_settles_through_transfer_from() {
+        let setup = setup();
+        let owner = PrivateKeySigner::random();
+        setup
+            .chain
+            .mint(setup.usdc, owner.address(), U256::from(1000));
+        let (_, _, request) = permit_payment(&setup, &owner, setup.facilitator.address());
+
+        let verified = setup.provider.verify(&request).await.unwrap();
+        assert!(matches!(verified, VerifyResponse::Valid { .. }));
+        assert!(setup.provider.settle(&request).await.unwrap().success);
+        assert_eq!(setup.chain.balance_of(setup.usdc, PAY_TO), U256::from(1000));
+        assert_eq!(
+            setup
+                .chain
+                .allowance(setup.usdc, owner.address(), setup.facilitator.address()),
+            U256::ZERO
+        );
+        // The permit nonce moved on, so the same permit is now stale
+        assert!(setup.provider.verify(&request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn permit_landed_outside_a_settlement_is_not_resumed() {
+        let setup = setup();
+        let owner = PrivateKeySigner::random();
+        setup
+            .chain
+            .mint(setup.usdc, owner.address(), U256::from(1000));
+        let (permit, signature, request) =
+            permit_payment(&setup, &owner, setup.facilitator.address());
+        // Anyone may submit the permit: its allowance does not tell where the payment should go
+        let call = USDC::permit_0Call {
+            owner: permit.owner,
+            spender: permit.spender,
+            value: permit.value,
+            deadline: permit.deadline,
+            signature: signature.as_bytes().into(),
+        };
+        setup
+            .chain
+            .state()
+            .call(setup.facilitator.address(), setup.usdc, &call.abi_encode())
+            .unwrap();
+
+        assert!(setup.provider.verify(&request).await.is_err());
+        assert!(setup.provider.settle(&request).await.is_err());
+        assert_eq!(setup.chain.balance_of(setup.usdc, PAY_TO), U256::ZERO);
+    }
+
+    #[tokio::test]
+    async fn settled_permit_is_not_replayed_on_a_later_approval() {
+        let setup = setup();
+        let owner = PrivateKeySigner::random();
+        setup
+            .chain
+            .mint(setup.usdc, owner.address(), U256::from(2000));
+        let (_, _, request) = permit_payment(&setup, &owner, setup.facilitator.address());
+        assert!(setup.provider.settle(&request).await.unwrap().success);
+        // The owner approves the facilitator again, for an unrelated payment
+        let approve = USDC::approveCall {
+            spender: setup.facilitator.address(),
+            value: U256::from(1000),
+        };
+        setup
+            .chain
+            .state()
+            .call(owner.address(), setup.usdc, &approve.abi_encode())
+            .unwrap();
+
+        let thief = Address::repeat_byte(0x66);
+        let mut replay = request.clone();
+        replay.payment_requirements.pay_to = thief.into();
+        for request in [&request, &replay] {
+            assert!(setup.provider.verify(request).await.is_err());
+            assert!(setup.provider.settle(request).await.is_err());
+        }
+        assert_eq!(setup.chain.balance_of(setup.usdc, PAY_TO), U256::from(1000));
+        assert_eq!(setup.chain.balance_of(setup.usdc, thief), U256::ZERO);
+    }
+
+    #[tokio::test]
+    async fn permit_for_another_spender_is_rejected() {
+        let setup = setup();
+        let owner = PrivateKeySigner::random();
+        setup
+            .chain
+            .mint(setup.usdc, owner.address(), U256::from(1000));
+        let (_, _, request) = permit_payment(&setup, &owner, Address::repeat_byte(0x42));
+
+        let error = setup.provider.verify(&request).await.unwrap_err();
+        assert!(matches!(error, FacilitatorLocalError::SpenderMismatch(..)));
+        assert!(setup.provider.settle(&request).await.is_err());
+        assert_eq!(setup.chain.balance_of(setup.usdc, PAY_TO), U256::ZERO);
+    }
+
+    #[tokio::test]
+    async fn supported_lists_the_permit_method_with_its_spender() {
+        let setup = setup();
+        let supported = setup.provider.supported().await.unwrap();
+        let permit = supported
+            .kinds
+            .iter()
+            .filter_map(|kind| kind.extra.as_ref())
+            .find(|extra| extra.asset_transfer_method.as_deref() == Some("permit"))
+            .unwrap();
+        assert_eq!(permit.spender, Some(setup.facilitator.address().into()));
+        // ERC-3009 stays first, as the default for networks matched by their first kind
+        assert!(supported.kinds[0].extra.is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use alloy_primitives::U256;
 use std::time::SystemTimeError;
 
 use crate::chain::evm::EvmProvider;
@@ -10,7 +11,13 @@ use crate::types::{
 };
 
 pub mod evm;
+#[cfg(any(test, feature = "evm-offline"))]
+#[allow(dead_code)] // Compiled into the facilitator binary too, which never uses it.
+pub mod evm_offline;
 pub mod solana;
+#[cfg(any(test, feature = "solana-offline"))]
+#[allow(dead_code)] // Compiled into the facilitator binary too, which never uses it.
+pub mod solana_offline;
 
 pub enum NetworkProvider {
     Evm(EvmProvider),
@@ -40,6 +47,39 @@ impl FromEnvByNetworkBuild for NetworkProvider {
     }
 }
 
+/// Native token balance of a facilitator signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerBalance {
+    pub address: MixedAddress,
+    /// Balance in the smallest native unit: wei on EVM networks, lamports on Solana.
+    pub balance: U256,
+}
+
+impl NetworkProvider {
+    /// Native balances of the signers paying gas or transaction fees on this network.
+    pub async fn signer_balances(&self) -> Result<Vec<SignerBalance>, FacilitatorLocalError> {
+        match self {
+            NetworkProvider::Evm(provider) => {
+                let balances = provider.signer_balances().await?;
+                Ok(balances
+                    .into_iter()
+                    .map(|(address, balance)| SignerBalance {
+                        address: address.into(),
+                        balance,
+                    })
+                    .collect())
+            }
+            NetworkProvider::Solana(provider) => {
+                let balance = provider.fee_payer_balance().await?;
+                Ok(vec![SignerBalance {
+                    address: provider.fee_payer(),
+                    balance: U256::from(balance),
+                }])
+            }
+        }
+    }
+}
+
 pub trait NetworkProviderOps {
     fn signer_address(&self) -> MixedAddress;
     fn network(&self) -> Network;
@@ -103,6 +143,9 @@ pub enum FacilitatorLocalError {
     /// The `pay_to` recipient in the requirements doesn't match the `to` address in the payload.
     #[error("Incompatible payload receivers (payload: {1}, requirements: {2})")]
     ReceiverMismatch(MixedAddress, String, String),
+    /// The `spender` of a permit is not a signer of this facilitator.
+    #[error("Permit spender {1} is not a facilitator signer ({2})")]
+    SpenderMismatch(MixedAddress, String, String),
     /// Failed to read a system clock to check timing.
     #[error("Can not get system clock")]
     ClockError(#[source] SystemTimeError),
@@ -121,6 +164,9 @@ pub enum FacilitatorLocalError {
     /// The payload's `value` is not enough to meet the requirements.
     #[error("Insufficient value")]
     InsufficientValue(MixedAddress),
+    /// The payment does not pay the facilitator fee.
+    #[error("Insufficient fee: {1}")]
+    InsufficientFee(MixedAddress, String),
     /// The payload decoding failed.
     #[error("Decoding error: {0}")]
     DecodingError(String),
@@ -1,15 +1,39 @@
+use alloy_primitives::hex;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use solana_account::Account;
+use solana_address_lookup_table_interface::state::AddressLookupTable;
 use solana_commitment_config::CommitmentConfig;
+use solana_epoch_info::EpochInfo;
 use solana_keypair::Keypair;
+use solana_message::Hash;
 use solana_message::compiled_instruction::CompiledInstruction;
+use solana_message::v0::{LoadedAddresses, MessageAddressTableLookup};
+use solana_nonce::state::State as NonceState;
+use solana_nonce::versions::Versions as NonceVersions;
 use solana_pubkey::{Pubkey, pubkey};
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::client_error::Result as ClientResult;
 use solana_rpc_client_api::config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig};
+use solana_rpc_client_api::response::{RpcResult, RpcSimulateTransactionResult};
 use solana_signature::Signature;
 use solana_signer::Signer;
+use solana_system_interface::instruction::SystemInstruction;
 use solana_transaction::versioned::VersionedTransaction;
+use solana_transaction_status_client_types::TransactionStatus;
+use spl_token_2022_interface::extension::pausable::PausableConfig;
+use spl_token_2022_interface::extension::permanent_delegate::PermanentDelegate;
+use spl_token_2022_interface::extension::transfer_fee::instruction::TransferFeeInstruction;
+use spl_token_2022_interface::extension::transfer_fee::{TransferFee, TransferFeeConfig};
+use spl_token_2022_interface::extension::transfer_hook::TransferHook;
+use spl_token_2022_interface::extension::{
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing_core::Level;
 
 use crate::chain::{FacilitatorLocalError, FromEnvByNetworkBuild, NetworkProviderOps};
@@ -17,13 +41,97 @@ use crate::facilitator::Facilitator;
 use crate::from_env;
 use crate::network::Network;
 use crate::types::{
-    Base64Bytes, ExactPaymentPayload, FacilitatorErrorReason, MixedAddress, PaymentRequirements,
-    SettleRequest, SettleResponse, SupportedPaymentKind, SupportedPaymentKindExtra,
-    SupportedPaymentKindsResponse, TokenAmount, TransactionHash, VerifyRequest, VerifyResponse,
+    Base64Bytes, ExactPaymentPayload, FacilitatorErrorReason, FacilitatorFee, MixedAddress,
+    PaymentRequirements, SettleRequest, SettleResponse, SettledFee, SupportedPaymentKind,
+    SupportedPaymentKindExtra, SupportedPaymentKindsResponse, TransactionHash, VerifyRequest,
+    VerifyResponse,
 };
 use crate::types::{Scheme, X402Version};
 
-const ATA_PROGRAM_PUBKEY: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+pub(crate) const ATA_PROGRAM_PUBKEY: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Base fee charged per transaction signature, in lamports.
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+/// Compute unit price is denominated in micro-lamports per compute unit.
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+/// Highest compute unit limit the runtime allows for a transaction.
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Token-2022 mint extensions accepted on a payment mint.
+///
+/// They only describe the token, or take a cut the facilitator accounts for (transfer fee).
+/// Transfer hook, permanent delegate and pausable are further checked to be unset.
+pub const ALLOWED_MINT_EXTENSIONS: &[ExtensionType] = &[
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::ScaledUiAmount,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::GroupPointer,
+    ExtensionType::TokenGroup,
+    ExtensionType::GroupMemberPointer,
+    ExtensionType::TokenGroupMember,
+    ExtensionType::TransferHook,
+    ExtensionType::PermanentDelegate,
+    ExtensionType::Pausable,
+];
+
+/// Facilitator-side caps on the compute budget of a payment transaction.
+///
+/// The facilitator is the fee payer, so without these a buyer could make it pay
+/// an arbitrarily high priority fee.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolanaComputeBudgetLimits {
+    /// Max compute unit price, in micro-lamports.
+    pub max_compute_unit_price: u64,
+    /// Max compute unit limit.
+    pub max_compute_unit_limit: u32,
+    /// Max total fee (base fee plus priority fee), in lamports. `None` disables the check.
+    pub max_lamports_per_transaction: Option<u64>,
+}
+
+impl Default for SolanaComputeBudgetLimits {
+    fn default() -> Self {
+        Self {
+            max_compute_unit_price: 5 * 1_000_000,
+            max_compute_unit_limit: MAX_COMPUTE_UNIT_LIMIT,
+            max_lamports_per_transaction: None,
+        }
+    }
+}
+
+impl SolanaComputeBudgetLimits {
+    /// Reads the caps for `network` from environment, falling back to [`Default`] for unset values.
+    pub fn from_env(network: Network) -> Result<Self, Box<dyn std::error::Error>> {
+        let defaults = Self::default();
+        let Some(names) = from_env::solana_compute_budget_env_names_from_network(network) else {
+            return Ok(defaults);
+        };
+        Ok(Self {
+            max_compute_unit_price: from_env::parse_optional_env(names.max_compute_unit_price)?
+                .unwrap_or(defaults.max_compute_unit_price),
+            max_compute_unit_limit: from_env::parse_optional_env(names.max_compute_unit_limit)?
+                .unwrap_or(defaults.max_compute_unit_limit),
+            max_lamports_per_transaction: from_env::parse_optional_env(
+                names.max_lamports_per_transaction,
+            )?,
+        })
+    }
+
+    /// Total fee in lamports the fee payer is charged: base fee per signature plus the priority fee.
+    pub fn transaction_fee(
+        num_signatures: u8,
+        compute_unit_limit: u32,
+        compute_unit_price: u64,
+    ) -> u64 {
+        let base_fee = u64::from(num_signatures) * LAMPORTS_PER_SIGNATURE;
+        let priority_fee = (u128::from(compute_unit_limit) * u128::from(compute_unit_price))
+            .div_ceil(MICRO_LAMPORTS_PER_LAMPORT);
+        base_fee.saturating_add(u64::try_from(priority_fee).unwrap_or(u64::MAX))
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct SolanaChain {
@@ -89,11 +197,196 @@ impl From<SolanaAddress> for MixedAddress {
     }
 }
 
+pub(crate) const MEMO_PROGRAM_PUBKEY: Pubkey =
+    pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+pub(crate) const MEMO_V1_PROGRAM_PUBKEY: Pubkey =
+    pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo");
+pub(crate) const LIGHTHOUSE_PROGRAM_PUBKEY: Pubkey =
+    pubkey!("L2TExMFKdjpN9kozasaurPirfHy9P8sbXoAN1qA3S95");
+
+/// An extra instruction accepted in payment transactions besides the required ones.
+///
+/// Parsed from `<program id>` or `<program id>:<hex data prefix>`, e.g. `ComputeBudget111111111111111111111111111111:04`.
+/// Extra instructions are still subject to the fee payer safety check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AllowedInstruction {
+    pub program_id: Pubkey,
+    /// Required prefix of the instruction data, usually a discriminator. Empty accepts any data.
+    pub data_prefix: Vec<u8>,
+}
+
+impl AllowedInstruction {
+    pub fn program(program_id: Pubkey) -> Self {
+        Self {
+            program_id,
+            data_prefix: Vec::new(),
+        }
+    }
+
+    pub fn with_data_prefix(program_id: Pubkey, data_prefix: impl Into<Vec<u8>>) -> Self {
+        Self {
+            program_id,
+            data_prefix: data_prefix.into(),
+        }
+    }
+
+    pub fn matches(&self, program_id: &Pubkey, data: &[u8]) -> bool {
+        self.program_id == *program_id && data.starts_with(&self.data_prefix)
+    }
+
+    /// Memo (v1 and v2), Lighthouse assertions, and the `RequestHeapFrame` and
+    /// `SetLoadedAccountsDataSizeLimit` compute budget instructions, as added by common wallets.
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::program(MEMO_PROGRAM_PUBKEY),
+            Self::program(MEMO_V1_PROGRAM_PUBKEY),
+            Self::program(LIGHTHOUSE_PROGRAM_PUBKEY),
+            Self::with_data_prefix(solana_compute_budget_interface::ID, [1]),
+            Self::with_data_prefix(solana_compute_budget_interface::ID, [4]),
+        ]
+    }
+}
+
+impl FromStr for AllowedInstruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (program_id, data_prefix) = match s.split_once(':') {
+            Some((program_id, data_prefix)) => (program_id, data_prefix),
+            None => (s, ""),
+        };
+        let program_id = Pubkey::from_str(program_id.trim())
+            .map_err(|e| format!("invalid program id {program_id}: {e}"))?;
+        let data_prefix = hex::decode(data_prefix.trim().trim_start_matches("0x"))
+            .map_err(|e| format!("invalid data prefix {data_prefix}: {e}"))?;
+        Ok(Self {
+            program_id,
+            data_prefix,
+        })
+    }
+}
+
+/// The Solana JSON-RPC calls [`SolanaProvider`] makes.
+///
+/// Implemented for [`RpcClient`]. Any other implementation, like an in-process chain for tests,
+/// can be passed to [`SolanaProvider::try_new_with_rpc`].
+#[async_trait]
+pub trait SolanaRpc: Send + Sync {
+    /// Endpoint the calls go to, for logging.
+    fn url(&self) -> String;
+
+    async fn get_multiple_accounts(&self, pubkeys: &[Pubkey])
+    -> ClientResult<Vec<Option<Account>>>;
+
+    async fn get_account_with_commitment(
+        &self,
+        pubkey: &Pubkey,
+        commitment_config: CommitmentConfig,
+    ) -> RpcResult<Option<Account>>;
+
+    async fn get_block_height_with_commitment(
+        &self,
+        commitment_config: CommitmentConfig,
+    ) -> ClientResult<u64>;
+
+    async fn get_epoch_info(&self) -> ClientResult<EpochInfo>;
+
+    /// Returns the latest blockhash and its last valid block height.
+    async fn get_latest_blockhash_with_commitment(
+        &self,
+        commitment_config: CommitmentConfig,
+    ) -> ClientResult<(Hash, u64)>;
+
+    async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> RpcResult<Vec<Option<TransactionStatus>>>;
+
+    async fn send_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature>;
+
+    async fn simulate_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> RpcResult<RpcSimulateTransactionResult>;
+}
+
+#[async_trait]
+impl SolanaRpc for RpcClient {
+    fn url(&self) -> String {
+        RpcClient::url(self)
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ClientResult<Vec<Option<Account>>> {
+        RpcClient::get_multiple_accounts(self, pubkeys).await
+    }
+
+    async fn get_account_with_commitment(
+        &self,
+        pubkey: &Pubkey,
+        commitment_config: CommitmentConfig,
+    ) -> RpcResult<Option<Account>> {
+        RpcClient::get_account_with_commitment(self, pubkey, commitment_config).await
+    }
+
+    async fn get_block_height_with_commitment(
+        &self,
+        commitment_config: CommitmentConfig,
+    ) -> ClientResult<u64> {
+        RpcClient::get_block_height_with_commitment(self, commitment_config).await
+    }
+
+    async fn get_epoch_info(&self) -> ClientResult<EpochInfo> {
+        RpcClient::get_epoch_info(self).await
+    }
+
+    async fn get_latest_blockhash_with_commitment(
+        &self,
+        commitment_config: CommitmentConfig,
+    ) -> ClientResult<(Hash, u64)> {
+        RpcClient::get_latest_blockhash_with_commitment(self, commitment_config).await
+    }
+
+    async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> RpcResult<Vec<Option<TransactionStatus>>> {
+        RpcClient::get_signature_statuses(self, signatures).await
+    }
+
+    async fn send_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        RpcClient::send_transaction_with_config(self, transaction, config).await
+    }
+
+    async fn simulate_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> RpcResult<RpcSimulateTransactionResult> {
+        RpcClient::simulate_transaction_with_config(self, transaction, config).await
+    }
+}
+
 #[derive(Clone)]
 pub struct SolanaProvider {
     keypair: Arc<Keypair>,
     chain: SolanaChain,
-    rpc_client: Arc<RpcClient>,
+    rpc_client: Arc<dyn SolanaRpc>,
+    compute_budget_limits: SolanaComputeBudgetLimits,
+    allowed_instructions: Vec<AllowedInstruction>,
+    lookup_tables: Arc<AddressLookupTableCache>,
+    fee: Option<FacilitatorFee>,
 }
 
 impl Debug for SolanaProvider {
@@ -102,6 +395,9 @@ impl Debug for SolanaProvider {
             .field("pubkey", &self.keypair.pubkey())
             .field("chain", &self.chain)
             .field("rpc_url", &self.rpc_client.url())
+            .field("compute_budget_limits", &self.compute_budget_limits)
+            .field("allowed_instructions", &self.allowed_instructions)
+            .field("fee", &self.fee)
             .finish()
     }
 }
@@ -111,38 +407,136 @@ impl SolanaProvider {
         keypair: Keypair,
         rpc_url: String,
         network: Network,
+    ) -> Result<Self, FacilitatorLocalError> {
+        let rpc_client = RpcClient::new(rpc_url);
+        Self::try_new_with_rpc(keypair, Arc::new(rpc_client), network)
+    }
+
+    /// Builds a provider that makes its RPC calls through `rpc_client`.
+    pub fn try_new_with_rpc(
+        keypair: Keypair,
+        rpc_client: Arc<dyn SolanaRpc>,
+        network: Network,
     ) -> Result<Self, FacilitatorLocalError> {
         let chain = SolanaChain::try_from(network)?;
         {
             let signer_addresses = vec![keypair.pubkey()];
-            tracing::info!(network=%network, rpc=rpc_url, signers=?signer_addresses, "Initialized provider");
+            tracing::info!(network=%network, rpc=rpc_client.url(), signers=?signer_addresses, "Initialized provider");
         }
-        let rpc_client = RpcClient::new(rpc_url);
         Ok(Self {
             keypair: Arc::new(keypair),
             chain,
-            rpc_client: Arc::new(rpc_client),
+            rpc_client,
+            compute_budget_limits: SolanaComputeBudgetLimits::default(),
+            allowed_instructions: AllowedInstruction::defaults(),
+            lookup_tables: Arc::new(AddressLookupTableCache::default()),
+            fee: None,
         })
     }
 
+    /// Sets the caps on compute unit price, limit and total fee accepted in payment transactions.
+    pub fn with_compute_budget_limits(mut self, limits: SolanaComputeBudgetLimits) -> Self {
+        self.compute_budget_limits = limits;
+        self
+    }
+
+    pub fn compute_budget_limits(&self) -> &SolanaComputeBudgetLimits {
+        &self.compute_budget_limits
+    }
+
+    /// Replaces the extra instructions accepted in payment transactions, [`AllowedInstruction::defaults`] by default.
+    pub fn with_allowed_instructions(
+        mut self,
+        allowed_instructions: Vec<AllowedInstruction>,
+    ) -> Self {
+        self.allowed_instructions = allowed_instructions;
+        self
+    }
+
+    pub fn allowed_instructions(&self) -> &[AllowedInstruction] {
+        &self.allowed_instructions
+    }
+
+    /// Sets the fee charged on top of every payment, paid by a transfer in the payment transaction.
+    pub fn with_fee(mut self, fee: Option<FacilitatorFee>) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn fee(&self) -> Option<&FacilitatorFee> {
+        self.fee.as_ref()
+    }
+
+    /// Returns `true` if the instruction at `instruction_index` is a System Program
+    /// `AdvanceNonceAccount`, meaning the transaction uses a durable nonce instead of
+    /// a recent blockhash and does not expire until the nonce is advanced.
+    pub fn is_advance_nonce_instruction(
+        &self,
+        transaction: &TransactionInt,
+        instruction_index: usize,
+    ) -> bool {
+        transaction.is_advance_nonce_instruction(instruction_index)
+    }
+
+    /// Returns `true` if the instruction at `instruction_index` matches one of the
+    /// [`AllowedInstruction`]s the facilitator accepts besides the required ones.
+    pub fn is_allowed_extra_instruction(
+        &self,
+        transaction: &TransactionInt,
+        instruction_index: usize,
+    ) -> bool {
+        let Ok(instruction) = transaction.instruction(instruction_index) else {
+            return false;
+        };
+        let program_id = instruction.program_id();
+        self.allowed_instructions
+            .iter()
+            .any(|allowed| allowed.matches(&program_id, instruction.data_slice()))
+    }
+
+    /// Indexes of the instructions that are not allowlisted extras.
+    ///
+    /// Extra instructions (memos, assertions, ...) may appear anywhere after the first `offset`
+    /// instructions. The required ones keep their order: `[advance nonce]`, compute unit limit,
+    /// compute unit price, `[create ATA]`, transfer, and the fee transfer if `with_fee`.
+    pub fn required_instruction_indexes(
+        &self,
+        transaction: &TransactionInt,
+        offset: usize,
+        with_fee: bool,
+    ) -> Result<Vec<usize>, FacilitatorLocalError> {
+        let required = (0..transaction.inner.message.instructions().len())
+            .filter(|index| {
+                *index < offset || !self.is_allowed_extra_instruction(transaction, *index)
+            })
+            .collect::<Vec<_>>();
+        let offset = offset + usize::from(with_fee);
+        if required.len() != offset + 3 && required.len() != offset + 4 {
+            return Err(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_instructions_count".to_string(),
+            ));
+        }
+        Ok(required)
+    }
+
     pub fn verify_compute_limit_instruction(
         &self,
-        transaction: &VersionedTransaction,
+        transaction: &TransactionInt,
         instruction_index: usize,
     ) -> Result<u32, FacilitatorLocalError> {
-        let instructions = transaction.message.instructions();
+        let instructions = transaction.inner.message.instructions();
         let instruction =
             instructions
                 .get(instruction_index)
                 .ok_or(FacilitatorLocalError::DecodingError(
                     "invalid_exact_svm_payload_transaction_instructions_length".to_string(),
                 ))?;
-        let account = instruction.program_id(transaction.message.static_account_keys());
+        let account = transaction.program_id(instruction)?;
         let compute_budget = solana_compute_budget_interface::ID;
         let data = instruction.data.as_slice();
 
         // Verify program ID, discriminator, and data length (1 byte discriminator + 4 bytes u32)
-        if compute_budget.ne(account) || data.first().cloned().unwrap_or(0) != 2 || data.len() != 5
+        if compute_budget.ne(&account) || data.first().cloned().unwrap_or(0) != 2 || data.len() != 5
         {
             return Err(FacilitatorLocalError::DecodingError(
                 "invalid_exact_svm_payload_transaction_compute_limit_instruction".to_string(),
@@ -153,16 +547,22 @@ impl SolanaProvider {
         let mut buf = [0u8; 4];
         buf.copy_from_slice(&data[1..5]);
         let compute_units = u32::from_le_bytes(buf);
+        if compute_units > self.compute_budget_limits.max_compute_unit_limit {
+            return Err(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_compute_limit_instruction_too_high"
+                    .to_string(),
+            ));
+        }
 
         Ok(compute_units)
     }
 
     pub fn verify_compute_price_instruction(
         &self,
-        transaction: &VersionedTransaction,
+        transaction: &TransactionInt,
         instruction_index: usize,
-    ) -> Result<(), FacilitatorLocalError> {
-        let instructions = transaction.message.instructions();
+    ) -> Result<u64, FacilitatorLocalError> {
+        let instructions = transaction.inner.message.instructions();
         let instruction =
             instructions
                 .get(instruction_index)
@@ -170,10 +570,10 @@ impl SolanaProvider {
                     "invalid_exact_svm_payload_transaction_instructions_compute_price_instruction"
                         .to_string(),
                 ))?;
-        let account = instruction.program_id(transaction.message.static_account_keys());
+        let account = transaction.program_id(instruction)?;
         let compute_budget = solana_compute_budget_interface::ID;
         let data = instruction.data.as_slice();
-        if compute_budget.ne(account) || data.first().cloned().unwrap_or(0) != 3 || data.len() != 9
+        if compute_budget.ne(&account) || data.first().cloned().unwrap_or(0) != 3 || data.len() != 9
         {
             return Err(FacilitatorLocalError::DecodingError(
                 "invalid_exact_svm_payload_transaction_instructions_compute_price_instruction"
@@ -183,22 +583,20 @@ impl SolanaProvider {
         // It is ComputeBudgetInstruction definitely by now!
         let mut buf = [0u8; 8];
         buf.copy_from_slice(&data[1..]);
-        // TODO: allow the facilitator to pass in an optional max compute unit price - from JS
         let microlamports = u64::from_le_bytes(buf);
-        if microlamports > 5 * 1_000_000 {
+        if microlamports > self.compute_budget_limits.max_compute_unit_price {
             return Err(FacilitatorLocalError::DecodingError("invalid_exact_svm_payload_transaction_instructions_compute_price_instruction_too_high".to_string()));
         }
-        Ok(())
+        Ok(microlamports)
     }
 
     pub fn verify_create_ata_instruction(
         &self,
-        transaction: &VersionedTransaction,
+        transaction: &TransactionInt,
         index: usize,
         requirements: &PaymentRequirements,
     ) -> Result<(), FacilitatorLocalError> {
-        let tx = TransactionInt::new(transaction.clone());
-        let instruction = tx.instruction(index)?;
+        let instruction = transaction.instruction(index)?;
         instruction.assert_not_empty()?;
 
         // Verify program ID is the Associated Token Account Program
@@ -257,16 +655,13 @@ impl SolanaProvider {
         Ok(())
     }
 
-    // this expects the destination ATA to already exist
-    pub async fn verify_transfer_instruction(
+    /// Decodes a `TransferChecked` (or Token-2022 `TransferCheckedWithFee`) instruction.
+    pub fn parse_transfer_checked_instruction(
         &self,
-        transaction: &VersionedTransaction,
+        transaction: &TransactionInt,
         instruction_index: usize,
-        requirements: &PaymentRequirements,
-        has_dest_ata: bool,
     ) -> Result<TransferCheckedInstruction, FacilitatorLocalError> {
-        let tx = TransactionInt::new(transaction.clone());
-        let instruction = tx.instruction(instruction_index)?;
+        let instruction = transaction.instruction(instruction_index)?;
         instruction.assert_not_empty()?;
         let program_id = instruction.program_id();
         let transfer_checked_instruction = if spl_token_interface::check_id(&program_id) {
@@ -305,6 +700,8 @@ impl SolanaProvider {
                 destination,
                 authority,
                 token_program: spl_token_interface::ID,
+                fee: None,
+                mint_transfer_fee: None,
                 data: instruction.data(),
             }
         } else if spl_token_2022_interface::check_id(&program_id) {
@@ -317,11 +714,25 @@ impl SolanaProvider {
                         "invalid_exact_svm_payload_transaction_instructions".to_string(),
                     )
                 })?;
-            let (amount, decimals) = match token_instruction {
+            let (amount, decimals, fee) = match token_instruction {
                 spl_token_2022_interface::instruction::TokenInstruction::TransferChecked {
                     amount,
                     decimals,
-                } => (amount, decimals),
+                } => (amount, decimals, None),
+                spl_token_2022_interface::instruction::TokenInstruction::TransferFeeExtension => {
+                    match TransferFeeInstruction::unpack(&instruction.data_slice()[1..]) {
+                        Ok(TransferFeeInstruction::TransferCheckedWithFee {
+                            amount,
+                            decimals,
+                            fee,
+                        }) => (amount, decimals, Some(fee)),
+                        _ => {
+                            return Err(FacilitatorLocalError::DecodingError(
+                                "invalid_exact_svm_payload_transaction_instructions".to_string(),
+                            ));
+                        }
+                    }
+                }
                 _ => {
                     return Err(FacilitatorLocalError::DecodingError(
                         "invalid_exact_svm_payload_transaction_instructions".to_string(),
@@ -344,6 +755,8 @@ impl SolanaProvider {
                 destination,
                 authority,
                 token_program: spl_token_2022_interface::ID,
+                fee,
+                mint_transfer_fee: None,
                 data: instruction.data(),
             }
         } else {
@@ -351,6 +764,19 @@ impl SolanaProvider {
                 "invalid_exact_svm_payload_transaction_not_a_transfer_instruction".to_string(),
             ));
         };
+        Ok(transfer_checked_instruction)
+    }
+
+    // this expects the destination ATA to already exist
+    pub async fn verify_transfer_instruction(
+        &self,
+        transaction: &TransactionInt,
+        instruction_index: usize,
+        requirements: &PaymentRequirements,
+        has_dest_ata: bool,
+    ) -> Result<TransferCheckedInstruction, FacilitatorLocalError> {
+        let mut transfer_checked_instruction =
+            self.parse_transfer_checked_instruction(transaction, instruction_index)?;
 
         // Verify that the fee payer is not transferring funds (not the authority)
         let fee_payer_pubkey = self.keypair.pubkey();
@@ -379,7 +805,11 @@ impl SolanaProvider {
         }
         let accounts = self
             .rpc_client
-            .get_multiple_accounts(&[transfer_checked_instruction.source, ata])
+            .get_multiple_accounts(&[
+                transfer_checked_instruction.source,
+                ata,
+                transfer_checked_instruction.mint,
+            ])
             .await
             .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?;
         let is_sender_missing = accounts.first().cloned().is_none_or(|a| a.is_none());
@@ -394,16 +824,197 @@ impl SolanaProvider {
                 "invalid_exact_svm_payload_transaction_receiver_ata_not_found".to_string(),
             ));
         }
-        let instruction_amount: TokenAmount = transfer_checked_instruction.amount.into();
-        let requirements_amount: TokenAmount = requirements.max_amount_required;
-        if instruction_amount != requirements_amount {
+        let mint_account =
+            accounts
+                .get(2)
+                .cloned()
+                .flatten()
+                .ok_or(FacilitatorLocalError::DecodingError(
+                    "invalid_exact_svm_payload_transaction_mint_not_found".to_string(),
+                ))?;
+        if mint_account.owner != token_program {
             return Err(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_mint_not_found".to_string(),
+            ));
+        }
+        let transfer_fee_config = Self::verify_mint_extensions(&mint_account.data, &token_program)?;
+
+        // With a transfer fee, the payee receives `amount - fee`, so the buyer must gross up
+        // the transfer for the payee to get exactly `max_amount_required`.
+        let amount_mismatch = || {
+            FacilitatorLocalError::DecodingError(
                 "invalid_exact_svm_payload_transaction_amount_mismatch".to_string(),
+            )
+        };
+        let requirements_amount =
+            u64::try_from(requirements.max_amount_required.0).map_err(|_| amount_mismatch())?;
+        let mint_transfer_fee = match transfer_fee_config {
+            None => None,
+            Some(config) => {
+                let epoch = self
+                    .rpc_client
+                    .get_epoch_info()
+                    .await
+                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?
+                    .epoch;
+                Some(*config.get_epoch_fee(epoch))
+            }
+        };
+        let (expected_amount, expected_fee) = match &mint_transfer_fee {
+            None => (requirements_amount, 0),
+            Some(transfer_fee) => {
+                let inverse_fee = transfer_fee
+                    .calculate_inverse_fee(requirements_amount)
+                    .ok_or_else(amount_mismatch)?;
+                let expected_amount = requirements_amount
+                    .checked_add(inverse_fee)
+                    .ok_or_else(amount_mismatch)?;
+                let expected_fee = transfer_fee
+                    .calculate_fee(expected_amount)
+                    .ok_or_else(amount_mismatch)?;
+                (expected_amount, expected_fee)
+            }
+        };
+        if transfer_checked_instruction.amount != expected_amount {
+            return Err(amount_mismatch());
+        }
+        if let Some(fee) = transfer_checked_instruction.fee
+            && fee != expected_fee
+        {
+            return Err(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_transfer_fee_mismatch".to_string(),
             ));
         }
+        transfer_checked_instruction.mint_transfer_fee = mint_transfer_fee;
         Ok(transfer_checked_instruction)
     }
 
+    /// Checks that the instruction at `instruction_index` pays the facilitator fee of `amount`.
+    ///
+    /// It must transfer the payment mint from the payment authority to the associated token
+    /// account of the fee recipient. Only the amount net of the Token-2022 transfer fee counts:
+    /// the mint withholds it whether the instruction declares it or not, see
+    /// [`TransferCheckedInstruction::mint_transfer_fee`].
+    pub fn verify_fee_transfer_instruction(
+        &self,
+        transaction: &TransactionInt,
+        instruction_index: usize,
+        payment: &TransferCheckedInstruction,
+        fee: &FacilitatorFee,
+        amount: u64,
+    ) -> Result<SettledFee, FacilitatorLocalError> {
+        let fee_transfer =
+            self.parse_transfer_checked_instruction(transaction, instruction_index)?;
+        let payer: MixedAddress = payment.authority.into();
+        if fee_transfer.authority != payment.authority
+            || fee_transfer.mint != payment.mint
+            || fee_transfer.token_program != payment.token_program
+        {
+            return Err(FacilitatorLocalError::InsufficientFee(
+                payer,
+                "Fee transfer does not match the payment".to_string(),
+            ));
+        }
+        let recipient: SolanaAddress = fee.recipient.clone().try_into()?;
+        let (ata, _) = Pubkey::find_program_address(
+            &[
+                recipient.pubkey.as_ref(),
+                fee_transfer.token_program.as_ref(),
+                fee_transfer.mint.as_ref(),
+            ],
+            &ATA_PROGRAM_PUBKEY,
+        );
+        if fee_transfer.destination != ata {
+            return Err(FacilitatorLocalError::InsufficientFee(
+                payer,
+                format!(
+                    "Fee transfer to {}, expected {ata}",
+                    fee_transfer.destination
+                ),
+            ));
+        }
+        let withheld = match &payment.mint_transfer_fee {
+            Some(transfer_fee) => transfer_fee
+                .calculate_fee(fee_transfer.amount)
+                .unwrap_or(fee_transfer.amount),
+            None => fee_transfer.fee.unwrap_or_default(),
+        };
+        let net_amount = fee_transfer.amount.saturating_sub(withheld);
+        if net_amount < amount {
+            return Err(FacilitatorLocalError::InsufficientFee(
+                payer,
+                format!("Fee transfer of {net_amount}, expected {amount}"),
+            ));
+        }
+        Ok(SettledFee {
+            recipient: recipient.into(),
+            amount: net_amount.into(),
+            transaction: None,
+        })
+    }
+
+    /// Checks the Token-2022 extensions of the mint and returns its transfer fee config, if any.
+    ///
+    /// Only the extensions in [`ALLOWED_MINT_EXTENSIONS`] are accepted, so an extension added
+    /// to Token-2022 later is refused until it is known not to block or claw back the payment.
+    /// A transfer hook, a permanent delegate and a pause are accepted only while unset.
+    /// Classic SPL Token mints have no extensions.
+    pub fn verify_mint_extensions(
+        mint_data: &[u8],
+        token_program: &Pubkey,
+    ) -> Result<Option<TransferFeeConfig>, FacilitatorLocalError> {
+        if !spl_token_2022_interface::check_id(token_program) {
+            return Ok(None);
+        }
+        let mint = StateWithExtensions::<spl_token_2022_interface::state::Mint>::unpack(mint_data)
+            .map_err(|_| {
+                FacilitatorLocalError::DecodingError(
+                    "invalid_exact_svm_payload_transaction_mint_not_found".to_string(),
+                )
+            })?;
+        let extensions = mint.get_extension_types().map_err(|_| {
+            FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_mint_not_found".to_string(),
+            )
+        })?;
+        if extensions.contains(&ExtensionType::NonTransferable) {
+            return Err(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_mint_non_transferable".to_string(),
+            ));
+        }
+        if let Some(extension) = extensions
+            .iter()
+            .find(|extension| !ALLOWED_MINT_EXTENSIONS.contains(extension))
+        {
+            tracing::debug!(?extension, "Mint extension not allowed");
+            return Err(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_mint_extension".to_string(),
+            ));
+        }
+        if let Ok(transfer_hook) = mint.get_extension::<TransferHook>()
+            && Option::<Pubkey>::from(transfer_hook.program_id).is_some()
+        {
+            return Err(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_mint_transfer_hook".to_string(),
+            ));
+        }
+        if let Ok(permanent_delegate) = mint.get_extension::<PermanentDelegate>()
+            && Option::<Pubkey>::from(permanent_delegate.delegate).is_some()
+        {
+            return Err(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_mint_permanent_delegate".to_string(),
+            ));
+        }
+        if let Ok(pausable) = mint.get_extension::<PausableConfig>()
+            && bool::from(pausable.paused)
+        {
+            return Err(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_mint_paused".to_string(),
+            ));
+        }
+        Ok(mint.get_extension::<TransferFeeConfig>().ok().copied())
+    }
+
     async fn verify_transfer(
         &self,
         request: &VerifyRequest,
@@ -413,7 +1024,7 @@ impl SolanaProvider {
 
         // Assert valid payment START
         let payment_payload = match &payload.payload {
-            ExactPaymentPayload::Evm(..) => {
+            ExactPaymentPayload::Evm(..) | ExactPaymentPayload::EvmPermit(..) => {
                 return Err(FacilitatorLocalError::UnsupportedNetwork(None));
             }
             ExactPaymentPayload::Solana(payload) => payload,
@@ -445,52 +1056,77 @@ impl SolanaProvider {
             .map_err(|e| FacilitatorLocalError::DecodingError(format!("{e}")))?;
         let transaction = bincode::deserialize::<VersionedTransaction>(bytes.as_slice())
             .map_err(|e| FacilitatorLocalError::DecodingError(format!("{e}")))?;
+        // Reject forged or missing buyer signatures before spending any RPC call on them
+        assert_signed_except(&transaction, &self.keypair.pubkey())?;
+        // v0 transactions may load instruction accounts from address lookup tables;
+        // every check below runs against the resolved account keys.
+        let tx = self.resolve_transaction(transaction).await?;
 
         // perform transaction introspection to validate the transaction structure and details
-        let instructions = transaction.message.instructions();
-        let compute_units = self.verify_compute_limit_instruction(&transaction, 0)?;
+        // A durable nonce transaction starts with `AdvanceNonceAccount`; the usual layout follows it.
+        // The fee payer safety check below makes sure the fee payer is not the nonce authority.
+        let offset = usize::from(self.is_advance_nonce_instruction(&tx, 0));
+        let fee = self.fee.as_ref().and_then(|fee| {
+            let amount = fee.amount(requirements.max_amount_required);
+            (!amount.0.is_zero()).then_some((fee, amount))
+        });
+        let required = self.required_instruction_indexes(&tx, offset, fee.is_some())?;
+        let compute_units = self.verify_compute_limit_instruction(&tx, required[offset])?;
         tracing::debug!(compute_units = compute_units, "Verified compute unit limit");
-        self.verify_compute_price_instruction(&transaction, 1)?;
-        let transfer_instruction = if instructions.len() == 3 {
+        let compute_unit_price =
+            self.verify_compute_price_instruction(&tx, required[offset + 1])?;
+        if let Some(max_lamports) = self.compute_budget_limits.max_lamports_per_transaction {
+            let fee = SolanaComputeBudgetLimits::transaction_fee(
+                tx.inner.message.header().num_required_signatures,
+                compute_units,
+                compute_unit_price,
+            );
+            if fee > max_lamports {
+                return Err(FacilitatorLocalError::DecodingError(
+                    "invalid_exact_svm_payload_transaction_fee_too_high".to_string(),
+                ));
+            }
+        }
+        let has_create_ata = required.len() - usize::from(fee.is_some()) == offset + 4;
+        let transfer_instruction = if !has_create_ata {
             // verify that the transfer instruction is valid
             // this expects the destination ATA to already exist
-            self.verify_transfer_instruction(&transaction, 2, requirements, false)
+            self.verify_transfer_instruction(&tx, required[offset + 2], requirements, false)
                 .await?
-        } else if instructions.len() == 4 {
+        } else {
             // verify that the transfer instruction is valid
             // this expects the destination ATA to be created in the same transaction
-            self.verify_create_ata_instruction(&transaction, 2, requirements)?;
-            self.verify_transfer_instruction(&transaction, 3, requirements, true)
+            self.verify_create_ata_instruction(&tx, required[offset + 2], requirements)?;
+            self.verify_transfer_instruction(&tx, required[offset + 3], requirements, true)
                 .await?
-        } else {
-            return Err(FacilitatorLocalError::DecodingError(
-                "invalid_exact_svm_payload_transaction_instructions_count".to_string(),
-            ));
+        };
+        let settled_fee = match fee {
+            Some((fee, amount)) => {
+                let amount = u64::try_from(amount.0).map_err(|_| {
+                    FacilitatorLocalError::InsufficientFee(
+                        transfer_instruction.authority.into(),
+                        format!("Fee of {amount} does not fit a token amount"),
+                    )
+                })?;
+                let index = *required.last().expect("fee transfer is required");
+                Some(self.verify_fee_transfer_instruction(
+                    &tx,
+                    index,
+                    &transfer_instruction,
+                    fee,
+                    amount,
+                )?)
+            }
+            None => None,
         };
 
         // Rule 2: Fee payer safety check
         // Verify that the fee payer is not included in any instruction's accounts
         // This single check covers all cases: authority, source, or any other role
-        let fee_payer_pubkey = self.keypair.pubkey();
-        for instruction in transaction.message.instructions().iter() {
-            for account_idx in instruction.accounts.iter() {
-                let account = transaction
-                    .message
-                    .static_account_keys()
-                    .get(*account_idx as usize)
-                    .ok_or(FacilitatorLocalError::DecodingError(
-                        "invalid_account_index".to_string(),
-                    ))?;
-
-                if *account == fee_payer_pubkey {
-                    return Err(FacilitatorLocalError::DecodingError(
-                        "invalid_exact_svm_payload_transaction_fee_payer_included_in_instruction_accounts".to_string(),
-                    ));
-                }
-            }
-        }
+        tx.assert_not_in_instruction_accounts(&self.keypair.pubkey())?;
 
-        let tx = TransactionInt::new(transaction.clone()).sign(&self.keypair)?;
+        let transaction = tx.inner.clone();
+        let tx = tx.sign(&self.keypair)?;
         let cfg = RpcSimulateTransactionConfig {
             sig_verify: false,
             replace_recent_blockhash: false,
@@ -511,13 +1147,53 @@ impl SolanaProvider {
             ));
         }
         let payer: SolanaAddress = transfer_instruction.authority.into();
-        Ok(VerifyTransferResult { payer, transaction })
+        Ok(VerifyTransferResult {
+            payer,
+            transaction,
+            fee: settled_fee,
+        })
     }
 
     pub fn fee_payer(&self) -> MixedAddress {
         let pubkey = self.keypair.pubkey();
         MixedAddress::Solana(pubkey)
     }
+
+    /// Balance of the fee payer, in lamports.
+    pub async fn fee_payer_balance(&self) -> Result<u64, FacilitatorLocalError> {
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&self.keypair.pubkey(), CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?
+            .value;
+        Ok(account.map(|account| account.lamports).unwrap_or_default())
+    }
+
+    /// Wraps `transaction` into a [`TransactionInt`] with its account keys resolved.
+    ///
+    /// Legacy messages and v0 messages without lookups use the static account keys as is.
+    /// Otherwise the referenced address lookup tables are fetched (or taken from cache)
+    /// and the loaded addresses are appended to the static keys.
+    pub async fn resolve_transaction(
+        &self,
+        transaction: VersionedTransaction,
+    ) -> Result<TransactionInt, FacilitatorLocalError> {
+        let lookups = transaction
+            .message
+            .address_table_lookups()
+            .unwrap_or_default()
+            .to_vec();
+        if lookups.is_empty() {
+            return Ok(TransactionInt::new(transaction));
+        }
+        let tables = self
+            .lookup_tables
+            .fetch(self.rpc_client.as_ref(), &lookups)
+            .await?;
+        let loaded_addresses = load_addresses(&lookups, &tables)?;
+        Ok(TransactionInt::new(transaction).with_loaded_addresses(loaded_addresses))
+    }
 }
 
 impl FromEnvByNetworkBuild for SolanaProvider {
@@ -531,7 +1207,18 @@ impl FromEnvByNetworkBuild for SolanaProvider {
             }
         };
         let keypair = from_env::SignerType::from_env()?.make_solana_wallet()?;
-        let provider = SolanaProvider::try_new(keypair, rpc_url, network)?;
+        let fee = from_env::facilitator_fee_from_env(network, keypair.pubkey())?;
+        let compute_budget_limits = SolanaComputeBudgetLimits::from_env(network)?;
+        let allowed_instructions =
+            match from_env::solana_allowed_instructions_env_name_from_network(network) {
+                Some(env_name) => from_env::parse_optional_env_list(env_name)?,
+                None => None,
+            }
+            .unwrap_or_else(AllowedInstruction::defaults);
+        let provider = SolanaProvider::try_new(keypair, rpc_url, network)?
+            .with_compute_budget_limits(compute_budget_limits)
+            .with_allowed_instructions(allowed_instructions)
+            .with_fee(fee);
         Ok(Some(provider))
     }
 }
@@ -539,6 +1226,8 @@ impl FromEnvByNetworkBuild for SolanaProvider {
 pub struct VerifyTransferResult {
     pub payer: SolanaAddress,
     pub transaction: VersionedTransaction,
+    /// Facilitator fee paid by the transaction, if one is charged.
+    pub fee: Option<SettledFee>,
 }
 
 #[derive(Debug)]
@@ -550,6 +1239,11 @@ pub struct TransferCheckedInstruction {
     pub destination: Pubkey,
     pub authority: Pubkey,
     pub token_program: Pubkey,
+    /// Fee declared by a Token-2022 `TransferCheckedWithFee` instruction.
+    pub fee: Option<u64>,
+    /// Token-2022 transfer fee of the mint in the current epoch, withheld from every transfer.
+    /// Set by [`SolanaProvider::verify_transfer_instruction`], `None` when just parsed.
+    pub mint_transfer_fee: Option<TransferFee>,
     pub data: Vec<u8>,
 }
 
@@ -583,17 +1277,41 @@ impl Facilitator for SolanaProvider {
                 payer: verification.payer.into(),
                 transaction: None,
                 network: self.network(),
+                fee: None,
             });
         }
-        let tx_sig = tx
-            .send_and_confirm(&self.rpc_client, CommitmentConfig::confirmed())
+        let max_wait = Duration::from_secs(request.payment_requirements.max_timeout_seconds);
+        let confirmation = tx
+            .send_and_confirm(
+                self.rpc_client.as_ref(),
+                CommitmentConfig::confirmed(),
+                max_wait,
+            )
             .await?;
+        let tx_sig = confirmation.signature();
+        let error_reason = match confirmation {
+            TransactionConfirmation::Confirmed(_) => None,
+            TransactionConfirmation::Failed(_) => {
+                tracing::event!(Level::WARN, status = "failed", tx = %tx_sig, "transaction failed");
+                Some(FacilitatorErrorReason::InvalidScheme)
+            }
+            TransactionConfirmation::Expired(_) => {
+                tracing::event!(Level::WARN, status = "expired", tx = %tx_sig, "transaction expired");
+                Some(FacilitatorErrorReason::UnexpectedSettleError)
+            }
+            TransactionConfirmation::Pending(_) => {
+                tracing::event!(Level::WARN, status = "pending", tx = %tx_sig, "transaction still pending");
+                Some(FacilitatorErrorReason::SettlementPending)
+            }
+        };
+        let success = error_reason.is_none();
         let settle_response = SettleResponse {
-            success: true,
-            error_reason: None,
+            success,
+            error_reason,
             payer: verification.payer.into(),
             transaction: Some(TransactionHash::Solana(*tx_sig.as_array())),
             network: self.network(),
+            fee: verification.fee.filter(|_| success),
         };
         Ok(settle_response)
     }
@@ -605,12 +1323,156 @@ impl Facilitator for SolanaProvider {
             x402_version: X402Version::V1,
             extra: Some(SupportedPaymentKindExtra {
                 fee_payer: self.signer_address(),
+                max_compute_unit_price: Some(self.compute_budget_limits.max_compute_unit_price),
+                max_compute_unit_limit: Some(self.compute_budget_limits.max_compute_unit_limit),
+                max_lamports_per_transaction: self
+                    .compute_budget_limits
+                    .max_lamports_per_transaction,
+                fee: self.fee.clone(),
+                asset_transfer_method: None,
+                spender: None,
             }),
         }];
         Ok(SupportedPaymentKindsResponse { kinds })
     }
 }
 
+/// Upper bound on cached lookup tables; the cache is emptied when it is reached.
+const ADDRESS_LOOKUP_TABLE_CACHE_CAPACITY: usize = 1024;
+
+/// Addresses of address lookup tables, keyed by table account.
+///
+/// Lookup tables are append-only, so a cached table stays valid for every index it already holds.
+/// It is re-fetched only when a transaction references an index past the cached length.
+/// Deactivated or closed tables are caught by the simulation.
+#[derive(Debug, Default)]
+pub struct AddressLookupTableCache {
+    tables: DashMap<Pubkey, Arc<Vec<Pubkey>>>,
+}
+
+impl AddressLookupTableCache {
+    /// Returns the addresses of every table referenced by `lookups`, fetching uncached ones in a single RPC call.
+    pub async fn fetch(
+        &self,
+        rpc_client: &dyn SolanaRpc,
+        lookups: &[MessageAddressTableLookup],
+    ) -> Result<HashMap<Pubkey, Arc<Vec<Pubkey>>>, FacilitatorLocalError> {
+        let mut tables = HashMap::with_capacity(lookups.len());
+        let mut missing = Vec::new();
+        for lookup in lookups {
+            let max_index = lookup
+                .writable_indexes
+                .iter()
+                .chain(lookup.readonly_indexes.iter())
+                .max()
+                .cloned()
+                .unwrap_or(0);
+            match self.tables.get(&lookup.account_key) {
+                Some(cached) if (max_index as usize) < cached.len() => {
+                    tables.insert(lookup.account_key, cached.clone());
+                }
+                _ => missing.push(lookup.account_key),
+            }
+        }
+        if missing.is_empty() {
+            return Ok(tables);
+        }
+        let accounts = rpc_client
+            .get_multiple_accounts(&missing)
+            .await
+            .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?;
+        let not_found = || {
+            FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_address_lookup_table_not_found".to_string(),
+            )
+        };
+        for (table_key, account) in missing.into_iter().zip(accounts) {
+            let account = account
+                .filter(|account| {
+                    solana_address_lookup_table_interface::program::check_id(&account.owner)
+                })
+                .ok_or_else(not_found)?;
+            let table = AddressLookupTable::deserialize(&account.data).map_err(|_| not_found())?;
+            let addresses = Arc::new(table.addresses.to_vec());
+            if self.tables.len() >= ADDRESS_LOOKUP_TABLE_CACHE_CAPACITY {
+                self.tables.clear();
+            }
+            self.tables.insert(table_key, addresses.clone());
+            tables.insert(table_key, addresses);
+        }
+        Ok(tables)
+    }
+}
+
+/// Checks the ed25519 signature of every required signer except `fee_payer` over the message bytes.
+///
+/// The facilitator signs as fee payer only at settlement, so all other signatures must already
+/// be present and valid. This runs locally, before any RPC call.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] for a missing or invalid signature.
+pub fn assert_signed_except(
+    transaction: &VersionedTransaction,
+    fee_payer: &Pubkey,
+) -> Result<(), FacilitatorLocalError> {
+    let num_required_signatures = usize::from(transaction.message.header().num_required_signatures);
+    let signers = transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .take(num_required_signatures);
+    if transaction.signatures.len() != num_required_signatures
+        || signers.len() != num_required_signatures
+    {
+        return Err(FacilitatorLocalError::DecodingError(
+            "invalid_exact_svm_payload_transaction_signatures".to_string(),
+        ));
+    }
+    let message = transaction.message.serialize();
+    for (signer, signature) in signers.zip(&transaction.signatures) {
+        if signer != fee_payer && !signature.verify(signer.as_ref(), &message) {
+            return Err(FacilitatorLocalError::InvalidSignature(
+                MixedAddress::Solana(*signer),
+                "Invalid transaction signature".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Resolves the addresses a v0 message loads from its lookup tables.
+///
+/// Writable addresses of all tables come first, then readonly ones, matching the order
+/// in which the runtime appends them to the static account keys.
+pub fn load_addresses(
+    lookups: &[MessageAddressTableLookup],
+    tables: &HashMap<Pubkey, Arc<Vec<Pubkey>>>,
+) -> Result<LoadedAddresses, FacilitatorLocalError> {
+    let mut loaded_addresses = LoadedAddresses::default();
+    for lookup in lookups {
+        let table = tables
+            .get(&lookup.account_key)
+            .ok_or(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_address_lookup_table_not_found".to_string(),
+            ))?;
+        let lookup_index = |index: &u8| {
+            table
+                .get(*index as usize)
+                .cloned()
+                .ok_or(FacilitatorLocalError::DecodingError(
+                    "invalid_exact_svm_payload_transaction_address_lookup_table_index".to_string(),
+                ))
+        };
+        for index in lookup.writable_indexes.iter() {
+            loaded_addresses.writable.push(lookup_index(index)?);
+        }
+        for index in lookup.readonly_indexes.iter() {
+            loaded_addresses.readonly.push(lookup_index(index)?);
+        }
+    }
+    Ok(loaded_addresses)
+}
+
 pub struct InstructionInt {
     instruction: CompiledInstruction,
     account_keys: Vec<Pubkey>,
@@ -663,14 +1525,126 @@ impl InstructionInt {
     }
 }
 
+/// How often [`TransactionInt::send_and_confirm`] resends a transaction that has not landed yet.
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Outcome of [`TransactionInt::send_and_confirm`]. All but [`Pending`](Self::Pending) are final.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionConfirmation {
+    /// The transaction landed and succeeded.
+    Confirmed(Signature),
+    /// The transaction landed but failed; its fee is charged.
+    Failed(Signature),
+    /// The blockhash expired or the durable nonce moved before the transaction landed,
+    /// so the transaction can never land.
+    Expired(Signature),
+    /// A durable nonce transaction had not landed in time, while its nonce is still current.
+    /// It is no longer resent, but still lands if anyone sends it before the nonce is advanced.
+    Pending(Signature),
+}
+
+impl TransactionConfirmation {
+    fn from_status(signature: Signature, status: &TransactionStatus) -> Self {
+        match status.err {
+            None => Self::Confirmed(signature),
+            Some(_) => Self::Failed(signature),
+        }
+    }
+
+    pub fn signature(&self) -> Signature {
+        match self {
+            Self::Confirmed(signature)
+            | Self::Failed(signature)
+            | Self::Expired(signature)
+            | Self::Pending(signature) => *signature,
+        }
+    }
+}
+
+/// What makes a transaction unable to land.
+enum TransactionExpiry {
+    /// Recent blockhash transaction: the block height passes the last valid block height.
+    BlockHeight(u64),
+    /// Durable nonce transaction: the nonce account no longer holds the nonce.
+    DurableNonce { nonce_account: Pubkey, nonce: Hash },
+}
+
+impl TransactionExpiry {
+    async fn is_expired(
+        &self,
+        rpc_client: &dyn SolanaRpc,
+        commitment_config: CommitmentConfig,
+    ) -> Result<bool, FacilitatorLocalError> {
+        match self {
+            TransactionExpiry::BlockHeight(last_valid_block_height) => {
+                let block_height = rpc_client
+                    .get_block_height_with_commitment(commitment_config)
+                    .await
+                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?;
+                Ok(block_height > *last_valid_block_height)
+            }
+            TransactionExpiry::DurableNonce {
+                nonce_account,
+                nonce,
+            } => {
+                let account = rpc_client
+                    .get_account_with_commitment(nonce_account, commitment_config)
+                    .await
+                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?
+                    .value;
+                let current_nonce = account
+                    .and_then(|account| bincode::deserialize::<NonceVersions>(&account.data).ok())
+                    .and_then(|versions| match versions.state() {
+                        NonceState::Initialized(data) => Some(data.blockhash()),
+                        NonceState::Uninitialized => None,
+                    });
+                Ok(current_nonce.as_ref() != Some(nonce))
+            }
+        }
+    }
+}
+
 pub struct TransactionInt {
     inner: VersionedTransaction,
+    /// Static account keys, followed by writable and then readonly addresses loaded from lookup tables.
+    account_keys: Vec<Pubkey>,
 }
 
 impl TransactionInt {
     pub fn new(transaction: VersionedTransaction) -> Self {
-        Self { inner: transaction }
+        let account_keys = transaction.message.static_account_keys().to_vec();
+        Self {
+            inner: transaction,
+            account_keys,
+        }
     }
+
+    /// Appends addresses loaded from the message's address lookup tables to the account keys.
+    pub fn with_loaded_addresses(mut self, loaded_addresses: LoadedAddresses) -> Self {
+        self.account_keys.extend(loaded_addresses.writable);
+        self.account_keys.extend(loaded_addresses.readonly);
+        self
+    }
+
+    pub fn account_keys(&self) -> &[Pubkey] {
+        &self.account_keys
+    }
+
+    /// Program ids can not be loaded from lookup tables, so they must index into the static keys.
+    pub fn program_id(
+        &self,
+        instruction: &CompiledInstruction,
+    ) -> Result<Pubkey, FacilitatorLocalError> {
+        self.inner
+            .message
+            .static_account_keys()
+            .get(instruction.program_id_index as usize)
+            .cloned()
+            .ok_or(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_instructions".to_string(),
+            ))
+    }
+
     pub fn instruction(&self, index: usize) -> Result<InstructionInt, FacilitatorLocalError> {
         let instruction = self
             .inner
@@ -681,7 +1655,9 @@ impl TransactionInt {
             .ok_or(FacilitatorLocalError::DecodingError(
                 "invalid_exact_svm_payload_transaction_instructions".to_string(),
             ))?;
-        let account_keys = self.inner.message.static_account_keys().to_vec();
+        // Makes `InstructionInt::program_id` infallible
+        self.program_id(&instruction)?;
+        let account_keys = self.account_keys.clone();
 
         Ok(InstructionInt {
             instruction,
@@ -689,6 +1665,48 @@ impl TransactionInt {
         })
     }
 
+    /// Fails if `pubkey` is referenced by any instruction, through static or loaded account keys.
+    pub fn assert_not_in_instruction_accounts(
+        &self,
+        pubkey: &Pubkey,
+    ) -> Result<(), FacilitatorLocalError> {
+        for instruction in self.inner.message.instructions().iter() {
+            for account_idx in instruction.accounts.iter() {
+                let account = self.account_keys.get(*account_idx as usize).ok_or(
+                    FacilitatorLocalError::DecodingError("invalid_account_index".to_string()),
+                )?;
+                if account == pubkey {
+                    return Err(FacilitatorLocalError::DecodingError(
+                        "invalid_exact_svm_payload_transaction_fee_payer_included_in_instruction_accounts".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// See [`SolanaProvider::is_advance_nonce_instruction`].
+    pub fn is_advance_nonce_instruction(&self, instruction_index: usize) -> bool {
+        let Ok(instruction) = self.instruction(instruction_index) else {
+            return false;
+        };
+        // Accounts: nonce account, RecentBlockhashes sysvar, nonce authority
+        solana_system_interface::program::check_id(&instruction.program_id())
+            && instruction.instruction.accounts.len() >= 3
+            && matches!(
+                bincode::deserialize::<SystemInstruction>(instruction.data_slice()),
+                Ok(SystemInstruction::AdvanceNonceAccount)
+            )
+    }
+
+    /// Nonce account of a durable nonce transaction, `None` for a recent blockhash transaction.
+    pub fn durable_nonce_account(&self) -> Option<Pubkey> {
+        if !self.is_advance_nonce_instruction(0) {
+            return None;
+        }
+        self.instruction(0).ok()?.account(0).ok()
+    }
+
     pub fn is_fully_signed(&self) -> bool {
         let num_required = self.inner.message.header().num_required_signatures;
         if self.inner.signatures.len() < num_required as usize {
@@ -725,10 +1743,16 @@ impl TransactionInt {
         }
         // tx.signatures.push(signature);
         tx.signatures[pos] = signature;
-        Ok(Self { inner: tx })
+        Ok(Self {
+            inner: tx,
+            account_keys: self.account_keys,
+        })
     }
 
-    pub async fn send(&self, rpc_client: &RpcClient) -> Result<Signature, FacilitatorLocalError> {
+    pub async fn send(
+        &self,
+        rpc_client: &dyn SolanaRpc,
+    ) -> Result<Signature, FacilitatorLocalError> {
         rpc_client
             .send_transaction_with_config(
                 &self.inner,
@@ -741,24 +1765,84 @@ impl TransactionInt {
             .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))
     }
 
+    /// Sends the transaction and resends it every [`REBROADCAST_INTERVAL`] until it reaches
+    /// `commitment_config` or can no longer land.
+    ///
+    /// A recent blockhash transaction can not land once the block height passes its
+    /// `lastValidBlockHeight`. The last valid block height of the newest blockhash, taken before
+    /// the first send, is used as an upper bound for it. A durable nonce transaction can not land
+    /// once the nonce stored in its nonce account changes, which may never happen, so it is given up
+    /// on after `max_wait` as [`TransactionConfirmation::Pending`]: it can still land.
+    /// Either way the signature status is checked one last time before giving up.
     pub async fn send_and_confirm(
         &self,
-        rpc_client: &RpcClient,
+        rpc_client: &dyn SolanaRpc,
         commitment_config: CommitmentConfig,
-    ) -> Result<Signature, FacilitatorLocalError> {
+        max_wait: Duration,
+    ) -> Result<TransactionConfirmation, FacilitatorLocalError> {
+        let expiry = match self.durable_nonce_account() {
+            Some(nonce_account) => TransactionExpiry::DurableNonce {
+                nonce_account,
+                nonce: *self.inner.message.recent_blockhash(),
+            },
+            None => {
+                let (_, last_valid_block_height) = rpc_client
+                    .get_latest_blockhash_with_commitment(commitment_config)
+                    .await
+                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?;
+                TransactionExpiry::BlockHeight(last_valid_block_height)
+            }
+        };
+        let deadline = match expiry {
+            TransactionExpiry::DurableNonce { .. } => Some(Instant::now() + max_wait),
+            TransactionExpiry::BlockHeight(_) => None,
+        };
         let tx_sig = self.send(rpc_client).await?;
+        let mut last_sent_at = Instant::now();
         loop {
-            let confirmed = rpc_client
-                .confirm_transaction_with_commitment(&tx_sig, commitment_config)
-                .await
-                .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?;
-            if confirmed.value {
-                return Ok(tx_sig);
-            }
             tokio::time::sleep(Duration::from_millis(500)).await;
+            let status = Self::signature_status(rpc_client, &tx_sig).await?;
+            if let Some(status) = status {
+                if status.satisfies_commitment(commitment_config) {
+                    return Ok(TransactionConfirmation::from_status(tx_sig, &status));
+                }
+                // Landed, waiting for the requested commitment
+                continue;
+            }
+            let expired = expiry.is_expired(rpc_client, commitment_config).await?;
+            let timed_out = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if expired || timed_out {
+                // It might have landed right before expiring
+                match Self::signature_status(rpc_client, &tx_sig).await? {
+                    Some(status) if status.satisfies_commitment(commitment_config) => {
+                        return Ok(TransactionConfirmation::from_status(tx_sig, &status));
+                    }
+                    Some(_) => continue,
+                    None if expired => return Ok(TransactionConfirmation::Expired(tx_sig)),
+                    None => return Ok(TransactionConfirmation::Pending(tx_sig)),
+                }
+            }
+            if last_sent_at.elapsed() >= REBROADCAST_INTERVAL {
+                // The signed transaction is identical, so resending it can not double-spend
+                if let Err(e) = self.send(rpc_client).await {
+                    tracing::warn!(tx = %tx_sig, error = %e, "Failed to rebroadcast transaction");
+                }
+                last_sent_at = Instant::now();
+            }
         }
     }
 
+    async fn signature_status(
+        rpc_client: &dyn SolanaRpc,
+        signature: &Signature,
+    ) -> Result<Option<TransactionStatus>, FacilitatorLocalError> {
+        let statuses = rpc_client
+            .get_signature_statuses(&[*signature])
+            .await
+            .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?;
+        Ok(statuses.value.into_iter().next().flatten())
+    }
+
     #[allow(dead_code)] // Public for consumption by downstream crates.
     pub fn as_base64(&self) -> Result<String, FacilitatorLocalError> {
         let bytes = bincode::serialize(&self.inner)
@@ -769,3 +1853,595 @@ impl TransactionInt {
         Ok(string)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_compute_budget_interface::ComputeBudgetInstruction;
+    use solana_message::v0::Message as MessageV0;
+    use solana_message::{AccountMeta, Instruction, VersionedMessage};
+    use solana_nonce::state::DurableNonce;
+    use solana_rpc_client_api::response::{Response, RpcResponseContext};
+    use solana_transaction_status_client_types::TransactionConfirmationStatus;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// v0 transfer of 1 USDC-like token (6 decimals) with fee payer `[1; 32]` and authority `[2; 32]`.
+    /// Source `[3; 32]`, mint `[4; 32]` and destination `[5; 32]` are loaded from lookup table `[6; 32]`,
+    /// which holds `[mint, source, destination]`. Instructions: compute unit limit 20 000,
+    /// compute unit price 1 000 micro-lamports, `TransferChecked`. Signatures are left empty.
+    const V0_TRANSFER_WITH_LOOKUP_TABLE: &str = "AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAgAIBAgQBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAwZGb+UhFzL/7K26csOb57yM5bvF9xJrLEObOkAAAAAG3fbh12Whk9nL4UbO63msHLSF7V9bN5E6jPWFfv8AqQcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHAwIABQIgTgAAAgAJA+gDAAAAAAAAAwQEBgUBCgxAQg8AAAAAAAYBBgYGBgYGBgYGBgYGBgYGBgYGBgYGBgYGBgYGBgYGBgYCAQIBAA==";
+
+    const FEE_PAYER: Pubkey = Pubkey::new_from_array([1; 32]);
+    const AUTHORITY: Pubkey = Pubkey::new_from_array([2; 32]);
+    const SOURCE: Pubkey = Pubkey::new_from_array([3; 32]);
+    const MINT: Pubkey = Pubkey::new_from_array([4; 32]);
+    const DESTINATION: Pubkey = Pubkey::new_from_array([5; 32]);
+    const LOOKUP_TABLE: Pubkey = Pubkey::new_from_array([6; 32]);
+
+    fn fixture_transaction() -> VersionedTransaction {
+        let bytes = Base64Bytes::from(V0_TRANSFER_WITH_LOOKUP_TABLE.as_bytes())
+            .decode()
+            .expect("fixture is valid base64");
+        bincode::deserialize(&bytes).expect("fixture is a valid transaction")
+    }
+
+    fn lookup_tables(addresses: Vec<Pubkey>) -> HashMap<Pubkey, Arc<Vec<Pubkey>>> {
+        HashMap::from([(LOOKUP_TABLE, Arc::new(addresses))])
+    }
+
+    fn resolve(tables: &HashMap<Pubkey, Arc<Vec<Pubkey>>>) -> TransactionInt {
+        let transaction = fixture_transaction();
+        let lookups = transaction
+            .message
+            .address_table_lookups()
+            .expect("fixture is a v0 message")
+            .to_vec();
+        let loaded_addresses = load_addresses(&lookups, tables).expect("lookups resolve");
+        TransactionInt::new(transaction).with_loaded_addresses(loaded_addresses)
+    }
+
+    fn decoding_reason(error: FacilitatorLocalError) -> String {
+        match error {
+            FacilitatorLocalError::DecodingError(reason) => reason,
+            other => panic!("expected decoding error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn v0_transfer_accounts_resolve_through_lookup_table() {
+        let tx = resolve(&lookup_tables(vec![MINT, SOURCE, DESTINATION]));
+        // Static keys, then writable loaded (source, destination), then readonly loaded (mint)
+        assert_eq!(tx.account_keys()[0], FEE_PAYER);
+        assert_eq!(tx.account_keys()[4..], [SOURCE, DESTINATION, MINT]);
+
+        let transfer = tx.instruction(2).expect("transfer instruction");
+        assert_eq!(transfer.program_id(), spl_token_interface::ID);
+        assert_eq!(transfer.account(0).unwrap(), SOURCE);
+        assert_eq!(transfer.account(1).unwrap(), MINT);
+        assert_eq!(transfer.account(2).unwrap(), DESTINATION);
+        assert_eq!(transfer.account(3).unwrap(), AUTHORITY);
+        tx.assert_not_in_instruction_accounts(&FEE_PAYER)
+            .expect("fee payer is not referenced");
+    }
+
+    #[test]
+    fn v0_transfer_accounts_do_not_resolve_from_static_keys_alone() {
+        let tx = TransactionInt::new(fixture_transaction());
+        let transfer = tx.instruction(2).expect("transfer instruction");
+        assert!(transfer.account(0).is_err());
+        assert_eq!(
+            decoding_reason(
+                tx.assert_not_in_instruction_accounts(&FEE_PAYER)
+                    .unwrap_err()
+            ),
+            "invalid_account_index"
+        );
+    }
+
+    #[test]
+    fn fee_payer_loaded_from_lookup_table_is_rejected() {
+        let tx = resolve(&lookup_tables(vec![MINT, FEE_PAYER, DESTINATION]));
+        assert_eq!(
+            decoding_reason(
+                tx.assert_not_in_instruction_accounts(&FEE_PAYER)
+                    .unwrap_err()
+            ),
+            "invalid_exact_svm_payload_transaction_fee_payer_included_in_instruction_accounts"
+        );
+    }
+
+    #[test]
+    fn unknown_lookup_table_or_index_is_rejected() {
+        let transaction = fixture_transaction();
+        let lookups = transaction.message.address_table_lookups().unwrap();
+        assert_eq!(
+            decoding_reason(load_addresses(lookups, &HashMap::new()).unwrap_err()),
+            "invalid_exact_svm_payload_transaction_address_lookup_table_not_found"
+        );
+        assert_eq!(
+            decoding_reason(
+                load_addresses(lookups, &lookup_tables(vec![MINT, SOURCE])).unwrap_err()
+            ),
+            "invalid_exact_svm_payload_transaction_address_lookup_table_index"
+        );
+    }
+
+    #[test]
+    fn compute_budget_caps_are_enforced() {
+        let provider =
+            |limits: SolanaComputeBudgetLimits| provider().with_compute_budget_limits(limits);
+        let tx = resolve(&lookup_tables(vec![MINT, SOURCE, DESTINATION]));
+
+        let default = provider(SolanaComputeBudgetLimits::default());
+        assert_eq!(
+            default.verify_compute_limit_instruction(&tx, 0).unwrap(),
+            20_000
+        );
+        assert_eq!(
+            default.verify_compute_price_instruction(&tx, 1).unwrap(),
+            1_000
+        );
+
+        let capped = provider(SolanaComputeBudgetLimits {
+            max_compute_unit_price: 999,
+            max_compute_unit_limit: 19_999,
+            max_lamports_per_transaction: None,
+        });
+        assert_eq!(
+            decoding_reason(capped.verify_compute_limit_instruction(&tx, 0).unwrap_err()),
+            "invalid_exact_svm_payload_transaction_compute_limit_instruction_too_high"
+        );
+        assert_eq!(
+            decoding_reason(capped.verify_compute_price_instruction(&tx, 1).unwrap_err()),
+            "invalid_exact_svm_payload_transaction_instructions_compute_price_instruction_too_high"
+        );
+
+        // 2 signatures * 5000 + ceil(20 000 * 1 000 / 1 000 000)
+        assert_eq!(
+            SolanaComputeBudgetLimits::transaction_fee(2, 20_000, 1_000),
+            10_020
+        );
+        assert_eq!(SolanaComputeBudgetLimits::transaction_fee(1, 1, 1), 5_001);
+    }
+
+    fn provider() -> SolanaProvider {
+        SolanaProvider::try_new(
+            Keypair::new(),
+            "http://127.0.0.1:8899".to_string(),
+            Network::SolanaDevnet,
+        )
+        .unwrap()
+    }
+
+    fn transaction_with(instructions: &[Instruction]) -> TransactionInt {
+        let message = MessageV0::try_compile(&FEE_PAYER, instructions, &[], Hash::default())
+            .expect("message compiles");
+        TransactionInt::new(VersionedTransaction {
+            signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+            message: VersionedMessage::V0(message),
+        })
+    }
+
+    fn memo(text: &str) -> Instruction {
+        Instruction::new_with_bytes(MEMO_PROGRAM_PUBKEY, text.as_bytes(), vec![])
+    }
+
+    fn transfer() -> Instruction {
+        spl_token_interface::instruction::transfer_checked(
+            &spl_token_interface::ID,
+            &SOURCE,
+            &MINT,
+            &DESTINATION,
+            &AUTHORITY,
+            &[],
+            1_000_000,
+            6,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn allowed_extra_instructions_may_appear_anywhere() {
+        let tx = transaction_with(&[
+            memo("order-1"),
+            ComputeBudgetInstruction::set_compute_unit_limit(20_000),
+            ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(64 * 1024),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            transfer(),
+            memo("order-1"),
+        ]);
+        let provider = provider();
+        assert_eq!(
+            provider
+                .required_instruction_indexes(&tx, 0, false)
+                .unwrap(),
+            vec![1, 3, 4]
+        );
+
+        // Without the allowlist the same transaction has too many instructions
+        let strict = provider.with_allowed_instructions(vec![]);
+        assert_eq!(
+            decoding_reason(
+                strict
+                    .required_instruction_indexes(&tx, 0, false)
+                    .unwrap_err()
+            ),
+            "invalid_exact_svm_payload_transaction_instructions_count"
+        );
+    }
+
+    #[test]
+    fn unlisted_extra_instructions_are_rejected() {
+        let tx = transaction_with(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(20_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            // A second price instruction is not an allowed compute budget op
+            ComputeBudgetInstruction::set_compute_unit_price(1_000_000),
+            transfer(),
+        ]);
+        let provider = provider();
+        // Parsed as limit, price, create ATA, transfer, so the create ATA check fails on the extra price
+        assert_eq!(
+            provider
+                .required_instruction_indexes(&tx, 0, false)
+                .unwrap(),
+            vec![0, 1, 2, 3]
+        );
+        assert!(!provider.is_allowed_extra_instruction(&tx, 2));
+    }
+
+    #[test]
+    fn fee_transfer_must_pay_the_fee_recipient() {
+        let recipient = Pubkey::new_unique();
+        let (recipient_ata, _) = Pubkey::find_program_address(
+            &[
+                recipient.as_ref(),
+                spl_token_interface::ID.as_ref(),
+                MINT.as_ref(),
+            ],
+            &ATA_PROGRAM_PUBKEY,
+        );
+        let fee_transfer = |destination: &Pubkey, amount: u64| {
+            spl_token_interface::instruction::transfer_checked(
+                &spl_token_interface::ID,
+                &SOURCE,
+                &MINT,
+                destination,
+                &AUTHORITY,
+                &[],
+                amount,
+                6,
+            )
+            .unwrap()
+        };
+        let fee = FacilitatorFee {
+            recipient: recipient.into(),
+            flat: Some(1_000u64.into()),
+            basis_points: Some(100),
+        };
+        // 1 000 flat + 1% of 1 000 000
+        assert_eq!(fee.amount(1_000_000u64.into()), 11_000u64.into());
+
+        let provider = provider().with_fee(Some(fee.clone()));
+        let tx = transaction_with(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(20_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            transfer(),
+            fee_transfer(&recipient_ata, 11_000),
+            memo("order-1"),
+        ]);
+        let required = provider.required_instruction_indexes(&tx, 0, true).unwrap();
+        assert_eq!(required, vec![0, 1, 2, 3]);
+        let payment = provider.parse_transfer_checked_instruction(&tx, 2).unwrap();
+        let settled = provider
+            .verify_fee_transfer_instruction(&tx, 3, &payment, &fee, 11_000)
+            .unwrap();
+        assert_eq!(settled.recipient, MixedAddress::from(recipient));
+        assert_eq!(settled.amount, 11_000u64.into());
+        assert!(matches!(
+            provider.verify_fee_transfer_instruction(&tx, 3, &payment, &fee, 11_001),
+            Err(FacilitatorLocalError::InsufficientFee(..))
+        ));
+
+        let tx = transaction_with(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(20_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            transfer(),
+            fee_transfer(&DESTINATION, 11_000),
+        ]);
+        let payment = provider.parse_transfer_checked_instruction(&tx, 2).unwrap();
+        assert!(matches!(
+            provider.verify_fee_transfer_instruction(&tx, 3, &payment, &fee, 11_000),
+            Err(FacilitatorLocalError::InsufficientFee(..))
+        ));
+    }
+
+    #[test]
+    fn fee_payer_in_allowed_extra_instruction_is_rejected() {
+        let memo_signed_by_fee_payer = Instruction::new_with_bytes(
+            MEMO_PROGRAM_PUBKEY,
+            b"order-1",
+            vec![AccountMeta::new_readonly(FEE_PAYER, true)],
+        );
+        let tx = transaction_with(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(20_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            transfer(),
+            memo_signed_by_fee_payer,
+        ]);
+        assert!(provider().is_allowed_extra_instruction(&tx, 3));
+        assert_eq!(
+            decoding_reason(
+                tx.assert_not_in_instruction_accounts(&FEE_PAYER)
+                    .unwrap_err()
+            ),
+            "invalid_exact_svm_payload_transaction_fee_payer_included_in_instruction_accounts"
+        );
+    }
+
+    #[test]
+    fn allowed_instruction_parses_program_and_data_prefix() {
+        let memo: AllowedInstruction = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"
+            .parse()
+            .unwrap();
+        assert_eq!(memo, AllowedInstruction::program(MEMO_PROGRAM_PUBKEY));
+        let heap_frame: AllowedInstruction = "ComputeBudget111111111111111111111111111111:01"
+            .parse()
+            .unwrap();
+        assert!(heap_frame.matches(&solana_compute_budget_interface::ID, &[1, 0, 0, 1, 0]));
+        assert!(!heap_frame.matches(&solana_compute_budget_interface::ID, &[3, 0]));
+        assert!("not-a-pubkey".parse::<AllowedInstruction>().is_err());
+        assert!(
+            "ComputeBudget111111111111111111111111111111:zz"
+                .parse::<AllowedInstruction>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn every_signer_but_the_fee_payer_must_sign() {
+        let buyer = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            MEMO_PROGRAM_PUBKEY,
+            b"order 42",
+            vec![AccountMeta::new_readonly(buyer.pubkey(), true)],
+        );
+        let message = VersionedMessage::V0(
+            MessageV0::try_compile(&FEE_PAYER, &[instruction], &[], Hash::default())
+                .expect("message compiles"),
+        );
+        let buyer_signature = buyer.sign_message(&message.serialize());
+        let mut transaction = VersionedTransaction {
+            signatures: vec![Signature::default(), buyer_signature],
+            message,
+        };
+        // The fee payer signs only at settlement
+        assert_signed_except(&transaction, &FEE_PAYER).unwrap();
+
+        transaction.signatures[1] = Signature::default();
+        let err = assert_signed_except(&transaction, &FEE_PAYER).unwrap_err();
+        assert!(matches!(
+            err,
+            FacilitatorLocalError::InvalidSignature(MixedAddress::Solana(signer), _) if signer == buyer.pubkey()
+        ));
+
+        transaction.signatures.pop();
+        assert_eq!(
+            decoding_reason(assert_signed_except(&transaction, &FEE_PAYER).unwrap_err()),
+            "invalid_exact_svm_payload_transaction_signatures"
+        );
+    }
+
+    /// RPC of a cluster that accepts every transaction but never lands one.
+    struct DroppingRpc {
+        block_height: u64,
+        last_valid_block_height: u64,
+        /// Nonce currently stored in [`NONCE_ACCOUNT`].
+        nonce: Hash,
+        sent: AtomicUsize,
+        /// Whether the transaction sent has landed, sent by someone else.
+        landed: AtomicBool,
+    }
+
+    const NONCE_ACCOUNT: Pubkey = Pubkey::new_from_array([7; 32]);
+
+    impl DroppingRpc {
+        fn new(block_height: u64, last_valid_block_height: u64, nonce: Hash) -> Self {
+            Self {
+                block_height,
+                last_valid_block_height,
+                nonce,
+                sent: Default::default(),
+                landed: Default::default(),
+            }
+        }
+
+        fn sent(&self) -> usize {
+            self.sent.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl SolanaRpc for DroppingRpc {
+        fn url(&self) -> String {
+            "dropping".to_string()
+        }
+
+        async fn get_multiple_accounts(
+            &self,
+            _pubkeys: &[Pubkey],
+        ) -> ClientResult<Vec<Option<Account>>> {
+            unreachable!("not read while confirming")
+        }
+
+        async fn get_account_with_commitment(
+            &self,
+            pubkey: &Pubkey,
+            _commitment_config: CommitmentConfig,
+        ) -> RpcResult<Option<Account>> {
+            assert_eq!(pubkey, &NONCE_ACCOUNT);
+            let state = NonceState::new_initialized(
+                &AUTHORITY,
+                DurableNonce::from_blockhash(&self.nonce),
+                5_000,
+            );
+            let account = Account {
+                lamports: 1_447_680,
+                data: bincode::serialize(&NonceVersions::new(state)).unwrap(),
+                owner: solana_system_interface::program::ID,
+                executable: false,
+                rent_epoch: 0,
+            };
+            Ok(Response {
+                context: RpcResponseContext::new(0),
+                value: Some(account),
+            })
+        }
+
+        async fn get_block_height_with_commitment(
+            &self,
+            _commitment_config: CommitmentConfig,
+        ) -> ClientResult<u64> {
+            Ok(self.block_height)
+        }
+
+        async fn get_epoch_info(&self) -> ClientResult<EpochInfo> {
+            unreachable!("not read while confirming")
+        }
+
+        async fn get_latest_blockhash_with_commitment(
+            &self,
+            _commitment_config: CommitmentConfig,
+        ) -> ClientResult<(Hash, u64)> {
+            Ok((Hash::new_unique(), self.last_valid_block_height))
+        }
+
+        async fn get_signature_statuses(
+            &self,
+            signatures: &[Signature],
+        ) -> RpcResult<Vec<Option<TransactionStatus>>> {
+            let status = self
+                .landed
+                .load(Ordering::SeqCst)
+                .then(|| TransactionStatus {
+                    slot: 0,
+                    confirmations: None,
+                    status: Ok(()),
+                    err: None,
+                    confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+                });
+            Ok(Response {
+                context: RpcResponseContext::new(0),
+                value: vec![status; signatures.len()],
+            })
+        }
+
+        async fn send_transaction_with_config(
+            &self,
+            transaction: &VersionedTransaction,
+            _config: RpcSendTransactionConfig,
+        ) -> ClientResult<Signature> {
+            self.sent.fetch_add(1, Ordering::SeqCst);
+            Ok(transaction.signatures[0])
+        }
+
+        async fn simulate_transaction_with_config(
+            &self,
+            _transaction: &VersionedTransaction,
+            _config: RpcSimulateTransactionConfig,
+        ) -> RpcResult<RpcSimulateTransactionResult> {
+            unreachable!("not simulated while confirming")
+        }
+    }
+
+    /// A memo transaction using the durable nonce derived from `blockhash` in [`NONCE_ACCOUNT`].
+    fn durable_nonce_transaction(blockhash: &Hash) -> TransactionInt {
+        let nonce = DurableNonce::from_blockhash(blockhash);
+        let instructions = [
+            solana_system_interface::instruction::advance_nonce_account(&NONCE_ACCOUNT, &AUTHORITY),
+            memo("order-1"),
+        ];
+        let message = MessageV0::try_compile(&FEE_PAYER, &instructions, &[], *nonce.as_hash())
+            .expect("message compiles");
+        TransactionInt::new(VersionedTransaction {
+            signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+            message: VersionedMessage::V0(message),
+        })
+    }
+
+    #[tokio::test]
+    async fn transaction_expires_past_its_last_valid_block_height() {
+        let rpc = DroppingRpc::new(101, 100, Hash::default());
+        let tx = transaction_with(&[memo("order-1")]);
+        assert_eq!(tx.durable_nonce_account(), None);
+        let confirmation = tx
+            .send_and_confirm(&rpc, CommitmentConfig::confirmed(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert!(matches!(confirmation, TransactionConfirmation::Expired(_)));
+        assert_eq!(rpc.sent(), 1);
+    }
+
+    #[tokio::test]
+    async fn durable_nonce_transaction_expires_once_the_nonce_advances() {
+        let blockhash = Hash::new_unique();
+        let rpc = DroppingRpc::new(101, 100, Hash::new_unique());
+        let tx = durable_nonce_transaction(&blockhash);
+        assert_eq!(tx.durable_nonce_account(), Some(NONCE_ACCOUNT));
+        let confirmation = tx
+            .send_and_confirm(&rpc, CommitmentConfig::confirmed(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        // The block height is past the last valid one, which does not matter to a nonce
+        assert!(matches!(confirmation, TransactionConfirmation::Expired(_)));
+        assert_eq!(rpc.sent(), 1);
+    }
+
+    #[tokio::test]
+    async fn durable_nonce_transaction_is_given_up_after_max_wait() {
+        let blockhash = Hash::new_unique();
+        let rpc = DroppingRpc::new(0, 100, blockhash);
+        let tx = durable_nonce_transaction(&blockhash);
+        let started_at = Instant::now();
+        let max_wait = REBROADCAST_INTERVAL + Duration::from_millis(500);
+        let confirmation = tx
+            .send_and_confirm(&rpc, CommitmentConfig::confirmed(), max_wait)
+            .await
+            .unwrap();
+        assert!(matches!(confirmation, TransactionConfirmation::Pending(_)));
+        assert!(started_at.elapsed() >= max_wait);
+        // Rebroadcast while waiting for the nonce to advance
+        assert_eq!(rpc.sent(), 2);
+    }
+
+    #[tokio::test]
+    async fn durable_nonce_transaction_given_up_on_may_still_land() {
+        let blockhash = Hash::new_unique();
+        let rpc = DroppingRpc::new(0, 100, blockhash);
+        let tx = durable_nonce_transaction(&blockhash);
+        let confirmation = tx
+            .send_and_confirm(&rpc, CommitmentConfig::confirmed(), Duration::ZERO)
+            .await
+            .unwrap();
+        // Not a final failure: the signed transaction is out there, and its nonce still current
+        let TransactionConfirmation::Pending(signature) = confirmation else {
+            panic!("expected a pending transaction, got {confirmation:?}");
+        };
+
+        // Someone else sends it after the deadline
+        rpc.landed.store(true, Ordering::SeqCst);
+        let status = TransactionInt::signature_status(&rpc, &signature)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            TransactionConfirmation::from_status(signature, &status),
+            TransactionConfirmation::Confirmed(signature)
+        );
+        let confirmation = tx
+            .send_and_confirm(&rpc, CommitmentConfig::confirmed(), Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(confirmation, TransactionConfirmation::Confirmed(signature));
+    }
+}
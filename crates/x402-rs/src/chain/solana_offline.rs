@@ -0,0 +1,953 @@
+//! In-process Solana chain for exercising [`SolanaProvider`] without network access.
+//!
+//! [`OfflineSolanaChain`] runs the programs of a real cluster in a [LiteSVM] instance and answers
+//! the RPC calls of [`SolanaRpc`] from it. The SPL Token, Token-2022 and Associated Token Account
+//! programs are loaded, so instruction layout, ATA creation and transfers go through the same code
+//! as on mainnet.
+//!
+//! Every transaction is processed as soon as it is sent and is finalized right away. The slot,
+//! and so the block height, only moves with [`OfflineSolanaChain::warp_to_slot`]. A transaction
+//! the runtime refuses to process, like one with an unknown blockhash or an invalid signature,
+//! is rejected by `sendTransaction` instead of being silently dropped.
+//!
+//! Available with the `solana-offline` feature.
+//!
+//! ```rust
+//! use solana_keypair::Keypair;
+//! use solana_signer::Signer;
+//! use x402_rs::chain::solana_offline::OfflineSolanaChain;
+//! use x402_rs::network::Network;
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let chain = OfflineSolanaChain::new(Network::SolanaDevnet)?;
+//! let mint = chain.create_mint(&spl_token_interface::ID, 6);
+//! let buyer = Keypair::new();
+//! chain.mint_to(&mint, &buyer.pubkey(), 1_000_000);
+//! let facilitator = Keypair::new();
+//! chain.airdrop(&facilitator.pubkey(), 1_000_000_000);
+//! let provider = chain.provider(facilitator)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [LiteSVM]: https://github.com/LiteSVM/litesvm
+
+use async_trait::async_trait;
+use litesvm::LiteSVM;
+use litesvm::types::TransactionResult;
+use solana_account::Account;
+use solana_clock::{Clock, MAX_PROCESSING_AGE};
+use solana_commitment_config::CommitmentConfig;
+use solana_epoch_info::EpochInfo;
+use solana_epoch_schedule::EpochSchedule;
+use solana_keypair::Keypair;
+use solana_message::{Hash, Instruction};
+use solana_pubkey::Pubkey;
+use solana_rpc_client_api::client_error::{ErrorKind as ClientErrorKind, Result as ClientResult};
+use solana_rpc_client_api::config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig};
+use solana_rpc_client_api::response::{
+    Response, RpcResponseContext, RpcResult, RpcSimulateTransactionResult,
+    TransactionConfirmationStatus,
+};
+use solana_signature::Signature;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use solana_transaction::versioned::VersionedTransaction;
+use solana_transaction_status_client_types::TransactionStatus;
+use spl_associated_token_account_interface::address::get_associated_token_address_with_program_id;
+use spl_associated_token_account_interface::instruction::create_associated_token_account_idempotent;
+use spl_token_2022_interface::extension::{ExtensionType, StateWithExtensions};
+use spl_token_2022_interface::state::{Account as TokenAccount, Mint};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::chain::FacilitatorLocalError;
+use crate::chain::solana::{SolanaChain, SolanaProvider, SolanaRpc};
+use crate::network::Network;
+
+/// Lamports held by the authority that pays for and signs the setup transactions.
+const AUTHORITY_LAMPORTS: u64 = 1_000_000_000_000;
+
+/// An in-process Solana chain, usable as the RPC of a [`SolanaProvider`].
+///
+/// Clones share the chain state.
+#[derive(Clone)]
+pub struct OfflineSolanaChain {
+    chain: SolanaChain,
+    svm: Arc<Mutex<LiteSVM>>,
+    /// Mint authority of every mint created here, and fee payer of the setup transactions.
+    authority: Arc<Keypair>,
+}
+
+impl std::fmt::Debug for OfflineSolanaChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OfflineSolanaChain")
+            .field("chain", &self.chain)
+            .field("authority", &self.authority.pubkey())
+            .finish()
+    }
+}
+
+impl OfflineSolanaChain {
+    /// Starts a chain with the SPL Token, Token-2022 and Associated Token Account programs loaded.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::UnsupportedNetwork`] if `network` is not a Solana network.
+    pub fn new(network: Network) -> Result<Self, FacilitatorLocalError> {
+        let chain = SolanaChain::try_from(network)?;
+        let mut svm = LiteSVM::new();
+        let authority = Keypair::new();
+        svm.airdrop(&authority.pubkey(), AUTHORITY_LAMPORTS)
+            .expect("airdrop to a fresh account succeeds");
+        Ok(Self {
+            chain,
+            svm: Arc::new(Mutex::new(svm)),
+            authority: Arc::new(authority),
+        })
+    }
+
+    fn svm(&self) -> MutexGuard<'_, LiteSVM> {
+        self.svm.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Chain descriptor of this chain.
+    pub fn chain(&self) -> SolanaChain {
+        self.chain.clone()
+    }
+
+    /// Processes `instructions` paid by the chain authority, and signed by it and `signers`.
+    ///
+    /// # Panics
+    /// If the transaction fails.
+    fn execute(&self, instructions: &[Instruction], signers: &[&Keypair]) {
+        let mut svm = self.svm();
+        let mut keypairs = vec![self.authority.as_ref()];
+        keypairs.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.authority.pubkey()),
+            &keypairs,
+            svm.latest_blockhash(),
+        );
+        if let Err(failed) = svm.send_transaction(transaction) {
+            panic!(
+                "setup transaction failed: {}\n{}",
+                failed.err,
+                failed.meta.pretty_logs()
+            );
+        }
+    }
+
+    /// Credits `lamports` to `to`.
+    pub fn airdrop(&self, to: &Pubkey, lamports: u64) {
+        self.svm()
+            .airdrop(to, lamports)
+            .expect("airdrop from the faucet succeeds");
+    }
+
+    /// Creates a mint owned by `token_program`, either SPL Token or Token-2022, and returns it.
+    pub fn create_mint(&self, token_program: &Pubkey, decimals: u8) -> Pubkey {
+        self.create_mint_with_extensions(token_program, decimals, &[], |_| Vec::new())
+    }
+
+    /// Creates a Token-2022 mint with fixed-length `extensions`, and returns it.
+    ///
+    /// `initialize` returns the instructions initializing the extensions of the given mint
+    /// address. They run before the mint itself is initialized.
+    ///
+    /// # Panics
+    /// If the extensions do not match their initialization.
+    pub fn create_token_2022_mint(
+        &self,
+        decimals: u8,
+        extensions: &[ExtensionType],
+        initialize: impl FnOnce(&Pubkey) -> Vec<Instruction>,
+    ) -> Pubkey {
+        self.create_mint_with_extensions(
+            &spl_token_2022_interface::ID,
+            decimals,
+            extensions,
+            initialize,
+        )
+    }
+
+    fn create_mint_with_extensions(
+        &self,
+        token_program: &Pubkey,
+        decimals: u8,
+        extensions: &[ExtensionType],
+        initialize: impl FnOnce(&Pubkey) -> Vec<Instruction>,
+    ) -> Pubkey {
+        let mint = Keypair::new();
+        let space = ExtensionType::try_calculate_account_len::<Mint>(extensions)
+            .expect("fixed-length extensions have a length");
+        let lamports = self.svm().minimum_balance_for_rent_exemption(space);
+        let mut instructions = vec![solana_system_interface::instruction::create_account(
+            &self.authority.pubkey(),
+            &mint.pubkey(),
+            lamports,
+            space as u64,
+            token_program,
+        )];
+        instructions.extend(initialize(&mint.pubkey()));
+        instructions.push(
+            spl_token_2022_interface::instruction::initialize_mint2(
+                token_program,
+                &mint.pubkey(),
+                &self.authority.pubkey(),
+                None,
+                decimals,
+            )
+            .expect("token program is SPL Token or Token-2022"),
+        );
+        self.execute(&instructions, &[&mint]);
+        mint.pubkey()
+    }
+
+    /// Address of the associated token account of `owner` for `mint`.
+    ///
+    /// # Panics
+    /// If there is no mint at `mint`.
+    pub fn associated_token_address(&self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.token_program(mint))
+    }
+
+    fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.svm()
+            .get_account(mint)
+            .map(|account| account.owner)
+            .expect("mint exists")
+    }
+
+    /// Creates the associated token account of `owner` for `mint` if missing, and returns it.
+    pub fn create_associated_token_account(&self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let token_program = self.token_program(mint);
+        self.execute(
+            &[create_associated_token_account_idempotent(
+                &self.authority.pubkey(),
+                owner,
+                mint,
+                &token_program,
+            )],
+            &[],
+        );
+        get_associated_token_address_with_program_id(owner, mint, &token_program)
+    }
+
+    /// Mints `amount` of `mint` into the associated token account of `owner`, creating it if missing.
+    pub fn mint_to(&self, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+        let token_program = self.token_program(mint);
+        let decimals = self
+            .svm()
+            .get_account(mint)
+            .and_then(|account| {
+                StateWithExtensions::<Mint>::unpack(&account.data)
+                    .ok()
+                    .map(|mint| mint.base.decimals)
+            })
+            .expect("mint is initialized");
+        let destination = get_associated_token_address_with_program_id(owner, mint, &token_program);
+        let instructions = [
+            create_associated_token_account_idempotent(
+                &self.authority.pubkey(),
+                owner,
+                mint,
+                &token_program,
+            ),
+            spl_token_2022_interface::instruction::mint_to_checked(
+                &token_program,
+                mint,
+                &destination,
+                &self.authority.pubkey(),
+                &[],
+                amount,
+                decimals,
+            )
+            .expect("token program is SPL Token or Token-2022"),
+        ];
+        self.execute(&instructions, &[]);
+    }
+
+    /// Balance of `owner` in `mint`, or `None` if it has no associated token account.
+    pub fn token_balance(&self, mint: &Pubkey, owner: &Pubkey) -> Option<u64> {
+        let address = self.associated_token_address(mint, owner);
+        let account = self.svm().get_account(&address)?;
+        StateWithExtensions::<TokenAccount>::unpack(&account.data)
+            .ok()
+            .map(|account| account.base.amount)
+    }
+
+    /// Lamports held by `address`.
+    pub fn lamports(&self, address: &Pubkey) -> u64 {
+        self.svm().get_balance(address).unwrap_or_default()
+    }
+
+    /// Account at `address`, if any.
+    pub fn account(&self, address: &Pubkey) -> Option<Account> {
+        self.svm().get_account(address)
+    }
+
+    /// Blockhash that transactions sent to this chain must reference.
+    pub fn latest_blockhash(&self) -> Hash {
+        self.svm().latest_blockhash()
+    }
+
+    /// Replaces the latest blockhash, so transactions referencing the previous one no longer land.
+    pub fn expire_blockhash(&self) {
+        self.svm().expire_blockhash();
+    }
+
+    /// Moves the clock to `slot`. The block height follows the slot.
+    pub fn warp_to_slot(&self, slot: u64) {
+        self.svm().warp_to_slot(slot);
+    }
+
+    /// A [`SolanaProvider`] on this chain, paying fees with `keypair`.
+    ///
+    /// The fee payer has no lamports until [`airdrop`](Self::airdrop)ped some.
+    pub fn provider(&self, keypair: Keypair) -> Result<SolanaProvider, FacilitatorLocalError> {
+        SolanaProvider::try_new_with_rpc(keypair, Arc::new(self.clone()), self.chain.network)
+    }
+
+    fn context(svm: &LiteSVM) -> RpcResponseContext {
+        RpcResponseContext::new(svm.get_sysvar::<Clock>().slot)
+    }
+}
+
+fn transaction_status(slot: u64, result: &TransactionResult) -> TransactionStatus {
+    let status = result
+        .as_ref()
+        .map(|_| ())
+        .map_err(|failed| failed.err.clone());
+    TransactionStatus {
+        slot,
+        confirmations: None,
+        err: status.clone().err(),
+        status,
+        confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+    }
+}
+
+#[async_trait]
+impl SolanaRpc for OfflineSolanaChain {
+    fn url(&self) -> String {
+        "offline".to_string()
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ClientResult<Vec<Option<Account>>> {
+        let svm = self.svm();
+        Ok(pubkeys
+            .iter()
+            .map(|pubkey| svm.get_account(pubkey))
+            .collect())
+    }
+
+    async fn get_account_with_commitment(
+        &self,
+        pubkey: &Pubkey,
+        _commitment_config: CommitmentConfig,
+    ) -> RpcResult<Option<Account>> {
+        let svm = self.svm();
+        Ok(Response {
+            context: Self::context(&svm),
+            value: svm.get_account(pubkey),
+        })
+    }
+
+    async fn get_block_height_with_commitment(
+        &self,
+        _commitment_config: CommitmentConfig,
+    ) -> ClientResult<u64> {
+        Ok(self.svm().get_sysvar::<Clock>().slot)
+    }
+
+    async fn get_epoch_info(&self) -> ClientResult<EpochInfo> {
+        let svm = self.svm();
+        let clock = svm.get_sysvar::<Clock>();
+        let schedule = svm.get_sysvar::<EpochSchedule>();
+        let (epoch, slot_index) = schedule.get_epoch_and_slot_index(clock.slot);
+        Ok(EpochInfo {
+            epoch,
+            slot_index,
+            slots_in_epoch: schedule.get_slots_in_epoch(epoch),
+            absolute_slot: clock.slot,
+            block_height: clock.slot,
+            transaction_count: None,
+        })
+    }
+
+    async fn get_latest_blockhash_with_commitment(
+        &self,
+        _commitment_config: CommitmentConfig,
+    ) -> ClientResult<(Hash, u64)> {
+        let svm = self.svm();
+        let slot = svm.get_sysvar::<Clock>().slot;
+        Ok((svm.latest_blockhash(), slot + MAX_PROCESSING_AGE as u64))
+    }
+
+    async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> RpcResult<Vec<Option<TransactionStatus>>> {
+        let svm = self.svm();
+        let context = Self::context(&svm);
+        let value = signatures
+            .iter()
+            .map(|signature| {
+                svm.get_transaction(signature)
+                    .map(|result| transaction_status(context.slot, result))
+            })
+            .collect();
+        Ok(Response { context, value })
+    }
+
+    async fn send_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        _config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        let mut svm = self.svm();
+        let signature =
+            transaction.signatures.first().copied().ok_or_else(|| {
+                ClientErrorKind::Custom("transaction has no signatures".to_string())
+            })?;
+        match svm.send_transaction(transaction.clone()) {
+            Ok(_) => Ok(signature),
+            // Failed, but landed and charged fees, like a transaction sent without preflight
+            Err(_) if svm.get_transaction(&signature).is_some() => Ok(signature),
+            Err(failed) => Err(failed.err.into()),
+        }
+    }
+
+    async fn simulate_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        _config: RpcSimulateTransactionConfig,
+    ) -> RpcResult<RpcSimulateTransactionResult> {
+        let svm = self.svm();
+        let (err, meta) = match svm.simulate_transaction(transaction.clone()) {
+            Ok(info) => (None, info.meta),
+            Err(failed) => (Some(failed.err.into()), failed.meta),
+        };
+        Ok(Response {
+            context: Self::context(&svm),
+            value: RpcSimulateTransactionResult {
+                err,
+                logs: Some(meta.logs),
+                accounts: None,
+                units_consumed: Some(meta.compute_units_consumed),
+                loaded_accounts_data_size: None,
+                return_data: None,
+                inner_instructions: None,
+                replacement_blockhash: None,
+                fee: None,
+                pre_balances: None,
+                post_balances: None,
+                pre_token_balances: None,
+                post_token_balances: None,
+                loaded_addresses: None,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::facilitator::Facilitator;
+    use crate::types::{Base64Bytes, FacilitatorFee, VerifyRequest, VerifyResponse};
+    use serde_json::{Value, json};
+    use solana_compute_budget_interface::ComputeBudgetInstruction;
+    use solana_message::VersionedMessage;
+    use solana_message::v0::Message as MessageV0;
+    use spl_token_2022_interface::extension::{
+        confidential_transfer, metadata_pointer, transfer_fee, transfer_hook,
+    };
+
+    const DECIMALS: u8 = 6;
+
+    struct Setup {
+        chain: OfflineSolanaChain,
+        mint: Pubkey,
+        token_program: Pubkey,
+        facilitator: Pubkey,
+        provider: SolanaProvider,
+        buyer: Keypair,
+        pay_to: Pubkey,
+    }
+
+    /// A buyer holding `buyer_balance` of a fresh `token_program` mint, and a payee without a token account.
+    fn setup(token_program: Pubkey, buyer_balance: u64) -> Setup {
+        let chain = OfflineSolanaChain::new(Network::SolanaDevnet).unwrap();
+        let mint = chain.create_mint(&token_program, DECIMALS);
+        setup_with_mint(chain, mint, token_program, buyer_balance)
+    }
+
+    /// Like [`setup`], with a Token-2022 mint carrying `extensions`.
+    fn setup_token_2022(
+        extensions: &[ExtensionType],
+        initialize: impl FnOnce(&Pubkey) -> Vec<Instruction>,
+    ) -> Setup {
+        let chain = OfflineSolanaChain::new(Network::SolanaDevnet).unwrap();
+        let mint = chain.create_token_2022_mint(DECIMALS, extensions, initialize);
+        setup_with_mint(chain, mint, spl_token_2022_interface::ID, 1_500_000)
+    }
+
+    fn setup_with_mint(
+        chain: OfflineSolanaChain,
+        mint: Pubkey,
+        token_program: Pubkey,
+        buyer_balance: u64,
+    ) -> Setup {
+        let buyer = Keypair::new();
+        chain.mint_to(&mint, &buyer.pubkey(), buyer_balance);
+        let facilitator = Keypair::new();
+        chain.airdrop(&facilitator.pubkey(), 1_000_000_000);
+        Setup {
+            facilitator: facilitator.pubkey(),
+            provider: chain.provider(facilitator).unwrap(),
+            chain,
+            mint,
+            token_program,
+            buyer,
+            pay_to: Pubkey::new_unique(),
+        }
+    }
+
+    fn requirements(setup: &Setup) -> Value {
+        json!({
+            "scheme": "exact",
+            "network": "solana-devnet",
+            "maxAmountRequired": "1000000",
+            "resource": "https://example.com/resource",
+            "description": "",
+            "mimeType": "application/json",
+            "payTo": setup.pay_to.to_string(),
+            "maxTimeoutSeconds": 600,
+            "asset": setup.mint.to_string(),
+            "extra": { "feePayer": setup.facilitator.to_string() },
+        })
+    }
+
+    fn compute_budget() -> Vec<Instruction> {
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+        ]
+    }
+
+    fn create_payee_ata(setup: &Setup, funding: &Pubkey) -> Instruction {
+        create_associated_token_account_idempotent(
+            funding,
+            &setup.pay_to,
+            &setup.mint,
+            &setup.token_program,
+        )
+    }
+
+    fn transfer(setup: &Setup, amount: u64) -> Instruction {
+        spl_token_2022_interface::instruction::transfer_checked(
+            &setup.token_program,
+            &get_associated_token_address_with_program_id(
+                &setup.buyer.pubkey(),
+                &setup.mint,
+                &setup.token_program,
+            ),
+            &setup.mint,
+            &get_associated_token_address_with_program_id(
+                &setup.pay_to,
+                &setup.mint,
+                &setup.token_program,
+            ),
+            &setup.buyer.pubkey(),
+            &[],
+            amount,
+            DECIMALS,
+        )
+        .unwrap()
+    }
+
+    /// A payment transaction of `instructions`, paid by the facilitator and signed by the buyer only.
+    fn payment(setup: &Setup, instructions: &[Instruction]) -> VerifyRequest {
+        let message = MessageV0::try_compile(
+            &setup.facilitator,
+            instructions,
+            &[],
+            setup.chain.latest_blockhash(),
+        )
+        .unwrap();
+        let num_signers = message.header.num_required_signatures as usize;
+        let position = message.account_keys[..num_signers]
+            .iter()
+            .position(|key| *key == setup.buyer.pubkey())
+            .unwrap();
+        let message = VersionedMessage::V0(message);
+        let mut signatures = vec![Signature::default(); num_signers];
+        signatures[position] = setup.buyer.sign_message(&message.serialize());
+        let transaction = VersionedTransaction {
+            signatures,
+            message,
+        };
+        let encoded = Base64Bytes::encode(bincode::serialize(&transaction).unwrap());
+        serde_json::from_value(json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "exact",
+                "network": "solana-devnet",
+                "payload": {
+                    "transaction": String::from_utf8(encoded.0.into_owned()).unwrap(),
+                }
+            },
+            "paymentRequirements": requirements(setup),
+        }))
+        .unwrap()
+    }
+
+    fn decoding_reason(error: FacilitatorLocalError) -> String {
+        match error {
+            FacilitatorLocalError::DecodingError(reason) => reason,
+            other => panic!("expected decoding error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn transfer_to_existing_ata_verifies_and_settles_once() {
+        let setup = setup(spl_token_interface::ID, 1_500_000);
+        setup
+            .chain
+            .create_associated_token_account(&setup.mint, &setup.pay_to);
+        let mut instructions = compute_budget();
+        instructions.push(transfer(&setup, 1_000_000));
+        let request = payment(&setup, &instructions);
+
+        let verified = setup.provider.verify(&request).await.unwrap();
+        assert!(matches!(verified, VerifyResponse::Valid { .. }));
+        // Verification only simulates
+        assert_eq!(
+            setup
+                .chain
+                .token_balance(&setup.mint, &setup.buyer.pubkey()),
+            Some(1_500_000)
+        );
+
+        let facilitator_lamports = setup.chain.lamports(&setup.facilitator);
+        let settled = setup.provider.settle(&request).await.unwrap();
+        assert!(settled.success);
+        assert_eq!(
+            setup
+                .chain
+                .token_balance(&setup.mint, &setup.buyer.pubkey()),
+            Some(500_000)
+        );
+        assert_eq!(
+            setup.chain.token_balance(&setup.mint, &setup.pay_to),
+            Some(1_000_000)
+        );
+        // 2 signatures * 5000 + ceil(200 000 * 1 000 / 1 000 000)
+        assert_eq!(
+            facilitator_lamports - setup.chain.lamports(&setup.facilitator),
+            10_200
+        );
+
+        // The signed transaction has landed
+        let replay = setup.provider.settle(&request).await.unwrap_err();
+        assert_eq!(
+            decoding_reason(replay),
+            "invalid_exact_svm_payload_transaction_simulation_failed"
+        );
+        assert_eq!(
+            setup.chain.token_balance(&setup.mint, &setup.pay_to),
+            Some(1_000_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn transfer_creating_payee_ata_settles_on_token_2022() {
+        let setup = setup(spl_token_2022_interface::ID, 1_500_000);
+        // The buyer pays the rent of the payee token account
+        setup.chain.airdrop(&setup.buyer.pubkey(), 100_000_000);
+        let mut instructions = compute_budget();
+        instructions.push(create_payee_ata(&setup, &setup.buyer.pubkey()));
+        instructions.push(transfer(&setup, 1_000_000));
+        let request = payment(&setup, &instructions);
+
+        let settled = setup.provider.settle(&request).await.unwrap();
+        assert!(settled.success);
+        assert_eq!(
+            setup.chain.token_balance(&setup.mint, &setup.pay_to),
+            Some(1_000_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn fee_payer_funding_the_payee_ata_is_rejected() {
+        let setup = setup(spl_token_interface::ID, 1_500_000);
+        let mut instructions = compute_budget();
+        instructions.push(create_payee_ata(&setup, &setup.facilitator));
+        instructions.push(transfer(&setup, 1_000_000));
+        let request = payment(&setup, &instructions);
+
+        let error = setup.provider.settle(&request).await.unwrap_err();
+        assert_eq!(
+            decoding_reason(error),
+            "invalid_exact_svm_payload_transaction_fee_payer_included_in_instruction_accounts"
+        );
+        assert_eq!(setup.chain.token_balance(&setup.mint, &setup.pay_to), None);
+    }
+
+    #[tokio::test]
+    async fn transfer_to_missing_payee_ata_is_rejected() {
+        let setup = setup(spl_token_interface::ID, 1_500_000);
+        let mut instructions = compute_budget();
+        instructions.push(transfer(&setup, 1_000_000));
+        let request = payment(&setup, &instructions);
+
+        let error = setup.provider.verify(&request).await.unwrap_err();
+        assert_eq!(
+            decoding_reason(error),
+            "invalid_exact_svm_payload_transaction_receiver_ata_not_found"
+        );
+    }
+
+    #[tokio::test]
+    async fn transfer_over_buyer_balance_fails_simulation() {
+        let setup = setup(spl_token_interface::ID, 500_000);
+        setup
+            .chain
+            .create_associated_token_account(&setup.mint, &setup.pay_to);
+        let mut instructions = compute_budget();
+        instructions.push(transfer(&setup, 1_000_000));
+        let request = payment(&setup, &instructions);
+
+        let error = setup.provider.verify(&request).await.unwrap_err();
+        assert_eq!(
+            decoding_reason(error),
+            "invalid_exact_svm_payload_transaction_simulation_failed"
+        );
+    }
+
+    #[tokio::test]
+    async fn fee_payer_balance_is_read_from_chain() {
+        let setup = setup(spl_token_interface::ID, 0);
+        assert_eq!(
+            setup.provider.fee_payer_balance().await.unwrap(),
+            1_000_000_000
+        );
+        let unfunded = setup.chain.provider(Keypair::new()).unwrap();
+        assert_eq!(unfunded.fee_payer_balance().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn unlisted_extra_instructions_are_rejected() {
+        let setup = setup(spl_token_interface::ID, 1_500_000);
+        setup
+            .chain
+            .create_associated_token_account(&setup.mint, &setup.pay_to);
+        let mut instructions = compute_budget();
+        instructions.push(transfer(&setup, 1_000_000));
+        // Neither a memo nor any other allowlisted program
+        instructions.push(Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            b"extra",
+            vec![],
+        ));
+        let request = payment(&setup, &instructions);
+
+        // Not skipped as an extra, it is taken for the optional create ATA instruction
+        let error = setup.provider.verify(&request).await.unwrap_err();
+        assert_eq!(
+            decoding_reason(error),
+            "invalid_exact_svm_payload_transaction_create_ata_instruction"
+        );
+        assert!(setup.provider.settle(&request).await.is_err());
+        assert_eq!(
+            setup.chain.token_balance(&setup.mint, &setup.pay_to),
+            Some(0)
+        );
+    }
+
+    /// Initializes an extension of the given mint.
+    type InitializeExtension = fn(&Pubkey) -> Instruction;
+
+    /// A payment of 1 token from the buyer to the payee's existing token account.
+    fn existing_ata_payment(setup: &Setup) -> VerifyRequest {
+        setup
+            .chain
+            .create_associated_token_account(&setup.mint, &setup.pay_to);
+        let mut instructions = compute_budget();
+        instructions.push(transfer(setup, 1_000_000));
+        payment(setup, &instructions)
+    }
+
+    #[tokio::test]
+    async fn descriptive_mint_extensions_are_accepted() {
+        let setup = setup_token_2022(
+            &[
+                ExtensionType::MetadataPointer,
+                ExtensionType::MintCloseAuthority,
+            ],
+            |mint| {
+                let token_program = &spl_token_2022_interface::ID;
+                vec![
+                    metadata_pointer::instruction::initialize(
+                        token_program,
+                        mint,
+                        None,
+                        Some(*mint),
+                    )
+                    .unwrap(),
+                    spl_token_2022_interface::instruction::initialize_mint_close_authority(
+                        token_program,
+                        mint,
+                        Some(&Pubkey::new_unique()),
+                    )
+                    .unwrap(),
+                ]
+            },
+        );
+        let request = existing_ata_payment(&setup);
+        let verified = setup.provider.verify(&request).await.unwrap();
+        assert!(matches!(verified, VerifyResponse::Valid { .. }));
+    }
+
+    #[tokio::test]
+    async fn fee_transfer_counts_net_of_the_mint_transfer_fee() {
+        // 1%, capped at 100
+        let setup = setup_token_2022(&[ExtensionType::TransferFeeConfig], |mint| {
+            vec![
+                transfer_fee::instruction::initialize_transfer_fee_config(
+                    &spl_token_2022_interface::ID,
+                    mint,
+                    None,
+                    None,
+                    100,
+                    100,
+                )
+                .unwrap(),
+            ]
+        });
+        setup
+            .chain
+            .create_associated_token_account(&setup.mint, &setup.pay_to);
+        let recipient = Pubkey::new_unique();
+        let recipient_ata = setup
+            .chain
+            .create_associated_token_account(&setup.mint, &recipient);
+        let provider = setup.provider.clone().with_fee(Some(FacilitatorFee {
+            recipient: recipient.into(),
+            flat: Some(10_000u64.into()),
+            basis_points: None,
+        }));
+        let fee_transfer = |amount: u64| {
+            spl_token_2022_interface::instruction::transfer_checked(
+                &setup.token_program,
+                &setup
+                    .chain
+                    .associated_token_address(&setup.mint, &setup.buyer.pubkey()),
+                &setup.mint,
+                &recipient_ata,
+                &setup.buyer.pubkey(),
+                &[],
+                amount,
+                DECIMALS,
+            )
+            .unwrap()
+        };
+        let request = |fee_amount: u64| {
+            let mut instructions = compute_budget();
+            instructions.push(transfer(&setup, 1_000_100));
+            instructions.push(fee_transfer(fee_amount));
+            payment(&setup, &instructions)
+        };
+
+        // A plain transfer of the fee leaves the recipient 9 900 once the mint withheld its fee
+        let error = provider.verify(&request(10_000)).await.unwrap_err();
+        assert!(matches!(error, FacilitatorLocalError::InsufficientFee(..)));
+
+        let settled = provider.settle(&request(10_100)).await.unwrap();
+        assert!(settled.success);
+        assert_eq!(settled.fee.unwrap().amount, 10_000u64.into());
+        assert_eq!(
+            setup.chain.token_balance(&setup.mint, &recipient),
+            Some(10_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn mint_extensions_that_can_block_the_payment_are_rejected() {
+        let token_program = &spl_token_2022_interface::ID;
+        let cases: [(ExtensionType, InitializeExtension, &str); 4] = [
+            (
+                ExtensionType::PermanentDelegate,
+                |mint| {
+                    spl_token_2022_interface::instruction::initialize_permanent_delegate(
+                        &spl_token_2022_interface::ID,
+                        mint,
+                        &Pubkey::new_unique(),
+                    )
+                    .unwrap()
+                },
+                "invalid_exact_svm_payload_transaction_mint_permanent_delegate",
+            ),
+            (
+                ExtensionType::TransferHook,
+                |mint| {
+                    transfer_hook::instruction::initialize(
+                        &spl_token_2022_interface::ID,
+                        mint,
+                        None,
+                        Some(Pubkey::new_unique()),
+                    )
+                    .unwrap()
+                },
+                "invalid_exact_svm_payload_transaction_mint_transfer_hook",
+            ),
+            (
+                ExtensionType::NonTransferable,
+                |mint| {
+                    spl_token_2022_interface::instruction::initialize_non_transferable_mint(
+                        &spl_token_2022_interface::ID,
+                        mint,
+                    )
+                    .unwrap()
+                },
+                "invalid_exact_svm_payload_transaction_mint_non_transferable",
+            ),
+            // Not on the allowlist
+            (
+                ExtensionType::ConfidentialTransferMint,
+                |mint| {
+                    confidential_transfer::instruction::initialize_mint(
+                        &spl_token_2022_interface::ID,
+                        mint,
+                        None,
+                        true,
+                        None,
+                    )
+                    .unwrap()
+                },
+                "invalid_exact_svm_payload_transaction_mint_extension",
+            ),
+        ];
+        for (extension, initialize, reason) in cases {
+            let chain = OfflineSolanaChain::new(Network::SolanaDevnet).unwrap();
+            let mint =
+                chain.create_token_2022_mint(DECIMALS, &[extension], |mint| vec![initialize(mint)]);
+            let mint_data = chain.account(&mint).unwrap().data;
+            let error =
+                SolanaProvider::verify_mint_extensions(&mint_data, token_program).unwrap_err();
+            assert_eq!(decoding_reason(error), reason, "{extension:?}");
+        }
+
+        // The same checks run on verification
+        let setup = setup_token_2022(&[ExtensionType::PermanentDelegate], |mint| {
+            vec![cases[0].1(mint)]
+        });
+        let request = existing_ata_payment(&setup);
+        let error = setup.provider.verify(&request).await.unwrap_err();
+        assert_eq!(decoding_reason(error), cases[0].2);
+    }
+}
@@ -0,0 +1,251 @@
+//! Command line interface of the facilitator binary.
+//!
+//! Without a subcommand the binary starts the HTTP server, same as `x402-rs serve`.
+//! The other subcommands run a single operation against the providers configured from environment
+//! and print the result, so a failing payment can be reproduced without the HTTP server:
+//! - `verify <request.json>` and `settle <request.json>` – run a `/verify` or `/settle` request body,
+//! - `supported` – print what `/supported` would return,
+//! - `signers` – print the address and native balance of every signer, per network,
+//! - `check-config` – build every configured provider and report configuration errors,
+//! - `inspect <header>` – decode an `X-Payment` or `X-Payment-Response` value, see [`crate::inspect`].
+//!
+//! `verify` and `settle` only configure the provider of the network named in the request.
+//! Responses are printed to stdout as JSON, logs go to stderr.
+
+use clap::{Parser, Subcommand};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::chain::{FromEnvByNetworkBuild, NetworkProvider, NetworkProviderOps};
+use crate::facilitator::Facilitator;
+use crate::facilitator_local::FacilitatorLocal;
+use crate::from_env;
+use crate::network::{Network, NetworkFamily};
+use crate::provider_cache::ProviderCache;
+use crate::types::{PaymentRequirements, SettleRequest, VerifyRequest, VerifyResponse};
+
+#[derive(Debug, Parser)]
+#[command(name = "x402-rs", version, about = "x402 payments facilitator")]
+pub struct Cli {
+    /// Load environment variables from this file instead of `.env`.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub env_file: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the HTTP server (default).
+    Serve,
+    /// Verify the payment in a `/verify` request body, `-` for stdin.
+    Verify { request: PathBuf },
+    /// Settle the payment in a `/settle` request body on-chain, `-` for stdin.
+    Settle { request: PathBuf },
+    /// Print the supported payment kinds.
+    Supported,
+    /// Print the address and native balance of every signer, per network.
+    Signers,
+    /// Build every configured provider and report configuration errors.
+    CheckConfig,
+    /// Decode an `X-Payment` or `X-Payment-Response` header value, `-` for stdin.
+    Inspect {
+        header: String,
+        /// `/verify` payment requirements giving the asset and its EIP-712 domain, `-` for stdin.
+        #[arg(long, value_name = "PATH")]
+        requirements: Option<PathBuf>,
+    },
+}
+
+impl Cli {
+    /// Loads the environment file: `--env-file` if given, `.env` if present otherwise.
+    pub fn load_env(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match &self.env_file {
+            Some(path) => {
+                dotenvy::from_path(path)
+                    .map_err(|e| format!("Can not load {}: {e}", path.display()))?;
+            }
+            None => {
+                dotenvy::dotenv().ok();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs a one-shot `command`, anything but [`Command::Serve`].
+///
+/// Exits with failure if the payment is invalid, the settlement fails, or an error occurs.
+pub async fn run(command: Command) -> ExitCode {
+    // Logs go to stderr, keeping stdout for the output
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| "error".into()))
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+    let result = match command {
+        Command::Serve => Err("serve is not a one-shot command".into()),
+        Command::Verify { request } => verify(&request).await,
+        Command::Settle { request } => settle(&request).await,
+        Command::Supported => supported().await,
+        Command::Signers => signers().await,
+        Command::CheckConfig => check_config().await,
+        Command::Inspect {
+            header,
+            requirements,
+        } => inspect(&header, requirements.as_deref()),
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Reads a JSON request body from `path`, or from stdin if `path` is `-`.
+fn read_request<T: serde::de::DeserializeOwned>(
+    path: &Path,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let json = if path == Path::new("-") {
+        let mut json = String::new();
+        std::io::stdin().read_to_string(&mut json)?;
+        json
+    } else {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("Can not read {}: {e}", path.display()))?
+    };
+    let request = serde_json::from_str(&json).map_err(|e| format!("Invalid request: {e}"))?;
+    Ok(request)
+}
+
+/// Provider of `network`, configured from environment.
+async fn provider(network: Network) -> Result<NetworkProvider, Box<dyn std::error::Error>> {
+    NetworkProvider::from_env(network).await?.ok_or_else(|| {
+        format!(
+            "{network} is not configured, set {}",
+            from_env::rpc_env_name_from_network(network)
+        )
+        .into()
+    })
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+async fn verify(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let request: VerifyRequest = read_request(path)?;
+    let provider = provider(request.network()).await?;
+    let response = provider.verify(&request).await?;
+    print_json(&response)?;
+    Ok(matches!(response, VerifyResponse::Valid { .. }))
+}
+
+async fn settle(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let request: SettleRequest = read_request(path)?;
+    let provider = provider(request.network()).await?;
+    let response = provider.settle(&request).await?;
+    print_json(&response)?;
+    Ok(response.success)
+}
+
+async fn supported() -> Result<bool, Box<dyn std::error::Error>> {
+    let facilitator = FacilitatorLocal::new(ProviderCache::from_env().await?);
+    let supported = facilitator.supported().await?;
+    print_json(&supported)?;
+    Ok(true)
+}
+
+async fn signers() -> Result<bool, Box<dyn std::error::Error>> {
+    let provider_cache = ProviderCache::from_env().await?;
+    let mut providers = provider_cache.into_iter().collect::<Vec<_>>();
+    providers.sort_by_key(|(network, _)| network.to_string());
+    let mut ok = true;
+    for (network, provider) in providers {
+        let unit = match NetworkFamily::from(*network) {
+            NetworkFamily::Evm => "wei",
+            NetworkFamily::Solana => "lamports",
+        };
+        match provider.signer_balances().await {
+            Ok(balances) => {
+                for balance in balances {
+                    println!("{network}\t{}\t{} {unit}", balance.address, balance.balance);
+                }
+            }
+            Err(e) => {
+                ok = false;
+                println!("{network}\t{}\terror: {e}", provider.signer_address());
+            }
+        }
+    }
+    Ok(ok)
+}
+
+async fn check_config() -> Result<bool, Box<dyn std::error::Error>> {
+    let mut configured = 0;
+    let mut ok = true;
+    for network in Network::variants() {
+        match NetworkProvider::from_env(*network).await {
+            Ok(Some(provider)) => {
+                configured += 1;
+                println!("ok\t{network}\tsigner {}", provider.signer_address());
+            }
+            Ok(None) => {
+                let env_var = from_env::rpc_env_name_from_network(*network);
+                println!("skip\t{network}\t{env_var} not set");
+            }
+            Err(e) => {
+                ok = false;
+                println!("error\t{network}\t{e}");
+            }
+        }
+    }
+    if configured == 0 {
+        println!("error\tno network configured");
+        ok = false;
+    }
+    Ok(ok)
+}
+
+fn inspect(header: &str, requirements: Option<&Path>) -> Result<bool, Box<dyn std::error::Error>> {
+    let header = if header == "-" {
+        let mut header = String::new();
+        std::io::stdin().read_to_string(&mut header)?;
+        header
+    } else {
+        header.to_string()
+    };
+    let requirements: Option<PaymentRequirements> = requirements.map(read_request).transpose()?;
+    let inspection = crate::inspect::inspect(&header, requirements.as_ref())?;
+    print_json(&inspection)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_is_well_formed() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn no_subcommand_means_serve() {
+        let cli = Cli::parse_from(["x402-rs"]);
+        assert!(cli.command.is_none());
+        let cli = Cli::parse_from(["x402-rs", "verify", "-", "--env-file", "prod.env"]);
+        assert!(
+            matches!(cli.command, Some(Command::Verify { request }) if request == Path::new("-"))
+        );
+        assert_eq!(cli.env_file, Some(PathBuf::from("prod.env")));
+    }
+}
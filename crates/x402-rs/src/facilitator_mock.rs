@@ -0,0 +1,492 @@
+//! In-memory [`Facilitator`] test double, available with the `mock` feature.
+//!
+//! [`MockFacilitator`] never touches a network. Each call to [`Facilitator::verify`] or
+//! [`Facilitator::settle`] takes the next scripted [`MockOutcome`], or the default outcome
+//! once the script runs out, and every call is recorded for later assertions.
+//!
+//! It is cheap to clone: clones share the script and the recorded calls, so a test can keep
+//! one handle while a middleware owns another.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use x402_rs::facilitator_mock::{MockFacilitator, MockOutcome};
+//! use x402_rs::network::Network;
+//! use x402_rs::types::FacilitatorErrorReason;
+//!
+//! let facilitator = MockFacilitator::new()
+//!     .with_supported_networks([Network::BaseSepolia])
+//!     .then_verify(MockOutcome::Reject(FacilitatorErrorReason::InsufficientFunds));
+//! // First verification is rejected, the ones after it are accepted.
+//! facilitator.assert_verify_count(0);
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use solana_transaction::versioned::VersionedTransaction;
+
+use crate::facilitator::Facilitator;
+use crate::network::{Network, NetworkFamily};
+use crate::types::{
+    Base64Bytes, ExactPaymentPayload, FacilitatorErrorReason, MixedAddress, Scheme, SettleRequest,
+    SettleResponse, SupportedPaymentKind, SupportedPaymentKindExtra, SupportedPaymentKindsResponse,
+    TransactionHash, VerifyRequest, VerifyResponse, X402Version,
+};
+
+/// Scripted result of a single `verify` or `settle` call.
+#[derive(Debug, Clone)]
+pub enum MockOutcome {
+    /// Verification is valid; settlement succeeds with a made-up transaction hash.
+    Accept,
+    /// Verification is invalid, or settlement reports `success: false`, with the given reason.
+    Reject(FacilitatorErrorReason),
+    /// The call fails with a [`MockFacilitatorError`], as a facilitator that is down would.
+    Fail(String),
+}
+
+/// A call received by a [`MockFacilitator`].
+#[derive(Debug, Clone)]
+pub enum MockCall {
+    Verify(VerifyRequest),
+    Settle(SettleRequest),
+    Supported,
+}
+
+/// Error returned for a [`MockOutcome::Fail`] outcome.
+#[derive(Debug, thiserror::Error)]
+#[error("Mock facilitator error: {0}")]
+pub struct MockFacilitatorError(pub String);
+
+#[derive(Debug)]
+struct MockState {
+    verify_script: VecDeque<MockOutcome>,
+    settle_script: VecDeque<MockOutcome>,
+    default_verify: MockOutcome,
+    default_settle: MockOutcome,
+    supported: Vec<SupportedPaymentKind>,
+    payer: Option<MixedAddress>,
+    calls: Vec<MockCall>,
+}
+
+/// A scriptable, recording [`Facilitator`] for tests of payment-gated services.
+///
+/// By default it supports the `exact` scheme on every [`Network`], accepts every payment,
+/// and reports the payer found in the payload.
+#[derive(Debug, Clone)]
+pub struct MockFacilitator {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl Default for MockFacilitator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockFacilitator {
+    /// Creates a mock that supports every network and accepts every payment.
+    pub fn new() -> Self {
+        let state = MockState {
+            verify_script: VecDeque::new(),
+            settle_script: VecDeque::new(),
+            default_verify: MockOutcome::Accept,
+            default_settle: MockOutcome::Accept,
+            supported: Vec::new(),
+            payer: None,
+            calls: Vec::new(),
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+        .with_supported_networks(Network::variants().iter().copied())
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        // A panicking test must not hide the calls recorded before it
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replaces the supported kinds with the `exact` scheme on each of `networks`.
+    pub fn with_supported_networks(self, networks: impl IntoIterator<Item = Network>) -> Self {
+        let kinds = networks
+            .into_iter()
+            .map(|network| SupportedPaymentKind {
+                x402_version: X402Version::V1,
+                scheme: Scheme::Exact,
+                network: network.to_string(),
+                extra: None,
+            })
+            .collect();
+        self.state().supported = kinds;
+        self
+    }
+
+    /// Adds a supported kind, e.g. one carrying a Solana `feePayer` in `extra`.
+    ///
+    /// Replaces the kind already listed for the same network and asset transfer method.
+    pub fn with_supported_kind(self, kind: SupportedPaymentKind) -> Self {
+        {
+            let transfer_method = |k: &SupportedPaymentKind| {
+                k.extra
+                    .as_ref()
+                    .and_then(|extra| extra.asset_transfer_method.clone())
+            };
+            let mut state = self.state();
+            state.supported.retain(|k| {
+                k.network != kind.network || transfer_method(k) != transfer_method(&kind)
+            });
+            state.supported.push(kind);
+        }
+        self
+    }
+
+    /// Advertises `fee_payer` in `extra` of the kind for `network`, as a Solana facilitator does.
+    pub fn with_fee_payer(self, network: Network, fee_payer: MixedAddress) -> Self {
+        self.with_supported_kind(SupportedPaymentKind {
+            x402_version: X402Version::V1,
+            scheme: Scheme::Exact,
+            network: network.to_string(),
+            extra: Some(SupportedPaymentKindExtra {
+                fee_payer,
+                max_compute_unit_price: None,
+                max_compute_unit_limit: None,
+                max_lamports_per_transaction: None,
+                fee: None,
+                asset_transfer_method: None,
+                spender: None,
+            }),
+        })
+    }
+
+    /// Advertises EIP-2612 `permit` payments approving `spender` on `network`, listed after
+    /// its ERC-3009 kind, as an EVM facilitator does.
+    pub fn with_permit_spender(self, network: Network, spender: MixedAddress) -> Self {
+        self.with_supported_kind(SupportedPaymentKind {
+            x402_version: X402Version::V1,
+            scheme: Scheme::Exact,
+            network: network.to_string(),
+            extra: Some(SupportedPaymentKindExtra {
+                fee_payer: spender.clone(),
+                max_compute_unit_price: None,
+                max_compute_unit_limit: None,
+                max_lamports_per_transaction: None,
+                fee: None,
+                asset_transfer_method: Some("permit".to_string()),
+                spender: Some(spender),
+            }),
+        })
+    }
+
+    /// Reports `payer` for every payment instead of the one found in the payload.
+    pub fn with_payer(self, payer: MixedAddress) -> Self {
+        self.state().payer = Some(payer);
+        self
+    }
+
+    /// Sets the outcome of `verify` calls once the script is exhausted.
+    pub fn with_default_verify(self, outcome: MockOutcome) -> Self {
+        self.state().default_verify = outcome;
+        self
+    }
+
+    /// Sets the outcome of `settle` calls once the script is exhausted.
+    pub fn with_default_settle(self, outcome: MockOutcome) -> Self {
+        self.state().default_settle = outcome;
+        self
+    }
+
+    /// Queues the outcome of the next unscripted `verify` call.
+    pub fn then_verify(self, outcome: MockOutcome) -> Self {
+        self.state().verify_script.push_back(outcome);
+        self
+    }
+
+    /// Queues the outcome of the next unscripted `settle` call.
+    pub fn then_settle(self, outcome: MockOutcome) -> Self {
+        self.state().settle_script.push_back(outcome);
+        self
+    }
+
+    /// All calls received so far, in order.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state().calls.clone()
+    }
+
+    /// Requests received by `verify`, in order.
+    pub fn verify_requests(&self) -> Vec<VerifyRequest> {
+        self.state()
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                MockCall::Verify(request) => Some(request.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Requests received by `settle`, in order.
+    pub fn settle_requests(&self) -> Vec<SettleRequest> {
+        self.state()
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                MockCall::Settle(request) => Some(request.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Forgets the recorded calls; the script and defaults are kept.
+    pub fn clear_calls(&self) {
+        self.state().calls.clear();
+    }
+
+    /// Panics unless `verify` was called exactly `expected` times.
+    #[track_caller]
+    pub fn assert_verify_count(&self, expected: usize) {
+        let actual = self.verify_requests().len();
+        assert_eq!(
+            actual, expected,
+            "expected {expected} verify call(s), got {actual}"
+        );
+    }
+
+    /// Panics unless `settle` was called exactly `expected` times.
+    #[track_caller]
+    pub fn assert_settle_count(&self, expected: usize) {
+        let actual = self.settle_requests().len();
+        assert_eq!(
+            actual, expected,
+            "expected {expected} settle call(s), got {actual}"
+        );
+    }
+
+    /// Panics unless the last settled payment went to `pay_to` on `network`.
+    #[track_caller]
+    pub fn assert_settled_to(&self, network: Network, pay_to: &MixedAddress) {
+        let last = self
+            .settle_requests()
+            .pop()
+            .expect("expected a settle call, got none");
+        let requirements = &last.payment_requirements;
+        assert_eq!(requirements.network, network, "settled on another network");
+        assert_eq!(&requirements.pay_to, pay_to, "settled to another address");
+    }
+
+    /// Panics if any of the scripted outcomes has not been used.
+    #[track_caller]
+    pub fn assert_script_consumed(&self) {
+        let state = self.state();
+        assert!(
+            state.verify_script.is_empty() && state.settle_script.is_empty(),
+            "unused scripted outcomes: verify {:?}, settle {:?}",
+            state.verify_script,
+            state.settle_script
+        );
+    }
+
+    /// Records `call`, then takes the next verify or settle outcome and the payer to report.
+    fn record(&self, call: MockCall) -> (MockOutcome, MixedAddress) {
+        let mut state = self.state();
+        let (outcome, request) = match &call {
+            MockCall::Verify(request) => (
+                state
+                    .verify_script
+                    .pop_front()
+                    .unwrap_or_else(|| state.default_verify.clone()),
+                request,
+            ),
+            MockCall::Settle(request) => (
+                state
+                    .settle_script
+                    .pop_front()
+                    .unwrap_or_else(|| state.default_settle.clone()),
+                request,
+            ),
+            MockCall::Supported => unreachable!("supported has no outcome"),
+        };
+        let payer = state
+            .payer
+            .clone()
+            .unwrap_or_else(|| payload_payer(request));
+        state.calls.push(call);
+        (outcome, payer)
+    }
+
+    /// Made-up transaction hash for the `index`-th settlement on `network`.
+    fn transaction_hash(network: Network, index: usize) -> TransactionHash {
+        let index = (index as u64).to_be_bytes();
+        match NetworkFamily::from(network) {
+            NetworkFamily::Evm => {
+                let mut hash = [0u8; 32];
+                hash[24..].copy_from_slice(&index);
+                TransactionHash::Evm(hash)
+            }
+            NetworkFamily::Solana => {
+                let mut signature = [0u8; 64];
+                signature[56..].copy_from_slice(&index);
+                TransactionHash::Solana(signature)
+            }
+        }
+    }
+}
+
+/// Payer named by a payment payload: the authorizer or permit owner on EVM, and the first
+/// signer besides the fee payer of a Solana transaction.
+fn payload_payer(request: &VerifyRequest) -> MixedAddress {
+    match &request.payment_payload.payload {
+        ExactPaymentPayload::Evm(payload) => payload.authorization.from.into(),
+        ExactPaymentPayload::EvmPermit(payload) => payload.permit.owner.into(),
+        ExactPaymentPayload::Solana(payload) => Base64Bytes::from(payload.transaction.as_bytes())
+            .decode()
+            .ok()
+            .and_then(|bytes| bincode::deserialize::<VersionedTransaction>(&bytes).ok())
+            .and_then(|transaction| {
+                let keys = transaction.message.static_account_keys();
+                let signers = usize::from(transaction.message.header().num_required_signatures);
+                keys.get(1.min(signers.saturating_sub(1))).copied()
+            })
+            .map(MixedAddress::from)
+            .unwrap_or_else(|| MixedAddress::Offchain("mock-payer".to_string())),
+    }
+}
+
+impl Facilitator for MockFacilitator {
+    type Error = MockFacilitatorError;
+
+    async fn verify(&self, request: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+        let (outcome, payer) = self.record(MockCall::Verify(request.clone()));
+        match outcome {
+            MockOutcome::Accept => Ok(VerifyResponse::valid(payer)),
+            MockOutcome::Reject(reason) => Ok(VerifyResponse::invalid(Some(payer), reason)),
+            MockOutcome::Fail(message) => Err(MockFacilitatorError(message)),
+        }
+    }
+
+    async fn settle(&self, request: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+        let (outcome, payer) = self.record(MockCall::Settle(request.clone()));
+        let network = request.network();
+        match outcome {
+            MockOutcome::Accept => {
+                let index = self.settle_requests().len();
+                Ok(SettleResponse {
+                    success: true,
+                    error_reason: None,
+                    payer,
+                    transaction: Some(Self::transaction_hash(network, index)),
+                    network,
+                    fee: None,
+                })
+            }
+            MockOutcome::Reject(reason) => Ok(SettleResponse {
+                success: false,
+                error_reason: Some(reason),
+                payer,
+                transaction: None,
+                network,
+                fee: None,
+            }),
+            MockOutcome::Fail(message) => Err(MockFacilitatorError(message)),
+        }
+    }
+
+    async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+        let mut state = self.state();
+        state.calls.push(MockCall::Supported);
+        Ok(SupportedPaymentKindsResponse {
+            kinds: state.supported.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verify_request() -> VerifyRequest {
+        serde_json::from_value(serde_json::json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "payload": {
+                    "signature": format!("0x{}", "11".repeat(65)),
+                    "authorization": {
+                        "from": "0x0000000000000000000000000000000000000037",
+                        "to": "0x000000000000000000000000000000000000beef",
+                        "value": "1000",
+                        "validAfter": "0",
+                        "validBefore": "4102444800",
+                        "nonce": format!("0x{}", "22".repeat(32)),
+                    }
+                }
+            },
+            "paymentRequirements": {
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "maxAmountRequired": "1000",
+                "resource": "https://example.com/resource",
+                "description": "",
+                "mimeType": "application/json",
+                "payTo": "0x000000000000000000000000000000000000beef",
+                "maxTimeoutSeconds": 600,
+                "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+                "extra": null,
+            }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn scripted_outcomes_run_before_defaults() {
+        let facilitator = MockFacilitator::new()
+            .then_verify(MockOutcome::Reject(
+                FacilitatorErrorReason::InsufficientFunds,
+            ))
+            .then_settle(MockOutcome::Fail("rpc down".to_string()));
+        let request = verify_request();
+        let payer: MixedAddress = "0x0000000000000000000000000000000000000037"
+            .parse::<crate::types::EvmAddress>()
+            .unwrap()
+            .into();
+
+        match facilitator.verify(&request).await.unwrap() {
+            VerifyResponse::Invalid { reason, payer: p } => {
+                assert!(matches!(reason, FacilitatorErrorReason::InsufficientFunds));
+                assert_eq!(p, Some(payer.clone()));
+            }
+            other => panic!("expected invalid, got {other:?}"),
+        }
+        assert!(matches!(
+            facilitator.verify(&request).await.unwrap(),
+            VerifyResponse::Valid { .. }
+        ));
+        assert!(facilitator.settle(&request).await.is_err());
+        let settled = facilitator.settle(&request).await.unwrap();
+        assert!(settled.success);
+        assert_eq!(settled.payer, payer);
+        assert_eq!(
+            settled.transaction,
+            Some(MockFacilitator::transaction_hash(Network::BaseSepolia, 2))
+        );
+
+        facilitator.assert_verify_count(2);
+        facilitator.assert_settle_count(2);
+        facilitator.assert_script_consumed();
+        facilitator.assert_settled_to(Network::BaseSepolia, &request.payment_requirements.pay_to);
+    }
+
+    #[tokio::test]
+    async fn clones_share_supported_kinds_and_calls() {
+        let facilitator = MockFacilitator::new().with_supported_networks([Network::Base]);
+        let clone = facilitator.clone();
+        let supported = clone.supported().await.unwrap();
+        assert_eq!(supported.kinds.len(), 1);
+        assert_eq!(supported.kinds[0].network, Network::Base.to_string());
+        assert!(matches!(facilitator.calls()[..], [MockCall::Supported]));
+        facilitator.clear_calls();
+        assert!(clone.calls().is_empty());
+    }
+}
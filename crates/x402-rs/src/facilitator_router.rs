@@ -0,0 +1,627 @@
+//! A [`Facilitator`] routing payments to one of several facilitator backends, by network.
+//!
+//! [`FacilitatorRouter`] wraps any mix of facilitators, such as an HTTP `FacilitatorClient` of
+//! `x402-axum` or an in-process [`crate::facilitator_local::FacilitatorLocal`]. It learns the
+//! networks of every backend from its [`Facilitator::supported`], and sends each `verify` and
+//! `settle` call to the first backend, in the order they were added, that supports the network of
+//! the payment. If that backend fails with an error, the call fails over to the next one.
+//! A rejected payment is a response, not an error, so it does not fail over.
+//!
+//! Failing over a settlement never settles a payment twice: the payload is single-use,
+//! by its ERC-3009 or EIP-2612 nonce on EVM and by its signature on Solana.
+//! On Solana the payment transaction names the fee payer of the facilitator advertised in
+//! [`Facilitator::supported`], so a secondary facilitator can only settle it if it shares the fee payer.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use x402_rs::facilitator_router::FacilitatorRouter;
+//!
+//! let facilitator = FacilitatorRouter::new()
+//!     .with_backend("solana", FacilitatorClient::try_from("https://solana.facilitator.example/")?)
+//!     .with_backend("evm", FacilitatorClient::try_from("https://evm.facilitator.example/")?)
+//!     .with_backend("evm-fallback", FacilitatorClient::try_from("https://facilitator.x402.rs/")?);
+//! let x402 = X402Middleware::new(facilitator);
+//! ```
+
+use std::fmt::{self, Display};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::facilitator::Facilitator;
+use crate::network::Network;
+use crate::types::{
+    SettleRequest, SettleResponse, SupportedPaymentKind, SupportedPaymentKindsResponse,
+    VerifyRequest, VerifyResponse,
+};
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Object-safe [`Facilitator`], with errors turned into messages, so that backends of different types can be mixed.
+trait DynFacilitator: Send + Sync {
+    fn verify<'a>(
+        &'a self,
+        request: &'a VerifyRequest,
+    ) -> BoxFuture<'a, Result<VerifyResponse, String>>;
+    fn settle<'a>(
+        &'a self,
+        request: &'a SettleRequest,
+    ) -> BoxFuture<'a, Result<SettleResponse, String>>;
+    fn supported(&self) -> BoxFuture<'_, Result<SupportedPaymentKindsResponse, String>>;
+}
+
+impl<F> DynFacilitator for F
+where
+    F: Facilitator + Send + Sync,
+{
+    fn verify<'a>(
+        &'a self,
+        request: &'a VerifyRequest,
+    ) -> BoxFuture<'a, Result<VerifyResponse, String>> {
+        Box::pin(async move {
+            Facilitator::verify(self, request)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn settle<'a>(
+        &'a self,
+        request: &'a SettleRequest,
+    ) -> BoxFuture<'a, Result<SettleResponse, String>> {
+        Box::pin(async move {
+            Facilitator::settle(self, request)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn supported(&self) -> BoxFuture<'_, Result<SupportedPaymentKindsResponse, String>> {
+        Box::pin(async move {
+            Facilitator::supported(self)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// How long a backend that failed to answer `supported` is left out before asking it again.
+const FAILED_DISCOVERY_RETRY: Duration = Duration::from_secs(10);
+
+struct Backend {
+    name: String,
+    facilitator: Box<dyn DynFacilitator>,
+    discovery: Mutex<Discovery>,
+    /// Notified when a `supported` call returns.
+    discovered: Notify,
+}
+
+/// Payment kinds a backend supports, as of its last `supported` call.
+#[derive(Default)]
+struct Discovery {
+    kinds: Vec<SupportedPaymentKind>,
+    /// `None` until the first `supported` call returns.
+    expires_at: Option<Instant>,
+    /// When the `supported` call in flight, if any, started.
+    refreshing_since: Option<Instant>,
+}
+
+impl Backend {
+    fn discovery(&self) -> MutexGuard<'_, Discovery> {
+        self.discovery.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Asks the backend for its payment kinds, for at most `supported_ttl`.
+    /// The lock is only taken to record the answer.
+    async fn refresh(&self, supported_ttl: Duration) {
+        // A backend that never answers is retried like one that failed
+        let result = tokio::time::timeout(supported_ttl, self.facilitator.supported())
+            .await
+            .unwrap_or_else(|_| Err(format!("No answer within {supported_ttl:?}")));
+        let now = Instant::now();
+        {
+            let mut discovery = self.discovery();
+            discovery.refreshing_since = None;
+            match result {
+                Ok(supported) => {
+                    discovery.kinds = supported.kinds;
+                    discovery.expires_at = Some(now + supported_ttl);
+                }
+                Err(error) => {
+                    // Keeps the kinds it had, if any, and asks again soon
+                    tracing::warn!(backend = %self.name, %error, "Failed to get supported payment kinds");
+                    discovery.expires_at = Some(now + FAILED_DISCOVERY_RETRY.min(supported_ttl));
+                }
+            }
+        }
+        self.discovered.notify_waiters();
+    }
+}
+
+/// Routes payments to facilitator backends by network, failing over to the next backend on errors.
+///
+/// Cheap to clone: clones share the backends and their discovered payment kinds.
+///
+/// Payment kinds are discovered independently for every backend. Once known, expired kinds keep
+/// being used while they are refreshed in the background. Until then, calls wait for the first
+/// discovery of a backend for at most the discovery timeout, so a slow or unreachable backend
+/// never holds up payments sent to the others for long.
+#[derive(Clone)]
+pub struct FacilitatorRouter {
+    backends: Arc<Vec<Backend>>,
+    supported_ttl: Duration,
+    discovery_timeout: Duration,
+}
+
+impl fmt::Debug for FacilitatorRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.backends.iter().map(|b| &b.name).collect::<Vec<_>>();
+        f.debug_struct("FacilitatorRouter")
+            .field("backends", &names)
+            .field("supported_ttl", &self.supported_ttl)
+            .field("discovery_timeout", &self.discovery_timeout)
+            .finish()
+    }
+}
+
+impl Default for FacilitatorRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Error of a single backend, as part of [`FacilitatorRouterError::AllFailed`].
+#[derive(Debug, Clone)]
+pub struct BackendFailure {
+    pub backend: String,
+    pub error: String,
+}
+
+/// Errors returned by [`FacilitatorRouter`].
+#[derive(Debug, thiserror::Error)]
+pub enum FacilitatorRouterError {
+    /// No backend supports the network of the payment.
+    #[error("No facilitator supports {0}")]
+    UnsupportedNetwork(Network),
+    /// Every backend supporting the network failed.
+    #[error("All facilitators failed: {}", DisplayFailures(.0))]
+    AllFailed(Vec<BackendFailure>),
+}
+
+struct DisplayFailures<'a>(&'a [BackendFailure]);
+
+impl Display for DisplayFailures<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, failure) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}: {}", failure.backend, failure.error)?;
+        }
+        Ok(())
+    }
+}
+
+impl FacilitatorRouter {
+    /// Creates a router without backends. Payment kinds of backends are refreshed every 5 minutes,
+    /// and their first discovery is waited for at most 5 seconds.
+    pub fn new() -> Self {
+        Self {
+            backends: Arc::new(Vec::new()),
+            supported_ttl: Duration::from_secs(300),
+            discovery_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Adds a backend, tried after the ones added before it. `name` identifies it in logs and errors.
+    ///
+    /// # Panics
+    ///
+    /// If the router has been cloned already.
+    pub fn with_backend<F>(mut self, name: impl Into<String>, facilitator: F) -> Self
+    where
+        F: Facilitator + Send + Sync + 'static,
+    {
+        Arc::get_mut(&mut self.backends)
+            .expect("FacilitatorRouter backends must be added before the router is cloned")
+            .push(Backend {
+                name: name.into(),
+                facilitator: Box::new(facilitator),
+                discovery: Mutex::new(Discovery::default()),
+                discovered: Notify::new(),
+            });
+        self
+    }
+
+    /// Sets how long the payment kinds a backend supports are cached.
+    pub fn with_supported_ttl(mut self, supported_ttl: Duration) -> Self {
+        self.supported_ttl = supported_ttl;
+        self
+    }
+
+    /// Sets how long calls wait for the first discovery of the payment kinds of a backend.
+    /// Once it elapses, the backend is left out until its discovery completes.
+    pub fn with_discovery_timeout(mut self, discovery_timeout: Duration) -> Self {
+        self.discovery_timeout = discovery_timeout;
+        self
+    }
+
+    /// Payment kinds of every backend, in order.
+    ///
+    /// Expired or missing kinds are refreshed concurrently, one `supported` call per backend at most,
+    /// and no lock is held while it is in flight. Expired kinds are returned while they are refreshed.
+    /// Kinds never discovered yet are waited for, up to the discovery timeout since the call started.
+    async fn discover(&self) -> Vec<Vec<SupportedPaymentKind>> {
+        let now = Instant::now();
+        let mut first_discoveries = Vec::new();
+        for (index, backend) in self.backends.iter().enumerate() {
+            let mut discovery = backend.discovery();
+            if discovery
+                .expires_at
+                .is_some_and(|expires_at| now < expires_at)
+            {
+                continue;
+            }
+            let refreshing_since = *discovery.refreshing_since.get_or_insert_with(|| {
+                let backends = self.backends.clone();
+                let supported_ttl = self.supported_ttl;
+                tokio::spawn(async move { backends[index].refresh(supported_ttl).await });
+                now
+            });
+            if discovery.expires_at.is_none() {
+                let deadline = refreshing_since + self.discovery_timeout;
+                first_discoveries.push((backend.discovered.notified(), deadline));
+            }
+        }
+        for (discovered, deadline) in first_discoveries {
+            let _ = tokio::time::timeout_at(deadline.into(), discovered).await;
+        }
+        self.backends
+            .iter()
+            .map(|backend| backend.discovery().kinds.clone())
+            .collect()
+    }
+
+    /// Backends supporting `network`, in order.
+    async fn route(&self, network: Network) -> Result<Vec<&Backend>, FacilitatorRouterError> {
+        let network_name = network.to_string();
+        let discoveries = self.discover().await;
+        let backends = self
+            .backends
+            .iter()
+            .zip(discoveries)
+            .filter(|(_, kinds)| kinds.iter().any(|k| k.network == network_name))
+            .map(|(backend, _)| backend)
+            .collect::<Vec<_>>();
+        if backends.is_empty() {
+            Err(FacilitatorRouterError::UnsupportedNetwork(network))
+        } else {
+            Ok(backends)
+        }
+    }
+
+    /// Names of the backends supporting `network`, in the order they are tried.
+    pub async fn backends_for(&self, network: Network) -> Vec<String> {
+        match self.route(network).await {
+            Ok(backends) => backends.iter().map(|b| b.name.clone()).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl Facilitator for FacilitatorRouter {
+    type Error = FacilitatorRouterError;
+
+    async fn verify(&self, request: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+        let mut failures = Vec::new();
+        for backend in self.route(request.network()).await? {
+            match backend.facilitator.verify(request).await {
+                Ok(response) => return Ok(response),
+                Err(error) => {
+                    tracing::warn!(backend = %backend.name, %error, "Verification failed, failing over");
+                    failures.push(BackendFailure {
+                        backend: backend.name.clone(),
+                        error,
+                    });
+                }
+            }
+        }
+        Err(FacilitatorRouterError::AllFailed(failures))
+    }
+
+    async fn settle(&self, request: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+        let mut failures = Vec::new();
+        for backend in self.route(request.network()).await? {
+            match backend.facilitator.settle(request).await {
+                Ok(response) => return Ok(response),
+                Err(error) => {
+                    tracing::warn!(backend = %backend.name, %error, "Settlement failed, failing over");
+                    failures.push(BackendFailure {
+                        backend: backend.name.clone(),
+                        error,
+                    });
+                }
+            }
+        }
+        Err(FacilitatorRouterError::AllFailed(failures))
+    }
+
+    /// Payment kinds of all backends. A network supported by several backends is listed
+    /// as advertised by the first one, the one payments are sent to, with all of its kinds
+    /// for that network, such as both the ERC-3009 and the `permit` kind of an EVM facilitator.
+    async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+        let mut kinds: Vec<SupportedPaymentKind> = Vec::new();
+        for backend_kinds in self.discover().await {
+            let listed_by_previous = kinds.len();
+            for kind in backend_kinds {
+                let known = kinds[..listed_by_previous]
+                    .iter()
+                    .any(|k| k.network == kind.network && k.scheme == kind.scheme);
+                if !known {
+                    kinds.push(kind);
+                }
+            }
+        }
+        Ok(SupportedPaymentKindsResponse { kinds })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FacilitatorRouter, FacilitatorRouterError};
+    use crate::facilitator::Facilitator;
+    use crate::facilitator_mock::{MockCall, MockFacilitator, MockOutcome};
+    use crate::network::Network;
+    use crate::types::MixedAddress;
+    use crate::types::{
+        FacilitatorErrorReason, SettleRequest, SettleResponse, SupportedPaymentKindsResponse,
+        VerifyRequest, VerifyResponse,
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    const PAY_TO: &str = "0x000000000000000000000000000000000000bEEF";
+
+    fn verify_request() -> VerifyRequest {
+        serde_json::from_value(json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "payload": {
+                    "signature": format!("0x{}", "11".repeat(65)),
+                    "authorization": {
+                        "from": "0x0000000000000000000000000000000000000037",
+                        "to": PAY_TO,
+                        "value": "1000",
+                        "validAfter": "0",
+                        "validBefore": "4102444800",
+                        "nonce": format!("0x{}", "22".repeat(32)),
+                    }
+                }
+            },
+            "paymentRequirements": {
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "maxAmountRequired": "1000",
+                "resource": "https://example.com/paywall",
+                "description": "",
+                "mimeType": "application/json",
+                "payTo": PAY_TO,
+                "maxTimeoutSeconds": 300,
+                "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+            }
+        }))
+        .unwrap()
+    }
+
+    fn supported_calls(facilitator: &MockFacilitator) -> usize {
+        facilitator
+            .calls()
+            .iter()
+            .filter(|call| matches!(call, MockCall::Supported))
+            .count()
+    }
+
+    /// A backend whose `supported` never returns.
+    #[derive(Clone, Default)]
+    struct Unresponsive {
+        supported_calls: Arc<AtomicUsize>,
+    }
+
+    impl Facilitator for Unresponsive {
+        type Error = String;
+
+        async fn verify(&self, _: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn settle(&self, _: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+            self.supported_calls.fetch_add(1, Ordering::SeqCst);
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_payments_are_routed_by_network() {
+        let solana = MockFacilitator::new().with_supported_networks([Network::Solana]);
+        let evm = MockFacilitator::new().with_supported_networks([Network::BaseSepolia]);
+        let router = FacilitatorRouter::new()
+            .with_backend("solana", solana.clone())
+            .with_backend("evm", evm.clone());
+
+        router.verify(&verify_request()).await.unwrap();
+        router.settle(&verify_request()).await.unwrap();
+        solana.assert_verify_count(0);
+        solana.assert_settle_count(0);
+        evm.assert_verify_count(1);
+        evm.assert_settle_count(1);
+
+        let networks = router
+            .supported()
+            .await
+            .unwrap()
+            .kinds
+            .into_iter()
+            .map(|kind| kind.network)
+            .collect::<Vec<_>>();
+        assert_eq!(networks, ["solana", "base-sepolia"]);
+    }
+
+    #[tokio::test]
+    async fn test_errors_fail_over_to_the_next_backend() {
+        let primary = MockFacilitator::new()
+            .with_supported_networks([Network::BaseSepolia])
+            .then_settle(MockOutcome::Fail("settlement RPC down".into()));
+        let secondary = MockFacilitator::new().with_supported_networks([Network::BaseSepolia]);
+        let router = FacilitatorRouter::new()
+            .with_backend("primary", primary.clone())
+            .with_backend("secondary", secondary.clone());
+
+        router.verify(&verify_request()).await.unwrap();
+        router.settle(&verify_request()).await.unwrap();
+        primary.assert_verify_count(1);
+        primary.assert_settle_count(1);
+        secondary.assert_verify_count(0);
+        secondary.assert_settle_count(1);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_payments_do_not_fail_over() {
+        let primary = MockFacilitator::new().then_verify(MockOutcome::Reject(
+            FacilitatorErrorReason::InsufficientFunds,
+        ));
+        let secondary = MockFacilitator::new();
+        let router = FacilitatorRouter::new()
+            .with_backend("primary", primary.clone())
+            .with_backend("secondary", secondary.clone());
+
+        let response = router.verify(&verify_request()).await.unwrap();
+        assert!(matches!(response, VerifyResponse::Invalid { .. }));
+        primary.assert_verify_count(1);
+        secondary.assert_verify_count(0);
+    }
+
+    #[tokio::test]
+    async fn test_all_failures_are_reported() {
+        let router = FacilitatorRouter::new()
+            .with_backend(
+                "primary",
+                MockFacilitator::new().with_default_verify(MockOutcome::Fail("down".into())),
+            )
+            .with_backend(
+                "secondary",
+                MockFacilitator::new().with_default_verify(MockOutcome::Fail("also down".into())),
+            );
+        let error = router.verify(&verify_request()).await.unwrap_err();
+        let FacilitatorRouterError::AllFailed(failures) = error else {
+            panic!("Expected AllFailed, got {error}");
+        };
+        let backends = failures
+            .iter()
+            .map(|f| f.backend.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(backends, ["primary", "secondary"]);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_networks_are_refused() {
+        let router = FacilitatorRouter::new().with_backend(
+            "solana",
+            MockFacilitator::new().with_supported_networks([Network::Solana]),
+        );
+        let error = router.verify(&verify_request()).await.unwrap_err();
+        assert!(matches!(
+            error,
+            FacilitatorRouterError::UnsupportedNetwork(Network::BaseSepolia)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_supported_kinds_are_cached_and_refreshed_in_background() {
+        let backend = MockFacilitator::new();
+        let router = FacilitatorRouter::new()
+            .with_backend("backend", backend.clone())
+            .with_supported_ttl(Duration::from_millis(50));
+        router.supported().await.unwrap();
+        router.verify(&verify_request()).await.unwrap();
+        assert_eq!(supported_calls(&backend), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        // Expired kinds are still served while they are refreshed
+        router.verify(&verify_request()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(supported_calls(&backend), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unresponsive_backend_does_not_block_payments() {
+        let evm = MockFacilitator::new().with_supported_networks([Network::BaseSepolia]);
+        let router = FacilitatorRouter::new()
+            .with_backend("unresponsive", Unresponsive::default())
+            .with_backend("evm", evm.clone())
+            .with_discovery_timeout(Duration::from_millis(50));
+
+        let started = Instant::now();
+        router.verify(&verify_request()).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        // The first discovery timed out already, so it is not waited for again
+        let started = Instant::now();
+        router.verify(&verify_request()).await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(50));
+        evm.assert_verify_count(2);
+        assert_eq!(router.backends_for(Network::BaseSepolia).await, ["evm"]);
+    }
+
+    #[tokio::test]
+    async fn test_hanging_discovery_is_given_up_and_retried() {
+        let unresponsive = Unresponsive::default();
+        let router = FacilitatorRouter::new()
+            .with_backend("unresponsive", unresponsive.clone())
+            .with_supported_ttl(Duration::from_millis(20))
+            .with_discovery_timeout(Duration::from_millis(10));
+
+        assert!(router.backends_for(Network::BaseSepolia).await.is_empty());
+        // Timed out after the TTL, and asked again once the retry delay passed
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(router.backends_for(Network::BaseSepolia).await.is_empty());
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(unresponsive.supported_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_all_kinds_of_a_network_are_listed_from_its_first_backend() {
+        let spender = MixedAddress::from(alloy_primitives::Address::repeat_byte(0x42));
+        let primary = MockFacilitator::new()
+            .with_supported_networks([Network::BaseSepolia])
+            .with_permit_spender(Network::BaseSepolia, spender.clone());
+        let secondary = MockFacilitator::new()
+            .with_supported_networks([Network::BaseSepolia])
+            .with_permit_spender(Network::BaseSepolia, spender);
+        for router in [
+            FacilitatorRouter::new().with_backend("primary", primary.clone()),
+            FacilitatorRouter::new()
+                .with_backend("primary", primary.clone())
+                .with_backend("secondary", secondary.clone()),
+        ] {
+            let methods = router
+                .supported()
+                .await
+                .unwrap()
+                .kinds
+                .into_iter()
+                .map(|kind| kind.extra.and_then(|extra| extra.asset_transfer_method))
+                .collect::<Vec<_>>();
+            assert_eq!(methods, [None, Some("permit".to_string())]);
+        }
+    }
+}
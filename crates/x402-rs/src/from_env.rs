@@ -1,5 +1,7 @@
 use crate::network::Network;
+use crate::types::{FacilitatorFee, MixedAddress, TokenAmount};
 use alloy_network::EthereumWallet;
+use alloy_primitives::U256;
 use alloy_signer_local::PrivateKeySigner;
 use serde::Deserialize;
 use serde::Serialize;
@@ -39,6 +41,220 @@ pub fn rpc_env_name_from_network(network: Network) -> &'static str {
     }
 }
 
+pub const ENV_SOLANA_MAX_COMPUTE_UNIT_PRICE: &str = "SOLANA_MAX_COMPUTE_UNIT_PRICE";
+pub const ENV_SOLANA_MAX_COMPUTE_UNIT_LIMIT: &str = "SOLANA_MAX_COMPUTE_UNIT_LIMIT";
+pub const ENV_SOLANA_MAX_LAMPORTS_PER_TRANSACTION: &str = "SOLANA_MAX_LAMPORTS_PER_TRANSACTION";
+pub const ENV_SOLANA_DEVNET_MAX_COMPUTE_UNIT_PRICE: &str = "SOLANA_DEVNET_MAX_COMPUTE_UNIT_PRICE";
+pub const ENV_SOLANA_DEVNET_MAX_COMPUTE_UNIT_LIMIT: &str = "SOLANA_DEVNET_MAX_COMPUTE_UNIT_LIMIT";
+pub const ENV_SOLANA_DEVNET_MAX_LAMPORTS_PER_TRANSACTION: &str =
+    "SOLANA_DEVNET_MAX_LAMPORTS_PER_TRANSACTION";
+pub const ENV_SOLANA_ALLOWED_INSTRUCTIONS: &str = "SOLANA_ALLOWED_INSTRUCTIONS";
+pub const ENV_SOLANA_DEVNET_ALLOWED_INSTRUCTIONS: &str = "SOLANA_DEVNET_ALLOWED_INSTRUCTIONS";
+pub const ENV_VERIFICATION_CACHE_TTL_SECS: &str = "VERIFICATION_CACHE_TTL_SECS";
+pub const ENV_VERIFICATION_CACHE_MAX_ENTRIES: &str = "VERIFICATION_CACHE_MAX_ENTRIES";
+
+/// Names of the environment variables capping the compute budget of Solana payment transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolanaComputeBudgetEnvNames {
+    /// Max compute unit price, in micro-lamports.
+    pub max_compute_unit_price: &'static str,
+    /// Max compute unit limit.
+    pub max_compute_unit_limit: &'static str,
+    /// Max total fee (base + priority), in lamports.
+    pub max_lamports_per_transaction: &'static str,
+}
+
+/// Returns the compute budget env variable names for a Solana network, or `None` for other networks.
+pub fn solana_compute_budget_env_names_from_network(
+    network: Network,
+) -> Option<SolanaComputeBudgetEnvNames> {
+    match network {
+        Network::Solana => Some(SolanaComputeBudgetEnvNames {
+            max_compute_unit_price: ENV_SOLANA_MAX_COMPUTE_UNIT_PRICE,
+            max_compute_unit_limit: ENV_SOLANA_MAX_COMPUTE_UNIT_LIMIT,
+            max_lamports_per_transaction: ENV_SOLANA_MAX_LAMPORTS_PER_TRANSACTION,
+        }),
+        Network::SolanaDevnet => Some(SolanaComputeBudgetEnvNames {
+            max_compute_unit_price: ENV_SOLANA_DEVNET_MAX_COMPUTE_UNIT_PRICE,
+            max_compute_unit_limit: ENV_SOLANA_DEVNET_MAX_COMPUTE_UNIT_LIMIT,
+            max_lamports_per_transaction: ENV_SOLANA_DEVNET_MAX_LAMPORTS_PER_TRANSACTION,
+        }),
+        Network::BaseSepolia
+        | Network::Base
+        | Network::XdcMainnet
+        | Network::AvalancheFuji
+        | Network::Avalanche
+        | Network::PolygonAmoy
+        | Network::Polygon
+        | Network::Sei
+        | Network::SeiTestnet => None,
+    }
+}
+
+/// Returns the env variable name listing extra instructions allowed in Solana payment transactions,
+/// or `None` for other networks.
+pub fn solana_allowed_instructions_env_name_from_network(network: Network) -> Option<&'static str> {
+    match network {
+        Network::Solana => Some(ENV_SOLANA_ALLOWED_INSTRUCTIONS),
+        Network::SolanaDevnet => Some(ENV_SOLANA_DEVNET_ALLOWED_INSTRUCTIONS),
+        Network::BaseSepolia
+        | Network::Base
+        | Network::XdcMainnet
+        | Network::AvalancheFuji
+        | Network::Avalanche
+        | Network::PolygonAmoy
+        | Network::Polygon
+        | Network::Sei
+        | Network::SeiTestnet => None,
+    }
+}
+
+/// Names of the environment variables configuring the facilitator fee on a network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FacilitatorFeeEnvNames {
+    /// Flat fee per payment, in token base units.
+    pub flat: &'static str,
+    /// Fee proportional to the price, in basis points.
+    pub basis_points: &'static str,
+    /// Fee recipient; the facilitator signer when unset.
+    pub recipient: &'static str,
+}
+
+/// Returns the facilitator fee env variable names for a network.
+pub fn facilitator_fee_env_names_from_network(network: Network) -> FacilitatorFeeEnvNames {
+    let (flat, basis_points, recipient) = match network {
+        Network::BaseSepolia => (
+            "FACILITATOR_FEE_FLAT_BASE_SEPOLIA",
+            "FACILITATOR_FEE_BPS_BASE_SEPOLIA",
+            "FACILITATOR_FEE_RECIPIENT_BASE_SEPOLIA",
+        ),
+        Network::Base => (
+            "FACILITATOR_FEE_FLAT_BASE",
+            "FACILITATOR_FEE_BPS_BASE",
+            "FACILITATOR_FEE_RECIPIENT_BASE",
+        ),
+        Network::XdcMainnet => (
+            "FACILITATOR_FEE_FLAT_XDC",
+            "FACILITATOR_FEE_BPS_XDC",
+            "FACILITATOR_FEE_RECIPIENT_XDC",
+        ),
+        Network::AvalancheFuji => (
+            "FACILITATOR_FEE_FLAT_AVALANCHE_FUJI",
+            "FACILITATOR_FEE_BPS_AVALANCHE_FUJI",
+            "FACILITATOR_FEE_RECIPIENT_AVALANCHE_FUJI",
+        ),
+        Network::Avalanche => (
+            "FACILITATOR_FEE_FLAT_AVALANCHE",
+            "FACILITATOR_FEE_BPS_AVALANCHE",
+            "FACILITATOR_FEE_RECIPIENT_AVALANCHE",
+        ),
+        Network::Solana => (
+            "FACILITATOR_FEE_FLAT_SOLANA",
+            "FACILITATOR_FEE_BPS_SOLANA",
+            "FACILITATOR_FEE_RECIPIENT_SOLANA",
+        ),
+        Network::SolanaDevnet => (
+            "FACILITATOR_FEE_FLAT_SOLANA_DEVNET",
+            "FACILITATOR_FEE_BPS_SOLANA_DEVNET",
+            "FACILITATOR_FEE_RECIPIENT_SOLANA_DEVNET",
+        ),
+        Network::PolygonAmoy => (
+            "FACILITATOR_FEE_FLAT_POLYGON_AMOY",
+            "FACILITATOR_FEE_BPS_POLYGON_AMOY",
+            "FACILITATOR_FEE_RECIPIENT_POLYGON_AMOY",
+        ),
+        Network::Polygon => (
+            "FACILITATOR_FEE_FLAT_POLYGON",
+            "FACILITATOR_FEE_BPS_POLYGON",
+            "FACILITATOR_FEE_RECIPIENT_POLYGON",
+        ),
+        Network::Sei => (
+            "FACILITATOR_FEE_FLAT_SEI",
+            "FACILITATOR_FEE_BPS_SEI",
+            "FACILITATOR_FEE_RECIPIENT_SEI",
+        ),
+        Network::SeiTestnet => (
+            "FACILITATOR_FEE_FLAT_SEI_TESTNET",
+            "FACILITATOR_FEE_BPS_SEI_TESTNET",
+            "FACILITATOR_FEE_RECIPIENT_SEI_TESTNET",
+        ),
+    };
+    FacilitatorFeeEnvNames {
+        flat,
+        basis_points,
+        recipient,
+    }
+}
+
+/// Reads the facilitator fee for `network` from environment.
+///
+/// Returns `None` unless a flat fee or a percentage is set. The fee goes to `default_recipient`
+/// unless a recipient is set; `A` parses it in the address format of the network.
+pub fn facilitator_fee_from_env<A>(
+    network: Network,
+    default_recipient: A,
+) -> Result<Option<FacilitatorFee>, Box<dyn std::error::Error>>
+where
+    A: FromStr + Into<MixedAddress>,
+    A::Err: std::fmt::Display,
+{
+    let names = facilitator_fee_env_names_from_network(network);
+    let flat = parse_optional_env::<U256>(names.flat)?.map(TokenAmount);
+    let basis_points = parse_optional_env::<u16>(names.basis_points)?;
+    if let Some(basis_points) = basis_points
+        && basis_points > 10_000
+    {
+        return Err(format!("env {} must be at most 10000", names.basis_points).into());
+    }
+    if flat.is_none() && basis_points.is_none() {
+        return Ok(None);
+    }
+    let recipient = parse_optional_env::<A>(names.recipient)?.unwrap_or(default_recipient);
+    Ok(Some(FacilitatorFee {
+        recipient: recipient.into(),
+        flat,
+        basis_points,
+    }))
+}
+
+/// Reads and parses an optional comma-separated environment variable. Unset or empty means `None`.
+pub fn parse_optional_env_list<T>(name: &str) -> Result<Option<Vec<T>>, Box<dyn std::error::Error>>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                entry
+                    .parse::<T>()
+                    .map_err(|e| format!("env {name} is invalid: {entry}: {e}").into())
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Reads and parses an optional environment variable. Unset or empty means `None`.
+pub fn parse_optional_env<T>(name: &str) -> Result<Option<T>, Box<dyn std::error::Error>>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse::<T>()
+            .map(Some)
+            .map_err(|e| format!("env {name} is invalid: {e}").into()),
+        _ => Ok(None),
+    }
+}
+
 /// Supported methods for constructing an Ethereum wallet from environment variables.
 #[derive(Debug, Hash, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SignerType {
@@ -177,4 +393,68 @@ mod tests {
         assert!(signers.contains(&expected_primary));
         assert!(signers.contains(&expected_secondary));
     }
+
+    #[test]
+    fn parse_optional_env_reads_solana_compute_budget_caps() {
+        let _guard = ENV_LOCK.lock().expect("env lock poisoned");
+        let names = solana_compute_budget_env_names_from_network(Network::SolanaDevnet)
+            .expect("devnet has compute budget env names");
+        let price_override = EnvOverride::new(names.max_compute_unit_price);
+        let limit_override = EnvOverride::new(names.max_compute_unit_limit);
+
+        price_override.set(" 250000 ");
+        limit_override.set("");
+        assert_eq!(
+            parse_optional_env::<u64>(names.max_compute_unit_price).expect("valid price"),
+            Some(250_000)
+        );
+        assert_eq!(
+            parse_optional_env::<u32>(names.max_compute_unit_limit).expect("empty limit"),
+            None
+        );
+
+        limit_override.set("lots");
+        assert!(parse_optional_env::<u32>(names.max_compute_unit_limit).is_err());
+        assert!(solana_compute_budget_env_names_from_network(Network::Base).is_none());
+    }
+
+    #[test]
+    fn facilitator_fee_from_env_reads_flat_and_percentage() {
+        let _guard = ENV_LOCK.lock().expect("env lock poisoned");
+        let names = facilitator_fee_env_names_from_network(Network::BaseSepolia);
+        let flat_override = EnvOverride::new(names.flat);
+        let bps_override = EnvOverride::new(names.basis_points);
+        let recipient_override = EnvOverride::new(names.recipient);
+        let signer = PrivateKeySigner::random().address();
+
+        flat_override.set("");
+        bps_override.set("");
+        recipient_override.set("");
+        assert_eq!(
+            facilitator_fee_from_env(Network::BaseSepolia, signer).expect("no fee"),
+            None
+        );
+
+        flat_override.set("1000");
+        bps_override.set("25");
+        let fee = facilitator_fee_from_env(Network::BaseSepolia, signer)
+            .expect("valid fee")
+            .expect("fee configured");
+        assert_eq!(fee.recipient, MixedAddress::from(signer));
+        // 1000 flat + 0.25% of 1_000_001, rounded up
+        assert_eq!(
+            fee.amount(TokenAmount::from(1_000_001u64)),
+            TokenAmount::from(3_501u64)
+        );
+
+        let treasury = PrivateKeySigner::random().address();
+        recipient_override.set(&treasury.to_string());
+        let fee = facilitator_fee_from_env(Network::BaseSepolia, signer)
+            .expect("valid fee")
+            .expect("fee configured");
+        assert_eq!(fee.recipient, MixedAddress::from(treasury));
+
+        bps_override.set("10001");
+        assert!(facilitator_fee_from_env(Network::BaseSepolia, signer).is_err());
+    }
 }
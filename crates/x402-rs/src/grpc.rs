@@ -0,0 +1,972 @@
+//! gRPC interface of the facilitator, alongside the HTTP routes in [`crate::handlers`].
+//!
+//! The `x402.facilitator.v1.Facilitator` service (see `proto/x402/facilitator/v1/facilitator.proto`)
+//! exposes `Verify`, `Settle` and `Supported`, with messages mirroring [`VerifyRequest`],
+//! [`VerifyResponse`], [`SettleResponse`] and [`SupportedPaymentKindsResponse`].
+//!
+//! - [`server`] wraps any [`Facilitator`] into a tonic service.
+//! - [`FacilitatorGrpcClient`] is a [`Facilitator`] calling a remote facilitator over gRPC.
+//!
+//! The facilitator binary serves gRPC on `GRPC_PORT` if it is set, over TLS if `GRPC_TLS_CERT` and
+//! `GRPC_TLS_KEY` are set, requiring client certificates signed by `GRPC_TLS_CLIENT_CA` if it is set.
+//!
+//! Requires the `grpc` feature.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+use tonic::transport::{Certificate, Channel, Identity, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+use tracing::instrument;
+
+use crate::chain::FacilitatorLocalError;
+use crate::facilitator::Facilitator;
+use crate::types::{
+    ExactEvmFeePayload, ExactEvmPayload, ExactEvmPayloadAuthorization, ExactEvmPayloadPermit,
+    ExactEvmPermitPayload, ExactPaymentPayload, ExactSolanaPayload, FacilitatorErrorReason,
+    FacilitatorFee, MixedAddress, PaymentPayload, PaymentRequirements, SettleRequest,
+    SettleResponse, SettledFee, SupportedPaymentKind, SupportedPaymentKindExtra,
+    SupportedPaymentKindsResponse, VerifyRequest, VerifyResponse, X402Version,
+};
+
+/// Messages and tonic stubs generated from `facilitator.proto`.
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("x402.facilitator.v1");
+}
+
+pub const ENV_GRPC_PORT: &str = "GRPC_PORT";
+pub const ENV_GRPC_TLS_CERT: &str = "GRPC_TLS_CERT";
+pub const ENV_GRPC_TLS_KEY: &str = "GRPC_TLS_KEY";
+pub const ENV_GRPC_TLS_CLIENT_CA: &str = "GRPC_TLS_CLIENT_CA";
+
+/// A protobuf message that can not be converted into its x402 counterpart.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid {field}: {reason}")]
+pub struct ProtoError {
+    pub field: &'static str,
+    pub reason: String,
+}
+
+impl ProtoError {
+    fn new(field: &'static str, reason: impl Display) -> Self {
+        Self {
+            field,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn missing(field: &'static str) -> Self {
+        Self::new(field, "missing")
+    }
+}
+
+impl From<ProtoError> for Status {
+    fn from(error: ProtoError) -> Self {
+        Status::invalid_argument(error.to_string())
+    }
+}
+
+/// Converts a facilitator error into a gRPC reply, the counterpart of `IntoResponse` for the HTTP handlers.
+pub trait IntoGrpcResponse {
+    /// An invalid [`VerifyResponse`] if the payment is rejected,
+    /// a [`Status`] if the request can not be processed.
+    #[allow(clippy::result_large_err)] // `Status` is what tonic services return.
+    fn into_verify_response(self) -> Result<VerifyResponse, Status>;
+
+    /// A [`Status`] for calls that can not return a [`VerifyResponse`]: `FAILED_PRECONDITION`
+    /// with the reason if the payment is rejected.
+    fn into_status(self) -> Status
+    where
+        Self: Sized,
+    {
+        match self.into_verify_response() {
+            Ok(VerifyResponse::Invalid { reason, .. }) => {
+                Status::failed_precondition(reason.to_string())
+            }
+            Ok(VerifyResponse::Valid { .. }) => Status::internal("Unexpected valid response"),
+            Err(status) => status,
+        }
+    }
+}
+
+impl IntoGrpcResponse for FacilitatorLocalError {
+    fn into_verify_response(self) -> Result<VerifyResponse, Status> {
+        let (payer, reason) = match self {
+            FacilitatorLocalError::SchemeMismatch(payer, ..) => {
+                (payer, FacilitatorErrorReason::InvalidScheme)
+            }
+            FacilitatorLocalError::ReceiverMismatch(payer, ..)
+            | FacilitatorLocalError::InvalidTiming(payer, ..)
+            | FacilitatorLocalError::InsufficientValue(payer) => {
+                (Some(payer), FacilitatorErrorReason::InvalidScheme)
+            }
+            FacilitatorLocalError::InvalidSignature(payer, ..) => {
+                (Some(payer), FacilitatorErrorReason::InvalidSignature)
+            }
+            FacilitatorLocalError::NetworkMismatch(payer, ..)
+            | FacilitatorLocalError::UnsupportedNetwork(payer) => {
+                (payer, FacilitatorErrorReason::InvalidNetwork)
+            }
+            FacilitatorLocalError::DecodingError(reason) => {
+                (None, FacilitatorErrorReason::FreeForm(reason))
+            }
+            FacilitatorLocalError::SpenderMismatch(payer, ..) => (
+                Some(payer),
+                FacilitatorErrorReason::FreeForm(
+                    "invalid_exact_evm_payload_permit_spender".to_string(),
+                ),
+            ),
+            FacilitatorLocalError::InsufficientFee(payer, ..) => {
+                (Some(payer), FacilitatorErrorReason::InsufficientFee)
+            }
+            FacilitatorLocalError::InsufficientFunds(payer) => {
+                (Some(payer), FacilitatorErrorReason::InsufficientFunds)
+            }
+            FacilitatorLocalError::InvalidAddress(..) => {
+                return Err(Status::invalid_argument(self.to_string()));
+            }
+            FacilitatorLocalError::ContractCall(..) | FacilitatorLocalError::ClockError(_) => {
+                return Err(Status::internal(self.to_string()));
+            }
+        };
+        Ok(VerifyResponse::invalid(payer, reason))
+    }
+}
+
+/// Tonic service serving a [`Facilitator`] over gRPC.
+#[derive(Clone, Debug)]
+pub struct FacilitatorGrpcService<A> {
+    facilitator: A,
+}
+
+/// gRPC server of `facilitator`, the counterpart of [`crate::handlers::routes`].
+///
+/// Add it to a [`tonic::transport::Server`]:
+/// `Server::builder().add_service(grpc::server(facilitator)).serve(addr)`.
+pub fn server<A>(
+    facilitator: A,
+) -> proto::facilitator_server::FacilitatorServer<FacilitatorGrpcService<A>>
+where
+    A: Facilitator + Send + Sync + 'static,
+    A::Error: IntoGrpcResponse,
+{
+    proto::facilitator_server::FacilitatorServer::new(FacilitatorGrpcService { facilitator })
+}
+
+#[tonic::async_trait]
+impl<A> proto::facilitator_server::Facilitator for FacilitatorGrpcService<A>
+where
+    A: Facilitator + Send + Sync + 'static,
+    A::Error: IntoGrpcResponse,
+{
+    #[instrument(skip_all)]
+    async fn verify(
+        &self,
+        request: Request<proto::VerifyRequest>,
+    ) -> Result<Response<proto::VerifyResponse>, Status> {
+        let request = VerifyRequest::try_from(request.into_inner())?;
+        let response = match self.facilitator.verify(&request).await {
+            Ok(response) => response,
+            Err(error) => {
+                tracing::warn!(error = ?error, "Verification failed");
+                error.into_verify_response()?
+            }
+        };
+        Ok(Response::new(response.into()))
+    }
+
+    #[instrument(skip_all)]
+    async fn settle(
+        &self,
+        request: Request<proto::VerifyRequest>,
+    ) -> Result<Response<proto::SettleResponse>, Status> {
+        let request = SettleRequest::try_from(request.into_inner())?;
+        match self.facilitator.settle(&request).await {
+            Ok(response) => Ok(Response::new(response.into())),
+            Err(error) => {
+                tracing::warn!(error = ?error, "Settlement failed");
+                Err(error.into_status())
+            }
+        }
+    }
+
+    #[instrument(skip_all)]
+    async fn supported(
+        &self,
+        _request: Request<proto::SupportedRequest>,
+    ) -> Result<Response<proto::SupportedResponse>, Status> {
+        match self.facilitator.supported().await {
+            Ok(supported) => Ok(Response::new(supported.into())),
+            Err(error) => Err(error.into_status()),
+        }
+    }
+}
+
+/// Reads the TLS configuration of the gRPC server from `GRPC_TLS_CERT`, `GRPC_TLS_KEY`
+/// and `GRPC_TLS_CLIENT_CA`, all paths to PEM files.
+///
+/// Returns `None` if neither the certificate nor the key is set, to serve plaintext.
+/// With `GRPC_TLS_CLIENT_CA` set, clients must present a certificate signed by it (mTLS).
+pub fn server_tls_config_from_env() -> Result<Option<ServerTlsConfig>, Box<dyn std::error::Error>> {
+    let read = |name: &str| -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        match std::env::var(name) {
+            Ok(path) => {
+                let pem =
+                    std::fs::read(&path).map_err(|e| format!("Can not read {name} {path}: {e}"))?;
+                Ok(Some(pem))
+            }
+            Err(_) => Ok(None),
+        }
+    };
+    let identity = match (read(ENV_GRPC_TLS_CERT)?, read(ENV_GRPC_TLS_KEY)?) {
+        (Some(cert), Some(key)) => Identity::from_pem(cert, key),
+        (None, None) => return Ok(None),
+        _ => {
+            return Err(
+                format!("{ENV_GRPC_TLS_CERT} and {ENV_GRPC_TLS_KEY} must be set together").into(),
+            );
+        }
+    };
+    let mut config = ServerTlsConfig::new().identity(identity);
+    if let Some(client_ca) = read(ENV_GRPC_TLS_CLIENT_CA)? {
+        config = config.client_ca_root(Certificate::from_pem(client_ca));
+    }
+    Ok(Some(config))
+}
+
+/// Errors of [`FacilitatorGrpcClient`].
+#[derive(Debug, thiserror::Error)]
+pub enum FacilitatorGrpcClientError {
+    /// The facilitator endpoint is not a valid URI.
+    #[error("Invalid facilitator endpoint: {0}")]
+    InvalidEndpoint(String),
+    /// The facilitator answered with a non-OK status.
+    #[error("Facilitator returned {}: {}", .0.code(), .0.message())]
+    Status(Box<Status>),
+    /// The facilitator answered with a message that is not a valid x402 response.
+    #[error("Invalid facilitator response: {0}")]
+    InvalidResponse(#[from] ProtoError),
+}
+
+impl From<Status> for FacilitatorGrpcClientError {
+    fn from(status: Status) -> Self {
+        FacilitatorGrpcClientError::Status(Box::new(status))
+    }
+}
+
+impl IntoGrpcResponse for FacilitatorGrpcClientError {
+    fn into_verify_response(self) -> Result<VerifyResponse, Status> {
+        match self {
+            FacilitatorGrpcClientError::Status(status) => Err(*status),
+            error => Err(Status::unavailable(error.to_string())),
+        }
+    }
+}
+
+/// A [`Facilitator`] calling a remote facilitator over gRPC.
+///
+/// For mTLS, build the [`Channel`] with a [`tonic::transport::ClientTlsConfig`] holding the client
+/// identity and the CA of the server, and pass it to [`FacilitatorGrpcClient::new`].
+#[derive(Clone, Debug)]
+pub struct FacilitatorGrpcClient {
+    client: proto::facilitator_client::FacilitatorClient<Channel>,
+}
+
+#[allow(dead_code)] // Only library users call a remote facilitator, the binary serves gRPC.
+impl FacilitatorGrpcClient {
+    /// Client over an established `channel`.
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            client: proto::facilitator_client::FacilitatorClient::new(channel),
+        }
+    }
+
+    /// Client of the facilitator at `endpoint`, e.g. `http://facilitator:50051`, without TLS.
+    /// Connects on the first call.
+    pub fn try_from_endpoint(endpoint: &str) -> Result<Self, FacilitatorGrpcClientError> {
+        let channel = Channel::from_shared(endpoint.to_string())
+            .map_err(|e| FacilitatorGrpcClientError::InvalidEndpoint(e.to_string()))?
+            .connect_lazy();
+        Ok(Self::new(channel))
+    }
+}
+
+impl Facilitator for FacilitatorGrpcClient {
+    type Error = FacilitatorGrpcClientError;
+
+    async fn verify(&self, request: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+        let response = self
+            .client
+            .clone()
+            .verify(proto::VerifyRequest::from(request))
+            .await?;
+        Ok(response.into_inner().try_into()?)
+    }
+
+    async fn settle(&self, request: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+        let response = self
+            .client
+            .clone()
+            .settle(proto::VerifyRequest::from(request))
+            .await?;
+        Ok(response.into_inner().try_into()?)
+    }
+
+    async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+        let response = self
+            .client
+            .clone()
+            .supported(proto::SupportedRequest {})
+            .await?;
+        Ok(response.into_inner().try_into()?)
+    }
+}
+
+/// JSON string form of `value`, as in the HTTP API.
+fn to_text<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(text)) => text,
+        Ok(value) => value.to_string(),
+        Err(e) => unreachable!("x402 values serialize to JSON: {e}"),
+    }
+}
+
+/// Parses `text` the way the HTTP API parses the JSON string.
+fn from_text<T: DeserializeOwned>(field: &'static str, text: String) -> Result<T, ProtoError> {
+    serde_json::from_value(serde_json::Value::String(text)).map_err(|e| ProtoError::new(field, e))
+}
+
+fn to_json(value: &serde_json::Value) -> String {
+    value.to_string()
+}
+
+fn from_json(field: &'static str, json: &str) -> Result<serde_json::Value, ProtoError> {
+    serde_json::from_str(json).map_err(|e| ProtoError::new(field, e))
+}
+
+fn x402_version(field: &'static str, version: u32) -> Result<X402Version, ProtoError> {
+    let version = u8::try_from(version).map_err(|e| ProtoError::new(field, e))?;
+    X402Version::try_from(version).map_err(|e| ProtoError::new(field, e))
+}
+
+fn version_number(version: X402Version) -> u32 {
+    match version {
+        X402Version::V1 => 1,
+    }
+}
+
+/// Known reasons by their JSON name, anything else as [`FacilitatorErrorReason::FreeForm`].
+fn error_reason(reason: String) -> FacilitatorErrorReason {
+    match reason.as_str() {
+        "insufficient_funds" => FacilitatorErrorReason::InsufficientFunds,
+        "invalid_scheme" => FacilitatorErrorReason::InvalidScheme,
+        "invalid_network" => FacilitatorErrorReason::InvalidNetwork,
+        "invalid_signature" => FacilitatorErrorReason::InvalidSignature,
+        "unexpected_settle_error" => FacilitatorErrorReason::UnexpectedSettleError,
+        "insufficient_fee" => FacilitatorErrorReason::InsufficientFee,
+        "settlement_pending" => FacilitatorErrorReason::SettlementPending,
+        _ => FacilitatorErrorReason::FreeForm(reason),
+    }
+}
+
+impl From<&VerifyRequest> for proto::VerifyRequest {
+    fn from(request: &VerifyRequest) -> Self {
+        Self {
+            x402_version: version_number(request.x402_version),
+            payment_payload: Some((&request.payment_payload).into()),
+            payment_requirements: Some((&request.payment_requirements).into()),
+        }
+    }
+}
+
+impl TryFrom<proto::VerifyRequest> for VerifyRequest {
+    type Error = ProtoError;
+
+    fn try_from(request: proto::VerifyRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            x402_version: x402_version("x402_version", request.x402_version)?,
+            payment_payload: request
+                .payment_payload
+                .ok_or(ProtoError::missing("payment_payload"))?
+                .try_into()?,
+            payment_requirements: request
+                .payment_requirements
+                .ok_or(ProtoError::missing("payment_requirements"))?
+                .try_into()?,
+        })
+    }
+}
+
+impl From<&PaymentPayload> for proto::PaymentPayload {
+    fn from(payload: &PaymentPayload) -> Self {
+        use proto::payment_payload::Payload;
+        let exact = match &payload.payload {
+            ExactPaymentPayload::Evm(evm) => Payload::Evm(proto::ExactEvmPayload {
+                signature: to_text(&evm.signature),
+                authorization: Some((&evm.authorization).into()),
+                fee: evm.fee.as_ref().map(|fee| proto::ExactEvmFeePayload {
+                    signature: to_text(&fee.signature),
+                    authorization: Some((&fee.authorization).into()),
+                }),
+            }),
+            ExactPaymentPayload::EvmPermit(evm_permit) => {
+                let permit = &evm_permit.permit;
+                Payload::EvmPermit(proto::ExactEvmPermitPayload {
+                    signature: to_text(&evm_permit.signature),
+                    permit: Some(proto::ExactEvmPermit {
+                        owner: to_text(&permit.owner),
+                        spender: to_text(&permit.spender),
+                        value: to_text(&permit.value),
+                        nonce: to_text(&permit.nonce),
+                        deadline: permit.deadline.0,
+                    }),
+                })
+            }
+            ExactPaymentPayload::Solana(solana) => Payload::Solana(proto::ExactSolanaPayload {
+                transaction: solana.transaction.clone(),
+            }),
+        };
+        Self {
+            x402_version: version_number(payload.x402_version),
+            scheme: to_text(&payload.scheme),
+            network: to_text(&payload.network),
+            payload: Some(exact),
+        }
+    }
+}
+
+impl TryFrom<proto::PaymentPayload> for PaymentPayload {
+    type Error = ProtoError;
+
+    fn try_from(payload: proto::PaymentPayload) -> Result<Self, Self::Error> {
+        use proto::payment_payload::Payload;
+        let exact = match payload.payload.ok_or(ProtoError::missing("payload"))? {
+            Payload::Evm(evm) => ExactPaymentPayload::Evm(ExactEvmPayload {
+                signature: from_text("signature", evm.signature)?,
+                authorization: evm
+                    .authorization
+                    .ok_or(ProtoError::missing("authorization"))?
+                    .try_into()?,
+                fee: evm
+                    .fee
+                    .map(|fee| -> Result<_, ProtoError> {
+                        Ok(ExactEvmFeePayload {
+                            signature: from_text("fee.signature", fee.signature)?,
+                            authorization: fee
+                                .authorization
+                                .ok_or(ProtoError::missing("fee.authorization"))?
+                                .try_into()?,
+                        })
+                    })
+                    .transpose()?,
+            }),
+            Payload::EvmPermit(evm_permit) => {
+                let permit = evm_permit.permit.ok_or(ProtoError::missing("permit"))?;
+                ExactPaymentPayload::EvmPermit(ExactEvmPermitPayload {
+                    signature: from_text("signature", evm_permit.signature)?,
+                    permit: ExactEvmPayloadPermit {
+                        owner: from_text("permit.owner", permit.owner)?,
+                        spender: from_text("permit.spender", permit.spender)?,
+                        value: from_text("permit.value", permit.value)?,
+                        nonce: from_text("permit.nonce", permit.nonce)?,
+                        deadline: crate::timestamp::UnixTimestamp(permit.deadline),
+                    },
+                })
+            }
+            Payload::Solana(solana) => ExactPaymentPayload::Solana(ExactSolanaPayload {
+                transaction: solana.transaction,
+            }),
+        };
+        Ok(Self {
+            x402_version: x402_version("payment_payload.x402_version", payload.x402_version)?,
+            scheme: from_text("scheme", payload.scheme)?,
+            network: from_text("network", payload.network)?,
+            payload: exact,
+        })
+    }
+}
+
+impl From<&ExactEvmPayloadAuthorization> for proto::ExactEvmAuthorization {
+    fn from(authorization: &ExactEvmPayloadAuthorization) -> Self {
+        Self {
+            from: to_text(&authorization.from),
+            to: to_text(&authorization.to),
+            value: to_text(&authorization.value),
+            valid_after: authorization.valid_after.0,
+            valid_before: authorization.valid_before.0,
+            nonce: to_text(&authorization.nonce),
+        }
+    }
+}
+
+impl TryFrom<proto::ExactEvmAuthorization> for ExactEvmPayloadAuthorization {
+    type Error = ProtoError;
+
+    fn try_from(authorization: proto::ExactEvmAuthorization) -> Result<Self, Self::Error> {
+        Ok(Self {
+            from: from_text("authorization.from", authorization.from)?,
+            to: from_text("authorization.to", authorization.to)?,
+            value: from_text("authorization.value", authorization.value)?,
+            valid_after: crate::timestamp::UnixTimestamp(authorization.valid_after),
+            valid_before: crate::timestamp::UnixTimestamp(authorization.valid_before),
+            nonce: from_text("authorization.nonce", authorization.nonce)?,
+        })
+    }
+}
+
+impl From<&PaymentRequirements> for proto::PaymentRequirements {
+    fn from(requirements: &PaymentRequirements) -> Self {
+        Self {
+            scheme: to_text(&requirements.scheme),
+            network: to_text(&requirements.network),
+            max_amount_required: to_text(&requirements.max_amount_required),
+            resource: requirements.resource.to_string(),
+            description: requirements.description.clone(),
+            mime_type: requirements.mime_type.clone(),
+            output_schema: requirements.output_schema.as_ref().map(to_json),
+            pay_to: to_text(&requirements.pay_to),
+            max_timeout_seconds: requirements.max_timeout_seconds,
+            asset: to_text(&requirements.asset),
+            extra: requirements.extra.as_ref().map(to_json),
+        }
+    }
+}
+
+impl TryFrom<proto::PaymentRequirements> for PaymentRequirements {
+    type Error = ProtoError;
+
+    fn try_from(requirements: proto::PaymentRequirements) -> Result<Self, Self::Error> {
+        Ok(Self {
+            scheme: from_text("scheme", requirements.scheme)?,
+            network: from_text("network", requirements.network)?,
+            max_amount_required: from_text(
+                "max_amount_required",
+                requirements.max_amount_required,
+            )?,
+            resource: from_text("resource", requirements.resource)?,
+            description: requirements.description,
+            mime_type: requirements.mime_type,
+            output_schema: requirements
+                .output_schema
+                .map(|json| from_json("output_schema", &json))
+                .transpose()?,
+            pay_to: from_text("pay_to", requirements.pay_to)?,
+            max_timeout_seconds: requirements.max_timeout_seconds,
+            asset: from_text("asset", requirements.asset)?,
+            extra: requirements
+                .extra
+                .map(|json| from_json("extra", &json))
+                .transpose()?,
+        })
+    }
+}
+
+impl From<VerifyResponse> for proto::VerifyResponse {
+    fn from(response: VerifyResponse) -> Self {
+        match response {
+            VerifyResponse::Valid { payer } => Self {
+                is_valid: true,
+                invalid_reason: None,
+                payer: Some(to_text(&payer)),
+            },
+            VerifyResponse::Invalid { reason, payer } => Self {
+                is_valid: false,
+                invalid_reason: Some(reason.to_string()),
+                payer: payer.as_ref().map(to_text),
+            },
+        }
+    }
+}
+
+impl TryFrom<proto::VerifyResponse> for VerifyResponse {
+    type Error = ProtoError;
+
+    fn try_from(response: proto::VerifyResponse) -> Result<Self, Self::Error> {
+        let payer: Option<MixedAddress> = response
+            .payer
+            .map(|payer| from_text("payer", payer))
+            .transpose()?;
+        match (response.is_valid, response.invalid_reason) {
+            (true, None) => Ok(VerifyResponse::valid(
+                payer.ok_or(ProtoError::missing("payer"))?,
+            )),
+            (false, Some(reason)) => Ok(VerifyResponse::invalid(payer, error_reason(reason))),
+            (true, Some(_)) => Err(ProtoError::new(
+                "invalid_reason",
+                "must be absent when is_valid is true",
+            )),
+            (false, None) => Err(ProtoError::missing("invalid_reason")),
+        }
+    }
+}
+
+impl From<SettleResponse> for proto::SettleResponse {
+    fn from(response: SettleResponse) -> Self {
+        Self {
+            success: response.success,
+            error_reason: response.error_reason.map(|reason| reason.to_string()),
+            payer: to_text(&response.payer),
+            transaction: response.transaction.as_ref().map(to_text),
+            network: to_text(&response.network),
+            fee: response.fee.map(|fee| proto::SettledFee {
+                recipient: to_text(&fee.recipient),
+                amount: to_text(&fee.amount),
+                transaction: fee.transaction.as_ref().map(to_text),
+            }),
+        }
+    }
+}
+
+impl TryFrom<proto::SettleResponse> for SettleResponse {
+    type Error = ProtoError;
+
+    fn try_from(response: proto::SettleResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            success: response.success,
+            error_reason: response.error_reason.map(error_reason),
+            payer: from_text("payer", response.payer)?,
+            transaction: response
+                .transaction
+                .map(|transaction| from_text("transaction", transaction))
+                .transpose()?,
+            network: from_text("network", response.network)?,
+            fee: response
+                .fee
+                .map(|fee| -> Result<_, ProtoError> {
+                    Ok(SettledFee {
+                        recipient: from_text("fee.recipient", fee.recipient)?,
+                        amount: from_text("fee.amount", fee.amount)?,
+                        transaction: fee
+                            .transaction
+                            .map(|transaction| from_text("fee.transaction", transaction))
+                            .transpose()?,
+                    })
+                })
+                .transpose()?,
+        })
+    }
+}
+
+impl From<SupportedPaymentKindsResponse> for proto::SupportedResponse {
+    fn from(supported: SupportedPaymentKindsResponse) -> Self {
+        let kinds = supported
+            .kinds
+            .into_iter()
+            .map(|kind| proto::SupportedPaymentKind {
+                x402_version: version_number(kind.x402_version),
+                scheme: to_text(&kind.scheme),
+                network: kind.network,
+                extra: kind.extra.map(|extra| proto::SupportedPaymentKindExtra {
+                    fee_payer: to_text(&extra.fee_payer),
+                    max_compute_unit_price: extra.max_compute_unit_price,
+                    max_compute_unit_limit: extra.max_compute_unit_limit,
+                    max_lamports_per_transaction: extra.max_lamports_per_transaction,
+                    fee: extra.fee.map(|fee| proto::FacilitatorFee {
+                        recipient: to_text(&fee.recipient),
+                        flat: fee.flat.as_ref().map(to_text),
+                        basis_points: fee.basis_points.map(u32::from),
+                    }),
+                    asset_transfer_method: extra.asset_transfer_method,
+                    spender: extra.spender.as_ref().map(to_text),
+                }),
+            })
+            .collect();
+        Self { kinds }
+    }
+}
+
+impl TryFrom<proto::SupportedResponse> for SupportedPaymentKindsResponse {
+    type Error = ProtoError;
+
+    fn try_from(supported: proto::SupportedResponse) -> Result<Self, Self::Error> {
+        let kinds = supported
+            .kinds
+            .into_iter()
+            .map(|kind| -> Result<_, ProtoError> {
+                Ok(SupportedPaymentKind {
+                    x402_version: x402_version("x402_version", kind.x402_version)?,
+                    scheme: from_text("scheme", kind.scheme)?,
+                    network: kind.network,
+                    extra: kind.extra.map(supported_extra).transpose()?,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { kinds })
+    }
+}
+
+fn supported_extra(
+    extra: proto::SupportedPaymentKindExtra,
+) -> Result<SupportedPaymentKindExtra, ProtoError> {
+    Ok(SupportedPaymentKindExtra {
+        fee_payer: from_text("fee_payer", extra.fee_payer)?,
+        max_compute_unit_price: extra.max_compute_unit_price,
+        max_compute_unit_limit: extra.max_compute_unit_limit,
+        max_lamports_per_transaction: extra.max_lamports_per_transaction,
+        fee: extra
+            .fee
+            .map(|fee| -> Result<_, ProtoError> {
+                Ok(FacilitatorFee {
+                    recipient: from_text("fee.recipient", fee.recipient)?,
+                    flat: fee
+                        .flat
+                        .map(|flat| from_text("fee.flat", flat))
+                        .transpose()?,
+                    basis_points: fee
+                        .basis_points
+                        .map(u16::try_from)
+                        .transpose()
+                        .map_err(|e| ProtoError::new("fee.basis_points", e))?,
+                })
+            })
+            .transpose()?,
+        asset_transfer_method: extra.asset_transfer_method,
+        spender: extra
+            .spender
+            .map(|spender| from_text("spender", spender))
+            .transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Network;
+    use crate::types::TransactionHash;
+    use serde_json::json;
+    use tonic::transport::server::TcpIncoming;
+
+    fn verify_request(payload: serde_json::Value) -> VerifyRequest {
+        serde_json::from_value(json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "payload": payload,
+            },
+            "paymentRequirements": {
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "maxAmountRequired": "1000",
+                "resource": "https://example.com/resource",
+                "description": "",
+                "mimeType": "application/json",
+                "outputSchema": { "type": "object" },
+                "payTo": "0x000000000000000000000000000000000000beef",
+                "maxTimeoutSeconds": 600,
+                "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+                "extra": { "name": "USDC", "version": "2" },
+            },
+        }))
+        .unwrap()
+    }
+
+    fn authorization(value: &str) -> serde_json::Value {
+        json!({
+            "from": "0x000000000000000000000000000000000000cafe",
+            "to": "0x000000000000000000000000000000000000beef",
+            "value": value,
+            "validAfter": "0",
+            "validBefore": "1999999999",
+            "nonce": format!("0x{}", "ab".repeat(32)),
+        })
+    }
+
+    fn evm_request(value: &str) -> VerifyRequest {
+        verify_request(json!({
+            "signature": format!("0x{}", "11".repeat(65)),
+            "authorization": authorization(value),
+            "fee": {
+                "signature": format!("0x{}", "22".repeat(65)),
+                "authorization": authorization("10"),
+            },
+        }))
+    }
+
+    /// Same JSON after a trip through protobuf.
+    fn assert_round_trip(request: &VerifyRequest) {
+        let message = proto::VerifyRequest::from(request);
+        let back = VerifyRequest::try_from(message).unwrap();
+        assert_eq!(
+            serde_json::to_value(&back).unwrap(),
+            serde_json::to_value(request).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_verify_request_round_trip() {
+        assert_round_trip(&evm_request("1000"));
+        assert_round_trip(&verify_request(json!({
+            "signature": format!("0x{}", "33".repeat(65)),
+            "permit": {
+                "owner": "0x000000000000000000000000000000000000cafe",
+                "spender": "0x000000000000000000000000000000000000f00d",
+                "value": "1010",
+                "nonce": "7",
+                "deadline": "1999999999",
+            },
+        })));
+        assert_round_trip(&verify_request(json!({ "transaction": "AQID" })));
+
+        let mut message = proto::VerifyRequest::from(&evm_request("1000"));
+        message.payment_requirements.as_mut().unwrap().pay_to = "not an address!".into();
+        let error = VerifyRequest::try_from(message).unwrap_err();
+        assert_eq!(error.field, "pay_to");
+        let mut message = proto::VerifyRequest::from(&evm_request("1000"));
+        message.payment_payload.as_mut().unwrap().payload = None;
+        let error = VerifyRequest::try_from(message).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid payload: missing");
+    }
+
+    #[test]
+    fn test_responses_round_trip() {
+        let payer = MixedAddress::from(alloy_primitives::address!(
+            "0x000000000000000000000000000000000000cafe"
+        ));
+        let response =
+            VerifyResponse::invalid(Some(payer.clone()), FacilitatorErrorReason::InsufficientFee);
+        let back = VerifyResponse::try_from(proto::VerifyResponse::from(response)).unwrap();
+        assert!(matches!(
+            back,
+            VerifyResponse::Invalid {
+                reason: FacilitatorErrorReason::InsufficientFee,
+                payer: Some(_),
+            }
+        ));
+        let invalid_valid = proto::VerifyResponse {
+            is_valid: true,
+            invalid_reason: None,
+            payer: None,
+        };
+        assert!(VerifyResponse::try_from(invalid_valid).is_err());
+
+        let response = SettleResponse {
+            success: true,
+            error_reason: None,
+            payer: payer.clone(),
+            transaction: Some(TransactionHash::Evm([7; 32])),
+            network: Network::BaseSepolia,
+            fee: Some(SettledFee {
+                recipient: payer,
+                amount: 10u64.into(),
+                transaction: None,
+            }),
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        let back = SettleResponse::try_from(proto::SettleResponse::from(response)).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), json);
+    }
+
+    #[test]
+    fn test_local_errors_map_like_http() {
+        let payer = MixedAddress::Offchain("payer".into());
+        let response = FacilitatorLocalError::InvalidSignature(payer.clone(), "bad".into())
+            .into_verify_response()
+            .unwrap();
+        assert!(matches!(
+            response,
+            VerifyResponse::Invalid {
+                reason: FacilitatorErrorReason::InvalidSignature,
+                ..
+            }
+        ));
+        let status = FacilitatorLocalError::InsufficientFunds(payer).into_status();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(status.message(), "insufficient_funds");
+        let status = FacilitatorLocalError::ContractCall("reverted".into()).into_status();
+        assert_eq!(status.code(), tonic::Code::Internal);
+    }
+
+    /// Accepts any payment of at least 1000, settles it in a fixed transaction.
+    struct FixedPrice;
+
+    impl Facilitator for FixedPrice {
+        type Error = FacilitatorLocalError;
+
+        async fn verify(&self, request: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+            let ExactPaymentPayload::Evm(payload) = &request.payment_payload.payload else {
+                return Err(FacilitatorLocalError::DecodingError("not EVM".into()));
+            };
+            let payer = MixedAddress::from(payload.authorization.from);
+            if payload.authorization.value < 1000u64.into() {
+                return Err(FacilitatorLocalError::InsufficientValue(payer));
+            }
+            Ok(VerifyResponse::valid(payer))
+        }
+
+        async fn settle(&self, request: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+            let VerifyResponse::Valid { payer } = self.verify(request).await? else {
+                unreachable!()
+            };
+            Ok(SettleResponse {
+                success: true,
+                error_reason: None,
+                payer,
+                transaction: Some(TransactionHash::Evm([1; 32])),
+                network: request.network(),
+                fee: None,
+            })
+        }
+
+        async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+            Ok(SupportedPaymentKindsResponse {
+                kinds: vec![SupportedPaymentKind {
+                    x402_version: X402Version::V1,
+                    scheme: crate::types::Scheme::Exact,
+                    network: Network::BaseSepolia.to_string(),
+                    extra: Some(SupportedPaymentKindExtra {
+                        fee_payer: MixedAddress::Offchain("facilitator".into()),
+                        max_compute_unit_price: None,
+                        max_compute_unit_limit: None,
+                        max_lamports_per_transaction: None,
+                        fee: Some(FacilitatorFee {
+                            recipient: MixedAddress::Offchain("facilitator".into()),
+                            flat: None,
+                            basis_points: Some(25),
+                        }),
+                        asset_transfer_method: None,
+                        spender: None,
+                    }),
+                }],
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_calls_server() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(server(FixedPrice))
+                .serve_with_incoming(TcpIncoming::from(listener)),
+        );
+        let client = FacilitatorGrpcClient::try_from_endpoint(&format!("http://{addr}")).unwrap();
+
+        let response = client.verify(&evm_request("1000")).await.unwrap();
+        assert!(matches!(response, VerifyResponse::Valid { .. }));
+        let response = client.verify(&evm_request("999")).await.unwrap();
+        assert!(matches!(
+            response,
+            VerifyResponse::Invalid {
+                reason: FacilitatorErrorReason::InvalidScheme,
+                payer: Some(_),
+            }
+        ));
+
+        let response = client.settle(&evm_request("1000")).await.unwrap();
+        assert!(response.success);
+        assert_eq!(response.transaction, Some(TransactionHash::Evm([1; 32])));
+        let error = client.settle(&evm_request("999")).await.unwrap_err();
+        assert!(
+            matches!(&error, FacilitatorGrpcClientError::Status(status) if status.code() == tonic::Code::FailedPrecondition),
+            "{error}"
+        );
+
+        let supported = client.supported().await.unwrap();
+        let fee = supported.kinds[0].extra.as_ref().unwrap().fee.as_ref();
+        assert_eq!(fee.unwrap().basis_points, Some(25));
+    }
+}
@@ -182,11 +182,18 @@ impl IntoResponse for FacilitatorLocalError {
                 (StatusCode::OK, Json(invalid_schema(payer))).into_response()
             }
             FacilitatorLocalError::ReceiverMismatch(payer, ..)
-            | FacilitatorLocalError::InvalidSignature(payer, ..)
             | FacilitatorLocalError::InvalidTiming(payer, ..)
             | FacilitatorLocalError::InsufficientValue(payer) => {
                 (StatusCode::OK, Json(invalid_schema(Some(payer)))).into_response()
             }
+            FacilitatorLocalError::InvalidSignature(payer, ..) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::InvalidSignature,
+                )),
+            )
+                .into_response(),
             FacilitatorLocalError::NetworkMismatch(payer, ..)
             | FacilitatorLocalError::UnsupportedNetwork(payer) => (
                 StatusCode::OK,
@@ -207,6 +214,24 @@ impl IntoResponse for FacilitatorLocalError {
                 )),
             )
                 .into_response(),
+            FacilitatorLocalError::SpenderMismatch(payer, ..) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::FreeForm(
+                        "invalid_exact_evm_payload_permit_spender".to_string(),
+                    ),
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::InsufficientFee(payer, ..) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::InsufficientFee,
+                )),
+            )
+                .into_response(),
             FacilitatorLocalError::InsufficientFunds(payer) => (
                 StatusCode::OK,
                 Json(VerifyResponse::invalid(
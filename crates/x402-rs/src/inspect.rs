@@ -0,0 +1,826 @@
+//! Decoding of `X-Payment` and `X-Payment-Response` header values, for debugging.
+//!
+//! [`inspect`] turns a base64 header value into an [`Inspection`]: the decoded [`PaymentPayload`]
+//! or [`SettleResponse`], plus what a facilitator derives from a payment before verifying it.
+//! - EVM: the EIP-712 domain, resolved by the same rules as verification, the signing hash,
+//!   the EOA recovered from the signature, and the parts of an EIP-6492 wrapped signature.
+//! - Solana: the transaction signers and their signature status, and every instruction
+//!   with its program, accounts and data, decoded for the programs a payment uses.
+//!
+//! Nothing is fetched from chain: a domain the facilitator would discover from the token contract
+//! must be given through `extra.name` and `extra.version` of the payment requirements.
+
+use alloy_primitives::{Address, B256, Bytes, hex};
+use alloy_sol_types::{SolStruct, eip712_domain};
+use serde::Serialize;
+use solana_message::VersionedMessage;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_system_interface::instruction::SystemInstruction;
+use solana_transaction::versioned::VersionedTransaction;
+
+use crate::chain::evm::{
+    EvmChain, StructuredSignature, recover_ecdsa_signer, static_domain_fields,
+};
+use crate::chain::solana::{
+    ATA_PROGRAM_PUBKEY, LIGHTHOUSE_PROGRAM_PUBKEY, MEMO_PROGRAM_PUBKEY, MEMO_V1_PROGRAM_PUBKEY,
+};
+use crate::network::USDCDeployment;
+use crate::types::{
+    Base64Bytes, EvmAddress, EvmSignature, ExactEvmPayloadAuthorization, ExactEvmPayloadPermit,
+    ExactPaymentPayload, PaymentPayload, PaymentRequirements, Permit, SettleResponse,
+    TransferWithAuthorization,
+};
+
+/// Error returned when a header value can not be inspected.
+#[derive(Debug, thiserror::Error)]
+pub enum InspectError {
+    /// The header value is not valid base64.
+    #[error("base64 decode error: {0}")]
+    Base64Decode(#[from] base64::DecodeError),
+    /// The decoded JSON is neither a [`PaymentPayload`] nor a [`SettleResponse`].
+    #[error("json parse error: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The Solana payment transaction can not be decoded.
+    #[error("invalid transaction: {0}")]
+    Transaction(String),
+}
+
+/// A decoded header value.
+#[derive(Debug, Serialize)]
+#[serde(tag = "header")]
+pub enum Inspection {
+    /// An `X-Payment` value.
+    #[serde(rename = "X-Payment")]
+    Payment {
+        payload: PaymentPayload,
+        details: PaymentDetails,
+    },
+    /// An `X-Payment-Response` value.
+    #[serde(rename = "X-Payment-Response")]
+    PaymentResponse { response: SettleResponse },
+}
+
+/// What a facilitator derives from a payment payload.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PaymentDetails {
+    /// ERC-3009 `transferWithAuthorization`, with the fee authorization if there is one.
+    #[serde(rename_all = "camelCase")]
+    Evm {
+        authorization: Box<Eip712Inspection>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee_authorization: Option<Box<Eip712Inspection>>,
+    },
+    /// EIP-2612 `permit`.
+    EvmPermit { permit: Box<Eip712Inspection> },
+    /// Solana transfer transaction.
+    Solana {
+        transaction: SolanaTransactionInspection,
+    },
+}
+
+/// An EIP-712 signed message, checked offline.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip712Inspection {
+    /// Primary type of the typed data, `TransferWithAuthorization` or `Permit`.
+    pub primary_type: &'static str,
+    /// Address expected to have signed the message.
+    pub signer: EvmAddress,
+    /// `None` if the network is not an EVM network.
+    pub domain: Option<Eip712DomainInspection>,
+    /// `None` unless the domain `name` and `version` are known.
+    pub signing_hash: Option<B256>,
+    pub signature: EvmSignatureInspection,
+    /// EOA recovered from a 65-byte signature, the inner one for EIP-6492, over `signing_hash`.
+    pub recovered_signer: Option<Address>,
+    /// Findings worth a look, e.g. a recovered signer other than `signer`.
+    pub notes: Vec<String>,
+}
+
+/// EIP-712 domain as the facilitator builds it for verification.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip712DomainInspection {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub chain_id: u64,
+    pub verifying_contract: Address,
+}
+
+/// Structure of an EVM signature.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum EvmSignatureInspection {
+    /// A signature validated as is, by ECDSA or ERC-1271.
+    #[serde(rename_all = "camelCase")]
+    Plain { length: usize },
+    /// An EIP-6492 wrapped signature of a counterfactual wallet.
+    #[serde(rename_all = "camelCase")]
+    Eip6492 {
+        factory: Address,
+        factory_calldata: Bytes,
+        inner: Bytes,
+    },
+    /// Ends with the EIP-6492 magic suffix, but the wrapper does not decode.
+    Malformed { error: String },
+}
+
+/// A Solana transaction, decoded without resolving address lookup tables.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolanaTransactionInspection {
+    /// `legacy` or `v0`.
+    pub version: &'static str,
+    pub fee_payer: String,
+    pub recent_blockhash: String,
+    pub signatures: Vec<SolanaSignatureInspection>,
+    pub address_table_lookups: Vec<SolanaLookupInspection>,
+    pub instructions: Vec<SolanaInstructionInspection>,
+}
+
+/// Signature of a required signer.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolanaSignatureInspection {
+    pub signer: String,
+    pub signature: String,
+    pub status: SolanaSignatureStatus,
+}
+
+/// Whether a signature is present and valid over the transaction message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SolanaSignatureStatus {
+    /// Not signed yet, as expected of the facilitator fee payer.
+    Missing,
+    Valid,
+    Invalid,
+}
+
+/// Address lookup table referenced by a v0 message.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolanaLookupInspection {
+    pub table: String,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+/// A compiled instruction of a Solana transaction.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolanaInstructionInspection {
+    pub index: usize,
+    pub program_id: String,
+    /// Name of a well-known program.
+    pub program: Option<&'static str>,
+    /// Account addresses; `<table>[<index>]` for an address loaded from a lookup table.
+    pub accounts: Vec<String>,
+    /// Instruction data, hex encoded.
+    pub data: String,
+    /// Decoded instruction data of a well-known program.
+    pub decoded: Option<String>,
+}
+
+/// Decodes an `X-Payment` or `X-Payment-Response` header value.
+///
+/// `header` may be prefixed by the header name, as in `X-Payment: eyJ4NDAy...`.
+/// `requirements`, if given, provide the token asset and the `extra.name` and `extra.version`
+/// of its EIP-712 domain; otherwise the known USDC deployment of the network is assumed.
+///
+/// # Errors
+/// Returns [`InspectError`] if the value does not decode into a payload or a settle response.
+pub fn inspect(
+    header: &str,
+    requirements: Option<&PaymentRequirements>,
+) -> Result<Inspection, InspectError> {
+    let value = header.rsplit(':').next().unwrap_or_default().trim();
+    let json = Base64Bytes::from(value.as_bytes()).decode()?;
+    let value: serde_json::Value = serde_json::from_slice(&json)?;
+    if value.get("payload").is_none() {
+        let response = serde_json::from_value(value)?;
+        return Ok(Inspection::PaymentResponse { response });
+    }
+    let payload: PaymentPayload = serde_json::from_value(value)?;
+    let details = match &payload.payload {
+        ExactPaymentPayload::Evm(evm) => PaymentDetails::Evm {
+            authorization: Box::new(inspect_authorization(
+                &payload,
+                &evm.authorization,
+                &evm.signature,
+                requirements,
+            )),
+            fee_authorization: evm.fee.as_ref().map(|fee| {
+                Box::new(inspect_authorization(
+                    &payload,
+                    &fee.authorization,
+                    &fee.signature,
+                    requirements,
+                ))
+            }),
+        },
+        ExactPaymentPayload::EvmPermit(permit) => PaymentDetails::EvmPermit {
+            permit: Box::new(inspect_permit(
+                &payload,
+                &permit.permit,
+                &permit.signature,
+                requirements,
+            )),
+        },
+        ExactPaymentPayload::Solana(solana) => {
+            let bytes = Base64Bytes::from(solana.transaction.as_bytes())
+                .decode()
+                .map_err(|e| InspectError::Transaction(format!("{e}")))?;
+            let transaction = bincode::deserialize::<VersionedTransaction>(&bytes)
+                .map_err(|e| InspectError::Transaction(format!("{e}")))?;
+            PaymentDetails::Solana {
+                transaction: inspect_transaction(&transaction),
+            }
+        }
+    };
+    Ok(Inspection::Payment { payload, details })
+}
+
+fn inspect_authorization(
+    payload: &PaymentPayload,
+    authorization: &ExactEvmPayloadAuthorization,
+    signature: &EvmSignature,
+    requirements: Option<&PaymentRequirements>,
+) -> Eip712Inspection {
+    let message = TransferWithAuthorization {
+        from: authorization.from.into(),
+        to: authorization.to.into(),
+        value: authorization.value.into(),
+        validAfter: authorization.valid_after.into(),
+        validBefore: authorization.valid_before.into(),
+        nonce: authorization.nonce.0.into(),
+    };
+    inspect_eip712(
+        payload,
+        &message,
+        authorization.from,
+        signature,
+        requirements,
+    )
+}
+
+fn inspect_permit(
+    payload: &PaymentPayload,
+    permit: &ExactEvmPayloadPermit,
+    signature: &EvmSignature,
+    requirements: Option<&PaymentRequirements>,
+) -> Eip712Inspection {
+    let message = Permit {
+        owner: permit.owner.into(),
+        spender: permit.spender.into(),
+        value: permit.value.into(),
+        nonce: permit.nonce.into(),
+        deadline: permit.deadline.into(),
+    };
+    let mut inspection = inspect_eip712(payload, &message, permit.owner, signature, requirements);
+    if let EvmSignatureInspection::Eip6492 { .. } = inspection.signature {
+        inspection
+            .notes
+            .push("EIP-6492 signatures are not supported for permit".to_string());
+    }
+    inspection
+}
+
+fn inspect_eip712<T: SolStruct>(
+    payload: &PaymentPayload,
+    message: &T,
+    signer: EvmAddress,
+    signature: &EvmSignature,
+    requirements: Option<&PaymentRequirements>,
+) -> Eip712Inspection {
+    let mut notes = Vec::new();
+    let domain = match EvmChain::try_from(payload.network) {
+        Ok(chain) => Some(inspect_domain(&chain, requirements, &mut notes)),
+        Err(_) => {
+            notes.push(format!("{} is not an EVM network", payload.network));
+            None
+        }
+    };
+    let signing_hash = domain.as_ref().and_then(|domain| {
+        let domain = eip712_domain! {
+            name: domain.name.clone()?,
+            version: domain.version.clone()?,
+            chain_id: domain.chain_id,
+            verifying_contract: domain.verifying_contract,
+        };
+        Some(message.eip712_signing_hash(&domain))
+    });
+    let (signature, ecdsa) = match StructuredSignature::try_from(signature.clone()) {
+        Ok(StructuredSignature::EIP6492 {
+            factory,
+            factory_calldata,
+            inner,
+            ..
+        }) => {
+            notes.push(format!(
+                "EIP-6492 wrapped: the wallet is deployed through factory {factory} if needed"
+            ));
+            let signature = EvmSignatureInspection::Eip6492 {
+                factory,
+                factory_calldata,
+                inner: inner.clone(),
+            };
+            (signature, Some(inner))
+        }
+        Ok(StructuredSignature::EIP1271(bytes)) => {
+            let signature = EvmSignatureInspection::Plain {
+                length: bytes.len(),
+            };
+            (signature, Some(bytes))
+        }
+        Err(e) => (
+            EvmSignatureInspection::Malformed {
+                error: format!("{e}"),
+            },
+            None,
+        ),
+    };
+    let recovered_signer = ecdsa
+        .zip(signing_hash)
+        .and_then(|(ecdsa, hash)| recover_ecdsa_signer(&ecdsa, &hash));
+    match (&signature, recovered_signer) {
+        (EvmSignatureInspection::Plain { .. }, Some(recovered)) if signer != recovered => {
+            notes.push(format!(
+                "Signature recovers to {recovered}, not {signer}: wrong domain or message, unless {signer} is a contract wallet"
+            ));
+        }
+        (EvmSignatureInspection::Plain { length }, None) if *length != 65 => {
+            notes.push(format!(
+                "Signature of {length} bytes is not ECDSA: {signer} must validate it through ERC-1271"
+            ));
+        }
+        _ => {}
+    }
+    Eip712Inspection {
+        primary_type: T::NAME,
+        signer,
+        domain,
+        signing_hash,
+        signature,
+        recovered_signer,
+        notes,
+    }
+}
+
+/// Builds the domain like `assert_domain`, without discovering it from the token contract.
+fn inspect_domain(
+    chain: &EvmChain,
+    requirements: Option<&PaymentRequirements>,
+    notes: &mut Vec<String>,
+) -> Eip712DomainInspection {
+    let asset = requirements.and_then(|requirements| requirements.asset.clone().try_into().ok());
+    let asset: Address = match asset {
+        Some(asset) => asset,
+        None => {
+            let usdc = USDCDeployment::by_network(chain.network);
+            notes.push(format!(
+                "No asset given, assuming USDC at {}",
+                usdc.address()
+            ));
+            usdc.address().try_into().unwrap_or_default()
+        }
+    };
+    let extra = requirements.and_then(|requirements| requirements.extra.as_ref());
+    let (name, version) = static_domain_fields(chain, &asset, extra);
+    if name.is_none() || version.is_none() {
+        notes.push(format!(
+            "EIP-712 domain of {asset} is not known offline: the facilitator reads it from the token, \
+             pass requirements with extra.name and extra.version to compute the signing hash"
+        ));
+    }
+    Eip712DomainInspection {
+        name,
+        version,
+        chain_id: chain.chain_id,
+        verifying_contract: asset,
+    }
+}
+
+fn inspect_transaction(transaction: &VersionedTransaction) -> SolanaTransactionInspection {
+    let message = &transaction.message;
+    let static_keys = message.static_account_keys();
+    let message_bytes = message.serialize();
+    let num_required_signatures = usize::from(message.header().num_required_signatures);
+    let signatures = static_keys
+        .iter()
+        .take(num_required_signatures)
+        .enumerate()
+        .map(|(index, signer)| {
+            let signature = transaction
+                .signatures
+                .get(index)
+                .copied()
+                .unwrap_or_default();
+            let status = if signature == Signature::default() {
+                SolanaSignatureStatus::Missing
+            } else if signature.verify(signer.as_ref(), &message_bytes) {
+                SolanaSignatureStatus::Valid
+            } else {
+                SolanaSignatureStatus::Invalid
+            };
+            SolanaSignatureInspection {
+                signer: signer.to_string(),
+                signature: signature.to_string(),
+                status,
+            }
+        })
+        .collect();
+    let lookups = message.address_table_lookups().unwrap_or_default();
+    // Loaded addresses follow the static keys: writable ones of all tables, then readonly ones
+    let account_names = static_keys
+        .iter()
+        .map(Pubkey::to_string)
+        .chain(lookups.iter().flat_map(|lookup| {
+            lookup
+                .writable_indexes
+                .iter()
+                .map(|index| format!("{}[{index}]", lookup.account_key))
+        }))
+        .chain(lookups.iter().flat_map(|lookup| {
+            lookup
+                .readonly_indexes
+                .iter()
+                .map(|index| format!("{}[{index}]", lookup.account_key))
+        }))
+        .collect::<Vec<_>>();
+    let instructions = message
+        .instructions()
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| {
+            let program_id = static_keys.get(usize::from(instruction.program_id_index));
+            let accounts = instruction
+                .accounts
+                .iter()
+                .map(|account| {
+                    account_names
+                        .get(usize::from(*account))
+                        .cloned()
+                        .unwrap_or_else(|| format!("invalid account index {account}"))
+                })
+                .collect();
+            SolanaInstructionInspection {
+                index,
+                program_id: program_id.map_or_else(
+                    || format!("invalid program index {}", instruction.program_id_index),
+                    Pubkey::to_string,
+                ),
+                program: program_id.and_then(program_name),
+                accounts,
+                data: hex::encode(&instruction.data),
+                decoded: program_id.and_then(|id| decode_instruction(id, &instruction.data)),
+            }
+        })
+        .collect();
+    SolanaTransactionInspection {
+        version: match message {
+            VersionedMessage::Legacy(_) => "legacy",
+            VersionedMessage::V0(_) => "v0",
+        },
+        fee_payer: static_keys
+            .first()
+            .map(Pubkey::to_string)
+            .unwrap_or_default(),
+        recent_blockhash: message.recent_blockhash().to_string(),
+        signatures,
+        address_table_lookups: lookups
+            .iter()
+            .map(|lookup| SolanaLookupInspection {
+                table: lookup.account_key.to_string(),
+                writable_indexes: lookup.writable_indexes.clone(),
+                readonly_indexes: lookup.readonly_indexes.clone(),
+            })
+            .collect(),
+        instructions,
+    }
+}
+
+fn program_name(program_id: &Pubkey) -> Option<&'static str> {
+    let name = match *program_id {
+        id if solana_system_interface::program::check_id(&id) => "System",
+        id if id == solana_compute_budget_interface::ID => "ComputeBudget",
+        id if id == spl_token_interface::ID => "Token",
+        id if id == spl_token_2022_interface::ID => "Token-2022",
+        ATA_PROGRAM_PUBKEY => "AssociatedToken",
+        MEMO_PROGRAM_PUBKEY | MEMO_V1_PROGRAM_PUBKEY => "Memo",
+        LIGHTHOUSE_PROGRAM_PUBKEY => "Lighthouse",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn decode_instruction(program_id: &Pubkey, data: &[u8]) -> Option<String> {
+    match program_name(program_id)? {
+        "System" => bincode::deserialize::<SystemInstruction>(data)
+            .ok()
+            .map(|instruction| format!("{instruction:?}")),
+        "ComputeBudget" => decode_compute_budget_instruction(data),
+        "Token" => spl_token_interface::instruction::TokenInstruction::unpack(data)
+            .ok()
+            .map(|instruction| format!("{instruction:?}")),
+        "Token-2022" => spl_token_2022_interface::instruction::TokenInstruction::unpack(data)
+            .ok()
+            .map(|instruction| format!("{instruction:?}")),
+        "AssociatedToken" => match data {
+            [] | [0] => Some("Create".to_string()),
+            [1] => Some("CreateIdempotent".to_string()),
+            [2] => Some("RecoverNested".to_string()),
+            _ => None,
+        },
+        "Memo" => std::str::from_utf8(data)
+            .ok()
+            .map(|memo| format!("Memo({memo:?})")),
+        _ => None,
+    }
+}
+
+/// Compute budget instructions are borsh encoded: a tag byte, then a little-endian argument.
+fn decode_compute_budget_instruction(data: &[u8]) -> Option<String> {
+    let (tag, argument) = data.split_first()?;
+    let decoded = match (tag, argument.len()) {
+        (1, 4) => format!(
+            "RequestHeapFrame({})",
+            u32::from_le_bytes(argument.try_into().ok()?)
+        ),
+        (2, 4) => format!(
+            "SetComputeUnitLimit({})",
+            u32::from_le_bytes(argument.try_into().ok()?)
+        ),
+        (3, 8) => format!(
+            "SetComputeUnitPrice({})",
+            u64::from_le_bytes(argument.try_into().ok()?)
+        ),
+        (4, 4) => format!(
+            "SetLoadedAccountsDataSizeLimit({})",
+            u32::from_le_bytes(argument.try_into().ok()?)
+        ),
+        _ => return None,
+    };
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::evm::EIP6492_MAGIC_SUFFIX;
+    use alloy_primitives::address;
+    use alloy_signer::SignerSync;
+    use alloy_signer_local::PrivateKeySigner;
+    use alloy_sol_types::SolValue;
+    use solana_compute_budget_interface::ComputeBudgetInstruction;
+    use solana_keypair::Keypair;
+    use solana_message::Hash;
+    use solana_message::v0::Message as MessageV0;
+    use solana_message::{AccountMeta, Instruction};
+    use solana_signer::Signer;
+
+    fn header(json: serde_json::Value) -> String {
+        let encoded = Base64Bytes::encode(json.to_string());
+        String::from_utf8(encoded.0.into_owned()).unwrap()
+    }
+
+    fn authorization(from: Address) -> TransferWithAuthorization {
+        TransferWithAuthorization {
+            from,
+            to: address!("0x000000000000000000000000000000000000beef"),
+            value: alloy_primitives::U256::from(1000),
+            validAfter: alloy_primitives::U256::ZERO,
+            validBefore: alloy_primitives::U256::from(4102444800u64),
+            nonce: B256::repeat_byte(0x42),
+        }
+    }
+
+    /// Base Sepolia USDC domain, as known to the facilitator.
+    fn usdc_domain() -> alloy_sol_types::Eip712Domain {
+        let usdc = USDCDeployment::by_network(crate::network::Network::BaseSepolia);
+        let eip712 = usdc.eip712.clone().unwrap();
+        eip712_domain! {
+            name: eip712.name,
+            version: eip712.version,
+            chain_id: 84532,
+            verifying_contract: usdc.address().try_into().unwrap(),
+        }
+    }
+
+    fn evm_header(authorization: &TransferWithAuthorization, signature: &[u8]) -> String {
+        header(serde_json::json!({
+            "x402Version": 1,
+            "scheme": "exact",
+            "network": "base-sepolia",
+            "payload": {
+                "signature": format!("0x{}", hex::encode(signature)),
+                "authorization": {
+                    "from": authorization.from,
+                    "to": authorization.to,
+                    "value": authorization.value.to_string(),
+                    "validAfter": "0",
+                    "validBefore": authorization.validBefore.to_string(),
+                    "nonce": authorization.nonce,
+                }
+            }
+        }))
+    }
+
+    fn evm_authorization(inspection: Inspection) -> Eip712Inspection {
+        match inspection {
+            Inspection::Payment {
+                details: PaymentDetails::Evm { authorization, .. },
+                ..
+            } => *authorization,
+            other => panic!("expected an EVM payment, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_evm_signer_is_recovered_with_known_domain() {
+        let signer = PrivateKeySigner::random();
+        let authorization = authorization(signer.address());
+        let hash = authorization.eip712_signing_hash(&usdc_domain());
+        let signature = signer.sign_hash_sync(&hash).unwrap();
+        let header = evm_header(&authorization, &signature.as_bytes());
+
+        let inspection = evm_authorization(inspect(&header, None).unwrap());
+        assert_eq!(inspection.primary_type, "TransferWithAuthorization");
+        assert_eq!(inspection.signing_hash, Some(hash));
+        assert_eq!(inspection.recovered_signer, Some(signer.address()));
+        assert!(matches!(
+            inspection.signature,
+            EvmSignatureInspection::Plain { length: 65 }
+        ));
+        assert_eq!(inspection.notes.len(), 1, "{:?}", inspection.notes);
+
+        // A domain other than the one signed recovers a different address
+        let usdc = USDCDeployment::by_network(crate::network::Network::BaseSepolia);
+        let requirements: PaymentRequirements = serde_json::from_value(serde_json::json!({
+            "scheme": "exact",
+            "network": "base-sepolia",
+            "maxAmountRequired": "1000",
+            "resource": "https://example.com/resource",
+            "description": "",
+            "mimeType": "application/json",
+            "payTo": "0x000000000000000000000000000000000000beef",
+            "maxTimeoutSeconds": 600,
+            "asset": usdc.address(),
+            "extra": { "name": "USD Coin", "version": "1" },
+        }))
+        .unwrap();
+        let inspection = evm_authorization(
+            inspect(&format!("X-Payment: {header}"), Some(&requirements)).unwrap(),
+        );
+        assert_ne!(inspection.signing_hash, Some(hash));
+        assert_ne!(inspection.recovered_signer, Some(signer.address()));
+        assert!(inspection.notes[0].starts_with("Signature recovers to"));
+    }
+
+    #[test]
+    fn test_evm_eip6492_wrapper_is_decoded() {
+        let signer = PrivateKeySigner::random();
+        let wallet = address!("0x0000000000000000000000000000000000006492");
+        let factory = address!("0x000000000000000000000000000000000000fac7");
+        let authorization = authorization(wallet);
+        let hash = authorization.eip712_signing_hash(&usdc_domain());
+        let inner = Bytes::from(signer.sign_hash_sync(&hash).unwrap().as_bytes());
+        let calldata = Bytes::from_static(&[0xde, 0xad]);
+        let mut signature = (factory, calldata.clone(), inner.clone()).abi_encode_params();
+        signature.extend_from_slice(&EIP6492_MAGIC_SUFFIX);
+
+        let inspection =
+            evm_authorization(inspect(&evm_header(&authorization, &signature), None).unwrap());
+        match inspection.signature {
+            EvmSignatureInspection::Eip6492 {
+                factory: decoded_factory,
+                factory_calldata,
+                inner: decoded_inner,
+            } => {
+                assert_eq!(decoded_factory, factory);
+                assert_eq!(factory_calldata, calldata);
+                assert_eq!(decoded_inner, inner);
+            }
+            other => panic!("expected an EIP-6492 signature, got {other:?}"),
+        }
+        // The owner of the counterfactual wallet
+        assert_eq!(inspection.recovered_signer, Some(signer.address()));
+
+        // Magic suffix without a valid wrapper
+        let inspection = evm_authorization(
+            inspect(&evm_header(&authorization, &EIP6492_MAGIC_SUFFIX), None).unwrap(),
+        );
+        assert!(matches!(
+            inspection.signature,
+            EvmSignatureInspection::Malformed { .. }
+        ));
+        assert_eq!(inspection.recovered_signer, None);
+    }
+
+    #[test]
+    fn test_solana_transaction_is_decoded_per_instruction() {
+        let fee_payer = Pubkey::new_from_array([1; 32]);
+        let buyer = Keypair::new();
+        let mint = Pubkey::new_from_array([4; 32]);
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(20_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            spl_token_2022_interface::instruction::transfer_checked(
+                &spl_token_2022_interface::ID,
+                &Pubkey::new_from_array([3; 32]),
+                &mint,
+                &Pubkey::new_from_array([5; 32]),
+                &buyer.pubkey(),
+                &[],
+                1_000_000,
+                6,
+            )
+            .unwrap(),
+            Instruction::new_with_bytes(
+                MEMO_PROGRAM_PUBKEY,
+                b"order 42",
+                vec![AccountMeta::new_readonly(buyer.pubkey(), true)],
+            ),
+        ];
+        let message = VersionedMessage::V0(
+            MessageV0::try_compile(&fee_payer, &instructions, &[], Hash::default()).unwrap(),
+        );
+        let transaction = VersionedTransaction {
+            signatures: vec![
+                Signature::default(),
+                buyer.sign_message(&message.serialize()),
+            ],
+            message,
+        };
+        let transaction = Base64Bytes::encode(bincode::serialize(&transaction).unwrap());
+        let header = header(serde_json::json!({
+            "x402Version": 1,
+            "scheme": "exact",
+            "network": "solana-devnet",
+            "payload": { "transaction": String::from_utf8(transaction.0.into_owned()).unwrap() }
+        }));
+
+        let transaction = match inspect(&header, None).unwrap() {
+            Inspection::Payment {
+                details: PaymentDetails::Solana { transaction },
+                ..
+            } => transaction,
+            other => panic!("expected a Solana payment, got {other:?}"),
+        };
+        assert_eq!(transaction.version, "v0");
+        assert_eq!(transaction.fee_payer, fee_payer.to_string());
+        let statuses = transaction
+            .signatures
+            .iter()
+            .map(|signature| signature.status)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [SolanaSignatureStatus::Missing, SolanaSignatureStatus::Valid]
+        );
+        let decoded = transaction
+            .instructions
+            .iter()
+            .map(|instruction| (instruction.program, instruction.decoded.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            [
+                (Some("ComputeBudget"), Some("SetComputeUnitLimit(20000)")),
+                (Some("ComputeBudget"), Some("SetComputeUnitPrice(1000)")),
+                (
+                    Some("Token-2022"),
+                    Some("TransferChecked { amount: 1000000, decimals: 6 }")
+                ),
+                (Some("Memo"), Some("Memo(\"order 42\")")),
+            ]
+        );
+        assert_eq!(transaction.instructions[2].accounts[1], mint.to_string());
+    }
+
+    #[test]
+    fn test_payment_response_and_invalid_values() {
+        let response = header(serde_json::json!({
+            "success": true,
+            "payer": "0x0000000000000000000000000000000000000037",
+            "transaction": format!("0x{}", "ab".repeat(32)),
+            "network": "base",
+        }));
+        let inspection = inspect(&format!("X-Payment-Response: {response}"), None).unwrap();
+        assert!(matches!(
+            inspection,
+            Inspection::PaymentResponse { response } if response.success
+        ));
+
+        assert!(matches!(
+            inspect("not base64!", None),
+            Err(InspectError::Base64Decode(_))
+        ));
+        assert!(matches!(
+            inspect(&header(serde_json::json!({ "payload": {} })), None),
+            Err(InspectError::Json(_))
+        ));
+    }
+}
@@ -11,6 +11,10 @@
 //! Modules:
 //! - [`facilitator`] — defines the [`facilitator::Facilitator`] trait used to validate and settle x402 payments.
 //! - [`facilitator_local`] — a concrete implementation of [`facilitator::Facilitator`].
+//! - `grpc` — gRPC service and client of the facilitator API (`grpc` feature).
+//! - [`facilitator_router`] — a [`facilitator::Facilitator`] routing payments to several facilitators by network, with failover.
+//! - `facilitator_mock` — a scriptable in-memory [`facilitator::Facilitator`] for tests (`mock` feature).
+//! - [`inspect`] — decodes `X-Payment` and `X-Payment-Response` header values for debugging.
 //! - [`network`] — enumerates supported Ethereum-compatible networks and known token deployments.
 //! - [`provider_cache`] — dynamic initialization and caching of Ethereum JSON-RPC providers.
 //! - [`telemetry`] — OpenTelemetry instrumentation setup for tracing and observability.
@@ -19,8 +23,14 @@
 pub mod chain;
 pub mod facilitator;
 pub mod facilitator_local;
+pub mod facilitator_router;
+#[cfg(any(test, feature = "mock"))]
+pub mod facilitator_mock;
 pub mod from_env;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod handlers;
+pub mod inspect;
 pub mod network;
 pub mod provider_cache;
 pub mod sig_down;
@@ -15,28 +15,40 @@
 //! - CORS support for cross-origin clients
 //! - Ethereum provider cache for per-network RPC routing
 //!
+//! With the `grpc` feature and `GRPC_PORT` set, the same facilitator is also served over gRPC,
+//! with optional mTLS (see [`grpc`]).
+//!
+//! Subcommands (see [`cli`]) run `verify`, `settle` and other one-shot operations from the command line.
+//!
 //! Environment:
-//! - `.env` values loaded at startup
+//! - `.env` values (or `--env-file`) loaded at startup
 //! - `HOST`, `PORT` control binding address
+//! - `GRPC_PORT`, `GRPC_TLS_*` control the gRPC server
 //! - `OTEL_*` variables enable tracing to systems like Honeycomb
 
 use axum::Router;
 use axum::http::Method;
-use dotenvy::dotenv;
+use clap::Parser;
 use std::net::SocketAddr;
+use std::process::ExitCode;
 use std::sync::Arc;
 use tower_http::cors;
 
+use crate::cli::{Cli, Command};
 use crate::facilitator_local::FacilitatorLocal;
 use crate::provider_cache::ProviderCache;
 use crate::sig_down::SigDown;
 use crate::telemetry::Telemetry;
 
 mod chain;
+mod cli;
 mod facilitator;
 mod facilitator_local;
 mod from_env;
+#[cfg(feature = "grpc")]
+mod grpc;
 mod handlers;
+mod inspect;
 mod network;
 mod provider_cache;
 mod sig_down;
@@ -44,19 +56,34 @@ mod telemetry;
 mod timestamp;
 mod types;
 
+/// Loads `.env` variables, then runs the subcommand, starting the server if there is none.
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if let Err(e) = cli.load_env() {
+        eprintln!("Error: {e}");
+        return ExitCode::FAILURE;
+    }
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => match serve().await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                ExitCode::FAILURE
+            }
+        },
+        command => cli::run(command).await,
+    }
+}
+
 /// Initializes the x402 facilitator server.
 ///
-/// - Loads `.env` variables.
 /// - Initializes OpenTelemetry tracing.
 /// - Connects to Ethereum providers for supported networks.
 /// - Starts an Axum HTTP server with the x402 protocol handlers.
 ///
 /// Binds to the address specified by the `HOST` and `PORT` env vars.
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Load .env variables
-    dotenv().ok();
-
+async fn serve() -> Result<(), Box<dyn std::error::Error>> {
     let telemetry = Telemetry::new()
         .with_name(env!("CARGO_PKG_NAME"))
         .with_version(env!("CARGO_PKG_VERSION"))
@@ -73,6 +100,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
     let facilitator = FacilitatorLocal::new(provider_cache);
     let axum_state = Arc::new(facilitator);
+    #[cfg(feature = "grpc")]
+    let grpc_state = axum_state.clone();
 
     let http_endpoints = Router::new()
         .merge(handlers::routes().with_state(axum_state))
@@ -90,7 +119,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .and_then(|s| s.parse::<u16>().ok())
         .unwrap_or(8080);
 
-    let addr = SocketAddr::new(host.parse().expect("HOST must be a valid IP address"), port);
+    let ip = host.parse().expect("HOST must be a valid IP address");
+    let addr = SocketAddr::new(ip, port);
     tracing::info!("Starting server at http://{}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr)
@@ -103,9 +133,51 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let sig_down = SigDown::try_new()?;
     let axum_cancellation_token = sig_down.cancellation_token();
     let axum_graceful_shutdown = async move { axum_cancellation_token.cancelled().await };
-    axum::serve(listener, http_endpoints)
-        .with_graceful_shutdown(axum_graceful_shutdown)
-        .await?;
+    let http_server = async {
+        axum::serve(listener, http_endpoints)
+            .with_graceful_shutdown(axum_graceful_shutdown)
+            .await
+            .map_err(Box::<dyn std::error::Error>::from)
+    };
 
+    #[cfg(feature = "grpc")]
+    tokio::try_join!(
+        http_server,
+        serve_grpc(ip, grpc_state, sig_down.cancellation_token())
+    )?;
+    #[cfg(not(feature = "grpc"))]
+    http_server.await?;
+
+    Ok(())
+}
+
+/// Serves `facilitator` over gRPC on `GRPC_PORT`, if it is set, until `cancellation_token` is cancelled.
+///
+/// Uses TLS, and mTLS, as configured by [`grpc::server_tls_config_from_env`].
+#[cfg(feature = "grpc")]
+async fn serve_grpc(
+    ip: std::net::IpAddr,
+    facilitator: Arc<FacilitatorLocal<ProviderCache>>,
+    cancellation_token: tokio_util::sync::CancellationToken,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let port = match std::env::var(grpc::ENV_GRPC_PORT) {
+        Ok(port) => port
+            .parse::<u16>()
+            .map_err(|e| format!("Invalid {}: {e}", grpc::ENV_GRPC_PORT))?,
+        Err(_) => return Ok(()),
+    };
+    let addr = SocketAddr::new(ip, port);
+    let mut server = tonic::transport::Server::builder();
+    match grpc::server_tls_config_from_env()? {
+        Some(tls_config) => {
+            server = server.tls_config(tls_config)?;
+            tracing::info!("Starting gRPC server at https://{}", addr);
+        }
+        None => tracing::info!("Starting gRPC server at http://{}", addr),
+    }
+    server
+        .add_service(grpc::server(facilitator))
+        .serve_with_shutdown(addr, cancellation_token.cancelled_owned())
+        .await?;
     Ok(())
 }
@@ -5,7 +5,7 @@
 //! which encode payment intent, authorization, and the result of verification/settlement.
 //!
 //! This module supports ERC-3009 style authorization for tokens (EIP-712 typed signatures),
-//! and provides serialization logic compatible with external clients.
+//! EIP-2612 `permit` for tokens without ERC-3009, and provides serialization logic compatible with external clients.
 
 use alloy_primitives::{Bytes, U256, hex};
 use alloy_sol_types::sol;
@@ -272,6 +272,40 @@ pub struct ExactEvmPayloadAuthorization {
 pub struct ExactEvmPayload {
     pub signature: EvmSignature,
     pub authorization: ExactEvmPayloadAuthorization,
+    /// Separate authorization paying the [`FacilitatorFee`], if the facilitator charges one.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fee: Option<ExactEvmFeePayload>,
+}
+
+/// ERC-3009 authorization transferring the facilitator fee from the payer to the fee recipient.
+/// Settled in the same transaction as the payment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExactEvmFeePayload {
+    pub signature: EvmSignature,
+    pub authorization: ExactEvmPayloadAuthorization,
+}
+
+/// EIP-712 structured data for an EIP-2612 `permit`.
+/// Grants `spender` (the facilitator) an allowance of `value` until `deadline`.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExactEvmPayloadPermit {
+    pub owner: EvmAddress,
+    pub spender: EvmAddress,
+    pub value: TokenAmount,
+    /// Sequential EIP-2612 nonce of the owner, as returned by `nonces(owner)`.
+    pub nonce: TokenAmount,
+    pub deadline: UnixTimestamp,
+}
+
+/// Full payload required to settle through EIP-2612 `permit` and `transferFrom`:
+/// includes the signature and the EIP-712 struct.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExactEvmPermitPayload {
+    pub signature: EvmSignature,
+    pub permit: ExactEvmPayloadPermit,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -284,6 +318,7 @@ pub struct ExactSolanaPayload {
 #[serde(untagged)]
 pub enum ExactPaymentPayload {
     Evm(ExactEvmPayload),
+    EvmPermit(ExactEvmPermitPayload),
     Solana(ExactSolanaPayload),
 }
 
@@ -899,7 +934,7 @@ impl VerifyRequest {
 /// to be used for settlement.
 pub type SettleRequest = VerifyRequest;
 
-#[derive(Debug, Serialize, Deserialize, thiserror::Error)]
+#[derive(Debug, Clone, Serialize, Deserialize, thiserror::Error)]
 #[serde(untagged, rename_all = "camelCase")]
 pub enum FacilitatorErrorReason {
     /// Payer doesn't have sufficient funds.
@@ -914,10 +949,23 @@ pub enum FacilitatorErrorReason {
     #[error("invalid_network")]
     #[serde(rename = "invalid_network")]
     InvalidNetwork,
+    /// The payment signature is not valid for the payer.
+    #[error("invalid_signature")]
+    #[serde(rename = "invalid_signature")]
+    InvalidSignature,
     /// Unexpected settle error
     #[error("unexpected_settle_error")]
     #[serde(rename = "unexpected_settle_error")]
     UnexpectedSettleError,
+    /// The payment does not pay the facilitator fee advertised in `/supported`.
+    #[error("insufficient_fee")]
+    #[serde(rename = "insufficient_fee")]
+    InsufficientFee,
+    /// The payment was submitted but has not landed in time. Not final: it may still land,
+    /// so check the `transaction` of the response before charging again.
+    #[error("settlement_pending")]
+    #[serde(rename = "settlement_pending")]
+    SettlementPending,
     #[error("{0}")]
     FreeForm(String),
 }
@@ -934,6 +982,20 @@ pub struct SettleResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction: Option<TransactionHash>,
     pub network: Network,
+    /// Facilitator fee collected along with the payment.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fee: Option<SettledFee>,
+}
+
+/// Facilitator fee moved to the fee recipient during settlement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettledFee {
+    pub recipient: MixedAddress,
+    pub amount: TokenAmount,
+    /// Transaction that moved the fee, if it is not the payment transaction itself.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub transaction: Option<TransactionHash>,
 }
 
 /// Error returned when encoding a [`SettleResponse`] into base64 fails.
@@ -1405,6 +1467,55 @@ pub struct SupportedPaymentKind {
 #[serde(rename_all = "camelCase")]
 pub struct SupportedPaymentKindExtra {
     pub fee_payer: MixedAddress,
+    /// Highest compute unit price, in micro-lamports, the facilitator accepts (Solana only).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_compute_unit_price: Option<u64>,
+    /// Highest compute unit limit the facilitator accepts (Solana only).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_compute_unit_limit: Option<u32>,
+    /// Highest total fee, in lamports, the facilitator pays for a single transaction (Solana only).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_lamports_per_transaction: Option<u64>,
+    /// Fee charged on top of the price, if any.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fee: Option<FacilitatorFee>,
+    /// How the payment asset is moved, `permit` for EIP-2612 (EVM only). ERC-3009 if unset.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub asset_transfer_method: Option<String>,
+    /// Address an EIP-2612 `permit` must name as spender (EVM only).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub spender: Option<MixedAddress>,
+}
+
+/// Fee a facilitator charges on top of the price of every payment it settles.
+///
+/// The flat part and the percentage add up. Both are in base units of the payment asset.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FacilitatorFee {
+    /// Address the fee is paid to.
+    pub recipient: MixedAddress,
+    /// Flat fee per payment.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub flat: Option<TokenAmount>,
+    /// Fee proportional to `maxAmountRequired`, in basis points (1/100 of a percent).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub basis_points: Option<u16>,
+}
+
+impl FacilitatorFee {
+    /// Fee owed for a payment of `price`: the flat fee plus the percentage, rounded up.
+    pub fn amount(&self, price: TokenAmount) -> TokenAmount {
+        let flat = self.flat.map(|flat| flat.0).unwrap_or_default();
+        let proportional = match self.basis_points {
+            Some(basis_points) => price
+                .0
+                .saturating_mul(U256::from(basis_points))
+                .div_ceil(U256::from(10_000)),
+            None => U256::ZERO,
+        };
+        TokenAmount(flat.saturating_add(proportional))
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -1434,3 +1545,19 @@ sol!(
         bytes32 nonce;
     }
 );
+
+sol!(
+    /// Solidity-compatible struct definition for EIP-2612 `permit`.
+    ///
+    /// This matches the EIP-2612 format used in EIP-712 typed data:
+    /// it grants `spender` an allowance of `value` tokens from `owner`,
+    /// valid until `deadline` and bound to the owner's sequential `nonce`.
+    #[derive(Serialize, Deserialize)]
+    struct Permit {
+        address owner;
+        address spender;
+        uint256 value;
+        uint256 nonce;
+        uint256 deadline;
+    }
+);